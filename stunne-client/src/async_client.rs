@@ -0,0 +1,655 @@
+use crate::{
+    mapped_address, transaction_response, AsyncDatagram, ClientConfig, ClientError, Interceptor,
+    LongTermAuth, RttStats, ShortTermAuth, Timer, TransactionTiming, MAX_RESPONSE_BYTES,
+};
+use bytes::Bytes;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::net::SocketAddr;
+use std::pin::pin;
+use std::task::Poll;
+use std::time::Instant;
+use stunne_protocol::{MessageBuilder, StunDecoder, StunMessage, StunTransaction};
+
+/// An async client for the STUN server that a socket is connected to.
+///
+/// The client is independent of any runtime: it sends and receives through an [AsyncDatagram],
+/// and waits for retransmissions and timeouts through a [Timer]. With the `tokio` feature, a
+/// client for a tokio `UdpSocket` can be created with `StunClient::new`. Only one request is
+/// outstanding at a time; to query several servers at once, use a client (and a socket) for each.
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// # async fn run() -> Result<(), stunne_client::ClientError> {
+/// use stunne_client::StunClient;
+/// use tokio::net::UdpSocket;
+///
+/// let socket = UdpSocket::bind("0.0.0.0:0").await?;
+/// socket.connect("stun.example.org:3478").await?;
+/// let mut client = StunClient::new(socket);
+/// println!("Reflexive address: {}", client.binding_request().await?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct StunClient<S, T> {
+    socket: S,
+    timer: T,
+    config: ClientConfig,
+    interceptors: Vec<Box<dyn Interceptor>>,
+    last_timing: Option<TransactionTiming>,
+    rtt: RttStats,
+}
+
+impl<S: fmt::Debug, T: fmt::Debug> fmt::Debug for StunClient<S, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StunClient")
+            .field("socket", &self.socket)
+            .field("timer", &self.timer)
+            .field("config", &self.config)
+            .field("interceptors", &self.interceptors.len())
+            .field("last_timing", &self.last_timing)
+            .field("rtt", &self.rtt)
+            .finish()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl StunClient<tokio::net::UdpSocket, crate::TokioTimer> {
+    /// Create a client for the server that a tokio `socket` is connected to, using the default
+    /// [ClientConfig].
+    pub fn new(socket: tokio::net::UdpSocket) -> Self {
+        Self::with_config(socket, ClientConfig::default())
+    }
+
+    /// Create a client for the server that a tokio `socket` is connected to, using the given
+    /// options.
+    pub fn with_config(socket: tokio::net::UdpSocket, config: ClientConfig) -> Self {
+        Self::with_timer(socket, crate::TokioTimer, config)
+    }
+
+    /// Create a client for `server`, with a new socket bound with the
+    /// [BindOptions](crate::BindOptions) of `config`.
+    ///
+    /// This must be called from within a tokio runtime.
+    pub fn connect(server: SocketAddr, config: ClientConfig) -> std::io::Result<Self> {
+        let socket = config.bind.bind(server)?;
+        socket.connect(server)?;
+        socket.set_nonblocking(true)?;
+        let socket = tokio::net::UdpSocket::from_std(socket)?;
+        Ok(Self::with_config(socket, config))
+    }
+}
+
+impl<S: AsyncDatagram, T: Timer> StunClient<S, T> {
+    /// Create a client for the server that `socket` is connected to, using the given timer and
+    /// options.
+    pub fn with_timer(socket: S, timer: T, config: ClientConfig) -> Self {
+        Self {
+            socket,
+            timer,
+            config,
+            interceptors: Vec::new(),
+            last_timing: None,
+            rtt: RttStats::default(),
+        }
+    }
+
+    /// Adds an [Interceptor] at the end of the chain that requests and responses pass through.
+    pub fn with_interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors.push(Box::new(interceptor));
+        self
+    }
+
+    /// Returns the socket used to send requests.
+    pub fn socket(&self) -> &S {
+        &self.socket
+    }
+
+    /// Returns the options used for requests.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
+
+    /// Returns when the request of the latest transaction was sent, and its response received.
+    pub fn last_timing(&self) -> Option<&TransactionTiming> {
+        self.last_timing.as_ref()
+    }
+
+    /// Returns the statistics of the round-trip times of all the transactions so far.
+    pub fn rtt_stats(&self) -> &RttStats {
+        &self.rtt
+    }
+
+    /// Returns the timer used to wait for retransmissions and timeouts.
+    pub(crate) fn timer(&self) -> &T {
+        &self.timer
+    }
+
+    /// Consumes the client, returning the socket.
+    pub fn into_socket(self) -> S {
+        self.socket
+    }
+
+    /// Sends a Binding request, returning the address of the socket as seen by the server.
+    ///
+    /// As with [get_mapped_address](crate::get_mapped_address), the address is taken from the
+    /// XOR-MAPPED-ADDRESS attribute of the response, or from the MAPPED-ADDRESS attribute.
+    pub async fn binding_request(&mut self) -> Result<SocketAddr, ClientError> {
+        let request = MessageBuilder::binding_request()
+            .to_bytes()
+            .expect("a request without attributes can always be encoded");
+        let response = self.send_request(request).await?;
+        mapped_address(&response)
+    }
+
+    /// Sends an encoded request, retransmitting it until a response is received or the
+    /// transaction times out, and returns the response.
+    ///
+    /// Datagrams that are not responses to the request are discarded. The response is returned
+    /// whatever its class, so an error response is not a [ClientError].
+    pub async fn send_request(&mut self, request: impl Into<Bytes>) -> Result<Bytes, ClientError> {
+        self.transact(request.into(), |_| true).await
+    }
+
+    /// Sends a request, and returns the first response to it that `accept` returns true for,
+    /// recording the timing of the transaction.
+    async fn transact(
+        &mut self,
+        request: Bytes,
+        accept: impl Fn(&[u8]) -> bool,
+    ) -> Result<Bytes, ClientError> {
+        let mut timing = TransactionTiming::default();
+        let result = self.run_transaction(request, accept, &mut timing).await;
+        self.rtt.record_timing(&timing);
+        self.last_timing = Some(timing);
+        result
+    }
+
+    async fn run_transaction(
+        &mut self,
+        request: Bytes,
+        accept: impl Fn(&[u8]) -> bool,
+        timing: &mut TransactionTiming,
+    ) -> Result<Bytes, ClientError> {
+        let mut transaction =
+            StunTransaction::new(request, self.config.transaction, Instant::now())?;
+        let mut buf = [0; MAX_RESPONSE_BYTES];
+        loop {
+            while let Some(request) = transaction.poll_transmit() {
+                timing.sent.push(Instant::now());
+                let request = self
+                    .interceptors
+                    .iter()
+                    .try_fold(request, |request, i| i.on_request(request));
+                if let Some(request) = request {
+                    self.socket.send(&request).await?;
+                }
+            }
+            let Some(timeout) = transaction.poll_timeout() else {
+                break;
+            };
+            if let Some(error) = self.config.aborted(Instant::now()) {
+                return Err(error);
+            }
+            // Unlike a blocking request, a cancellation wakes the request up by itself.
+            let wake = self
+                .config
+                .deadline
+                .map_or(timeout, |deadline| deadline.min(timeout));
+            let cancelled = async {
+                match &self.config.cancellation {
+                    Some(token) => token.cancelled().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let recv = self.socket.recv(&mut buf);
+            let wake_up = async {
+                until(self.timer.sleep_until(wake), cancelled).await;
+            };
+            match until(recv, wake_up).await {
+                Some(Ok(len)) => {
+                    let received = Instant::now();
+                    let response = self
+                        .interceptors
+                        .iter()
+                        .rev()
+                        .try_fold(Bytes::copy_from_slice(&buf[..len]), |response, i| {
+                            i.on_response(response)
+                        });
+                    if let Some(response) = response.filter(|response| accept(response)) {
+                        if transaction.handle_packet(&response) {
+                            timing.received = Some(received);
+                        }
+                    }
+                }
+                Some(Err(e)) => return Err(e.into()),
+                // The timer may complete slightly early, or be simulated.
+                None => {
+                    if let Some(error) = self.config.aborted(Instant::now().max(wake)) {
+                        return Err(error);
+                    }
+                    transaction.handle_timeout(Instant::now().max(timeout));
+                }
+            }
+        }
+        transaction_response(&transaction)
+    }
+
+    /// Sends a request authenticated with long-term credentials, and returns the response.
+    ///
+    /// The request is signed by `auth`. If the response is a 401 (Unauthorized) giving the realm
+    /// and nonce, or a 438 (Stale Nonce) giving a new nonce, the request is signed again and
+    /// resent as a new transaction, up to [LongTermAuth::max_retries] times; only the final
+    /// response is returned.
+    pub async fn send_authenticated(
+        &mut self,
+        request: &StunMessage,
+        auth: &mut LongTermAuth,
+    ) -> Result<Bytes, ClientError> {
+        let mut retries = 0;
+        loop {
+            let response = self.send_request(auth.sign(request)).await?;
+            let message = StunDecoder::new(&response)?;
+            if auth.handle_response(&message) && retries < auth.max_retries() {
+                retries += 1;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Sends a request authenticated with short-term credentials, e.g. an ICE connectivity check,
+    /// and returns the response.
+    ///
+    /// The request is signed by `auth`. Responses whose MESSAGE-INTEGRITY attribute cannot be
+    /// verified with the same credentials are discarded, as if they had not been received,
+    /// except for 400 (Bad Request) and 401 (Unauthorized) responses.
+    pub async fn send_short_term(
+        &mut self,
+        request: &StunMessage,
+        auth: &ShortTermAuth,
+    ) -> Result<Bytes, ClientError> {
+        self.transact(auth.sign(request), |response| auth.accepts(response))
+            .await
+    }
+}
+
+/// Runs `future` until `deadline` completes, returning `None` if the deadline came first.
+async fn until<F: Future>(future: F, deadline: impl Future<Output = ()>) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut deadline = pin!(deadline);
+    poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            Poll::Ready(Some(output))
+        } else if deadline.as_mut().poll(cx).is_ready() {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::tests::fast_config;
+    use bytes::BytesMut;
+    use futures_executor::block_on;
+    use std::cell::{Cell, RefCell};
+    use std::io;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use stunne_protocol::attributes::types::{NONCE, SOFTWARE};
+    use stunne_protocol::encodings::{ErrorCode, Utf8Decoder};
+    use stunne_protocol::{LongTermKey, MessageClass, MessageHeader, StunEncoder};
+
+    type Respond = Box<dyn Fn(&[u8]) -> Option<Bytes>>;
+
+    /// A socket that answers every request itself, once, with the response given by `respond`,
+    /// or never answers if it gives none.
+    pub(crate) struct Loopback {
+        respond: Respond,
+        sent: RefCell<Vec<Vec<u8>>>,
+        answered: Cell<usize>,
+    }
+
+    impl Loopback {
+        pub(crate) fn new(respond: impl Fn(&[u8]) -> Option<Bytes> + 'static) -> Self {
+            Self {
+                respond: Box::new(respond),
+                sent: RefCell::new(Vec::new()),
+                answered: Cell::new(0),
+            }
+        }
+
+        pub(crate) fn sent(&self) -> usize {
+            self.sent.borrow().len()
+        }
+
+        pub(crate) fn last_sent(&self) -> Vec<u8> {
+            self.sent.borrow().last().unwrap().clone()
+        }
+    }
+
+    fn success_response(request: &[u8]) -> Option<Bytes> {
+        let request = MessageHeader::peek(request).unwrap();
+        let response = MessageBuilder::new(request.success_response())
+            .xor_mapped_address("192.0.2.1:32853".parse().unwrap())
+            .to_bytes()
+            .unwrap();
+        Some(response)
+    }
+
+    impl AsyncDatagram for Loopback {
+        async fn send(&self, buf: &[u8]) -> io::Result<usize> {
+            self.sent.borrow_mut().push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        async fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+            let response = {
+                let sent = self.sent.borrow();
+                if self.answered.replace(sent.len()) < sent.len() {
+                    (self.respond)(sent.last().unwrap())
+                } else {
+                    None
+                }
+            };
+            let Some(response) = response else {
+                return std::future::pending().await;
+            };
+            buf[..response.len()].copy_from_slice(&response);
+            Ok(response.len())
+        }
+    }
+
+    /// A timer whose deadlines are reached immediately.
+    pub(crate) struct Immediate;
+
+    impl Timer for Immediate {
+        async fn sleep_until(&self, _deadline: Instant) {}
+    }
+
+    #[test]
+    fn test_runtime_agnostic() {
+        let socket = Loopback::new(success_response);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        let address = block_on(client.binding_request()).unwrap();
+        assert_eq!(address, "192.0.2.1:32853".parse().unwrap());
+        assert_eq!(client.socket().sent(), 1);
+
+        let socket = Loopback::new(|_| None);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        let result = block_on(client.binding_request());
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+        assert_eq!(client.socket().sent() as u32, fast_config().transaction.rc);
+    }
+
+    /// Drops the first request sent, and discards all responses once `discard` is set.
+    #[derive(Default)]
+    struct Lossy {
+        requests: AtomicUsize,
+        discard: AtomicBool,
+    }
+
+    impl Interceptor for Lossy {
+        fn on_request(&self, request: Bytes) -> Option<Bytes> {
+            (self.requests.fetch_add(1, Ordering::Relaxed) > 0).then_some(request)
+        }
+
+        fn on_response(&self, response: Bytes) -> Option<Bytes> {
+            (!self.discard.load(Ordering::Relaxed)).then_some(response)
+        }
+    }
+
+    /// Adds a SOFTWARE attribute to requests.
+    struct Software;
+
+    impl Interceptor for Software {
+        fn on_request(&self, request: Bytes) -> Option<Bytes> {
+            let request = StunEncoder::new(BytesMut::from(&request[..]))
+                .resume()
+                .unwrap()
+                .add_attribute(SOFTWARE, &"intercepted")
+                .finish();
+            Some(request)
+        }
+    }
+
+    #[test]
+    fn test_interceptors() {
+        let socket = Loopback::new(success_response);
+        let lossy = Arc::new(Lossy::default());
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config())
+            .with_interceptor(Software)
+            .with_interceptor(lossy.clone());
+        let address = block_on(client.binding_request()).unwrap();
+        assert_eq!(address, "192.0.2.1:32853".parse().unwrap());
+        assert_eq!(lossy.requests.load(Ordering::Relaxed), 2);
+        assert_eq!(client.socket().sent(), 1);
+        let request = client.socket().last_sent();
+        let software = StunDecoder::new(&request)
+            .unwrap()
+            .get_attribute(SOFTWARE, &Utf8Decoder)
+            .map(|software| software.unwrap().to_string());
+        assert_eq!(software.as_deref(), Some("intercepted"));
+
+        lossy.discard.store(true, Ordering::Relaxed);
+        let result = block_on(client.binding_request());
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+
+    #[test]
+    fn test_timing() {
+        let socket = Loopback::new(success_response);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        assert_eq!(client.last_timing(), None);
+        block_on(client.binding_request()).unwrap();
+        let timing = client.last_timing().unwrap();
+        assert_eq!(timing.sent.len(), 1);
+        assert_eq!(timing.answered(), Some(0));
+        assert!(!timing.is_ambiguous());
+        assert_eq!(client.rtt_stats().samples(), 1);
+        assert_eq!(client.rtt_stats().min(), timing.rtt());
+
+        // The response to a retransmitted request is not measured.
+        let socket = Loopback::new(success_response);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config())
+            .with_interceptor(Lossy::default());
+        block_on(client.binding_request()).unwrap();
+        let timing = client.last_timing().unwrap();
+        assert_eq!(timing.sent.len(), 2);
+        assert_eq!(timing.answered(), Some(1));
+        assert!(timing.is_ambiguous());
+        assert_eq!(client.rtt_stats().samples(), 0);
+
+        let socket = Loopback::new(|_| None);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        block_on(client.binding_request()).unwrap_err();
+        let timing = client.last_timing().unwrap();
+        assert_eq!(timing.sent.len() as u32, fast_config().transaction.rc);
+        assert_eq!(timing.received, None);
+    }
+
+    /// Answers requests as a server with the realm "realm", for which the nonce "n2" is valid
+    /// and the nonce "n1" is stale.
+    fn authenticating_server(request: &[u8]) -> Option<Bytes> {
+        let message = StunDecoder::new(request).unwrap();
+        let header = MessageHeader::peek(request).unwrap();
+        let (error, nonce) = match message.get_attribute(NONCE, &Utf8Decoder) {
+            None => (ErrorCode::UNAUTHORIZED, "n1"),
+            Some(Ok("n1")) => (ErrorCode::STALE_NONCE, "n2"),
+            Some(_) => {
+                let key = LongTermKey::new("user", "realm", "pass");
+                assert_eq!(message.verify_integrity(key.as_bytes()), Ok(()));
+                return success_response(request);
+            }
+        };
+        let response = MessageBuilder::new(header.error_response())
+            .error_code(error)
+            .realm("realm")
+            .nonce(nonce)
+            .to_bytes()
+            .unwrap();
+        Some(response)
+    }
+
+    #[test]
+    fn test_send_short_term() {
+        let request = MessageBuilder::binding_request().build().unwrap();
+        let sign_response = |password: &'static str| {
+            move |request: &[u8]| {
+                let header = MessageHeader::peek(request).unwrap();
+                let response = MessageBuilder::new(header.success_response())
+                    .build()
+                    .unwrap();
+                Some(ShortTermAuth::new("", password).sign(&response))
+            }
+        };
+        let auth = ShortTermAuth::ice("local", "remote", "password");
+
+        let socket = Loopback::new(sign_response("password"));
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        let response = block_on(client.send_short_term(&request, &auth)).unwrap();
+        assert_eq!(auth.verify(&StunDecoder::new(&response).unwrap()), Ok(()));
+
+        // Responses signed with another password are discarded.
+        let socket = Loopback::new(sign_response("wrong"));
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+        let result = block_on(client.send_short_term(&request, &auth));
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+
+    #[test]
+    fn test_send_authenticated() {
+        let request = MessageBuilder::binding_request().build().unwrap();
+        let socket = Loopback::new(authenticating_server);
+        let mut client = StunClient::with_timer(socket, Immediate, fast_config());
+
+        let mut auth = LongTermAuth::new("user", "pass");
+        let response = block_on(client.send_authenticated(&request, &mut auth)).unwrap();
+        let message = StunDecoder::new(&response).unwrap();
+        assert_eq!(message.class(), MessageClass::SuccessResponse);
+        assert_eq!(auth.nonce(), Some("n2"));
+        assert_eq!(client.socket().sent(), 3);
+
+        // Too few retries to reach the valid nonce.
+        let mut auth = LongTermAuth::new("user", "pass").with_max_retries(1);
+        let response = block_on(client.send_authenticated(&request, &mut auth)).unwrap();
+        let message = StunDecoder::new(&response).unwrap();
+        assert_eq!(message.class(), MessageClass::ErrorResponse);
+        assert_eq!(auth.nonce(), Some("n2"));
+        assert_eq!(client.socket().sent(), 5);
+    }
+
+    #[cfg(feature = "tokio")]
+    mod with_tokio {
+        use super::*;
+        use crate::tests::serve_once;
+        use tokio::net::UdpSocket;
+
+        async fn connect(server: &str) -> UdpSocket {
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            socket.connect(server).await.unwrap();
+            socket
+        }
+
+        #[tokio::test]
+        async fn test_binding_request() {
+            let server = serve_once(|request, peer| {
+                MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+            });
+            let socket = connect(&server).await;
+            let local = socket.local_addr().unwrap();
+            let mut client = StunClient::new(socket);
+            assert_eq!(client.binding_request().await.unwrap(), local);
+        }
+
+        #[tokio::test]
+        async fn test_connect() {
+            let server = serve_once(|request, peer| {
+                MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+            });
+            let config = ClientConfig {
+                bind: crate::BindOptions {
+                    local_address: Some("127.0.0.1:0".parse().unwrap()),
+                    ..Default::default()
+                },
+                ..fast_config()
+            };
+            let mut client = StunClient::connect(server.parse().unwrap(), config).unwrap();
+            let local = client.socket().local_addr().unwrap();
+            assert_eq!(client.binding_request().await.unwrap(), local);
+        }
+
+        #[tokio::test]
+        async fn test_error_response() {
+            let server = serve_once(|request, _| {
+                MessageBuilder::new(request.error_response()).error_code(ErrorCode::BAD_REQUEST)
+            });
+            let mut client = StunClient::new(connect(&server).await);
+            let request = MessageBuilder::binding_request().to_bytes().unwrap();
+            let response = client.send_request(request).await.unwrap();
+            let message = StunDecoder::new(&response).unwrap();
+            assert_eq!(message.class(), MessageClass::ErrorResponse);
+        }
+
+        #[tokio::test]
+        async fn test_timeout() {
+            let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server = silent.local_addr().unwrap().to_string();
+            let mut client = StunClient::with_config(connect(&server).await, fast_config());
+            assert!(matches!(
+                client.binding_request().await,
+                Err(ClientError::TimedOut)
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_cancellation() {
+            let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server = silent.local_addr().unwrap().to_string();
+            let token = crate::CancellationToken::new();
+            let config = ClientConfig {
+                cancellation: Some(token.clone()),
+                ..ClientConfig::default()
+            };
+            let mut client = StunClient::with_config(connect(&server).await, config);
+            let start = Instant::now();
+            let cancel = tokio::spawn(async move {
+                tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                token.cancel();
+            });
+            assert!(matches!(
+                client.binding_request().await,
+                Err(ClientError::Cancelled)
+            ));
+            assert!(start.elapsed() < client.config().transaction.rto);
+            cancel.await.unwrap();
+
+            // A request with a cancelled token is given up at once.
+            assert!(matches!(
+                client.binding_request().await,
+                Err(ClientError::Cancelled)
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_deadline() {
+            let silent = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let server = silent.local_addr().unwrap().to_string();
+            let start = Instant::now();
+            let config = ClientConfig {
+                deadline: Some(start + std::time::Duration::from_millis(50)),
+                ..ClientConfig::default()
+            };
+            let mut client = StunClient::with_config(connect(&server).await, config);
+            assert!(matches!(
+                client.binding_request().await,
+                Err(ClientError::TimedOut)
+            ));
+            assert!(start.elapsed() < client.config().transaction.rto);
+        }
+    }
+}
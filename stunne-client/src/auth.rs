@@ -0,0 +1,360 @@
+use bytes::{Bytes, BytesMut};
+use std::fmt;
+use stunne_protocol::attributes::types::{ERROR_CODE, NONCE, REALM, USERNAME};
+use stunne_protocol::encodings::{ErrorCode, ErrorCodeDecoder, Utf8Decoder};
+use stunne_protocol::errors::IntegrityError;
+use stunne_protocol::{
+    LongTermKey, MessageClass, StunDecoder, StunEncoder, StunMessage, TransactionId,
+};
+
+/// Authenticates requests with the [long-term credential mechanism][], as used by TURN servers.
+///
+/// The realm and nonce are learned from the server: a request sent before they are known carries
+/// no credentials, and is rejected with a 401 (Unauthorized) response giving them. When the nonce
+/// expires, the server rejects a request with a 438 (Stale Nonce) response giving a new one.
+/// [StunClient::send_authenticated](crate::StunClient::send_authenticated) handles both responses
+/// by signing the request again and resending it, so that callers only see the final response.
+///
+/// The username, realm and password are used as given, without SASLprep or OpaqueString
+/// processing (see [LongTermKey]).
+///
+/// [long-term credential mechanism]: https://datatracker.ietf.org/doc/html/rfc8489#section-9.2
+#[derive(Clone)]
+pub struct LongTermAuth {
+    username: String,
+    password: String,
+    realm: Option<String>,
+    nonce: Option<String>,
+    key: Option<LongTermKey>,
+    max_retries: u32,
+}
+
+impl LongTermAuth {
+    /// The number of times a request is sent again by default, after the response it received
+    /// gave a new realm or nonce.
+    pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+    /// Create the credentials for a user, with no realm or nonce yet known.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+            realm: None,
+            nonce: None,
+            key: None,
+            max_retries: Self::DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    /// Sets the number of times a request is sent again after a 401 or 438 response, which is
+    /// [DEFAULT_MAX_RETRIES](Self::DEFAULT_MAX_RETRIES) by default. Once the retries are
+    /// exhausted, the error response is returned.
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns the realm given by the server, if one has been received. Like the nonce, it is kept
+    /// as received (i.e. still escaped, see [Realm](stunne_protocol::encodings::Realm)), so that
+    /// it is echoed to the server byte for byte.
+    pub fn realm(&self) -> Option<&str> {
+        self.realm.as_deref()
+    }
+
+    /// Returns the most recent nonce given by the server, if one has been received, as received.
+    pub fn nonce(&self) -> Option<&str> {
+        self.nonce.as_deref()
+    }
+
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    /// Encodes a request with a new transaction ID, adding USERNAME, REALM, NONCE and
+    /// MESSAGE-INTEGRITY attributes if the realm and nonce are known.
+    ///
+    /// The request must not already contain any of these attributes. A new transaction ID is used
+    /// so that a request can be signed again after a 401 or 438 response, as a new transaction.
+    pub fn sign(&self, request: &StunMessage) -> Bytes {
+        let mut request = request.clone();
+        request.header_mut().tx_id = TransactionId::random();
+        self.sign_in_place(&request)
+    }
+
+    /// Encodes a request like [sign](Self::sign), but keeping its transaction ID, for requests
+    /// with attributes that depend on it (e.g. XOR-PEER-ADDRESS), which are built again for each
+    /// attempt.
+    pub(crate) fn sign_in_place(&self, request: &StunMessage) -> Bytes {
+        let bytes = request.to_bytes();
+        let (Some(realm), Some(nonce), Some(key)) = (&self.realm, &self.nonce, &self.key) else {
+            return bytes;
+        };
+        StunEncoder::new(BytesMut::from(&bytes[..]))
+            .resume()
+            .expect("an encoded message can be resumed")
+            .add_attribute(USERNAME, &self.username.as_str())
+            .add_attribute(REALM, &realm.as_str())
+            .add_attribute(NONCE, &nonce.as_str())
+            .add_message_integrity(key.as_bytes())
+            .finish()
+    }
+
+    /// Learns the realm and nonce from the response to a signed request, returning whether the
+    /// request should be signed and sent again.
+    ///
+    /// This is the case for a 438 (Stale Nonce) response with a NONCE attribute, and for a 401
+    /// (Unauthorized) response with REALM and NONCE attributes that differ from those the
+    /// request was signed with. Otherwise, a 401 response means that the credentials were
+    /// rejected.
+    pub fn handle_response(&mut self, response: &StunDecoder) -> bool {
+        if response.class() != MessageClass::ErrorResponse {
+            return false;
+        }
+        let code = match response.get_attribute(ERROR_CODE, &ErrorCodeDecoder) {
+            Some(Ok(error)) => error.code(),
+            _ => return false,
+        };
+        let realm = match response.get_attribute(REALM, &Utf8Decoder) {
+            Some(Ok(realm)) => Some(realm),
+            _ => None,
+        };
+        let Some(Ok(nonce)) = response.get_attribute(NONCE, &Utf8Decoder) else {
+            return false;
+        };
+
+        if code == ErrorCode::UNAUTHORIZED.code() {
+            let Some(realm) = realm else {
+                return false;
+            };
+            if self.realm() == Some(realm) && self.nonce() == Some(nonce) {
+                return false;
+            }
+            self.set_realm(realm);
+        } else if code == ErrorCode::STALE_NONCE.code() {
+            if let Some(realm) = realm {
+                self.set_realm(realm);
+            }
+        } else {
+            return false;
+        }
+        self.nonce = Some(nonce.to_string());
+        true
+    }
+
+    fn set_realm(&mut self, realm: &str) {
+        if self.realm() != Some(realm) {
+            self.key = Some(LongTermKey::new(&self.username, realm, &self.password));
+            self.realm = Some(realm.to_string());
+        }
+    }
+}
+
+impl fmt::Debug for LongTermAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LongTermAuth")
+            .field("username", &self.username)
+            .field("realm", &self.realm)
+            .field("nonce", &self.nonce)
+            .field("max_retries", &self.max_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Authenticates requests, and verifies responses, with the [short-term credential mechanism][],
+/// as used by ICE connectivity checks.
+///
+/// Requests are signed with USERNAME, MESSAGE-INTEGRITY and FINGERPRINT attributes, using the
+/// password itself as the key. The response must be signed with the same key;
+/// [StunClient::send_short_term](crate::StunClient::send_short_term) discards responses that are
+/// not, as RFC 8489 requires.
+///
+/// ```
+/// # use stunne_client::ShortTermAuth;
+/// // The remote agent's credentials authenticate checks sent to it.
+/// let auth = ShortTermAuth::ice("localufrag", "remoteufrag", "remotepassword");
+/// assert_eq!(auth.username(), "remoteufrag:localufrag");
+/// ```
+///
+/// [short-term credential mechanism]: https://datatracker.ietf.org/doc/html/rfc8489#section-9.1
+#[derive(Clone)]
+pub struct ShortTermAuth {
+    username: String,
+    password: String,
+}
+
+impl ShortTermAuth {
+    /// Create the credentials for a user. The password is used as given, without OpaqueString
+    /// processing.
+    pub fn new(username: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            username: username.into(),
+            password: password.into(),
+        }
+    }
+
+    /// Create the credentials for connectivity checks from the local agent to a remote agent, as
+    /// described in RFC 8445: the username is the remote and local username fragments separated
+    /// by a colon, and the password is the remote agent's.
+    pub fn ice(local_ufrag: &str, remote_ufrag: &str, remote_password: &str) -> Self {
+        Self::new(format!("{}:{}", remote_ufrag, local_ufrag), remote_password)
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Encodes a request, adding USERNAME, MESSAGE-INTEGRITY and FINGERPRINT attributes.
+    ///
+    /// The request must not already contain any of these attributes.
+    pub fn sign(&self, request: &StunMessage) -> Bytes {
+        let bytes = request.to_bytes();
+        StunEncoder::new(BytesMut::from(&bytes[..]))
+            .resume()
+            .expect("an encoded message can be resumed")
+            .add_attribute(USERNAME, &self.username.as_str())
+            .add_message_integrity(self.password.as_bytes())
+            .finish_with_fingerprint()
+    }
+
+    /// Verifies the MESSAGE-INTEGRITY attribute of a response.
+    pub fn verify(&self, response: &StunDecoder) -> Result<(), IntegrityError> {
+        response.verify_integrity(self.password.as_bytes())
+    }
+
+    /// Returns whether a response should be accepted: either its MESSAGE-INTEGRITY attribute is
+    /// valid, or it is a 400 (Bad Request) or 401 (Unauthorized) response, which the server sends
+    /// without one when it cannot authenticate the request.
+    pub(crate) fn accepts(&self, response: &[u8]) -> bool {
+        let Ok(message) = StunDecoder::new(response) else {
+            return false;
+        };
+        if message.class() == MessageClass::ErrorResponse {
+            if let Some(Ok(error)) = message.get_attribute(ERROR_CODE, &ErrorCodeDecoder) {
+                let code = error.code();
+                if code == ErrorCode::BAD_REQUEST.code() || code == ErrorCode::UNAUTHORIZED.code() {
+                    return true;
+                }
+            }
+        }
+        self.verify(&message).is_ok()
+    }
+}
+
+impl fmt::Debug for ShortTermAuth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShortTermAuth")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stunne_protocol::encodings::NonceDecoder;
+    use stunne_protocol::{MessageBuilder, MessageHeader, MessageMethod};
+
+    #[test]
+    fn test_short_term() {
+        let request = MessageBuilder::binding_request().build().unwrap();
+        let auth = ShortTermAuth::ice("local", "remote", "password");
+        let signed = auth.sign(&request);
+        let message = StunDecoder::new(&signed).unwrap();
+        assert_eq!(message.tx_id(), request.header().tx_id);
+        assert_eq!(auth.verify(&message), Ok(()));
+        assert_eq!(message.verify_fingerprint(), Ok(()));
+        let username = message.get_attribute(USERNAME, &Utf8Decoder).unwrap();
+        assert_eq!(username, Ok("remote:local"));
+
+        let wrong = ShortTermAuth::new("remote:local", "wrong");
+        assert_eq!(wrong.verify(&message), Err(IntegrityError::Mismatch));
+        assert!(auth.accepts(&signed));
+        assert!(!wrong.accepts(&signed));
+
+        let unsigned = MessageBuilder::new(request.header().error_response())
+            .error_code(ErrorCode::UNAUTHORIZED)
+            .to_bytes()
+            .unwrap();
+        assert!(wrong.accepts(&unsigned));
+    }
+
+    fn challenge(request: &[u8], error: ErrorCode, realm: Option<&str>, nonce: &str) -> Bytes {
+        let header = MessageHeader::peek(request).unwrap();
+        let mut response = MessageBuilder::new(header.error_response()).error_code(error);
+        if let Some(realm) = realm {
+            response = response.realm(realm);
+        }
+        response.nonce(nonce).to_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_sign() {
+        let request = MessageBuilder::request(MessageMethod::BINDING)
+            .build()
+            .unwrap();
+        let mut auth = LongTermAuth::new("user", "pass");
+        let unsigned = auth.sign(&request);
+        assert_eq!(StunDecoder::new(&unsigned).unwrap().attributes().count(), 0);
+
+        let response = challenge(&unsigned, ErrorCode::UNAUTHORIZED, Some("realm"), "n1");
+        assert!(auth.handle_response(&StunDecoder::new(&response).unwrap()));
+        assert_eq!(auth.realm(), Some("realm"));
+        assert_eq!(auth.nonce(), Some("n1"));
+
+        let signed = auth.sign(&request);
+        let message = StunDecoder::new(&signed).unwrap();
+        assert_ne!(message.tx_id(), request.header().tx_id);
+        let key = LongTermKey::new("user", "realm", "pass");
+        assert_eq!(message.verify_integrity(key.as_bytes()), Ok(()));
+        let nonce = message.get_attribute(NONCE, &Utf8Decoder).unwrap();
+        assert_eq!(nonce, Ok("n1"));
+    }
+
+    #[test]
+    fn test_echo_escaped() {
+        let request = MessageBuilder::binding_request().build().unwrap();
+        let mut auth = LongTermAuth::new("user", "pass");
+        let unsigned = auth.sign(&request);
+        let response = challenge(&unsigned, ErrorCode::UNAUTHORIZED, Some("a\\b"), r#"n"1"#);
+        assert!(auth.handle_response(&StunDecoder::new(&response).unwrap()));
+
+        let signed = auth.sign(&request);
+        let message = StunDecoder::new(&signed).unwrap();
+        let realm = message.get_attribute(REALM, &Utf8Decoder).unwrap();
+        assert_eq!(realm, Ok(r"a\\b"));
+        let nonce = message
+            .get_attribute(NONCE, &NonceDecoder)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nonce.to_string(), r#"n"1"#);
+        let nonce = message.get_attribute(NONCE, &Utf8Decoder).unwrap();
+        assert_eq!(nonce, Ok(r#"n\"1"#));
+    }
+
+    #[test]
+    fn test_handle_response() {
+        let request = MessageBuilder::binding_request().to_bytes().unwrap();
+        let mut auth = LongTermAuth::new("user", "pass");
+        let mut handle = |error, realm, nonce| {
+            let response = challenge(&request, error, realm, nonce);
+            auth.handle_response(&StunDecoder::new(&response).unwrap())
+        };
+
+        assert!(!handle(ErrorCode::UNAUTHORIZED, None, "n1"));
+        assert!(handle(ErrorCode::UNAUTHORIZED, Some("realm"), "n1"));
+        // The same realm and nonce: the credentials were rejected.
+        assert!(!handle(ErrorCode::UNAUTHORIZED, Some("realm"), "n1"));
+        assert!(handle(ErrorCode::STALE_NONCE, None, "n2"));
+        assert!(!handle(ErrorCode::BAD_REQUEST, Some("realm"), "n3"));
+        assert_eq!(auth.nonce(), Some("n2"));
+
+        let response = MessageBuilder::binding_success_response(TransactionId::random())
+            .to_bytes()
+            .unwrap();
+        assert!(!auth.handle_response(&StunDecoder::new(&response).unwrap()));
+    }
+}
@@ -0,0 +1,148 @@
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+
+/// Options for the local UDP sockets that requests are sent from.
+///
+/// By default, sockets are bound to an ephemeral port on all interfaces, with the system's
+/// default TTL and TOS. Options that the platform does not support make binding fail with an
+/// [Unsupported](io::ErrorKind::Unsupported) error, rather than being ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BindOptions {
+    /// The local address and port to bind to, e.g. to send from a fixed source port, or from
+    /// one address of a multi-homed host. It must be of the same address family as the server.
+    pub local_address: Option<SocketAddr>,
+
+    /// The name of the network interface to send from (e.g. `eth0`), whatever the routing table
+    /// says. Only supported on Linux, Android and Fuchsia, where it may require privileges.
+    pub interface: Option<String>,
+
+    /// The TTL of IPv4 packets, or the hop limit of IPv6 packets.
+    pub ttl: Option<u32>,
+
+    /// The type of service of IPv4 packets, or the traffic class of IPv6 packets (e.g. `0xb8`
+    /// for expedited forwarding).
+    pub tos: Option<u32>,
+}
+
+impl BindOptions {
+    /// Create a UDP socket for sending requests to `server`, with these options.
+    ///
+    /// The socket is bound, but not connected.
+    pub fn bind(&self, server: SocketAddr) -> io::Result<UdpSocket> {
+        let local = self.local_address.unwrap_or(match server {
+            SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+            SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+        });
+        if local.is_ipv4() != server.is_ipv4() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the local address and the server are of different address families",
+            ));
+        }
+        let socket = Socket::new(Domain::for_address(local), Type::DGRAM, Some(Protocol::UDP))?;
+        if let Some(interface) = &self.interface {
+            bind_device(&socket, interface)?;
+        }
+        if let Some(ttl) = self.ttl {
+            match local {
+                SocketAddr::V4(_) => socket.set_ttl_v4(ttl)?,
+                SocketAddr::V6(_) => socket.set_unicast_hops_v6(ttl)?,
+            }
+        }
+        if let Some(tos) = self.tos {
+            set_tos(&socket, local, tos)?;
+        }
+        socket.bind(&local.into())?;
+        Ok(socket.into())
+    }
+}
+
+#[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+fn bind_device(socket: &Socket, interface: &str) -> io::Result<()> {
+    socket.bind_device(Some(interface.as_bytes()))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+fn bind_device(_socket: &Socket, _interface: &str) -> io::Result<()> {
+    Err(unsupported("binding to an interface"))
+}
+
+#[cfg(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "linux",
+    target_os = "macos"
+))]
+fn set_tos(socket: &Socket, local: SocketAddr, tos: u32) -> io::Result<()> {
+    match local {
+        SocketAddr::V4(_) => socket.set_tos_v4(tos),
+        SocketAddr::V6(_) => socket.set_tclass_v6(tos),
+    }
+}
+
+#[cfg(windows)]
+fn set_tos(socket: &Socket, local: SocketAddr, tos: u32) -> io::Result<()> {
+    match local {
+        SocketAddr::V4(_) => socket.set_tos_v4(tos),
+        SocketAddr::V6(_) => Err(unsupported("setting the traffic class")),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "android",
+    target_os = "freebsd",
+    target_os = "linux",
+    target_os = "macos",
+    windows
+)))]
+fn set_tos(_socket: &Socket, _local: SocketAddr, _tos: u32) -> io::Result<()> {
+    Err(unsupported("setting the type of service"))
+}
+
+#[cfg(not(any(target_os = "android", target_os = "linux")))]
+fn unsupported(option: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{} is not supported on this platform", option),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bind() {
+        let server = "127.0.0.1:3478".parse().unwrap();
+        let socket = BindOptions::default().bind(server).unwrap();
+        assert!(socket.local_addr().unwrap().ip().is_unspecified());
+
+        let reserved = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let local = reserved.local_addr().unwrap();
+        drop(reserved);
+        let options = BindOptions {
+            local_address: Some(local),
+            ttl: Some(7),
+            ..BindOptions::default()
+        };
+        let socket = options.bind(server).unwrap();
+        assert_eq!(socket.local_addr().unwrap(), local);
+        assert_eq!(socket.ttl().unwrap(), 7);
+
+        let server = "[::1]:3478".parse().unwrap();
+        let error = options.bind(server).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_tos() {
+        let options = BindOptions {
+            tos: Some(0xb8),
+            ..BindOptions::default()
+        };
+        let socket = Socket::from(options.bind("127.0.0.1:3478".parse().unwrap()).unwrap());
+        assert_eq!(socket.tos_v4().unwrap(), 0xb8);
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+
+/// A token that gives up the requests made with it, e.g. when the user closes a window or a
+/// server shuts down.
+///
+/// The token is given to requests through [ClientConfig::cancellation](crate::ClientConfig), and
+/// cancelled from anywhere (e.g. another thread) through a clone of it. A cancelled request fails
+/// with [ClientError::Cancelled](crate::ClientError::Cancelled); once cancelled, a token stays
+/// cancelled, so requests made with it afterwards fail immediately.
+///
+/// ```no_run
+/// # use stunne_client::{get_mapped_address_with_config, CancellationToken, ClientConfig};
+/// let token = CancellationToken::new();
+/// let config = ClientConfig {
+///     cancellation: Some(token.clone()),
+///     ..ClientConfig::default()
+/// };
+/// let query = std::thread::spawn(move || get_mapped_address_with_config("stun.example.org", &config));
+/// token.cancel();
+/// # let _ = query.join();
+/// ```
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// The wakers of the [cancelled](CancellationToken::cancelled) futures waiting for the token,
+/// each keyed so that it can be removed when its future is dropped.
+#[derive(Default)]
+struct Wakers {
+    next_key: u64,
+    wakers: HashMap<u64, Waker>,
+}
+
+impl CancellationToken {
+    /// Create a token that has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the requests made with this token, and with its clones.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::Release);
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().unwrap().wakers);
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// Returns true if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Returns a future that completes once the token has been cancelled.
+    ///
+    /// The future is registered with the token while it is pending, and unregistered when it is
+    /// dropped, so a token that is never cancelled does not keep a waker for every future that
+    /// gave up waiting for it (e.g., every request that finished in time).
+    pub fn cancelled(&self) -> impl Future<Output = ()> + '_ {
+        Cancelled {
+            token: self,
+            key: None,
+        }
+    }
+}
+
+/// The future returned by [CancellationToken::cancelled].
+struct Cancelled<'a> {
+    token: &'a CancellationToken,
+    /// The key of the waker registered with the token, if any.
+    key: Option<u64>,
+}
+
+impl Future for Cancelled<'_> {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.token.inner.wakers.lock().unwrap();
+        let key = match self.key {
+            Some(key) => key,
+            None => {
+                let key = wakers.next_key;
+                wakers.next_key += 1;
+                key
+            }
+        };
+        match wakers.wakers.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => waker.clone_from(cx.waker()),
+            None => {
+                wakers.wakers.insert(key, cx.waker().clone());
+            }
+        }
+        drop(wakers);
+        self.key = Some(key);
+        // The token may have been cancelled before the waker was registered.
+        if self.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for Cancelled<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.token.inner.wakers.lock().unwrap().wakers.remove(&key);
+        }
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_executor::block_on;
+    use std::thread;
+
+    #[test]
+    fn test_cancel() {
+        let token = CancellationToken::new();
+        assert!(!token.is_cancelled());
+        let clone = token.clone();
+        let waiter = thread::spawn(move || block_on(clone.cancelled()));
+        token.cancel();
+        waiter.join().unwrap();
+        assert!(token.is_cancelled());
+        block_on(token.cancelled());
+    }
+
+    #[test]
+    fn test_drop_unregisters() {
+        let token = CancellationToken::new();
+        let waker = Waker::noop();
+        let mut cx = Context::from_waker(waker);
+        for _ in 0..3 {
+            let mut cancelled = Box::pin(token.cancelled());
+            assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+            assert!(cancelled.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(token.inner.wakers.lock().unwrap().wakers.len(), 1);
+        }
+        assert!(token.inner.wakers.lock().unwrap().wakers.is_empty());
+    }
+}
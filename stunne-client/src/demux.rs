@@ -0,0 +1,283 @@
+use crate::{ClientConfig, DatagramTransport};
+use bytes::Bytes;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use stunne_protocol::errors::TransactionError;
+use stunne_protocol::{
+    is_stun, MessageHeader, TransactionConfig, TransactionId, TransactionManager,
+    TransactionOutcome,
+};
+
+/// What [DemuxSocket::recv_from] received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Demuxed {
+    /// A packet that is not STUN (e.g., RTP, RTCP or DTLS), copied to the start of the buffer.
+    Data { len: usize, source: SocketAddr },
+
+    /// A STUN request or indication (e.g., an ICE connectivity check or a Binding indication
+    /// keeping a binding alive), copied to the start of the buffer.
+    Stun { len: usize, source: SocketAddr },
+
+    /// A transaction started with [start](DemuxSocket::start) has finished.
+    Finished {
+        tx_id: TransactionId,
+        outcome: TransactionOutcome,
+    },
+}
+
+/// A UDP socket (or other [DatagramTransport]) shared between STUN transactions and other traffic,
+/// such as the media port of an ICE agent, which also carries RTP and DTLS (RFC 7983).
+///
+/// Packets received are told apart with [is_stun]. STUN responses are given to the
+/// transactions started with [start](Self::start), which are retransmitted while waiting for
+/// packets, and each finished transaction is returned by [recv_from](Self::recv_from). Other
+/// packets, including STUN requests and indications, are returned to the application as they
+/// arrive. Responses that match no transaction, e.g. a second response to a retransmitted
+/// request, are discarded.
+///
+/// The wrapper manages the read timeout of the socket, so it must not be changed directly; use
+/// [set_read_timeout](Self::set_read_timeout) instead.
+///
+/// ```no_run
+/// # use std::net::UdpSocket;
+/// # use stunne_client::{DemuxSocket, Demuxed};
+/// # use stunne_protocol::MessageBuilder;
+/// let server = "192.0.2.1:3478".parse().unwrap();
+/// let mut socket = DemuxSocket::new(UdpSocket::bind("0.0.0.0:5004")?);
+/// let request = MessageBuilder::binding_request().to_bytes().unwrap();
+/// socket.start(server, request).unwrap();
+/// let mut buf = [0; 1500];
+/// loop {
+///     match socket.recv_from(&mut buf)? {
+///         Demuxed::Data { len, source } => println!("{} media bytes from {}", len, source),
+///         Demuxed::Stun { source, .. } => println!("STUN request from {}", source),
+///         Demuxed::Finished { outcome, .. } => println!("Binding finished: {:?}", outcome),
+///     }
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct DemuxSocket<T = UdpSocket> {
+    socket: T,
+    manager: TransactionManager,
+    read_timeout: Option<Duration>,
+}
+
+impl<T: DatagramTransport> DemuxSocket<T> {
+    /// Wraps `socket`, starting transactions with the retransmission parameters of the default
+    /// [ClientConfig].
+    pub fn new(socket: T) -> Self {
+        Self::with_transaction_config(socket, ClientConfig::default().transaction)
+    }
+
+    /// Wraps `socket`, starting transactions with the given retransmission parameters.
+    pub fn with_transaction_config(socket: T, config: TransactionConfig) -> Self {
+        Self {
+            socket,
+            manager: TransactionManager::new(config),
+            read_timeout: None,
+        }
+    }
+
+    pub fn socket(&self) -> &T {
+        &self.socket
+    }
+
+    pub fn into_socket(self) -> T {
+        self.socket
+    }
+
+    /// Returns the number of transactions in progress, or finished but not yet returned.
+    pub fn transactions(&self) -> usize {
+        self.manager.len()
+    }
+
+    /// Sets how long [recv_from](Self::recv_from) waits for a packet or a finished transaction,
+    /// as with [UdpSocket::set_read_timeout]. By default, it waits indefinitely.
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.read_timeout = timeout;
+    }
+
+    /// Sends a packet that is not STUN, or a STUN message that is not a request (e.g., a response
+    /// to a connectivity check), to `destination`.
+    pub fn send_to(&self, buf: &[u8], destination: SocketAddr) -> io::Result<usize> {
+        self.socket.send_to(buf, destination)
+    }
+
+    /// Starts a transaction for the given encoded request, and sends it to `destination`. Its
+    /// outcome is returned by [recv_from](Self::recv_from) once it has finished.
+    ///
+    /// A destination that cannot be reached is reported when its transaction times out.
+    pub fn start(
+        &mut self,
+        destination: SocketAddr,
+        request: impl Into<Bytes>,
+    ) -> Result<TransactionId, TransactionError> {
+        let tx_id = self.manager.start(destination, request, Instant::now())?;
+        self.transmit();
+        Ok(tx_id)
+    }
+
+    /// Cancels the transaction with the given ID, returning false if there is no such
+    /// transaction or it has already finished. It is still returned as
+    /// [Finished](Demuxed::Finished).
+    pub fn cancel(&mut self, tx_id: &TransactionId) -> bool {
+        self.manager.cancel(tx_id)
+    }
+
+    /// Receives a packet that is not a STUN response into `buf`, or returns a transaction that
+    /// has finished, whichever comes first, retransmitting the requests of the transactions in
+    /// progress meanwhile.
+    ///
+    /// If the [read timeout](Self::set_read_timeout) elapses first, an error of kind
+    /// [TimedOut](io::ErrorKind::TimedOut) is returned.
+    pub fn recv_from(&mut self, buf: &mut [u8]) -> io::Result<Demuxed> {
+        let deadline = self.read_timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some((tx_id, outcome)) = self.manager.poll_outcome() {
+                return Ok(Demuxed::Finished { tx_id, outcome });
+            }
+            self.transmit();
+
+            let now = Instant::now();
+            let wake = match (self.manager.poll_timeout(), deadline) {
+                (Some(timeout), Some(deadline)) => Some(timeout.min(deadline)),
+                (timeout, deadline) => timeout.or(deadline),
+            };
+            if let Some(wake) = wake.filter(|&wake| wake <= now) {
+                if deadline == Some(wake) {
+                    return Err(io::ErrorKind::TimedOut.into());
+                }
+                self.manager.handle_timeout(now);
+                continue;
+            }
+            // A zero timeout is an error, but the wake time has been checked to be in the future.
+            self.socket.set_read_timeout(wake.map(|wake| wake - now))?;
+            match self.socket.recv_from(buf) {
+                Ok((len, source)) => {
+                    let packet = &buf[..len];
+                    if !is_stun(packet) {
+                        return Ok(Demuxed::Data { len, source });
+                    }
+                    match MessageHeader::peek(packet) {
+                        // Matched responses are returned by poll_outcome, the others discarded.
+                        Ok(header) if header.class.is_response() => {
+                            self.manager.handle_packet(source, packet);
+                        }
+                        _ => return Ok(Demuxed::Stun { len, source }),
+                    }
+                }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    self.manager.handle_timeout(Instant::now())
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Sends the requests that are due.
+    fn transmit(&mut self) {
+        while let Some((destination, request)) = self.manager.poll_transmit() {
+            // A destination that cannot be reached is reported when its transaction times out.
+            let _ = self.socket.send_to(&request, destination);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fast_config;
+    use stunne_protocol::MessageBuilder;
+
+    fn demux() -> (DemuxSocket, UdpSocket) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let peer = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let socket = DemuxSocket::with_transaction_config(socket, fast_config().transaction);
+        (socket, peer)
+    }
+
+    #[test]
+    fn test_demux() {
+        let (mut socket, peer) = demux();
+        let local = socket.socket().local_addr().unwrap();
+        let peer_address = peer.local_addr().unwrap();
+        let request = MessageBuilder::binding_request().to_bytes().unwrap();
+        let tx_id = socket.start(peer_address, request).unwrap();
+        let mut buf = [0; 1500];
+        let (len, _) = peer.recv_from(&mut buf).unwrap();
+        assert_eq!(MessageHeader::peek(&buf[..len]).unwrap().tx_id, tx_id);
+
+        // An RTP packet, a check from the peer, then the response to the request.
+        let rtp = [0x80, 0x60, 0, 1, 0, 0, 0, 0, 0, 0, 0, 1];
+        peer.send_to(&rtp, local).unwrap();
+        let check = MessageBuilder::binding_request().to_bytes().unwrap();
+        peer.send_to(&check, local).unwrap();
+        let response = MessageBuilder::binding_success_response(tx_id)
+            .xor_mapped_address(local)
+            .to_bytes()
+            .unwrap();
+        peer.send_to(&response, local).unwrap();
+
+        let source = peer_address;
+        let received = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(received, Demuxed::Data { len: 12, source });
+        assert_eq!(buf[..12], rtp);
+        let received = socket.recv_from(&mut buf).unwrap();
+        let len = check.len();
+        assert_eq!(received, Demuxed::Stun { len, source });
+        assert_eq!(buf[..len], check);
+        let outcome = TransactionOutcome::Response(response.clone());
+        let received = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(received, Demuxed::Finished { tx_id, outcome });
+        assert_eq!(socket.transactions(), 0);
+
+        // A second response is stale, and is discarded.
+        peer.send_to(&response, local).unwrap();
+        peer.send_to(&rtp, local).unwrap();
+        let received = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(received, Demuxed::Data { len: 12, source });
+    }
+
+    #[test]
+    fn test_retransmission() {
+        let (mut socket, peer) = demux();
+        let request = MessageBuilder::binding_request().to_bytes().unwrap();
+        let tx_id = socket.start(peer.local_addr().unwrap(), request).unwrap();
+        let mut buf = [0; 1500];
+        let outcome = TransactionOutcome::TimedOut;
+        let received = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(received, Demuxed::Finished { tx_id, outcome });
+
+        peer.set_nonblocking(true).unwrap();
+        let mut sent = 0;
+        while peer.recv_from(&mut buf).is_ok() {
+            sent += 1;
+        }
+        assert_eq!(sent, fast_config().transaction.rc);
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        let (mut socket, _peer) = demux();
+        socket.set_read_timeout(Some(Duration::from_millis(10)));
+        let mut buf = [0; 1500];
+        let error = socket.recv_from(&mut buf).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+
+        let request = MessageBuilder::binding_request().to_bytes().unwrap();
+        let tx_id = socket
+            .start("127.0.0.1:9".parse().unwrap(), request)
+            .unwrap();
+        assert!(socket.cancel(&tx_id));
+        let outcome = TransactionOutcome::Cancelled;
+        let received = socket.recv_from(&mut buf).unwrap();
+        assert_eq!(received, Demuxed::Finished { tx_id, outcome });
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{query, resolve_all, ClientConfig, ClientError};
+use std::net::SocketAddr;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+/// The family of an IP address.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum AddressFamily {
+    Ipv4,
+    Ipv6,
+}
+
+impl AddressFamily {
+    /// Returns the family of a socket address.
+    pub fn of(address: SocketAddr) -> Self {
+        match address {
+            SocketAddr::V4(_) => AddressFamily::Ipv4,
+            SocketAddr::V6(_) => AddressFamily::Ipv6,
+        }
+    }
+}
+
+/// Options for [get_mapped_address_dual_stack], when a server has both IPv4 and IPv6 addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DualStackOptions {
+    /// The address family that is queried first. By default, IPv6, as recommended by RFC 8305.
+    pub preferred: AddressFamily,
+
+    /// How long to wait for a response from the preferred family before also querying the
+    /// other one, which is queried at once if the preferred family fails. By default, 250
+    /// milliseconds, as recommended by RFC 8305. With zero, both families are raced from the
+    /// start; with [Duration::MAX], the other family is only queried if the preferred one fails.
+    pub fallback_delay: Duration,
+}
+
+impl Default for DualStackOptions {
+    fn default() -> Self {
+        Self {
+            preferred: AddressFamily::Ipv6,
+            fallback_delay: Duration::from_millis(250),
+        }
+    }
+}
+
+/// The result of [get_mapped_address_dual_stack].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DualStackResult {
+    /// The address of the local socket as seen by the server.
+    pub mapped_address: SocketAddr,
+
+    /// The address of the server that answered.
+    pub server: SocketAddr,
+}
+
+impl DualStackResult {
+    /// Returns the address family that the mapped address was obtained over.
+    pub fn family(&self) -> AddressFamily {
+        AddressFamily::of(self.server)
+    }
+}
+
+/// Returns the address of a local UDP socket as seen by the STUN server at `server`, as with
+/// [get_mapped_address_with_config](crate::get_mapped_address_with_config), querying both its
+/// IPv4 and IPv6 addresses if it has both.
+///
+/// As in the "Happy Eyeballs" algorithm of RFC 8305, the first address of the
+/// [preferred](DualStackOptions::preferred) family is queried first, and the first address of the
+/// other family is queried too if no response has been received after the
+/// [fallback delay](DualStackOptions::fallback_delay). The first successful response is returned,
+/// along with the family it came from; if both queries fail, the error of the preferred family is
+/// returned. The query that did not win carries on in the background until it finishes.
+///
+/// ```no_run
+/// # use stunne_client::{get_mapped_address_dual_stack, ClientConfig};
+/// let result = get_mapped_address_dual_stack("stun.example.org", &ClientConfig::default())?;
+/// println!("Reflexive address over {:?}: {}", result.family(), result.mapped_address);
+/// # Ok::<(), stunne_client::ClientError>(())
+/// ```
+pub fn get_mapped_address_dual_stack(
+    server: &str,
+    config: &ClientConfig,
+) -> Result<DualStackResult, ClientError> {
+    let addresses = resolve_all(server)?;
+    let preferred = config.dual_stack.preferred;
+    let first = addresses
+        .iter()
+        .find(|a| AddressFamily::of(**a) == preferred);
+    let second = addresses
+        .iter()
+        .find(|a| AddressFamily::of(**a) != preferred);
+    match (first, second) {
+        (Some(&first), Some(&second)) => race(first, second, config),
+        (Some(&server), None) | (None, Some(&server)) => {
+            let mapped_address = query(server, config)?;
+            Ok(DualStackResult {
+                mapped_address,
+                server,
+            })
+        }
+        (None, None) => unreachable!("a server resolves to at least one address"),
+    }
+}
+
+/// Queries `first`, then `second` after the fallback delay (or once `first` fails), returning the
+/// first successful result.
+fn race(
+    first: SocketAddr,
+    second: SocketAddr,
+    config: &ClientConfig,
+) -> Result<DualStackResult, ClientError> {
+    let (sender, receiver) = mpsc::channel();
+    let spawn = |server: SocketAddr| {
+        let sender = sender.clone();
+        let config = config.clone();
+        thread::spawn(move || {
+            // The receiver is gone if the other query has already succeeded.
+            let _ = sender.send((server, query(server, &config)));
+        });
+    };
+
+    spawn(first);
+    let mut early = receiver.recv_timeout(config.dual_stack.fallback_delay).ok();
+    if !matches!(early, Some((_, Ok(_)))) {
+        spawn(second);
+    }
+    let mut first_error = None;
+    let mut second_error = None;
+    for _ in 0..2 {
+        let (server, result) = match early.take() {
+            Some(outcome) => outcome,
+            None => receiver.recv().expect("each query sends its result"),
+        };
+        match result {
+            Ok(mapped_address) => {
+                return Ok(DualStackResult {
+                    mapped_address,
+                    server,
+                })
+            }
+            Err(e) if server == first => first_error = Some(e),
+            Err(e) => second_error = Some(e),
+        }
+    }
+    Err(first_error
+        .or(second_error)
+        .expect("both queries have failed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fast_config, serve_once, serve_once_on};
+    use std::net::UdpSocket;
+    use std::time::Instant;
+    use stunne_protocol::{MessageBuilder, MessageHeader};
+
+    fn answer(request: MessageHeader, peer: SocketAddr) -> MessageBuilder {
+        MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+    }
+
+    fn config(fallback_delay: Duration) -> ClientConfig {
+        ClientConfig {
+            dual_stack: DualStackOptions {
+                fallback_delay,
+                ..DualStackOptions::default()
+            },
+            ..fast_config()
+        }
+    }
+
+    #[test]
+    fn test_preferred_family() {
+        let ipv6 = serve_once_on("[::1]:0", answer).parse().unwrap();
+        let ipv4 = serve_once(answer).parse().unwrap();
+        let result = race(ipv6, ipv4, &config(Duration::from_secs(1))).unwrap();
+        assert_eq!(result.family(), AddressFamily::Ipv6);
+        assert_eq!(result.server, ipv6);
+    }
+
+    #[test]
+    fn test_fallback() {
+        let silent = UdpSocket::bind("[::1]:0").unwrap();
+        let ipv6 = silent.local_addr().unwrap();
+        let ipv4 = serve_once(answer).parse().unwrap();
+        let mut config = config(Duration::from_millis(10));
+        config.transaction.rto = Duration::from_secs(1);
+        let start = Instant::now();
+        let result = race(ipv6, ipv4, &config).unwrap();
+        assert_eq!(result.family(), AddressFamily::Ipv4);
+        // The IPv6 query is not waited for.
+        assert!(start.elapsed() < config.transaction.rto);
+    }
+
+    #[test]
+    fn test_both_fail() {
+        let silent_ipv6 = UdpSocket::bind("[::1]:0").unwrap();
+        let silent_ipv4 = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let result = race(
+            silent_ipv6.local_addr().unwrap(),
+            silent_ipv4.local_addr().unwrap(),
+            &config(Duration::ZERO),
+        );
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{Scheme, UriError};
+use std::fmt;
+use stunne_protocol::errors::MessageDecodeError;
+
+/// Gives the reason that a request to a STUN server failed.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The server's address could not be resolved, or resolved to no addresses.
+    Resolve(std::io::Error),
+
+    /// The server was given as an invalid `stun:` or `stuns:` URI.
+    Uri(UriError),
+
+    /// The server was given as a URI with a scheme that the client does not support.
+    UnsupportedScheme(Scheme),
+
+    /// A socket could not be created, or sending or receiving failed.
+    Io(std::io::Error),
+
+    /// No response was received from the server before the transaction timed out, or before the
+    /// [deadline](crate::ClientConfig::deadline).
+    TimedOut,
+
+    /// The request was given up through a [CancellationToken](crate::CancellationToken).
+    Cancelled,
+
+    /// The TLS connection to a `stuns:` server failed, e.g. because its certificate could not be
+    /// verified.
+    #[cfg(feature = "tls")]
+    Tls(rustls::Error),
+
+    /// The response could not be decoded.
+    Decode(MessageDecodeError),
+
+    /// The server returned an error response.
+    ErrorResponse {
+        /// The error code, or 0 if the response did not contain a valid ERROR-CODE attribute.
+        code: u16,
+
+        /// The reason phrase given by the server.
+        reason: String,
+    },
+
+    /// The success response contained neither an XOR-MAPPED-ADDRESS nor a MAPPED-ADDRESS
+    /// attribute that could be decoded, or the success response to an Allocate request contained
+    /// no XOR-RELAYED-ADDRESS attribute that could be decoded.
+    MissingAddress,
+
+    /// A request needs an allocation on the TURN server, but none has been made, or it has been
+    /// deleted.
+    NoAllocation,
+
+    /// A channel could not be bound, as every channel number has already been bound to a peer
+    /// during the allocation.
+    NoChannelNumber,
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Resolve(e) => write!(f, "failed to resolve server address: {}", e),
+            ClientError::Uri(e) => write!(f, "invalid server URI: {}", e),
+            ClientError::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported URI scheme: {:?}", scheme)
+            }
+            ClientError::Io(e) => write!(f, "I/O error: {}", e),
+            ClientError::TimedOut => f.write_str("no response from server"),
+            ClientError::Cancelled => f.write_str("request cancelled"),
+            #[cfg(feature = "tls")]
+            ClientError::Tls(e) => write!(f, "TLS error: {}", e),
+            ClientError::Decode(e) => write!(f, "failed to decode response: {}", e),
+            ClientError::ErrorResponse { code, reason } => {
+                write!(f, "server returned error {} {}", code, reason)
+            }
+            ClientError::MissingAddress => {
+                f.write_str("response does not contain a mapped address")
+            }
+            ClientError::NoAllocation => f.write_str("no allocation on the TURN server"),
+            ClientError::NoChannelNumber => f.write_str("no channel number left to bind"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Resolve(e) => Some(e),
+            ClientError::Uri(e) => Some(e),
+            ClientError::Io(e) => Some(e),
+            #[cfg(feature = "tls")]
+            ClientError::Tls(e) => Some(e),
+            ClientError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ClientError {
+    fn from(other: std::io::Error) -> Self {
+        ClientError::Io(other)
+    }
+}
+
+impl From<UriError> for ClientError {
+    fn from(other: UriError) -> Self {
+        ClientError::Uri(other)
+    }
+}
+
+#[cfg(feature = "tls")]
+impl From<rustls::Error> for ClientError {
+    fn from(other: rustls::Error) -> Self {
+        ClientError::Tls(other)
+    }
+}
+
+impl From<MessageDecodeError> for ClientError {
+    fn from(other: MessageDecodeError) -> Self {
+        ClientError::Decode(other)
+    }
+}
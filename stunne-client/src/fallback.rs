@@ -0,0 +1,141 @@
+#[cfg(feature = "tls")]
+use crate::{parse_uri, tls, Scheme, StunUri};
+use crate::{query, resolve, tcp, ClientConfig, ClientError};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+/// A transport that STUN requests are sent over.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Transport {
+    Udp,
+    Tcp,
+    /// TLS over TCP.
+    #[cfg(feature = "tls")]
+    Tls,
+}
+
+/// The result of [get_mapped_address_with_fallback].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FallbackResult {
+    /// The address of the local socket as seen by the server. For TCP and TLS, this is the address
+    /// of the TCP connection, which may not be mapped like UDP traffic is.
+    pub mapped_address: SocketAddr,
+
+    /// The transport that the response was received over.
+    pub transport: Transport,
+}
+
+/// Returns the address of a local socket as seen by the STUN server at `server`, falling back
+/// to TCP, then to TLS, if no response is received over UDP (e.g. because a firewall blocks UDP).
+///
+/// The server is given as for [get_mapped_address](crate::get_mapped_address). UDP and TCP are
+/// tried on the same port; TLS is tried on the default port of `stuns:` (5349), and is only tried
+/// if the `tls` feature is enabled. A transport is abandoned for the next one if its request
+/// times out, or if its socket fails (e.g., if the connection is refused), but not if the server
+/// answers with an error response. If every transport fails, the error of the last one is
+/// returned. A `stuns:` URI is only tried over TLS.
+///
+/// ```no_run
+/// # use stunne_client::{get_mapped_address_with_fallback, ClientConfig};
+/// let result = get_mapped_address_with_fallback("stun.example.org", &ClientConfig::default())?;
+/// println!("Reflexive address over {:?}: {}", result.transport, result.mapped_address);
+/// # Ok::<(), stunne_client::ClientError>(())
+/// ```
+pub fn get_mapped_address_with_fallback(
+    server: &str,
+    config: &ClientConfig,
+) -> Result<FallbackResult, ClientError> {
+    #[cfg(feature = "tls")]
+    if let Some(uri) = parse_uri(server)?.filter(|uri| uri.scheme() == Scheme::Stuns) {
+        return tls::get_mapped_address(&uri, config).map(result(Transport::Tls));
+    }
+
+    let address = resolve(server)?;
+    match query(address, config) {
+        Err(e) if falls_back(&e, config) => {}
+        udp => return udp.map(result(Transport::Udp)),
+    }
+    match tcp::get_mapped_address(address, config) {
+        Err(e) if falls_back(&e, config) => tls_fallback(server, address, config, e),
+        tcp => tcp.map(result(Transport::Tcp)),
+    }
+}
+
+/// Queries the server over TLS, on the default port of `stuns:`, after TCP failed with
+/// `tcp_error`.
+#[cfg(feature = "tls")]
+fn tls_fallback(
+    server: &str,
+    address: SocketAddr,
+    config: &ClientConfig,
+    _tcp_error: ClientError,
+) -> Result<FallbackResult, ClientError> {
+    // The host name is needed to verify the certificate of the server.
+    let uri = match parse_uri(server)? {
+        Some(uri) => uri,
+        None => format!("stun:{}", server)
+            .parse::<StunUri>()
+            .or_else(|_| format!("stun:{}", address).parse())?,
+    };
+    tls::get_mapped_address(&uri.with_scheme(Scheme::Stuns), config).map(result(Transport::Tls))
+}
+
+#[cfg(not(feature = "tls"))]
+fn tls_fallback(
+    _server: &str,
+    _address: SocketAddr,
+    _config: &ClientConfig,
+    tcp_error: ClientError,
+) -> Result<FallbackResult, ClientError> {
+    Err(tcp_error)
+}
+
+fn result(transport: Transport) -> impl Fn(SocketAddr) -> FallbackResult {
+    move |mapped_address| FallbackResult {
+        mapped_address,
+        transport,
+    }
+}
+
+/// Returns true if the next transport should be tried after an error.
+fn falls_back(error: &ClientError, config: &ClientConfig) -> bool {
+    let aborted = config.aborted(Instant::now()).is_some();
+    !aborted && matches!(error, ClientError::TimedOut | ClientError::Io(_))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fast_config;
+    use std::net::{TcpListener, UdpSocket};
+    use stunne_protocol::MessageBuilder;
+
+    #[test]
+    fn test_tcp_fallback() {
+        // A TCP server on the port of a UDP socket that never answers.
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = tcp::tests::serve_once_on(silent.local_addr().unwrap(), |request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let result = get_mapped_address_with_fallback(&server.to_string(), &fast_config());
+        assert_eq!(result.unwrap().transport, Transport::Tcp);
+    }
+
+    #[test]
+    fn test_udp() {
+        let server = crate::tests::serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let result = get_mapped_address_with_fallback(&server, &fast_config()).unwrap();
+        assert_eq!(result.transport, Transport::Udp);
+    }
+
+    #[test]
+    fn test_all_fail() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap();
+        drop(listener);
+        let result = get_mapped_address_with_fallback(&server.to_string(), &fast_config());
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use crate::multi::query_from;
+use crate::{resolve, ClientConfig, DatagramTransport};
+use std::net::SocketAddr;
+use std::thread;
+
+/// A server-reflexive candidate (RFC 8445 section 5.1.1.2): the address of a local socket as
+/// seen by STUN servers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerReflexiveCandidate {
+    /// The transport address of the candidate, i.e. the mapped address reported by the servers.
+    pub address: SocketAddr,
+
+    /// The base of the candidate: the local address of the socket it was gathered from, which
+    /// checks for it are sent from.
+    pub base: SocketAddr,
+
+    /// The servers that reported the candidate, as given to [gather_srflx].
+    pub servers: Vec<String>,
+}
+
+impl ServerReflexiveCandidate {
+    /// Returns the related address of the candidate, as signalled in its `raddr` and `rport`
+    /// (RFC 8839), which is its base.
+    pub fn related_address(&self) -> SocketAddr {
+        self.base
+    }
+}
+
+/// Gathers the server-reflexive candidates of local sockets (or other [DatagramTransport]s), by
+/// sending Binding requests from each socket to each STUN server of the same address family.
+///
+/// Servers are given as for [get_mapped_address](crate::get_mapped_address), and are resolved
+/// once. The requests of each socket are all in flight at the same time, and the sockets are
+/// queried in parallel, so that gathering takes no longer than a single request. The sockets are
+/// those of the host candidates, and should be bound to a specific address rather than an
+/// unspecified one, which would be reported as the base.
+///
+/// Candidates are returned in the order of the sockets, then in the order they were first
+/// reported. As in RFC 8445 section 5.1.3, redundant candidates are removed: a candidate
+/// reported by several servers for the same socket is returned once, and a mapped address that
+/// is the base itself (i.e. the socket is not behind a NAT) is not a candidate. Servers that
+/// cannot be resolved or do not answer are skipped; use [query_servers](crate::query_servers) to
+/// find out why.
+///
+/// ```no_run
+/// # use std::net::UdpSocket;
+/// # use stunne_client::{gather_srflx, ClientConfig};
+/// let socket = UdpSocket::bind("192.168.1.2:0")?;
+/// let servers = ["stun.example.org", "stun.example.net"];
+/// for candidate in gather_srflx(&[&socket], &servers, &ClientConfig::default()) {
+///     println!("srflx {} raddr {}", candidate.address, candidate.related_address());
+/// }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn gather_srflx<T: DatagramTransport + Sync + ?Sized>(
+    sockets: &[&T],
+    servers: &[&str],
+    config: &ClientConfig,
+) -> Vec<ServerReflexiveCandidate> {
+    let resolved: Vec<_> = servers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, server)| Some((i, resolve(server).ok()?)))
+        .collect();
+
+    let reports: Vec<_> = thread::scope(|scope| {
+        let queries: Vec<_> = sockets
+            .iter()
+            .filter_map(|socket| {
+                // A socket without a local address cannot be the base of a candidate.
+                let base = socket.local_addr().ok()?;
+                let servers: Vec<_> = resolved
+                    .iter()
+                    .filter(|(_, server)| server.is_ipv4() == base.is_ipv4())
+                    .copied()
+                    .collect();
+                Some((
+                    base,
+                    scope.spawn(move || query_from(*socket, &servers, config)),
+                ))
+            })
+            .collect();
+        queries
+            .into_iter()
+            .map(|(base, query)| (base, query.join().expect("a query does not panic")))
+            .collect()
+    });
+
+    let mut candidates: Vec<ServerReflexiveCandidate> = Vec::new();
+    for (base, report) in reports {
+        let Ok(mut results) = report else {
+            continue;
+        };
+        // The results are in the order the responses arrived.
+        results.sort_by_key(|(i, _, _)| *i);
+        for (i, result, _) in results {
+            let Ok(address) = result else {
+                continue;
+            };
+            if address == base {
+                continue;
+            }
+            let server = servers[i].to_string();
+            match candidates
+                .iter_mut()
+                .find(|candidate| candidate.address == address && candidate.base == base)
+            {
+                Some(candidate) => candidate.servers.push(server),
+                None => candidates.push(ServerReflexiveCandidate {
+                    address,
+                    base,
+                    servers: vec![server],
+                }),
+            }
+        }
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fast_config, serve_once};
+    use std::net::UdpSocket;
+    use stunne_protocol::MessageBuilder;
+
+    fn reflect(address: &'static str) -> String {
+        serve_once(move |request, _| {
+            MessageBuilder::new(request.success_response())
+                .xor_mapped_address(address.parse().unwrap())
+        })
+    }
+
+    #[test]
+    fn test_gather_srflx() {
+        let first = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let second = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let servers = [
+            reflect("192.0.2.1:1"),
+            reflect("192.0.2.1:1"),
+            reflect("192.0.2.2:2"),
+        ];
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let silent = silent.local_addr().unwrap().to_string();
+        let servers = [
+            servers[0].as_str(),
+            &servers[1],
+            &servers[2],
+            &silent,
+            "[::1]:3478",
+            "stuns:localhost",
+        ];
+
+        let candidates = gather_srflx(&[&first], &servers, &fast_config());
+        let base = first.local_addr().unwrap();
+        assert_eq!(
+            candidates,
+            [
+                ServerReflexiveCandidate {
+                    address: "192.0.2.1:1".parse().unwrap(),
+                    base,
+                    servers: vec![servers[0].to_string(), servers[1].to_string()],
+                },
+                ServerReflexiveCandidate {
+                    address: "192.0.2.2:2".parse().unwrap(),
+                    base,
+                    servers: vec![servers[2].to_string()],
+                },
+            ]
+        );
+        assert_eq!(candidates[0].related_address(), base);
+
+        // The server answers a single request, from either socket.
+        let server = reflect("192.0.2.3:3");
+        let candidates = gather_srflx(&[&first, &second], &[&server], &fast_config());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].address, "192.0.2.3:3".parse().unwrap());
+    }
+
+    #[test]
+    fn test_host_address() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        assert!(gather_srflx(&[&socket], &[&server], &fast_config()).is_empty());
+    }
+}
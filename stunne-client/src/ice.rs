@@ -0,0 +1,693 @@
+use crate::{
+    mapped_address, transaction_response, ClientConfig, ClientError, DatagramTransport,
+    ShortTermAuth, MAX_RESPONSE_BYTES,
+};
+use bytes::{Bytes, BytesMut};
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+use stunne_protocol::attributes::types::{USERNAME, USE_CANDIDATE};
+use stunne_protocol::encodings::{ErrorCode, Utf8Decoder};
+use stunne_protocol::errors::IntegrityError;
+pub use stunne_protocol::IceRole;
+use stunne_protocol::RoleConflict;
+use stunne_protocol::{
+    detect_role_conflict, role_after_conflict, IceCheck, MessageBuilder, MessageClass,
+    MessageHeader, MessageMethod, StunDecoder, StunEncoder, StunTransaction, TransactionConfig,
+    TransactionId,
+};
+
+/// The username fragment and password of an ICE agent, exchanged through signalling.
+#[derive(Clone, PartialEq, Eq)]
+pub struct IceCredentials {
+    pub ufrag: String,
+    pub password: String,
+}
+
+impl IceCredentials {
+    pub fn new(ufrag: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            ufrag: ufrag.into(),
+            password: password.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for IceCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("IceCredentials")
+            .field("ufrag", &self.ufrag)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The local side of connectivity checks: the credentials of both agents, and the role and
+/// tie-breaker of the local agent.
+#[derive(Debug, Clone)]
+pub struct IceAgent {
+    local: IceCredentials,
+    remote: IceCredentials,
+    role: IceRole,
+    tie_breaker: u64,
+}
+
+impl IceAgent {
+    /// Create an agent with the given role and a random tie-breaker.
+    pub fn new(local: IceCredentials, remote: IceCredentials, role: IceRole) -> Self {
+        let tx_id = TransactionId::random();
+        let mut tie_breaker = [0; 8];
+        tie_breaker.copy_from_slice(&tx_id.as_ref()[..8]);
+        Self {
+            local,
+            remote,
+            role,
+            tie_breaker: u64::from_be_bytes(tie_breaker),
+        }
+    }
+
+    /// Sets the tie-breaker sent in the ICE-CONTROLLING or ICE-CONTROLLED attribute.
+    pub fn with_tie_breaker(mut self, tie_breaker: u64) -> Self {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    pub fn local(&self) -> &IceCredentials {
+        &self.local
+    }
+
+    pub fn remote(&self) -> &IceCredentials {
+        &self.remote
+    }
+
+    pub fn role(&self) -> IceRole {
+        self.role
+    }
+
+    pub fn tie_breaker(&self) -> u64 {
+        self.tie_breaker
+    }
+
+    /// Returns the credentials that checks sent to the remote agent are signed with.
+    fn auth(&self) -> ShortTermAuth {
+        ShortTermAuth::ice(&self.local.ufrag, &self.remote.ufrag, &self.remote.password)
+    }
+}
+
+/// Options for [check_connectivity].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IceOptions {
+    /// The retransmission parameters of each check. By default, a check is sent 5 times,
+    /// starting 100 milliseconds apart, so that a pair fails after about 2.3 seconds.
+    pub transaction: TransactionConfig,
+
+    /// Whether the controlling agent nominates every pair that succeeds, by sending
+    /// USE-CANDIDATE in its checks ("aggressive nomination"). By default, no pair is nominated.
+    pub nominate: bool,
+
+    /// How long checks from the remote agent are still answered after all local checks have
+    /// finished, in case the remote agent started later. By default, 500 milliseconds.
+    pub linger: Duration,
+}
+
+impl Default for IceOptions {
+    fn default() -> Self {
+        Self {
+            transaction: TransactionConfig {
+                rto: Duration::from_millis(100),
+                rc: 5,
+                rm: 8,
+            },
+            nominate: false,
+            linger: Duration::from_millis(500),
+        }
+    }
+}
+
+/// A local candidate: a socket (or other [DatagramTransport]) that checks are sent from, with the
+/// priority it is given in the PRIORITY attribute of checks, which is that of a peer-reflexive
+/// candidate for the socket (see [candidate_priority]).
+#[derive(Clone, Copy)]
+pub struct LocalCandidate<'a> {
+    pub socket: &'a (dyn DatagramTransport + Sync),
+    pub priority: u32,
+}
+
+impl std::fmt::Debug for LocalCandidate<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalCandidate")
+            .field("socket", &self.socket.local_addr().ok())
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+/// The result of the connectivity check of a candidate pair.
+#[derive(Debug)]
+pub struct CheckResult {
+    /// The local address of the socket of the local candidate.
+    pub local: SocketAddr,
+
+    /// The address of the remote candidate.
+    pub remote: SocketAddr,
+
+    /// The mapped address of the local candidate, as seen by the remote agent, if the check
+    /// succeeded.
+    pub result: Result<SocketAddr, ClientError>,
+
+    /// Whether the pair succeeded and was nominated, by the local agent if it is controlling, or
+    /// by the remote agent if it is.
+    pub nominated: bool,
+}
+
+/// The type preference of a peer-reflexive candidate recommended by RFC 8445.
+pub const PEER_REFLEXIVE_PREFERENCE: u8 = 110;
+
+/// Computes the priority of a candidate as recommended by RFC 8445 section 5.1.2.1, from the
+/// preference of its type (e.g. [PEER_REFLEXIVE_PREFERENCE]), the preference of its local
+/// address, and its component ID (1 for RTP).
+pub fn candidate_priority(type_preference: u8, local_preference: u16, component: u8) -> u32 {
+    (u32::from(type_preference) << 24)
+        | (u32::from(local_preference) << 8)
+        | (256 - u32::from(component))
+}
+
+/// Runs connectivity checks between every local candidate and every remote candidate of the
+/// same address family, as an ICE agent (RFC 8445) would, returning the result of each pair.
+///
+/// Checks are Binding requests carrying PRIORITY and ICE-CONTROLLING or ICE-CONTROLLED
+/// attributes, signed with the short-term credentials of the remote agent. At the same time, the
+/// checks of the remote agent arriving on the local sockets are answered, if they are signed
+/// with the local credentials, so that the remote agent can run its own checks. Only responses
+/// from the address that a check was sent to are accepted, so a pair only succeeds if the path
+/// works in both directions.
+///
+/// Role conflicts are resolved as described in RFC 8445: a check from a remote agent claiming
+/// the same role either switches the role of the agent or is rejected with a 487 (Role Conflict)
+/// response, depending on the tie-breakers, and a check that is rejected that way switches the
+/// role of the agent and is sent again. The role of `agent` is updated if it was switched.
+///
+/// The checks of all pairs run at the same time, with one thread for each local candidate, and
+/// the function returns once every check has finished and the options' linger time has passed.
+pub fn check_connectivity(
+    agent: &mut IceAgent,
+    local: &[LocalCandidate],
+    remote: &[SocketAddr],
+    config: &ClientConfig,
+) -> Vec<CheckResult> {
+    let shared = Shared {
+        agent: &*agent,
+        role: Mutex::new(agent.role),
+    };
+    let results = thread::scope(|scope| {
+        let threads: Vec<_> = local
+            .iter()
+            .map(|candidate| scope.spawn(|| check_candidate(&shared, candidate, remote, config)))
+            .collect();
+        threads
+            .into_iter()
+            .flat_map(|thread| thread.join().expect("a check thread panicked"))
+            .collect()
+    });
+    let role = shared.role();
+    agent.role = role;
+    results
+}
+
+/// The agent, as shared by the threads of [check_connectivity], whose role may be switched by
+/// any of them.
+struct Shared<'a> {
+    agent: &'a IceAgent,
+    role: Mutex<IceRole>,
+}
+
+impl Shared<'_> {
+    fn role(&self) -> IceRole {
+        *self.role.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Switches the role from `from` to `to`, unless it has already been switched.
+    fn switch_role(&self, from: IceRole, to: IceRole) {
+        let mut role = self.role.lock().unwrap_or_else(PoisonError::into_inner);
+        if *role == from {
+            *role = to;
+        }
+    }
+}
+
+/// The check of a candidate pair.
+struct Check {
+    remote: SocketAddr,
+    transaction: StunTransaction,
+    /// The role that the check was sent in.
+    role: IceRole,
+    /// Whether the check nominates the pair.
+    use_candidate: bool,
+    error: Option<ClientError>,
+    /// Whether the remote agent has nominated the pair.
+    nominated: bool,
+}
+
+impl Check {
+    /// Starts the check of the pair with the given remote candidate, in the current role.
+    fn new(shared: &Shared, remote: SocketAddr, priority: u32, config: &ClientConfig) -> Self {
+        let role = shared.role();
+        let use_candidate = config.ice.nominate && role == IceRole::Controlling;
+        let request = check_request(shared.agent, role, priority, use_candidate);
+        Check {
+            remote,
+            transaction: StunTransaction::new(request, config.ice.transaction, Instant::now())
+                .expect("a check has a valid header"),
+            role,
+            use_candidate,
+            error: None,
+            nominated: false,
+        }
+    }
+}
+
+/// Runs the checks from one local candidate.
+fn check_candidate(
+    shared: &Shared,
+    candidate: &LocalCandidate,
+    remote: &[SocketAddr],
+    config: &ClientConfig,
+) -> Vec<CheckResult> {
+    let socket = candidate.socket;
+    let local = match socket.local_addr() {
+        Ok(local) => local,
+        Err(e) => {
+            return remote
+                .iter()
+                .map(|remote| CheckResult {
+                    local: SocketAddr::from(([0, 0, 0, 0], 0)),
+                    remote: *remote,
+                    result: Err(io::Error::new(e.kind(), e.to_string()).into()),
+                    nominated: false,
+                })
+                .collect()
+        }
+    };
+    let mut checks: Vec<_> = remote
+        .iter()
+        .filter(|remote| remote.is_ipv4() == local.is_ipv4())
+        .map(|remote| Check::new(shared, *remote, candidate.priority, config))
+        .collect();
+
+    if let Err(e) = run_checks(shared, socket, candidate.priority, &mut checks, config) {
+        let kind = e.kind();
+        for check in checks.iter_mut().filter(|check| check.error.is_none()) {
+            check.error = Some(io::Error::new(kind, e.to_string()).into());
+        }
+    }
+
+    checks
+        .into_iter()
+        .map(|check| {
+            let result = match check.error {
+                Some(error) => Err(error),
+                None => transaction_response(&check.transaction)
+                    .and_then(|response| mapped_address(&response)),
+            };
+            let nominated = result.is_ok() && (check.use_candidate || check.nominated);
+            CheckResult {
+                local,
+                remote: check.remote,
+                result,
+                nominated,
+            }
+        })
+        .collect()
+}
+
+/// Sends the checks from a socket and waits for their responses, answering the checks of the
+/// remote agent, until every check has finished and the linger time has passed.
+fn run_checks(
+    shared: &Shared,
+    socket: &dyn DatagramTransport,
+    priority: u32,
+    checks: &mut [Check],
+    config: &ClientConfig,
+) -> io::Result<()> {
+    let auth = shared.agent.auth();
+    let mut linger_until = None;
+    let mut buf = [0; MAX_RESPONSE_BYTES];
+    loop {
+        for check in checks.iter_mut() {
+            while let Some(request) = check.transaction.poll_transmit() {
+                if let Err(e) = socket.send_to(&request, check.remote) {
+                    check.transaction.cancel();
+                    check.error = Some(e.into());
+                }
+            }
+        }
+        let now = Instant::now();
+        for check in checks
+            .iter_mut()
+            .filter(|check| !check.transaction.is_finished())
+        {
+            if let Some(error) = config.aborted(now) {
+                check.transaction.cancel();
+                check.error = Some(error);
+            }
+        }
+        let next_timeout = checks
+            .iter()
+            .filter_map(|check| check.transaction.poll_timeout())
+            .min();
+        if next_timeout.is_none() && linger_until.is_none() {
+            linger_until = Some(now + config.ice.linger);
+        }
+        let timeout = linger_until.or(next_timeout).unwrap_or(now);
+        if timeout <= now {
+            if linger_until.is_some() {
+                return Ok(());
+            }
+            checks
+                .iter_mut()
+                .for_each(|check| check.transaction.handle_timeout(now));
+            continue;
+        }
+
+        socket.set_read_timeout(Some(config.wake_time(timeout, now) - now))?;
+        let (len, source) = match socket.recv_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                let now = Instant::now();
+                checks
+                    .iter_mut()
+                    .for_each(|check| check.transaction.handle_timeout(now));
+                continue;
+            }
+            // ICMP errors from checks sent to candidates that cannot be reached.
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => continue,
+            Err(e) => return Err(e),
+        };
+        let packet = &buf[..len];
+        let Ok(header) = MessageHeader::peek(packet) else {
+            continue;
+        };
+        if header.class == MessageClass::Request && header.method == MessageMethod::BINDING {
+            let Some((response, nominated)) = answer_check(shared, packet, source) else {
+                continue;
+            };
+            socket.send_to(&response, source)?;
+            if nominated {
+                if let Some(check) = checks.iter_mut().find(|check| check.remote == source) {
+                    check.nominated = true;
+                }
+            }
+        } else if auth.accepts(packet) {
+            let Some(check) = checks
+                .iter_mut()
+                .filter(|check| check.remote == source)
+                .find_map(|check| check.transaction.handle_packet(packet).then_some(check))
+            else {
+                continue;
+            };
+            let Ok(response) = StunDecoder::new(packet) else {
+                continue;
+            };
+            // The check is sent again once the role has been switched, by this check or another.
+            if let Some(role) = role_after_conflict(&response, check.role) {
+                shared.switch_role(check.role, role);
+                let nominated = check.nominated;
+                *check = Check::new(shared, check.remote, priority, config);
+                check.nominated = nominated;
+            }
+        }
+    }
+}
+
+/// Encodes a check with the given role and priority, signed with the credentials of the remote
+/// agent.
+fn check_request(agent: &IceAgent, role: IceRole, priority: u32, nominate: bool) -> Bytes {
+    IceCheck {
+        local_ufrag: &agent.local.ufrag,
+        remote_ufrag: &agent.remote.ufrag,
+        remote_password: &agent.remote.password,
+        priority,
+        role,
+        tie_breaker: agent.tie_breaker,
+        use_candidate: nominate,
+    }
+    .to_bytes()
+}
+
+/// Answers a check from the remote agent, returning the response and whether the check
+/// nominated the pair, or `None` if the packet is not a valid request.
+///
+/// As in RFC 8489 section 9.1.3, a check without a USERNAME or MESSAGE-INTEGRITY attribute is
+/// answered with a 400 (Bad Request) response, and a check with an unknown username or a
+/// MESSAGE-INTEGRITY attribute that does not verify with the local password with a 401
+/// (Unauthorized) response; neither response has a MESSAGE-INTEGRITY attribute. A role conflict is resolved by switching the
+/// role of the agent, or by answering with a 487 (Role Conflict) response.
+fn answer_check(shared: &Shared, packet: &[u8], source: SocketAddr) -> Option<(Bytes, bool)> {
+    let agent = shared.agent;
+    let message = StunDecoder::new(packet).ok()?;
+    let header = MessageHeader::peek(packet).ok()?;
+    let username = format!("{}:{}", agent.local.ufrag, agent.remote.ufrag);
+    let error = match (
+        message.get_attribute(USERNAME, &Utf8Decoder),
+        message.verify_integrity(agent.local.password.as_bytes()),
+    ) {
+        (None, _) | (_, Err(IntegrityError::MissingAttribute)) => Some(ErrorCode::BAD_REQUEST),
+        (Some(Ok(name)), Ok(())) if name == username => None,
+        _ => Some(ErrorCode::UNAUTHORIZED),
+    };
+    if let Some(error) = error {
+        let response = MessageBuilder::new(header.error_response())
+            .error_code(error)
+            .to_bytes()
+            .expect("an error response can always be encoded");
+        return Some((response, false));
+    }
+    let role = shared.role();
+    let (response, nominated) = match detect_role_conflict(&message, role, agent.tie_breaker) {
+        Some(RoleConflict::Reject) => {
+            let response = MessageBuilder::new(header.error_response())
+                .error_code(ErrorCode::ROLE_CONFLICT)
+                .to_bytes()
+                .expect("an error response can always be encoded");
+            (response, false)
+        }
+        conflict => {
+            if let Some(RoleConflict::Switch(to)) = conflict {
+                shared.switch_role(role, to);
+            }
+            let response = MessageBuilder::new(header.success_response())
+                .xor_mapped_address(source)
+                .to_bytes()
+                .expect("a Binding response can always be encoded");
+            let nominated = message.get_attribute(USE_CANDIDATE, &Utf8Decoder).is_some();
+            (response, nominated)
+        }
+    };
+    let response = StunEncoder::new(BytesMut::from(&response[..]))
+        .resume()
+        .expect("an encoded message can be resumed")
+        .add_message_integrity(agent.local.password.as_bytes())
+        .finish_with_fingerprint();
+    Some((response, nominated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::UdpSocket;
+    use stunne_protocol::attributes::types::{ERROR_CODE, ICE_CONTROLLED};
+    use stunne_protocol::encodings::ErrorCodeDecoder;
+
+    fn config(nominate: bool) -> ClientConfig {
+        ClientConfig {
+            ice: IceOptions {
+                transaction: TransactionConfig {
+                    rto: Duration::from_millis(20),
+                    rc: 4,
+                    rm: 4,
+                },
+                nominate,
+                linger: Duration::from_millis(200),
+            },
+            ..ClientConfig::default()
+        }
+    }
+
+    fn agents() -> (IceAgent, IceAgent) {
+        let a = IceCredentials::new("aaaa", "a-password-of-22-chars");
+        let b = IceCredentials::new("bbbb", "b-password-of-22-chars");
+        (
+            IceAgent::new(a.clone(), b.clone(), IceRole::Controlling),
+            IceAgent::new(b, a, IceRole::Controlled),
+        )
+    }
+
+    #[test]
+    fn test_check_connectivity() {
+        let (mut a_agent, mut b_agent) = agents();
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        // Nothing answers on this candidate.
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let a_remote = [b.local_addr().unwrap(), silent.local_addr().unwrap()];
+        let b_remote = [a.local_addr().unwrap()];
+        let priority = candidate_priority(PEER_REFLEXIVE_PREFERENCE, 65535, 1);
+
+        let (a_results, b_results) = thread::scope(|scope| {
+            let b_results = scope.spawn(|| {
+                let local = [LocalCandidate {
+                    socket: &b,
+                    priority,
+                }];
+                check_connectivity(&mut b_agent, &local, &b_remote, &config(false))
+            });
+            let local = [LocalCandidate {
+                socket: &a,
+                priority,
+            }];
+            let a_results = check_connectivity(&mut a_agent, &local, &a_remote, &config(true));
+            (a_results, b_results.join().unwrap())
+        });
+
+        assert_eq!(a_results.len(), 2);
+        assert_eq!(a_results[0].remote, b.local_addr().unwrap());
+        assert_eq!(
+            a_results[0].result.as_ref().unwrap(),
+            &a.local_addr().unwrap()
+        );
+        assert!(a_results[0].nominated);
+        assert!(matches!(a_results[1].result, Err(ClientError::TimedOut)));
+        assert!(!a_results[1].nominated);
+
+        assert_eq!(b_results.len(), 1);
+        assert_eq!(
+            b_results[0].result.as_ref().unwrap(),
+            &b.local_addr().unwrap()
+        );
+        assert!(b_results[0].nominated);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let (mut a_agent, _) = agents();
+        let (_, mut b_agent) = agents();
+        b_agent.local.password = "wrong".into();
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_address, b_address) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+
+        let a_results = thread::scope(|scope| {
+            scope.spawn(|| {
+                let local = [LocalCandidate {
+                    socket: &b,
+                    priority: 1,
+                }];
+                check_connectivity(&mut b_agent, &local, &[a_address], &config(false))
+            });
+            let local = [LocalCandidate {
+                socket: &a,
+                priority: 1,
+            }];
+            check_connectivity(&mut a_agent, &local, &[b_address], &config(false))
+        });
+        assert!(matches!(
+            a_results[0].result,
+            Err(ClientError::ErrorResponse { code: 401, .. })
+        ));
+    }
+
+    #[test]
+    fn test_answer_unsigned_check() {
+        let (agent, _) = agents();
+        let shared = Shared {
+            agent: &agent,
+            role: Mutex::new(agent.role),
+        };
+        let source = "127.0.0.1:5000".parse().unwrap();
+        let error_code = |request: &[u8]| {
+            let (response, nominated) = answer_check(&shared, request, source).unwrap();
+            assert!(!nominated);
+            let response = StunDecoder::new(&response).unwrap();
+            let error = response.get_attribute(ERROR_CODE, &ErrorCodeDecoder);
+            error.unwrap().unwrap().code()
+        };
+
+        let unsigned = MessageBuilder::binding_request().to_bytes().unwrap();
+        assert_eq!(error_code(&unsigned), 400);
+        let unsigned = MessageBuilder::binding_request()
+            .username("aaaa:bbbb")
+            .to_bytes()
+            .unwrap();
+        assert_eq!(error_code(&unsigned), 400);
+
+        // A check signed with the credentials of the wrong agent.
+        let wrong_username = check_request(&agent, IceRole::Controlled, 1, false);
+        assert_eq!(error_code(&wrong_username), 401);
+    }
+
+    #[test]
+    fn test_role_conflict() {
+        // Both agents start as controlling; the one with the larger tie-breaker stays so.
+        let (a_agent, b_agent) = agents();
+        let mut a_agent = a_agent.with_tie_breaker(1);
+        let mut b_agent =
+            IceAgent::new(b_agent.local, b_agent.remote, IceRole::Controlling).with_tie_breaker(2);
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_address, b_address) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+
+        let (a_results, b_results) = thread::scope(|scope| {
+            let b_results = scope.spawn(|| {
+                let local = [LocalCandidate {
+                    socket: &b,
+                    priority: 1,
+                }];
+                check_connectivity(&mut b_agent, &local, &[a_address], &config(false))
+            });
+            let local = [LocalCandidate {
+                socket: &a,
+                priority: 1,
+            }];
+            let a_results = check_connectivity(&mut a_agent, &local, &[b_address], &config(false));
+            (a_results, b_results.join().unwrap())
+        });
+        assert!(a_results[0].result.is_ok());
+        assert!(b_results[0].result.is_ok());
+        assert_eq!(a_agent.role(), IceRole::Controlled);
+        assert_eq!(b_agent.role(), IceRole::Controlling);
+    }
+
+    #[test]
+    fn test_check_request() {
+        let (agent, _) = agents();
+        let request = check_request(
+            &agent.with_tie_breaker(42),
+            IceRole::Controlling,
+            1234,
+            true,
+        );
+        let message = StunDecoder::new(&request).unwrap();
+        assert_eq!(message.verify_fingerprint(), Ok(()));
+        assert_eq!(message.verify_integrity(b"b-password-of-22-chars"), Ok(()));
+        let username = message.get_attribute(USERNAME, &Utf8Decoder).unwrap();
+        assert_eq!(username, Ok("bbbb:aaaa"));
+        assert!(message.get_attribute(USE_CANDIDATE, &Utf8Decoder).is_some());
+        assert!(message
+            .get_attribute(ICE_CONTROLLED, &Utf8Decoder)
+            .is_none());
+    }
+
+    #[test]
+    fn test_candidate_priority() {
+        assert_eq!(
+            candidate_priority(PEER_REFLEXIVE_PREFERENCE, 65535, 1),
+            1_862_270_975
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use bytes::Bytes;
+
+/// A hook into the datagrams sent and received by a [StunClient](crate::StunClient), e.g. to add
+/// a vendor attribute to requests, record metrics, or simulate packet loss in tests.
+///
+/// Interceptors are registered with
+/// [with_interceptor](crate::StunClient::with_interceptor), and form a chain: requests pass
+/// through them in the order they were registered, and responses in the reverse order. Each
+/// interceptor sees the datagram as returned by the previous one, and can stop it from going any
+/// further by returning `None`.
+///
+/// ```
+/// use bytes::Bytes;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use stunne_client::Interceptor;
+///
+/// /// Counts the requests sent, including retransmissions.
+/// #[derive(Default)]
+/// struct Transmissions(AtomicUsize);
+///
+/// impl Interceptor for Transmissions {
+///     fn on_request(&self, request: Bytes) -> Option<Bytes> {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///         Some(request)
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Called with every request about to be sent, including retransmissions. Returns the
+    /// datagram to send instead, or `None` to drop it as if it had been lost.
+    ///
+    /// A modified request must keep its transaction ID, or its responses will not be matched to
+    /// it.
+    fn on_request(&self, request: Bytes) -> Option<Bytes> {
+        Some(request)
+    }
+
+    /// Called with every datagram received while waiting for a response, before it is matched
+    /// to the request. Returns the datagram to handle instead, or `None` to discard it.
+    fn on_response(&self, response: Bytes) -> Option<Bytes> {
+        Some(response)
+    }
+}
+
+/// Shares an interceptor with the client, e.g. to read the metrics that it records.
+impl<I: Interceptor + ?Sized> Interceptor for std::sync::Arc<I> {
+    fn on_request(&self, request: Bytes) -> Option<Bytes> {
+        (**self).on_request(request)
+    }
+
+    fn on_response(&self, response: Bytes) -> Option<Bytes> {
+        (**self).on_response(response)
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{AsyncDatagram, ClientError, StunClient, Timer};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use stunne_protocol::{MessageBuilder, MessageMethod};
+
+/// A change of the mapped address reported by [KeepAlive].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressChange {
+    /// The address reported by the previous response, or `None` for the first response.
+    pub previous: Option<SocketAddr>,
+
+    /// The address reported by the latest response.
+    pub current: SocketAddr,
+}
+
+/// Keeps the NAT binding of a socket alive by periodically sending Binding requests (or
+/// indications) to a STUN server, for long-lived sessions such as peer-to-peer connections.
+///
+/// Each call to [tick](KeepAlive::tick) waits until the next keep-alive is due and sends it. With
+/// requests, the mapped address of each response is compared with the previous one, so that a
+/// change of the binding (e.g., after the NAT has rebooted) is reported. Binding indications are
+/// cheaper, as the server does not answer them, but cannot detect a change.
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// # async fn run() -> Result<(), stunne_client::ClientError> {
+/// use std::time::Duration;
+/// use stunne_client::{KeepAlive, StunClient};
+/// use tokio::net::UdpSocket;
+///
+/// let socket = UdpSocket::bind("0.0.0.0:0").await?;
+/// socket.connect("stun.example.org:3478").await?;
+/// let mut keepalive = KeepAlive::new(StunClient::new(socket)).with_interval(Duration::from_secs(20));
+/// loop {
+///     if let Some(change) = keepalive.tick().await? {
+///         println!("Reflexive address: {}", change.current);
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct KeepAlive<S, T> {
+    client: StunClient<S, T>,
+    interval: Duration,
+    indications: bool,
+    next: Instant,
+    address: Option<SocketAddr>,
+}
+
+impl<S: AsyncDatagram, T: Timer> KeepAlive<S, T> {
+    /// The default interval between keep-alives, which is the minimum recommended by RFC 8445 for
+    /// keeping the bindings of ICE candidates alive.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(15);
+
+    /// Create a scheduler that sends Binding requests through `client`, the first one
+    /// immediately, then every [DEFAULT_INTERVAL](Self::DEFAULT_INTERVAL).
+    pub fn new(client: StunClient<S, T>) -> Self {
+        Self {
+            client,
+            interval: Self::DEFAULT_INTERVAL,
+            indications: false,
+            next: Instant::now(),
+            address: None,
+        }
+    }
+
+    /// Sets the interval between keep-alives.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Sends Binding indications instead of requests. No change of the mapped address is
+    /// reported.
+    pub fn with_indications(mut self) -> Self {
+        self.indications = true;
+        self
+    }
+
+    /// Returns the interval between keep-alives.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the mapped address reported by the latest response, if any.
+    pub fn address(&self) -> Option<SocketAddr> {
+        self.address
+    }
+
+    /// Returns when the next keep-alive is due.
+    pub fn next_keepalive(&self) -> Instant {
+        self.next
+    }
+
+    /// Returns the client used to send keep-alives.
+    pub fn client(&self) -> &StunClient<S, T> {
+        &self.client
+    }
+
+    /// Consumes the scheduler, returning the client.
+    pub fn into_client(self) -> StunClient<S, T> {
+        self.client
+    }
+
+    /// Waits until the next keep-alive is due and sends it, returning the change of the mapped
+    /// address if the response reports a different one than before.
+    ///
+    /// The next keep-alive is scheduled an interval after this one is sent, even if it fails
+    /// (e.g., if the request times out), so the caller may keep ticking after an error.
+    pub async fn tick(&mut self) -> Result<Option<AddressChange>, ClientError> {
+        self.client.timer().sleep_until(self.next).await;
+        self.next = Instant::now() + self.interval;
+
+        if self.indications {
+            let indication = MessageBuilder::indication(MessageMethod::BINDING)
+                .to_bytes()
+                .expect("an indication without attributes can always be encoded");
+            self.client.socket().send(&indication).await?;
+            return Ok(None);
+        }
+
+        let current = self.client.binding_request().await?;
+        let previous = self.address.replace(current);
+        Ok((previous != Some(current)).then_some(AddressChange { previous, current }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::async_client::tests::{Immediate, Loopback};
+    use crate::tests::fast_config;
+    use futures_executor::block_on;
+    use std::cell::Cell;
+    use stunne_protocol::{MessageClass, MessageHeader};
+
+    #[test]
+    fn test_address_change() {
+        let addresses = ["192.0.2.1:1000", "192.0.2.1:1000", "192.0.2.1:2000"];
+        let count = Cell::new(0);
+        let socket = Loopback::new(move |request| {
+            let request = MessageHeader::peek(request).unwrap();
+            let address = addresses[count.replace(count.get() + 1)].parse().unwrap();
+            let response = MessageBuilder::new(request.success_response())
+                .xor_mapped_address(address)
+                .to_bytes()
+                .unwrap();
+            Some(response)
+        });
+        let client = StunClient::with_timer(socket, Immediate, fast_config());
+        let mut keepalive = KeepAlive::new(client).with_interval(Duration::from_secs(60));
+
+        let first = "192.0.2.1:1000".parse().unwrap();
+        let change = block_on(keepalive.tick()).unwrap();
+        assert_eq!(
+            change,
+            Some(AddressChange {
+                previous: None,
+                current: first
+            })
+        );
+        assert!(keepalive.next_keepalive() > Instant::now() + Duration::from_secs(59));
+        assert_eq!(block_on(keepalive.tick()).unwrap(), None);
+        let change = block_on(keepalive.tick()).unwrap().unwrap();
+        assert_eq!(change.previous, Some(first));
+        assert_eq!(change.current, "192.0.2.1:2000".parse().unwrap());
+        assert_eq!(keepalive.address(), Some(change.current));
+    }
+
+    #[test]
+    fn test_indications() {
+        let socket = Loopback::new(|_| None);
+        let client = StunClient::with_timer(socket, Immediate, fast_config());
+        let mut keepalive = KeepAlive::new(client).with_indications();
+        assert_eq!(block_on(keepalive.tick()).unwrap(), None);
+        assert_eq!(block_on(keepalive.tick()).unwrap(), None);
+
+        let socket = keepalive.into_client().into_socket();
+        assert_eq!(socket.sent(), 2);
+        let header = MessageHeader::peek(&socket.last_sent()).unwrap();
+        assert_eq!(header.class, MessageClass::Indication);
+        assert_eq!(header.method, MessageMethod::BINDING);
+    }
+}
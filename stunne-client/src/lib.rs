@@ -0,0 +1,590 @@
+//! A client for STUN servers, built on [stunne_protocol].
+//!
+//! The most common use of STUN, discovering the address and port that a NAT has mapped a local
+//! UDP socket to, takes a single call to [get_mapped_address]:
+//!
+//! ```no_run
+//! let address = stunne_client::get_mapped_address("stun.example.org")?;
+//! println!("Reflexive address: {}", address);
+//! # Ok::<(), stunne_client::ClientError>(())
+//! ```
+//!
+//! Requests are retransmitted as described in RFC 5389, using a [StunTransaction]; the
+//! retransmission parameters can be changed through [ClientConfig].
+//!
+//! # Features
+//!
+//! * `tokio`: Implements [AsyncDatagram] for tokio's UDP sockets and provides `TokioTimer`, so
+//!   that [StunClient] can be used with tokio.
+//! * `tls`: Supports `stuns:` servers, using STUN over TLS with rustls.
+
+mod async_client;
+mod auth;
+mod bind;
+mod cancel;
+mod demux;
+mod dual_stack;
+mod error;
+mod fallback;
+mod gather;
+mod ice;
+mod interceptor;
+mod keepalive;
+mod multi;
+mod proxy;
+mod punch;
+mod rtt;
+mod runtime;
+mod tcp;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+mod turn;
+mod turn_refresh;
+mod uri;
+
+pub use async_client::StunClient;
+pub use auth::{LongTermAuth, ShortTermAuth};
+pub use bind::BindOptions;
+pub use cancel::CancellationToken;
+pub use demux::{DemuxSocket, Demuxed};
+pub use dual_stack::{
+    get_mapped_address_dual_stack, AddressFamily, DualStackOptions, DualStackResult,
+};
+pub use error::ClientError;
+pub use fallback::{get_mapped_address_with_fallback, FallbackResult, Transport};
+pub use gather::{gather_srflx, ServerReflexiveCandidate};
+pub use ice::{
+    candidate_priority, check_connectivity, CheckResult, IceAgent, IceCredentials, IceOptions,
+    IceRole, LocalCandidate, PEER_REFLEXIVE_PREFERENCE,
+};
+pub use interceptor::Interceptor;
+pub use keepalive::{AddressChange, KeepAlive};
+pub use multi::{query_servers, query_servers_with_config, QueryReport, ServerResult};
+pub use proxy::{Proxy, ProxyProtocol};
+pub use punch::{punch_hole, PunchOptions};
+pub use rtt::{RttStats, TransactionTiming};
+#[cfg(feature = "tokio")]
+pub use runtime::TokioTimer;
+pub use runtime::{AsyncDatagram, Timer};
+pub use tcp::TcpConfig;
+#[cfg(feature = "tls")]
+pub use tls::TlsConfig;
+pub use transport::DatagramTransport;
+pub use turn::{
+    decode_channel_data, decode_data_indication, encode_channel_data, encode_send_indication,
+    Allocation, TurnClient, CHANNEL_LIFETIME, PERMISSION_LIFETIME,
+};
+pub use turn_refresh::{TurnEvent, TurnRefresher};
+pub use uri::{Scheme, StunUri, UriError};
+
+use bytes::Bytes;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use stunne_protocol::attributes::types::{ERROR_CODE, MAPPED_ADDRESS, XOR_MAPPED_ADDRESS};
+use stunne_protocol::encodings::{ErrorCodeDecoder, MappedAddress, XorMappedAddress};
+use stunne_protocol::{
+    MessageBuilder, MessageClass, StunDecoder, StunTransaction, TransactionConfig,
+    TransactionOutcome,
+};
+use transport::Connected;
+
+/// The port used for STUN over UDP and TCP when the server's address does not give one.
+pub const DEFAULT_PORT: u16 = 3478;
+
+/// The largest response that can be received. Responses are expected to fit in a single
+/// unfragmented datagram.
+const MAX_RESPONSE_BYTES: usize = 1500;
+
+/// How often a blocking request checks its [CancellationToken], which cannot interrupt a socket
+/// that is waiting for a datagram.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Options for the requests made by the client.
+#[derive(Debug, Clone, Default)]
+pub struct ClientConfig {
+    /// The retransmission parameters: the initial timeout, the number of times a request is
+    /// sent, and how long to wait for a response after the last one.
+    pub transaction: TransactionConfig,
+
+    /// The options for the local UDP socket, e.g. its address and port.
+    pub bind: BindOptions,
+
+    /// The options for servers with both IPv4 and IPv6 addresses, used by
+    /// [get_mapped_address_dual_stack].
+    pub dual_stack: DualStackOptions,
+
+    /// The options for STUN over TCP.
+    pub tcp: TcpConfig,
+
+    /// The proxy that TCP and TLS connections to servers are made through, if any.
+    pub proxy: Option<Proxy>,
+
+    /// The options for UDP hole punching, used by [punch_hole].
+    pub punch: PunchOptions,
+
+    /// The options for ICE connectivity checks, used by [check_connectivity].
+    pub ice: IceOptions,
+
+    /// The options for `stuns:` servers.
+    #[cfg(feature = "tls")]
+    pub tls: TlsConfig,
+
+    /// A time after which requests are given up, failing with [ClientError::TimedOut], even if
+    /// their transactions have not timed out yet.
+    pub deadline: Option<Instant>,
+
+    /// A token for giving up requests from elsewhere, which makes them fail with
+    /// [ClientError::Cancelled].
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl ClientConfig {
+    /// Returns the error to give up a request with at the time `now`, if it has been cancelled or
+    /// its deadline has passed.
+    pub(crate) fn aborted(&self, now: Instant) -> Option<ClientError> {
+        if let Some(token) = &self.cancellation {
+            if token.is_cancelled() {
+                return Some(ClientError::Cancelled);
+            }
+        }
+        match self.deadline {
+            Some(deadline) if deadline <= now => Some(ClientError::TimedOut),
+            _ => None,
+        }
+    }
+
+    /// Returns until when a blocking request may wait for a datagram, given the next timeout of
+    /// its transaction: no later than the deadline, and not so long that a cancellation goes
+    /// unnoticed.
+    pub(crate) fn wake_time(&self, timeout: Instant, now: Instant) -> Instant {
+        let mut wake = self
+            .deadline
+            .map_or(timeout, |deadline| deadline.min(timeout));
+        if self.cancellation.is_some() {
+            wake = wake.min(now + CANCELLATION_POLL_INTERVAL);
+        }
+        wake
+    }
+}
+
+/// Returns the address of a local UDP socket as seen by the STUN server at `server`, using the
+/// default [ClientConfig].
+///
+/// The server is given as a host name or IP address, optionally followed by a port (e.g.
+/// `stun.example.org`, `192.0.2.1:3478` or `[2001:db8::1]:3478`); the [DEFAULT_PORT] is used
+/// if none is given. A `stun:` URI (see [StunUri]) is also accepted, as is a `stuns:` URI when the
+/// `tls` feature is enabled. The address is taken from the XOR-MAPPED-ADDRESS attribute of the
+/// response, or from the MAPPED-ADDRESS attribute for servers that only implement RFC 3489.
+pub fn get_mapped_address(server: &str) -> Result<SocketAddr, ClientError> {
+    get_mapped_address_with_config(server, &ClientConfig::default())
+}
+
+/// Returns the address of a local UDP socket as seen by the STUN server at `server`, as with
+/// [get_mapped_address], using the given options.
+pub fn get_mapped_address_with_config(
+    server: &str,
+    config: &ClientConfig,
+) -> Result<SocketAddr, ClientError> {
+    #[cfg(feature = "tls")]
+    if let Some(uri) = parse_uri(server)?.filter(|uri| uri.scheme() == Scheme::Stuns) {
+        return tls::get_mapped_address(&uri, config);
+    }
+
+    query(resolve(server)?, config)
+}
+
+/// Returns the address of a local UDP socket as seen by the STUN server at `server`, as with
+/// [get_mapped_address_with_config], along with when the request was sent and the response
+/// received, e.g. to measure the round-trip time to the server (see [RttStats]).
+///
+/// `stuns:` URIs are not supported.
+///
+/// ```no_run
+/// # use stunne_client::{get_mapped_address_with_timing, ClientConfig};
+/// let (address, timing) =
+///     get_mapped_address_with_timing("stun.example.org", &ClientConfig::default())?;
+/// println!("Reflexive address: {}, RTT: {:?}", address, timing.rtt());
+/// # Ok::<(), stunne_client::ClientError>(())
+/// ```
+pub fn get_mapped_address_with_timing(
+    server: &str,
+    config: &ClientConfig,
+) -> Result<(SocketAddr, TransactionTiming), ClientError> {
+    let mut timing = TransactionTiming::default();
+    let address = query_with_timing(resolve(server)?, config, &mut timing)?;
+    Ok((address, timing))
+}
+
+/// Returns the address of `socket` as seen by the STUN server at `server`, given as for
+/// [get_mapped_address].
+///
+/// Unlike the other functions, which query from a new socket, this queries from a socket of the
+/// caller (or any other [DatagramTransport]), which is not connected to the server, so that it
+/// can then be used with other peers (e.g. for [punch_hole]). Datagrams that arrive from other
+/// addresses while waiting for the response are discarded. `stuns:` URIs are not supported. The [bind](ClientConfig::bind)
+/// options are not used.
+pub fn get_mapped_address_from<T: DatagramTransport + ?Sized>(
+    socket: &T,
+    server: &str,
+    config: &ClientConfig,
+) -> Result<SocketAddr, ClientError> {
+    let server = resolve(server)?;
+    let request = MessageBuilder::binding_request()
+        .to_bytes()
+        .expect("a request without attributes can always be encoded");
+    let response = transact(
+        socket,
+        server,
+        request,
+        config,
+        &mut TransactionTiming::default(),
+    )?;
+    mapped_address(&response)
+}
+
+/// Sends a Binding request to `server` from a new local socket, returning the address of the
+/// socket as seen by the server.
+fn query(server: SocketAddr, config: &ClientConfig) -> Result<SocketAddr, ClientError> {
+    query_with_timing(server, config, &mut TransactionTiming::default())
+}
+
+/// Sends a Binding request as with [query], recording the timing of the transaction.
+fn query_with_timing(
+    server: SocketAddr,
+    config: &ClientConfig,
+    timing: &mut TransactionTiming,
+) -> Result<SocketAddr, ClientError> {
+    let socket = config.bind.bind(server)?;
+    socket.connect(server)?;
+
+    let request = MessageBuilder::binding_request()
+        .to_bytes()
+        .expect("a request without attributes can always be encoded");
+    let socket = Connected {
+        socket: &socket,
+        server,
+    };
+    let response = transact(&socket, server, request, config, timing)?;
+    mapped_address(&response)
+}
+
+/// Resolves the address of a server, given as a `stun:` URI or as a host with an optional port,
+/// adding the default port if none is given.
+fn resolve(server: &str) -> Result<SocketAddr, ClientError> {
+    Ok(resolve_all(server)?[0])
+}
+
+/// Resolves all the addresses of a server, as with [resolve], of which there is at least one.
+fn resolve_all(server: &str) -> Result<Vec<SocketAddr>, ClientError> {
+    let addresses = match parse_uri(server)? {
+        Some(uri) if uri.scheme() != Scheme::Stun => {
+            return Err(ClientError::UnsupportedScheme(uri.scheme()))
+        }
+        Some(uri) => uri.to_socket_addrs(),
+        None => server
+            .to_socket_addrs()
+            .or_else(|_| (server, DEFAULT_PORT).to_socket_addrs()),
+    };
+    let addresses: Vec<_> = addresses.map_err(ClientError::Resolve)?.collect();
+    if addresses.is_empty() {
+        return Err(no_addresses());
+    }
+    Ok(addresses)
+}
+
+/// Parses a server given as a `stun:` or `stuns:` URI, returning `None` if it is not a URI.
+fn parse_uri(server: &str) -> Result<Option<StunUri>, ClientError> {
+    let is_uri = server.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.eq_ignore_ascii_case("stun") || scheme.eq_ignore_ascii_case("stuns")
+    });
+    Ok(if is_uri { Some(server.parse()?) } else { None })
+}
+
+/// Returns the first of the addresses that a server resolved to.
+#[cfg(feature = "tls")]
+fn first_address(
+    addresses: io::Result<impl Iterator<Item = SocketAddr>>,
+) -> Result<SocketAddr, ClientError> {
+    addresses
+        .map_err(ClientError::Resolve)?
+        .next()
+        .ok_or_else(no_addresses)
+}
+
+fn no_addresses() -> ClientError {
+    ClientError::Resolve(io::Error::new(
+        io::ErrorKind::NotFound,
+        "no addresses found",
+    ))
+}
+
+/// Sends the request to `server` over a transport, retransmitting it until a response is
+/// received or the transaction times out, and recording when the request was sent and the
+/// response received.
+fn transact<T: DatagramTransport + ?Sized>(
+    socket: &T,
+    server: SocketAddr,
+    request: Bytes,
+    config: &ClientConfig,
+    timing: &mut TransactionTiming,
+) -> Result<Bytes, ClientError> {
+    let mut transaction = StunTransaction::new(request, config.transaction, Instant::now())?;
+    let mut buf = [0; MAX_RESPONSE_BYTES];
+    loop {
+        while let Some(request) = transaction.poll_transmit() {
+            timing.sent.push(Instant::now());
+            socket.send_to(&request, server)?;
+        }
+        let Some(timeout) = transaction.poll_timeout() else {
+            break;
+        };
+        let now = Instant::now();
+        if let Some(error) = config.aborted(now) {
+            return Err(error);
+        }
+        if timeout <= now {
+            transaction.handle_timeout(now);
+            continue;
+        }
+        socket.set_read_timeout(Some(config.wake_time(timeout, now) - now))?;
+        // Datagrams from elsewhere are discarded, as if they were empty.
+        let received = socket
+            .recv_from(&mut buf)
+            .map(|(len, source)| if source == server { len } else { 0 });
+        match received {
+            Ok(len) => {
+                if transaction.handle_packet(&buf[..len]) {
+                    timing.received = Some(Instant::now());
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                transaction.handle_timeout(Instant::now())
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    transaction_response(&transaction)
+}
+
+/// Returns the response received by a finished transaction.
+pub(crate) fn transaction_response(transaction: &StunTransaction) -> Result<Bytes, ClientError> {
+    match transaction.outcome() {
+        Some(TransactionOutcome::Response(response)) => Ok(response.clone()),
+        _ => Err(ClientError::TimedOut),
+    }
+}
+
+/// Decodes a response, returning an [ErrorResponse](ClientError::ErrorResponse) error if it is
+/// an error response.
+pub(crate) fn success_response(response: &[u8]) -> Result<StunDecoder<'_>, ClientError> {
+    let message = StunDecoder::new(response)?;
+    if message.class() == MessageClass::ErrorResponse {
+        let (code, reason) = match message.get_attribute(ERROR_CODE, &ErrorCodeDecoder) {
+            Some(Ok(error)) => (error.code(), error.reason().to_string()),
+            _ => (0, String::new()),
+        };
+        return Err(ClientError::ErrorResponse { code, reason });
+    }
+    Ok(message)
+}
+
+/// Reads the mapped address from a Binding response.
+pub(crate) fn mapped_address(response: &[u8]) -> Result<SocketAddr, ClientError> {
+    let message = success_response(response)?;
+    let decoder = XorMappedAddress::decoder(message.tx_id());
+    if let Some(Ok(address)) = message.get_attribute(XOR_MAPPED_ADDRESS, &decoder) {
+        return Ok(address);
+    }
+    match message.get_attribute(MAPPED_ADDRESS, &MappedAddress::decoder()) {
+        Some(Ok(address)) => Ok(address),
+        _ => Err(ClientError::MissingAddress),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, UdpSocket};
+    use std::thread;
+    use std::time::Duration;
+    use stunne_protocol::encodings::ErrorCode;
+    use stunne_protocol::MessageHeader;
+
+    /// Starts a server that answers a single request with the response built by `respond`,
+    /// returning its address.
+    pub(crate) fn serve_once(
+        respond: impl FnOnce(MessageHeader, SocketAddr) -> MessageBuilder + Send + 'static,
+    ) -> String {
+        serve_once_on("127.0.0.1:0", respond)
+    }
+
+    /// Starts a server as with [serve_once], bound to `local`.
+    pub(crate) fn serve_once_on(
+        local: &str,
+        respond: impl FnOnce(MessageHeader, SocketAddr) -> MessageBuilder + Send + 'static,
+    ) -> String {
+        let socket = UdpSocket::bind(local).unwrap();
+        let address = socket.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let mut buf = [0; MAX_RESPONSE_BYTES];
+            let (len, peer) = socket.recv_from(&mut buf).unwrap();
+            let request = MessageHeader::peek(&buf[..len]).unwrap();
+            let response = respond(request, peer).to_bytes().unwrap();
+            socket.send_to(&response, peer).unwrap();
+        });
+        address
+    }
+
+    // The other fields depend on the enabled features.
+    #[allow(clippy::needless_update)]
+    pub(crate) fn fast_config() -> ClientConfig {
+        ClientConfig {
+            transaction: TransactionConfig {
+                rto: Duration::from_millis(20),
+                rc: 2,
+                rm: 2,
+            },
+            ..ClientConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_xor_mapped_address() {
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response())
+                .mapped_address("192.0.2.1:1".parse().unwrap())
+                .xor_mapped_address(peer)
+        });
+        let address = get_mapped_address(&server).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn test_mapped_address_from() {
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        // A datagram from elsewhere is discarded.
+        let other = UdpSocket::bind("127.0.0.1:0").unwrap();
+        other
+            .send_to(b"noise", socket.local_addr().unwrap())
+            .unwrap();
+        let address = get_mapped_address_from(&socket, &server, &fast_config()).unwrap();
+        assert_eq!(address, socket.local_addr().unwrap());
+    }
+
+    #[test]
+    fn test_timing() {
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let (address, timing) = get_mapped_address_with_timing(&server, &fast_config()).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+        assert_eq!(timing.sent.len(), 1);
+        assert_eq!(timing.answered(), Some(0));
+        assert!(timing.rtt().is_some());
+    }
+
+    #[test]
+    fn test_mapped_address_fallback() {
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).mapped_address(peer)
+        });
+        let address = get_mapped_address(&server).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+
+        let server = serve_once(|request, _| MessageBuilder::new(request.success_response()));
+        assert!(matches!(
+            get_mapped_address(&server),
+            Err(ClientError::MissingAddress)
+        ));
+    }
+
+    #[test]
+    fn test_error_response() {
+        let server = serve_once(|request, _| {
+            MessageBuilder::new(request.error_response()).error_code(ErrorCode::BAD_REQUEST)
+        });
+        match get_mapped_address(&server) {
+            Err(ClientError::ErrorResponse { code, reason }) => {
+                assert_eq!(code, 400);
+                assert_eq!(reason, "Bad Request");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_timeout() {
+        // A socket that never answers.
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = silent.local_addr().unwrap().to_string();
+        let result = get_mapped_address_with_config(&server, &fast_config());
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+
+    #[test]
+    fn test_deadline() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = silent.local_addr().unwrap().to_string();
+        let start = Instant::now();
+        let config = ClientConfig {
+            deadline: Some(start + Duration::from_millis(50)),
+            ..ClientConfig::default()
+        };
+        let result = get_mapped_address_with_config(&server, &config);
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+        assert!(start.elapsed() < config.transaction.rto);
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server = silent.local_addr().unwrap().to_string();
+        let token = CancellationToken::new();
+        let config = ClientConfig {
+            cancellation: Some(token.clone()),
+            ..ClientConfig::default()
+        };
+        let start = Instant::now();
+        let query = thread::spawn(move || get_mapped_address_with_config(&server, &config));
+        thread::sleep(Duration::from_millis(20));
+        token.cancel();
+        let result = query.join().unwrap();
+        assert!(matches!(result, Err(ClientError::Cancelled)));
+        assert!(start.elapsed() < ClientConfig::default().transaction.rto);
+    }
+
+    #[test]
+    fn test_resolve() {
+        assert_eq!(
+            resolve("127.0.0.1").unwrap(),
+            "127.0.0.1:3478".parse().unwrap()
+        );
+        assert_eq!(resolve("::1").unwrap(), "[::1]:3478".parse().unwrap());
+        assert_eq!(
+            resolve("[::1]:1234").unwrap(),
+            "[::1]:1234".parse().unwrap()
+        );
+        assert_eq!(
+            resolve("stun:[::1]:1234").unwrap(),
+            "[::1]:1234".parse().unwrap()
+        );
+        assert!(matches!(
+            resolve("stun:[::1"),
+            Err(ClientError::Uri(UriError::InvalidHost))
+        ));
+        assert!(matches!(
+            resolve("stuns:[::1]"),
+            Err(ClientError::UnsupportedScheme(Scheme::Stuns))
+        ));
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{
+    mapped_address, resolve, ClientConfig, ClientError, DatagramTransport, MAX_RESPONSE_BYTES,
+};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::thread;
+use std::time::{Duration, Instant};
+use stunne_protocol::{
+    MessageBuilder, ResponseMatch, TransactionId, TransactionManager, TransactionOutcome,
+};
+
+/// The result of querying one of the servers given to [query_servers].
+#[derive(Debug)]
+pub struct ServerResult {
+    /// The server, as given to [query_servers].
+    pub server: String,
+
+    /// The address of the local socket as seen by the server, or the reason it is unknown.
+    pub mapped_address: Result<SocketAddr, ClientError>,
+
+    /// The round-trip time of the request, if the response was received without any
+    /// retransmission. As in Karn's algorithm, a response to a retransmitted request is not
+    /// measured, as it cannot be told which transmission it answers.
+    pub rtt: Option<Duration>,
+}
+
+/// The results of [query_servers], in the order the servers were given.
+#[derive(Debug)]
+pub struct QueryReport {
+    pub results: Vec<ServerResult>,
+}
+
+impl QueryReport {
+    /// Returns the distinct mapped addresses reported by the servers, in the order they were
+    /// first reported.
+    pub fn mapped_addresses(&self) -> Vec<SocketAddr> {
+        let mut addresses = Vec::new();
+        for result in &self.results {
+            if let Ok(address) = result.mapped_address {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+        addresses
+    }
+
+    /// Returns true if servers of the same address family reported different mapped addresses
+    /// for the same local socket.
+    ///
+    /// This is a strong hint that the NAT is symmetric (i.e., that it maps the socket to a
+    /// different address for each destination), or that an application-level gateway rewrites
+    /// the responses.
+    pub fn has_disagreement(&self) -> bool {
+        let addresses = self.mapped_addresses();
+        let count = |ipv4| addresses.iter().filter(|a| a.is_ipv4() == ipv4).count();
+        count(true) > 1 || count(false) > 1
+    }
+}
+
+/// Sends Binding requests to several servers at once, from the same local socket, using the
+/// default [ClientConfig].
+///
+/// Servers are given as for [get_mapped_address](crate::get_mapped_address). One socket is used
+/// for the IPv4 servers and another for the IPv6 servers, and their requests are all in flight
+/// at the same time, so the whole query takes no longer than a single request. A failure to
+/// resolve or query one server is reported in its [ServerResult], not as an error of the query.
+///
+/// ```no_run
+/// let report = stunne_client::query_servers(&["stun.example.org", "stun.example.net"]);
+/// for result in &report.results {
+///     println!("{}: {:?} ({:?})", result.server, result.mapped_address, result.rtt);
+/// }
+/// if report.has_disagreement() {
+///     println!("The NAT is probably symmetric");
+/// }
+/// ```
+pub fn query_servers(servers: &[&str]) -> QueryReport {
+    query_servers_with_config(servers, &ClientConfig::default())
+}
+
+/// Sends Binding requests to several servers at once, as with [query_servers], using the given
+/// options.
+///
+/// The local sockets are created with the [BindOptions](crate::BindOptions) of `config`; if one
+/// cannot be created, each server of its address family gives an [Io](ClientError::Io) result.
+/// `stuns:` servers are not supported, and give [UnsupportedScheme](ClientError::UnsupportedScheme)
+/// results.
+pub fn query_servers_with_config(servers: &[&str], config: &ClientConfig) -> QueryReport {
+    let mut results: Vec<_> = servers
+        .iter()
+        .map(|server| (resolve(server), None))
+        .collect();
+    let (ipv4, ipv6): (Vec<_>, Vec<_>) = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (result, _))| Some((i, *result.as_ref().ok()?)))
+        .partition(|(_, server)| server.is_ipv4());
+
+    let (ipv4_query, ipv6_query) = thread::scope(|scope| {
+        let ipv4_query = scope.spawn(|| query_family(&ipv4, config));
+        let ipv6_query = query_family(&ipv6, config);
+        let ipv4_query = ipv4_query.join().expect("the IPv4 query does not panic");
+        (ipv4_query, ipv6_query)
+    });
+    for (servers, query) in [(ipv4, ipv4_query), (ipv6, ipv6_query)] {
+        match query {
+            Ok(query) => {
+                for (i, result, rtt) in query {
+                    results[i] = (result, rtt);
+                }
+            }
+            // The socket could not be created, e.g. because of the BindOptions.
+            Err(e) => {
+                for (i, _) in servers {
+                    let error = io::Error::new(e.kind(), e.to_string());
+                    results[i] = (Err(ClientError::Io(error)), None);
+                }
+            }
+        }
+    }
+
+    let results = servers
+        .iter()
+        .zip(results)
+        .map(|(server, (mapped_address, rtt))| ServerResult {
+            server: server.to_string(),
+            mapped_address,
+            rtt,
+        })
+        .collect();
+    QueryReport { results }
+}
+
+pub(crate) type FamilyResult = (usize, Result<SocketAddr, ClientError>, Option<Duration>);
+
+/// Queries servers of the same address family from a single socket, returning the result for
+/// each server along with its index.
+fn query_family(
+    servers: &[(usize, SocketAddr)],
+    config: &ClientConfig,
+) -> io::Result<Vec<FamilyResult>> {
+    let Some(&(_, first)) = servers.first() else {
+        return Ok(Vec::new());
+    };
+    let socket = config.bind.bind(first)?;
+    query_from(&socket, servers, config)
+}
+
+/// Queries servers from `socket` at once, returning the result for each server along with its
+/// index.
+pub(crate) fn query_from<T: DatagramTransport + ?Sized>(
+    socket: &T,
+    servers: &[(usize, SocketAddr)],
+    config: &ClientConfig,
+) -> io::Result<Vec<FamilyResult>> {
+    let mut manager = TransactionManager::new(config.transaction);
+    let start = Instant::now();
+    let mut pending: HashMap<TransactionId, (usize, Option<Instant>)> = HashMap::new();
+    for &(i, server) in servers {
+        let request = MessageBuilder::binding_request()
+            .to_bytes()
+            .expect("a request without attributes can always be encoded");
+        let tx_id = manager
+            .start(server, request, start)
+            .expect("a request with a random transaction ID can always be started");
+        pending.insert(tx_id, (i, None));
+    }
+
+    let mut results = Vec::with_capacity(pending.len());
+    let mut buf = [0; MAX_RESPONSE_BYTES];
+    loop {
+        while let Some((server, request)) = manager.poll_transmit() {
+            // A server that cannot be reached is reported when its transaction times out.
+            let _ = socket.send_to(&request, server);
+        }
+        while let Some((tx_id, outcome)) = manager.poll_outcome() {
+            let (i, received) = pending
+                .remove(&tx_id)
+                .expect("every transaction is pending");
+            let (result, rtt) = match outcome {
+                TransactionOutcome::Response(response) => {
+                    (mapped_address(&response), received.map(|at| at - start))
+                }
+                _ => (Err(ClientError::TimedOut), None),
+            };
+            results.push((i, result, rtt));
+        }
+        let Some(timeout) = manager.poll_timeout() else {
+            break;
+        };
+        let now = Instant::now();
+        if config.aborted(now).is_some() {
+            for (_, (i, _)) in pending.drain() {
+                let error = config.aborted(now).expect("the query has been given up");
+                results.push((i, Err(error), None));
+            }
+            break;
+        }
+        if timeout <= now {
+            manager.handle_timeout(now);
+            continue;
+        }
+        socket.set_read_timeout(Some(config.wake_time(timeout, now) - now))?;
+        match socket.recv_from(&mut buf) {
+            Ok((len, source)) => {
+                let now = Instant::now();
+                if let ResponseMatch::Matched(tx_id) = manager.handle_packet(source, &buf[..len]) {
+                    let sent_once = manager
+                        .get(&tx_id)
+                        .is_some_and(|transaction| transaction.transmissions() == 1);
+                    if let Some((_, received)) = pending.get_mut(&tx_id) {
+                        *received = Some(now).filter(|_| sent_once);
+                    }
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                manager.handle_timeout(Instant::now())
+            }
+            // e.g. an ICMP error for one of the servers, which must not end the other queries.
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fast_config, serve_once};
+    use crate::BindOptions;
+    use std::net::UdpSocket;
+
+    #[test]
+    fn test_query_servers() {
+        let first = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let second = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let silent = silent.local_addr().unwrap().to_string();
+        let servers = [first.as_str(), &second, &silent, "stuns:localhost"];
+        let report = query_servers_with_config(&servers, &fast_config());
+
+        let results = &report.results;
+        assert_eq!(results[0].server, first);
+        let address = *results[0].mapped_address.as_ref().unwrap();
+        assert!(results[0].rtt.is_some());
+        assert_eq!(results[1].mapped_address.as_ref().unwrap(), &address);
+        assert!(matches!(
+            results[2].mapped_address,
+            Err(ClientError::TimedOut)
+        ));
+        assert_eq!(results[2].rtt, None);
+        assert!(matches!(
+            results[3].mapped_address,
+            Err(ClientError::UnsupportedScheme(_))
+        ));
+        assert_eq!(report.mapped_addresses(), [address]);
+        assert!(!report.has_disagreement());
+    }
+
+    #[test]
+    fn test_bind_error() {
+        let config = ClientConfig {
+            bind: BindOptions {
+                local_address: Some("[::1]:0".parse().unwrap()),
+                ..BindOptions::default()
+            },
+            ..fast_config()
+        };
+        let report = query_servers_with_config(&["127.0.0.1:3478"], &config);
+        assert!(matches!(
+            report.results[0].mapped_address,
+            Err(ClientError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_disagreement() {
+        let servers = ["192.0.2.1:1", "192.0.2.1:2", "[2001:db8::1]:1"];
+        let report = QueryReport {
+            results: servers
+                .iter()
+                .map(|address| ServerResult {
+                    server: String::new(),
+                    mapped_address: Ok(address.parse().unwrap()),
+                    rtt: None,
+                })
+                .collect(),
+        };
+        assert!(report.has_disagreement());
+        assert_eq!(report.mapped_addresses().len(), 3);
+        let report = QueryReport {
+            results: report.results.into_iter().skip(1).collect(),
+        };
+        assert!(!report.has_disagreement());
+    }
+}
@@ -0,0 +1,386 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+
+/// The protocol spoken with a [Proxy].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ProxyProtocol {
+    /// SOCKS version 5, as described in RFC 1928.
+    Socks5,
+
+    /// HTTP, through which connections are tunnelled with CONNECT requests.
+    HttpConnect,
+}
+
+/// A proxy that connections to STUN servers over TCP and TLS are made through, for networks that
+/// only allow proxied egress. UDP requests are never proxied.
+///
+/// Connections are made to the address that the server resolved to locally; the proxy is not
+/// asked to resolve host names.
+///
+/// ```no_run
+/// # use stunne_client::{get_mapped_address_with_fallback, ClientConfig, Proxy};
+/// let config = ClientConfig {
+///     proxy: Some(Proxy::http_connect("10.0.0.1:3128".parse().unwrap())),
+///     ..ClientConfig::default()
+/// };
+/// let result = get_mapped_address_with_fallback("stun.example.org", &config)?;
+/// # Ok::<(), stunne_client::ClientError>(())
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct Proxy {
+    protocol: ProxyProtocol,
+    address: SocketAddr,
+    credentials: Option<(String, String)>,
+}
+
+impl Proxy {
+    /// Create a SOCKS5 proxy at `address`, used without authentication.
+    pub fn socks5(address: SocketAddr) -> Self {
+        Self {
+            protocol: ProxyProtocol::Socks5,
+            address,
+            credentials: None,
+        }
+    }
+
+    /// Create an HTTP proxy at `address`, used without authentication.
+    pub fn http_connect(address: SocketAddr) -> Self {
+        Self {
+            protocol: ProxyProtocol::HttpConnect,
+            address,
+            credentials: None,
+        }
+    }
+
+    /// Authenticates with the proxy by a username and password: the username/password method of
+    /// RFC 1929 for SOCKS5, or the Basic scheme for HTTP.
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    pub fn protocol(&self) -> ProxyProtocol {
+        self.protocol
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Opens a TCP connection to `target` through the proxy. The connection to the proxy, and each
+    /// read and write of the handshake, time out after `timeout`.
+    pub(crate) fn connect(&self, target: SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect_timeout(&self.address, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        match self.protocol {
+            ProxyProtocol::Socks5 => self.socks5_handshake(&mut stream, target)?,
+            ProxyProtocol::HttpConnect => self.http_handshake(&mut stream, target)?,
+        }
+        Ok(stream)
+    }
+
+    fn socks5_handshake(&self, stream: &mut TcpStream, target: SocketAddr) -> io::Result<()> {
+        const NO_AUTHENTICATION: u8 = 0x00;
+        const USERNAME_PASSWORD: u8 = 0x02;
+
+        let method = match self.credentials {
+            Some(_) => USERNAME_PASSWORD,
+            None => NO_AUTHENTICATION,
+        };
+        stream.write_all(&[5, 1, method])?;
+        let mut reply = [0; 2];
+        stream.read_exact(&mut reply)?;
+        if reply[0] != 5 {
+            return Err(invalid_reply("not a SOCKS5 proxy"));
+        }
+        if reply[1] != method {
+            return Err(refused("SOCKS5 proxy rejected the authentication method"));
+        }
+        if let Some((username, password)) = &self.credentials {
+            let mut request = vec![1];
+            for field in [username, password] {
+                let len = u8::try_from(field.len()).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "SOCKS5 credentials are longer than 255 bytes",
+                    )
+                })?;
+                request.push(len);
+                request.extend_from_slice(field.as_bytes());
+            }
+            stream.write_all(&request)?;
+            stream.read_exact(&mut reply)?;
+            // The version of the username/password subnegotiation (RFC 1929).
+            if reply[0] != 1 {
+                return Err(invalid_reply("invalid SOCKS5 authentication reply"));
+            }
+            if reply[1] != 0 {
+                return Err(refused("SOCKS5 proxy rejected the credentials"));
+            }
+        }
+
+        // A CONNECT request.
+        let mut request = vec![5, 1, 0];
+        match target {
+            SocketAddr::V4(target) => {
+                request.push(1);
+                request.extend_from_slice(&target.ip().octets());
+            }
+            SocketAddr::V6(target) => {
+                request.push(4);
+                request.extend_from_slice(&target.ip().octets());
+            }
+        }
+        request.extend_from_slice(&target.port().to_be_bytes());
+        stream.write_all(&request)?;
+
+        let mut reply = [0; 4];
+        stream.read_exact(&mut reply)?;
+        if reply[1] != 0 {
+            return Err(refused(match reply[1] {
+                2 => "SOCKS5 proxy does not allow the connection",
+                3 => "SOCKS5 proxy reports the network is unreachable",
+                4 => "SOCKS5 proxy reports the host is unreachable",
+                5 => "SOCKS5 proxy reports the connection was refused",
+                _ => "SOCKS5 proxy failed to connect",
+            }));
+        }
+        // The address that the proxy bound, which is not needed, and its port.
+        let len = match reply[3] {
+            1 => 4,
+            4 => 16,
+            3 => {
+                let mut len = [0];
+                stream.read_exact(&mut len)?;
+                len[0] as usize
+            }
+            _ => return Err(invalid_reply("invalid SOCKS5 address type")),
+        };
+        stream.read_exact(&mut vec![0; len + 2])
+    }
+
+    fn http_handshake(&self, stream: &mut TcpStream, target: SocketAddr) -> io::Result<()> {
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+        if let Some((username, password)) = &self.credentials {
+            let credentials = base64(format!("{}:{}", username, password).as_bytes());
+            request += &format!("Proxy-Authorization: Basic {}\r\n", credentials);
+        }
+        request += "\r\n";
+        stream.write_all(request.as_bytes())?;
+
+        // The response is read a byte at a time, so that nothing after its header is consumed.
+        let mut header = Vec::new();
+        while !header.ends_with(b"\r\n\r\n") {
+            if header.len() >= 8192 {
+                return Err(invalid_reply("HTTP proxy response header is too long"));
+            }
+            let mut byte = [0];
+            stream.read_exact(&mut byte)?;
+            header.push(byte[0]);
+        }
+        let status = std::str::from_utf8(&header)
+            .ok()
+            .and_then(|header| header.split("\r\n").next())
+            .and_then(|line| line.strip_prefix("HTTP/1."))
+            .and_then(|line| line.split(' ').nth(1))
+            .ok_or_else(|| invalid_reply("invalid HTTP proxy response"))?;
+        match status {
+            _ if status.starts_with('2') => Ok(()),
+            "407" => Err(refused("HTTP proxy requires authentication")),
+            _ => Err(refused(&format!("HTTP proxy returned status {}", status))),
+        }
+    }
+}
+
+impl fmt::Debug for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Proxy")
+            .field("protocol", &self.protocol)
+            .field("address", &self.address)
+            .field(
+                "username",
+                &self.credentials.as_ref().map(|(username, _)| username),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+fn refused(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::ConnectionRefused, message)
+}
+
+fn invalid_reply(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Encodes bytes with the standard, padded base64 alphabet.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tcp::{self, tests::serve_once};
+    use crate::{ClientConfig, ClientError};
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::thread;
+    use stunne_protocol::MessageBuilder;
+
+    /// Starts a proxy that accepts a single connection, performs the handshake with `handshake`,
+    /// which returns the target it was asked for (or None to refuse), and then relays the
+    /// connection to the target.
+    fn proxy(
+        handshake: impl FnOnce(&mut TcpStream) -> Option<SocketAddr> + Send + 'static,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut client, _) = listener.accept().unwrap();
+            let Some(target) = handshake(&mut client) else {
+                return;
+            };
+            let mut server = TcpStream::connect(target).unwrap();
+            let (mut client_reader, mut server_writer) =
+                (client.try_clone().unwrap(), server.try_clone().unwrap());
+            thread::spawn(move || io::copy(&mut client_reader, &mut server_writer));
+            let _ = io::copy(&mut server, &mut client);
+        });
+        address
+    }
+
+    fn server() -> SocketAddr {
+        serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        })
+    }
+
+    fn config(proxy: Proxy) -> ClientConfig {
+        ClientConfig {
+            proxy: Some(proxy),
+            ..ClientConfig::default()
+        }
+    }
+
+    fn read_http_header(stream: &mut TcpStream) -> String {
+        let mut header = Vec::new();
+        while !header.ends_with(b"\r\n\r\n") {
+            let mut byte = [0];
+            stream.read_exact(&mut byte).unwrap();
+            header.push(byte[0]);
+        }
+        String::from_utf8(header).unwrap()
+    }
+
+    #[test]
+    fn test_socks5() {
+        let proxy = proxy(|stream| {
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 1, 2]);
+            stream.write_all(&[5, 2]).unwrap();
+            let mut auth = [0; 12];
+            stream.read_exact(&mut auth).unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x05hello");
+            stream.write_all(&[1, 0]).unwrap();
+
+            let mut request = [0; 10];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(request[..4], [5, 1, 0, 1]);
+            let ip = Ipv4Addr::new(request[4], request[5], request[6], request[7]);
+            let port = u16::from_be_bytes([request[8], request[9]]);
+            stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+            Some(SocketAddr::from((ip, port)))
+        });
+        let config = config(Proxy::socks5(proxy).with_credentials("user", "hello"));
+        let address = tcp::get_mapped_address(server(), &config).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn test_socks5_invalid_auth_reply() {
+        let proxy = proxy(|stream| {
+            let mut greeting = [0; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[5, 2]).unwrap();
+            let mut auth = [0; 12];
+            stream.read_exact(&mut auth).unwrap();
+            // A success status, but with the SOCKS version rather than the subnegotiation's.
+            stream.write_all(&[5, 0]).unwrap();
+            None
+        });
+        let config = config(Proxy::socks5(proxy).with_credentials("user", "hello"));
+        let result = tcp::get_mapped_address(server(), &config);
+        match result {
+            Err(ClientError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::InvalidData),
+            _ => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_http_connect() {
+        let proxy = proxy(|stream| {
+            let header = read_http_header(stream);
+            let target = header
+                .strip_prefix("CONNECT ")
+                .and_then(|rest| rest.split(' ').next())
+                .unwrap();
+            assert!(header.contains("\r\nProxy-Authorization: Basic dXNlcjpoZWxsbw==\r\n"));
+            stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .unwrap();
+            Some(target.parse().unwrap())
+        });
+        let config = config(Proxy::http_connect(proxy).with_credentials("user", "hello"));
+        let address = tcp::get_mapped_address(server(), &config).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn test_http_refused() {
+        let proxy = proxy(|stream| {
+            read_http_header(stream);
+            stream
+                .write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n")
+                .unwrap();
+            None
+        });
+        let config = config(Proxy::http_connect(proxy));
+        let result = tcp::get_mapped_address(server(), &config);
+        match result {
+            Err(ClientError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::ConnectionRefused),
+            _ => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"user:hello"), "dXNlcjpoZWxsbw==");
+    }
+}
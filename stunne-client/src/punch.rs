@@ -0,0 +1,237 @@
+use crate::{mapped_address, transaction_response, ClientConfig, ClientError, MAX_RESPONSE_BYTES};
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+use stunne_protocol::{
+    is_stun, MessageBuilder, MessageClass, MessageHeader, MessageMethod, StunTransaction,
+    TransactionConfig,
+};
+
+/// Options for [punch_hole].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PunchOptions {
+    /// The retransmission parameters of the Binding request sent to the peer. By default, the
+    /// request is sent 6 times, starting 100 milliseconds apart, so that the peer is given about
+    /// 4 seconds to start punching too.
+    pub transaction: TransactionConfig,
+
+    /// The number of copies of the request that are sent at each transmission, so that the
+    /// packets dropped while the NAT of the peer has no binding for this socket yet, or lost on
+    /// the way, are less likely to delay the exchange. By default, 3.
+    pub burst: u32,
+
+    /// How long requests from the peer are still answered after both directions have been
+    /// confirmed, in case the last response to the peer was lost. Lingering ends early if the
+    /// peer sends anything other than STUN. By default, 500 milliseconds.
+    pub linger: Duration,
+}
+
+impl Default for PunchOptions {
+    fn default() -> Self {
+        Self {
+            transaction: TransactionConfig {
+                rto: Duration::from_millis(100),
+                rc: 6,
+                rm: 8,
+            },
+            burst: 3,
+            linger: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Opens a path through the NATs between `socket` and a peer by simultaneous open, returning the
+/// socket connected to the peer.
+///
+/// Each peer learns its server-reflexive address (e.g. with
+/// [get_mapped_address_from](crate::get_mapped_address_from) on the socket it will punch from),
+/// passes it to the other peer through some rendezvous channel, and then calls this function at
+/// about the same time with the address of the other. Each side sends bursts of Binding requests
+/// to the other, whose first packets open a binding in the local NAT for the responses, and
+/// answers the requests of the other. Once a response to its own request has been received (so
+/// packets reach the peer) and a request of the peer has been answered (so packets arrive from
+/// the peer), the path is open in both directions.
+///
+/// Anything other than STUN that arrives from the peer (e.g. the first datagram of the
+/// application, which the peer sends once it is done) also shows that the path is open, and is
+/// left on the returned socket to be received by the caller.
+///
+/// Packets are only accepted from `peer` itself, so a peer behind a NAT that maps each destination
+/// to a different port (a "symmetric" NAT) cannot be reached this way. The function fails with
+/// [ClientError::TimedOut] if the path could not be opened before the request to the peer timed
+/// out (see [PunchOptions]).
+pub fn punch_hole(
+    socket: UdpSocket,
+    peer: SocketAddr,
+    config: &ClientConfig,
+) -> Result<UdpSocket, ClientError> {
+    let options = &config.punch;
+    let request = MessageBuilder::binding_request()
+        .to_bytes()
+        .expect("a request without attributes can always be encoded");
+    let start = Instant::now();
+    let mut transaction = StunTransaction::new(request, options.transaction, start)?;
+    // How long the peer keeps punching, if it started at the same time.
+    let give_up = start + transaction_duration(options.transaction);
+    let mut answered = false;
+    let mut linger_until = None;
+    let mut buf = [0; MAX_RESPONSE_BYTES];
+    loop {
+        while let Some(request) = transaction.poll_transmit() {
+            for _ in 0..options.burst.max(1) {
+                socket.send_to(&request, peer)?;
+            }
+        }
+        if linger_until.is_none() && answered && transaction.is_finished() {
+            mapped_address(&transaction_response(&transaction)?)?;
+            linger_until = Some(Instant::now() + options.linger);
+        }
+
+        let now = Instant::now();
+        if let Some(error) = config.aborted(now) {
+            return Err(error);
+        }
+        let timeout = match (linger_until, transaction.poll_timeout()) {
+            (Some(linger_until), _) => linger_until,
+            (None, Some(timeout)) => timeout,
+            // The request was answered, or timed out, but the peer may still be sending.
+            (None, None) => {
+                transaction_response(&transaction)?;
+                give_up
+            }
+        };
+        if timeout <= now {
+            if linger_until.is_some() {
+                break;
+            } else if transaction.is_finished() {
+                return Err(ClientError::TimedOut);
+            }
+            transaction.handle_timeout(now);
+            continue;
+        }
+
+        socket.set_read_timeout(Some(config.wake_time(timeout, now) - now))?;
+        let (len, source) = match socket.peek_from(&mut buf) {
+            Ok(received) => received,
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                transaction.handle_timeout(Instant::now());
+                continue;
+            }
+            // ICMP errors from packets sent before the peer has opened its NAT.
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if source == peer && !is_stun(&buf[..len]) {
+            break;
+        }
+        // The datagram that was peeked at is now taken off the socket.
+        socket.recv_from(&mut buf)?;
+        if source != peer {
+            continue;
+        }
+        let packet = &buf[..len];
+        match MessageHeader::peek(packet) {
+            Ok(header)
+                if header.class == MessageClass::Request
+                    && header.method == MessageMethod::BINDING =>
+            {
+                let response = MessageBuilder::new(header.success_response())
+                    .xor_mapped_address(source)
+                    .to_bytes()
+                    .expect("a Binding response can always be encoded");
+                socket.send_to(&response, peer)?;
+                answered = true;
+            }
+            _ => {
+                transaction.handle_packet(packet);
+            }
+        }
+    }
+
+    socket.set_read_timeout(None)?;
+    socket.connect(peer)?;
+    Ok(socket)
+}
+
+/// Returns how long a transaction lasts if no response is received.
+fn transaction_duration(config: TransactionConfig) -> Duration {
+    let intervals = (1u32 << config.rc.saturating_sub(1).min(31)) - 1;
+    config
+        .rto
+        .saturating_mul(intervals.saturating_add(config.rm))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn config() -> ClientConfig {
+        ClientConfig {
+            punch: PunchOptions {
+                transaction: TransactionConfig {
+                    rto: Duration::from_millis(20),
+                    rc: 4,
+                    rm: 4,
+                },
+                linger: Duration::from_millis(50),
+                ..PunchOptions::default()
+            },
+            ..ClientConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_punch_hole() {
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_address, b_address) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        let b = thread::spawn(move || punch_hole(b, a_address, &config()).unwrap());
+        // The peers do not start at exactly the same time.
+        thread::sleep(Duration::from_millis(30));
+        let a = punch_hole(a, b_address, &config()).unwrap();
+        let b = b.join().unwrap();
+
+        a.send(b"hello").unwrap();
+        let mut buf = [0; 16];
+        let len = b.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"hello");
+    }
+
+    #[test]
+    fn test_data_ends_linger() {
+        let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let (a_address, b_address) = (a.local_addr().unwrap(), b.local_addr().unwrap());
+        let mut b_config = config();
+        b_config.punch.linger = Duration::from_secs(10);
+        let b = thread::spawn(move || punch_hole(b, a_address, &b_config).unwrap());
+        let a = punch_hole(a, b_address, &config()).unwrap();
+        a.send(b"hello").unwrap();
+
+        let b = b.join().unwrap();
+        b.set_read_timeout(Some(Duration::from_secs(1))).unwrap();
+        let mut buf = [0; 16];
+        let len = b.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"hello");
+    }
+
+    #[test]
+    fn test_silent_peer() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let result = punch_hole(socket, silent.local_addr().unwrap(), &config());
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+
+    #[test]
+    fn test_transaction_duration() {
+        let duration = transaction_duration(TransactionConfig::default());
+        assert_eq!(duration, Duration::from_millis(39_500));
+    }
+}
@@ -0,0 +1,131 @@
+use std::time::{Duration, Instant};
+
+/// When the request of a transaction was sent and its response received, as recorded by
+/// [StunClient](crate::StunClient) and
+/// [get_mapped_address_with_timing](crate::get_mapped_address_with_timing).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionTiming {
+    /// When the request was sent, once for each transmission.
+    pub sent: Vec<Instant>,
+
+    /// When the response was received, if it was.
+    pub received: Option<Instant>,
+}
+
+impl TransactionTiming {
+    /// Returns the index in [sent](Self::sent) of the transmission that the response is taken to
+    /// answer: the latest one sent before the response was received.
+    ///
+    /// As a retransmission is identical to the original request, a response to an earlier
+    /// transmission that was delayed cannot be told apart; see [is_ambiguous](Self::is_ambiguous).
+    pub fn answered(&self) -> Option<usize> {
+        let received = self.received?;
+        self.sent.iter().rposition(|sent| *sent <= received)
+    }
+
+    /// Returns the round-trip time from the [answered](Self::answered) transmission to the
+    /// response.
+    pub fn rtt(&self) -> Option<Duration> {
+        Some(self.received? - self.sent[self.answered()?])
+    }
+
+    /// Returns true if the request was retransmitted, in which case the response may answer an
+    /// earlier transmission than the one it is taken to, and the [rtt](Self::rtt) may be too
+    /// short.
+    pub fn is_ambiguous(&self) -> bool {
+        self.sent.len() > 1
+    }
+}
+
+/// Statistics of the round-trip times measured by a [StunClient](crate::StunClient), e.g. to
+/// choose the closest of several servers.
+///
+/// Following Karn's algorithm, only responses to requests that were not retransmitted are
+/// measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RttStats {
+    samples: u32,
+    min: Option<Duration>,
+    total: Duration,
+}
+
+impl RttStats {
+    /// Adds a round-trip time to the statistics.
+    pub fn record(&mut self, rtt: Duration) {
+        self.samples += 1;
+        self.min = Some(self.min.map_or(rtt, |min| min.min(rtt)));
+        self.total += rtt;
+    }
+
+    /// Adds the round-trip time of a transaction to the statistics, unless it has none or it is
+    /// ambiguous. Returns true if it was added.
+    pub fn record_timing(&mut self, timing: &TransactionTiming) -> bool {
+        match timing.rtt() {
+            Some(rtt) if !timing.is_ambiguous() => {
+                self.record(rtt);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the number of round-trip times measured.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns the shortest round-trip time measured.
+    pub fn min(&self) -> Option<Duration> {
+        self.min
+    }
+
+    /// Returns the mean of the round-trip times measured.
+    pub fn mean(&self) -> Option<Duration> {
+        (self.samples > 0).then(|| self.total / self.samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut timing = TransactionTiming {
+            sent: vec![start, start + ms(100), start + ms(300)],
+            received: Some(start + ms(150)),
+        };
+        assert_eq!(timing.answered(), Some(1));
+        assert_eq!(timing.rtt(), Some(ms(50)));
+        assert!(timing.is_ambiguous());
+
+        timing.received = None;
+        assert_eq!(timing.answered(), None);
+        assert_eq!(timing.rtt(), None);
+    }
+
+    #[test]
+    fn test_stats() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut stats = RttStats::default();
+        assert_eq!(stats.mean(), None);
+
+        let timing = TransactionTiming {
+            sent: vec![start],
+            received: Some(start + ms(30)),
+        };
+        assert!(stats.record_timing(&timing));
+        let timing = TransactionTiming {
+            sent: vec![start, start + ms(100)],
+            received: Some(start + ms(110)),
+        };
+        assert!(!stats.record_timing(&timing));
+        stats.record(ms(10));
+        assert_eq!(stats.samples(), 2);
+        assert_eq!(stats.min(), Some(ms(10)));
+        assert_eq!(stats.mean(), Some(ms(20)));
+    }
+}
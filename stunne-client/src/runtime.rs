@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::io;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Instant;
+
+/// A datagram socket connected to a single server, over which [StunClient](crate::StunClient)
+/// sends requests.
+///
+/// This is implemented for tokio's `UdpSocket` when the `tokio` feature is enabled. To use the
+/// client with another runtime (e.g., async-std, smol or embassy), implement it for a wrapper
+/// around the runtime's socket type.
+pub trait AsyncDatagram {
+    /// Sends a datagram to the connected server, returning the number of bytes sent.
+    fn send(&self, buf: &[u8]) -> impl Future<Output = io::Result<usize>>;
+
+    /// Receives a datagram from the connected server into `buf`, returning its length.
+    fn recv(&self, buf: &mut [u8]) -> impl Future<Output = io::Result<usize>>;
+}
+
+/// The timers of a runtime, used by [StunClient](crate::StunClient) to wait for retransmissions
+/// and timeouts.
+pub trait Timer {
+    /// Returns a future that completes once `deadline` has been reached.
+    fn sleep_until(&self, deadline: Instant) -> impl Future<Output = ()>;
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncDatagram for tokio::net::UdpSocket {
+    fn send(&self, buf: &[u8]) -> impl Future<Output = io::Result<usize>> {
+        tokio::net::UdpSocket::send(self, buf)
+    }
+
+    fn recv(&self, buf: &mut [u8]) -> impl Future<Output = io::Result<usize>> {
+        tokio::net::UdpSocket::recv(self, buf)
+    }
+}
+
+/// The timers of the tokio runtime, which must have the time driver enabled.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioTimer;
+
+#[cfg(feature = "tokio")]
+impl Timer for TokioTimer {
+    fn sleep_until(&self, deadline: Instant) -> impl Future<Output = ()> {
+        tokio::time::sleep_until(tokio::time::Instant::from_std(deadline))
+    }
+}
+
+/// Wakes a thread parked by [block_on].
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Runs a future to completion on the current thread, parking the thread whenever the future is
+/// pending until it is woken.
+pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+        thread::park();
+    }
+}
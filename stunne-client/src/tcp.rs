@@ -0,0 +1,184 @@
+use crate::{mapped_address, transaction_response, ClientConfig, ClientError};
+use bytes::Bytes;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Instant;
+use stunne_protocol::{
+    MessageBuilder, StreamDecoder, StreamItem, StunTransaction, TransactionConfig,
+};
+
+/// Options for STUN over TCP.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpConfig {
+    /// The timeouts of a request. As TCP is reliable, a request is sent only once; by default, the
+    /// response is awaited for 39.5 seconds, as described in RFC 5389. The initial timeout is also
+    /// used for connecting to the server.
+    pub transaction: TransactionConfig,
+}
+
+impl Default for TcpConfig {
+    fn default() -> Self {
+        Self {
+            transaction: TransactionConfig::reliable(),
+        }
+    }
+}
+
+/// A stream that is carried over a TCP connection, whose timeouts can be set.
+pub(crate) trait OverTcp: Read + Write {
+    fn tcp(&self) -> &TcpStream;
+}
+
+impl OverTcp for TcpStream {
+    fn tcp(&self) -> &TcpStream {
+        self
+    }
+}
+
+/// Returns the address of a local TCP socket as seen by the STUN server at `server`.
+pub(crate) fn get_mapped_address(
+    server: SocketAddr,
+    config: &ClientConfig,
+) -> Result<SocketAddr, ClientError> {
+    let transaction = config.tcp.transaction;
+    let mut stream = connect(server, transaction, config)?;
+    let response = exchange(&mut stream, transaction, config, ClientError::Io)?;
+    mapped_address(&response)
+}
+
+/// Opens a TCP connection to `server`, through the proxy of the client if it has one, within the
+/// initial timeout of a transaction.
+pub(crate) fn connect(
+    server: SocketAddr,
+    transaction_config: TransactionConfig,
+    config: &ClientConfig,
+) -> io::Result<TcpStream> {
+    let timeout = transaction_config.rto;
+    let socket = match &config.proxy {
+        Some(proxy) => proxy.connect(server, timeout)?,
+        None => TcpStream::connect_timeout(&server, timeout)?,
+    };
+    socket.set_write_timeout(Some(timeout))?;
+    Ok(socket)
+}
+
+/// Sends a Binding request over a stream and returns the response. Errors of the stream are
+/// converted by `stream_error`.
+pub(crate) fn exchange(
+    stream: &mut impl OverTcp,
+    transaction_config: TransactionConfig,
+    config: &ClientConfig,
+    stream_error: fn(io::Error) -> ClientError,
+) -> Result<Bytes, ClientError> {
+    let request = MessageBuilder::binding_request()
+        .to_bytes()
+        .expect("a request without attributes can always be encoded");
+    let mut transaction = StunTransaction::new(request, transaction_config, Instant::now())?;
+    let mut decoder = StreamDecoder::new();
+    let mut buf = [0; 4096];
+    loop {
+        while let Some(request) = transaction.poll_transmit() {
+            stream.write_all(&request).map_err(stream_error)?;
+            stream.flush().map_err(stream_error)?;
+        }
+        let Some(timeout) = transaction.poll_timeout() else {
+            break;
+        };
+        let now = Instant::now();
+        if let Some(error) = config.aborted(now) {
+            return Err(error);
+        }
+        if timeout <= now {
+            transaction.handle_timeout(now);
+            continue;
+        }
+        stream
+            .tcp()
+            .set_read_timeout(Some(config.wake_time(timeout, now) - now))?;
+        match stream.read(&mut buf) {
+            Ok(0) => {
+                return Err(ClientError::Io(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "connection closed by server",
+                )))
+            }
+            Ok(len) => {
+                decoder.extend_from_slice(&buf[..len]);
+                while let StreamItem::Message(message) = decoder.next_message()? {
+                    transaction.handle_packet(&message);
+                }
+            }
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                transaction.handle_timeout(Instant::now())
+            }
+            Err(e) => return Err(stream_error(e)),
+        }
+    }
+    transaction_response(&transaction)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::net::{Ipv4Addr, TcpListener, ToSocketAddrs};
+    use std::thread;
+    use stunne_protocol::MessageHeader;
+
+    /// Starts a TCP server that answers a single request with the response built by `respond`,
+    /// returning its address.
+    pub(crate) fn serve_once(
+        respond: impl FnOnce(MessageHeader, SocketAddr) -> MessageBuilder + Send + 'static,
+    ) -> SocketAddr {
+        serve_once_on("127.0.0.1:0", respond)
+    }
+
+    /// Starts a TCP server as with [serve_once], bound to `local`.
+    pub(crate) fn serve_once_on(
+        local: impl ToSocketAddrs,
+        respond: impl FnOnce(MessageHeader, SocketAddr) -> MessageBuilder + Send + 'static,
+    ) -> SocketAddr {
+        let listener = TcpListener::bind(local).unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut socket, peer) = listener.accept().unwrap();
+            let mut decoder = StreamDecoder::new();
+            let mut buf = [0; 4096];
+            let request = loop {
+                let Ok(len @ 1..) = socket.read(&mut buf) else {
+                    return;
+                };
+                decoder.extend_from_slice(&buf[..len]);
+                if let StreamItem::Message(request) = decoder.next_message().unwrap() {
+                    break request;
+                }
+            };
+            let request = MessageHeader::peek(&request).unwrap();
+            let response = respond(request, peer).to_bytes().unwrap();
+            socket.write_all(&response).unwrap();
+        });
+        address
+    }
+
+    #[test]
+    fn test_tcp() {
+        let server = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let address = get_mapped_address(server, &ClientConfig::default()).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn test_connection_refused() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = listener.local_addr().unwrap();
+        drop(listener);
+        let result = get_mapped_address(server, &ClientConfig::default());
+        assert!(matches!(result, Err(ClientError::Io(_))));
+    }
+}
@@ -0,0 +1,225 @@
+use crate::tcp::{self, OverTcp};
+use crate::{first_address, mapped_address, ClientConfig, ClientError, StunUri, UriError};
+use bytes::Bytes;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConnection, RootCertStore, StreamOwned};
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use stunne_protocol::attributes::types::{ALTERNATE_DOMAIN, ALTERNATE_SERVER, ERROR_CODE};
+use stunne_protocol::encodings::{ErrorCode, ErrorCodeDecoder, MappedAddress, Utf8Decoder};
+use stunne_protocol::{MessageClass, StunDecoder, TransactionConfig};
+
+/// Options for STUN over TLS, used for `stuns:` servers.
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// The rustls configuration, which determines the certificates that are trusted. By default,
+    /// the root certificates of the webpki-roots crate are trusted.
+    pub client_config: Arc<rustls::ClientConfig>,
+
+    /// The timeouts of a request. As TLS is reliable, a request is sent only once; by default, the
+    /// response is awaited for 39.5 seconds, as described in RFC 5389. The initial timeout is also
+    /// used for connecting to the server.
+    pub transaction: TransactionConfig,
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        let roots = webpki_roots::TLS_SERVER_ROOTS.iter().cloned().collect();
+        Self::with_root_certificates(roots)
+    }
+}
+
+impl TlsConfig {
+    /// Create options that trust only the given root certificates, e.g. those of a private
+    /// certificate authority.
+    pub fn with_root_certificates(roots: RootCertStore) -> Self {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let client_config = rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .expect("the ring provider supports the default protocol versions")
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Self {
+            client_config: Arc::new(client_config),
+            transaction: TransactionConfig::reliable(),
+        }
+    }
+}
+
+/// Returns the address of the local TCP socket as seen by the STUN server at a `stuns:` URI.
+///
+/// A 300 (Try Alternate) response is followed once: the request is sent to the server in its
+/// ALTERNATE-SERVER attribute, whose certificate is verified against the name in its
+/// ALTERNATE-DOMAIN attribute, or against the original host if there is none.
+pub(crate) fn get_mapped_address(
+    uri: &StunUri,
+    config: &ClientConfig,
+) -> Result<SocketAddr, ClientError> {
+    let server = first_address(uri.to_socket_addrs())?;
+    let response = exchange(uri.host(), server, config)?;
+    match try_alternate(&response) {
+        Some((server, domain)) => {
+            let response = exchange(domain.as_deref().unwrap_or(uri.host()), server, config)?;
+            mapped_address(&response)
+        }
+        None => mapped_address(&response),
+    }
+}
+
+/// Returns the ALTERNATE-SERVER and ALTERNATE-DOMAIN of a 300 (Try Alternate) response.
+fn try_alternate(response: &[u8]) -> Option<(SocketAddr, Option<String>)> {
+    let message = StunDecoder::new(response).ok()?;
+    if message.class() != MessageClass::ErrorResponse {
+        return None;
+    }
+    let error = message.get_attribute(ERROR_CODE, &ErrorCodeDecoder)?.ok()?;
+    if error.code() != ErrorCode::TRY_ALTERNATE.code() {
+        return None;
+    }
+    let server = message
+        .get_attribute(ALTERNATE_SERVER, &MappedAddress::decoder())?
+        .ok()?;
+    let domain = match message.get_attribute(ALTERNATE_DOMAIN, &Utf8Decoder) {
+        Some(Ok(domain)) => Some(domain.to_string()),
+        _ => None,
+    };
+    Some((server, domain))
+}
+
+/// Sends a Binding request over a new TLS connection to `server`, verifying its certificate
+/// against `name`, and returns the response.
+fn exchange(name: &str, server: SocketAddr, config: &ClientConfig) -> Result<Bytes, ClientError> {
+    let tls = &config.tls;
+    let name = ServerName::try_from(name.to_string())
+        .map_err(|_| ClientError::Uri(UriError::InvalidHost))?;
+    let connection = ClientConnection::new(tls.client_config.clone(), name)?;
+    let socket = tcp::connect(server, tls.transaction, config)?;
+    let mut stream = StreamOwned::new(connection, socket);
+    tcp::exchange(&mut stream, tls.transaction, config, tls_error)
+}
+
+impl OverTcp for StreamOwned<ClientConnection, TcpStream> {
+    fn tcp(&self) -> &TcpStream {
+        &self.sock
+    }
+}
+
+/// Converts an error from a TLS stream, which wraps any TLS error (e.g., a certificate that cannot
+/// be verified) in an I/O error.
+fn tls_error(error: io::Error) -> ClientError {
+    match error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+    {
+        Some(tls) => ClientError::Tls(tls.clone()),
+        None => ClientError::Io(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_mapped_address_with_config, ClientConfig};
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+    use rustls::ServerConnection;
+    use std::io::{Read, Write};
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::thread;
+    use stunne_protocol::ext::SocketAddrExt;
+    use stunne_protocol::{MessageBuilder, MessageHeader, StreamDecoder, StreamItem};
+
+    const CA: &[u8] = include_bytes!("../tests/data/ca.der");
+    const CERTIFICATE: &[u8] = include_bytes!("../tests/data/localhost.der");
+    const KEY: &[u8] = include_bytes!("../tests/data/localhost.key.der");
+
+    /// Starts a TLS server, with a certificate for localhost, 127.0.0.1 and stun.example.org, that
+    /// answers a single request with the response built by `respond`, returning its port.
+    fn serve_once(
+        respond: impl FnOnce(MessageHeader, SocketAddr) -> MessageBuilder + Send + 'static,
+    ) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let server_config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from(CERTIFICATE)],
+                PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(KEY)),
+            )
+            .unwrap();
+        thread::spawn(move || {
+            let (socket, peer) = listener.accept().unwrap();
+            let connection = ServerConnection::new(Arc::new(server_config)).unwrap();
+            let mut stream = StreamOwned::new(connection, socket);
+            let mut decoder = StreamDecoder::new();
+            let mut buf = [0; 4096];
+            let request = loop {
+                let Ok(len @ 1..) = stream.read(&mut buf) else {
+                    return;
+                };
+                decoder.extend_from_slice(&buf[..len]);
+                if let StreamItem::Message(request) = decoder.next_message().unwrap() {
+                    break request;
+                }
+            };
+            let request = MessageHeader::peek(&request).unwrap();
+            let response = respond(request, peer).to_bytes().unwrap();
+            stream.write_all(&response).unwrap();
+            stream.flush().unwrap();
+        });
+        port
+    }
+
+    fn test_config() -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots.add(CertificateDer::from(CA)).unwrap();
+        ClientConfig {
+            tls: TlsConfig::with_root_certificates(roots),
+            ..ClientConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_stuns() {
+        let port = serve_once(|request, peer| {
+            MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+        });
+        let server = format!("stuns:localhost:{}", port);
+        let address = get_mapped_address_with_config(&server, &test_config()).unwrap();
+        assert_eq!(address.ip(), Ipv4Addr::LOCALHOST);
+    }
+
+    #[test]
+    fn test_certificate_verification() {
+        let port = serve_once(|request, _| MessageBuilder::new(request.success_response()));
+        let server = format!("stuns:localhost:{}", port);
+        let result = get_mapped_address_with_config(&server, &ClientConfig::default());
+        assert!(matches!(result, Err(ClientError::Tls(_))));
+    }
+
+    #[test]
+    fn test_alternate_domain() {
+        for (domain, valid) in [("stun.example.org", true), ("other.example.org", false)] {
+            let alternate = serve_once(|request, peer| {
+                MessageBuilder::new(request.success_response()).xor_mapped_address(peer)
+            });
+            let port = serve_once(move |request, _| {
+                let alternate = SocketAddr::from((Ipv4Addr::LOCALHOST, alternate));
+                MessageBuilder::new(request.error_response())
+                    .error_code(ErrorCode::TRY_ALTERNATE)
+                    .attribute(ALTERNATE_SERVER, &alternate.as_alternate_server().1)
+                    .attribute(ALTERNATE_DOMAIN, &domain)
+            });
+            let server = format!("stuns:127.0.0.1:{}", port);
+            let result = get_mapped_address_with_config(&server, &test_config());
+            if valid {
+                assert!(result.is_ok());
+            } else {
+                assert!(matches!(result, Err(ClientError::Tls(_))));
+            }
+        }
+    }
+}
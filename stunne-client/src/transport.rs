@@ -0,0 +1,160 @@
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::time::Duration;
+
+/// A blocking, unconnected datagram transport that STUN messages are exchanged over.
+///
+/// The blocking functions of the client that use a socket of the caller (e.g.
+/// [get_mapped_address_from](crate::get_mapped_address_from),
+/// [check_connectivity](crate::check_connectivity) and [DemuxSocket](crate::DemuxSocket)) accept
+/// any implementation of this trait, which is implemented for [UdpSocket]. To use them over
+/// another transport (e.g., QUIC datagrams, a tunnel, or an in-memory transport in tests),
+/// implement it for the transport type.
+///
+/// As with a [UdpSocket], each datagram is sent and received whole, and the functions take
+/// `&self`, so that a transport can be shared between threads.
+pub trait DatagramTransport {
+    /// Sends a datagram to `destination`, returning the number of bytes sent.
+    fn send_to(&self, buf: &[u8], destination: SocketAddr) -> io::Result<usize>;
+
+    /// Receives a datagram into `buf`, returning its length and the address it came from.
+    ///
+    /// If the [read timeout](Self::set_read_timeout) elapses first, an error of kind
+    /// [WouldBlock](io::ErrorKind::WouldBlock) or [TimedOut](io::ErrorKind::TimedOut) must be
+    /// returned.
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)>;
+
+    /// Returns the local address of the transport, as given in candidates for it.
+    fn local_addr(&self) -> io::Result<SocketAddr>;
+
+    /// Sets how long [recv_from](Self::recv_from) waits for a datagram, or lets it wait
+    /// indefinitely if `None`. The timeout is never zero.
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl DatagramTransport for UdpSocket {
+    fn send_to(&self, buf: &[u8], destination: SocketAddr) -> io::Result<usize> {
+        UdpSocket::send_to(self, buf, destination)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        UdpSocket::recv_from(self, buf)
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        UdpSocket::local_addr(self)
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        UdpSocket::set_read_timeout(self, timeout)
+    }
+}
+
+/// A [UdpSocket] connected to `server`, so that ICMP errors for the server are reported, and
+/// datagrams from elsewhere are filtered out by the operating system.
+pub(crate) struct Connected<'a> {
+    pub(crate) socket: &'a UdpSocket,
+    pub(crate) server: SocketAddr,
+}
+
+impl DatagramTransport for Connected<'_> {
+    fn send_to(&self, buf: &[u8], _destination: SocketAddr) -> io::Result<usize> {
+        self.socket.send(buf)
+    }
+
+    fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+        Ok((self.socket.recv(buf)?, self.server))
+    }
+
+    fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.socket.set_read_timeout(timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::fast_config;
+    use crate::{get_mapped_address_from, ClientError};
+    use std::sync::Mutex;
+    use std::thread;
+    use stunne_protocol::{MessageBuilder, MessageHeader};
+
+    /// A transport that answers every Binding request itself, after dropping the first
+    /// `drop` ones.
+    struct Mock {
+        local: SocketAddr,
+        mapped: SocketAddr,
+        drop: Mutex<usize>,
+        timeout: Mutex<Option<Duration>>,
+        responses: Mutex<Vec<(Vec<u8>, SocketAddr)>>,
+    }
+
+    impl Mock {
+        fn new(drop: usize) -> Self {
+            Self {
+                local: "10.0.0.1:5000".parse().unwrap(),
+                mapped: "192.0.2.1:6000".parse().unwrap(),
+                drop: Mutex::new(drop),
+                timeout: Mutex::new(None),
+                responses: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    impl DatagramTransport for Mock {
+        fn send_to(&self, buf: &[u8], destination: SocketAddr) -> io::Result<usize> {
+            let mut drop = self.drop.lock().unwrap();
+            if *drop > 0 {
+                *drop -= 1;
+                return Ok(buf.len());
+            }
+            let request = MessageHeader::peek(buf).unwrap();
+            let response = MessageBuilder::new(request.success_response())
+                .xor_mapped_address(self.mapped)
+                .to_bytes()
+                .unwrap();
+            let responses = &mut self.responses.lock().unwrap();
+            responses.push((response.to_vec(), destination));
+            Ok(buf.len())
+        }
+
+        fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SocketAddr)> {
+            match self.responses.lock().unwrap().pop() {
+                Some((response, source)) => {
+                    buf[..response.len()].copy_from_slice(&response);
+                    Ok((response.len(), source))
+                }
+                None => {
+                    let timeout = self.timeout.lock().unwrap().expect("a timeout is set");
+                    thread::sleep(timeout);
+                    Err(io::ErrorKind::WouldBlock.into())
+                }
+            }
+        }
+
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.local)
+        }
+
+        fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+            *self.timeout.lock().unwrap() = timeout;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mock_transport() {
+        let mock = Mock::new(1);
+        let address = get_mapped_address_from(&mock, "192.0.2.2:3478", &fast_config()).unwrap();
+        assert_eq!(address, mock.mapped);
+
+        let mock = Mock::new(usize::MAX);
+        let result = get_mapped_address_from(&mock, "192.0.2.2:3478", &fast_config());
+        assert!(matches!(result, Err(ClientError::TimedOut)));
+    }
+}
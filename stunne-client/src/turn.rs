@@ -0,0 +1,754 @@
+use crate::{
+    success_response, AsyncDatagram, ClientError, LongTermAuth, StunClient, Timer,
+    MAX_RESPONSE_BYTES,
+};
+use bytes::{BufMut, Bytes, BytesMut};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+use stunne_protocol::attributes::types::{
+    CHANNEL_NUMBER, DATA, LIFETIME, REQUESTED_TRANSPORT, XOR_MAPPED_ADDRESS, XOR_PEER_ADDRESS,
+    XOR_RELAYED_ADDRESS,
+};
+use stunne_protocol::encodings::{Data, DataDecoder, U32Decoder, XorMappedAddress};
+use stunne_protocol::{MessageBuilder, MessageClass, MessageMethod, StunDecoder, StunMessage};
+
+/// The protocol number of UDP, the transport requested for allocations.
+const UDP: u32 = 17;
+
+/// The lifetime of an allocation if the server does not give one, as recommended by RFC 8656.
+const DEFAULT_LIFETIME: Duration = Duration::from_secs(600);
+
+/// The lifetime of a permission, which cannot be changed (RFC 8656 section 9).
+pub const PERMISSION_LIFETIME: Duration = Duration::from_secs(300);
+
+/// The lifetime of a channel binding, which cannot be changed (RFC 8656 section 12).
+pub const CHANNEL_LIFETIME: Duration = Duration::from_secs(600);
+
+/// The range of channel numbers that can be bound to peers.
+const CHANNEL_NUMBERS: RangeInclusive<u16> = 0x4000..=0x4FFF;
+
+/// The length of the header of a ChannelData message.
+const CHANNEL_DATA_HEADER: usize = 4;
+
+/// An allocation of a relayed transport address on a TURN server.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Allocation {
+    /// The address on the server that peers send to, and receive from, to reach the client.
+    pub relayed_address: SocketAddr,
+
+    /// The address of the client as seen by the server, if the server gave one.
+    pub mapped_address: Option<SocketAddr>,
+
+    /// The lifetime of the allocation given by the server in its latest response.
+    pub lifetime: Duration,
+
+    /// When the allocation expires, unless it is refreshed.
+    pub expires_at: Instant,
+}
+
+/// A client for a TURN server (RFC 8656), which relays UDP traffic between the client and its
+/// peers through an allocation on the server.
+///
+/// The client sends its requests through a [StunClient] for the server, authenticated with
+/// long-term credentials. An allocation is made with [allocate](Self::allocate), kept alive with
+/// [refresh](Self::refresh) before it expires, and deleted with
+/// [deallocate](Self::deallocate).
+///
+/// The server only relays datagrams from peers that the client has a permission for, which is made
+/// with [create_permission](Self::create_permission) and lasts for [PERMISSION_LIFETIME] unless it
+/// is made again. Payloads are relayed to and from peers in Send and Data indications, with
+/// [send_to](Self::send_to) and [recv_from](Self::recv_from). As the [StunClient] discards
+/// datagrams that are not responses while it waits for one, Data indications that arrive during a
+/// request are lost.
+///
+/// For a lower overhead at high packet rates, a channel can be bound to a peer with
+/// [bind_channel](Self::bind_channel), after which payloads to and from that peer are relayed in
+/// ChannelData messages, which have a 4-byte header instead of a STUN message. A channel binding
+/// lasts for [CHANNEL_LIFETIME] unless it is bound again, and also creates a permission for the
+/// peer.
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// # async fn run() -> Result<(), stunne_client::ClientError> {
+/// use stunne_client::{ClientConfig, LongTermAuth, StunClient, TurnClient};
+///
+/// let client = StunClient::connect("192.0.2.1:3478".parse().unwrap(), ClientConfig::default())?;
+/// let mut turn = TurnClient::new(client, LongTermAuth::new("user", "password"));
+/// let allocation = turn.allocate().await?;
+/// println!("Relayed address: {}", allocation.relayed_address);
+/// turn.deallocate().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TurnClient<S, T> {
+    client: StunClient<S, T>,
+    auth: LongTermAuth,
+    lifetime: Option<Duration>,
+    allocation: Option<Allocation>,
+    permissions: HashMap<IpAddr, Instant>,
+    channels: HashMap<SocketAddr, Channel>,
+}
+
+/// A channel bound to a peer.
+#[derive(Debug, Copy, Clone)]
+struct Channel {
+    number: u16,
+    expires_at: Instant,
+}
+
+impl<S: AsyncDatagram, T: Timer> TurnClient<S, T> {
+    /// Create a client for the TURN server that `client` sends to, authenticated with `auth`.
+    pub fn new(client: StunClient<S, T>, auth: LongTermAuth) -> Self {
+        Self {
+            client,
+            auth,
+            lifetime: None,
+            allocation: None,
+            permissions: HashMap::new(),
+            channels: HashMap::new(),
+        }
+    }
+
+    /// Asks the server for allocations with the given lifetime, when allocating and refreshing.
+    /// By default, no lifetime is asked for, and the server chooses (usually 10 minutes). The
+    /// server may give a shorter lifetime than the one asked for.
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = Some(lifetime);
+        self
+    }
+
+    /// Returns the current allocation, if one has been made.
+    pub fn allocation(&self) -> Option<&Allocation> {
+        self.allocation.as_ref()
+    }
+
+    /// Returns the relayed address of the current allocation, if one has been made.
+    pub fn relayed_address(&self) -> Option<SocketAddr> {
+        self.allocation.map(|allocation| allocation.relayed_address)
+    }
+
+    /// Returns true if the client has a permission for the peer with the given IP address, which
+    /// has not expired.
+    pub fn has_permission(&self, peer: IpAddr) -> bool {
+        self.permissions
+            .get(&peer)
+            .is_some_and(|expires_at| *expires_at > Instant::now())
+    }
+
+    /// Returns the number of the channel bound to the peer, if it has not expired.
+    pub fn channel(&self, peer: SocketAddr) -> Option<u16> {
+        self.channels
+            .get(&peer)
+            .filter(|channel| channel.expires_at > Instant::now())
+            .map(|channel| channel.number)
+    }
+
+    /// Returns the peers that the client has made permissions for, with when each expires.
+    pub(crate) fn permissions(&self) -> impl Iterator<Item = (IpAddr, Instant)> + '_ {
+        self.permissions
+            .iter()
+            .map(|(peer, expires_at)| (*peer, *expires_at))
+    }
+
+    /// Returns the peers that the client has bound channels to, with when each binding expires.
+    pub(crate) fn channels(&self) -> impl Iterator<Item = (SocketAddr, Instant)> + '_ {
+        self.channels
+            .iter()
+            .map(|(peer, channel)| (*peer, channel.expires_at))
+    }
+
+    /// Returns the credentials, with the realm and nonce learned from the server.
+    pub fn auth(&self) -> &LongTermAuth {
+        &self.auth
+    }
+
+    /// Returns the client that requests are sent through.
+    pub fn client(&self) -> &StunClient<S, T> {
+        &self.client
+    }
+
+    /// Consumes the TURN client, returning the client that requests were sent through. The
+    /// allocation, if any, is left to expire.
+    pub fn into_client(self) -> StunClient<S, T> {
+        self.client
+    }
+
+    /// Asks the server for an allocation for UDP, returning it.
+    ///
+    /// Fails with [ClientError::ErrorResponse] if the server refuses, e.g. with a 437 (Allocation
+    /// Mismatch) response if an allocation has already been made from the same address.
+    pub async fn allocate(&mut self) -> Result<Allocation, ClientError> {
+        let mut request = MessageBuilder::request(MessageMethod::ALLOCATE)
+            .attribute(REQUESTED_TRANSPORT, &(UDP << 24))
+            .build()
+            .expect("an Allocate request can always be built");
+        add_lifetime(&mut request, self.lifetime);
+        let sent = Instant::now();
+        let response = self.send(&request).await?;
+        let message = success_response(&response)?;
+        let decoder = XorMappedAddress::decoder(message.tx_id());
+        let Some(Ok(relayed_address)) = message.get_attribute(XOR_RELAYED_ADDRESS, &decoder) else {
+            return Err(ClientError::MissingAddress);
+        };
+        let mapped_address = message
+            .get_attribute(XOR_MAPPED_ADDRESS, &decoder)
+            .and_then(Result::ok);
+        let lifetime = lifetime(&message);
+        let allocation = Allocation {
+            relayed_address,
+            mapped_address,
+            lifetime,
+            expires_at: sent + lifetime,
+        };
+        self.allocation = Some(allocation);
+        Ok(allocation)
+    }
+
+    /// Refreshes the allocation, returning its new lifetime.
+    ///
+    /// Fails with [ClientError::NoAllocation] if no allocation has been made. If the server no
+    /// longer has the allocation (a 437 (Allocation Mismatch) response), it is forgotten.
+    pub async fn refresh(&mut self) -> Result<Duration, ClientError> {
+        let mut allocation = self.allocation.ok_or(ClientError::NoAllocation)?;
+        let sent = Instant::now();
+        let response = self.send_refresh(self.lifetime).await?;
+        let message = success_response(&response)?;
+        allocation.lifetime = lifetime(&message);
+        allocation.expires_at = sent + allocation.lifetime;
+        self.allocation = Some(allocation);
+        Ok(allocation.lifetime)
+    }
+
+    /// Deletes the allocation, with a Refresh request whose lifetime is zero.
+    ///
+    /// Fails with [ClientError::NoAllocation] if no allocation has been made. The allocation is
+    /// forgotten even if the request fails.
+    pub async fn deallocate(&mut self) -> Result<(), ClientError> {
+        self.allocation.ok_or(ClientError::NoAllocation)?;
+        let response = self.send_refresh(Some(Duration::ZERO)).await;
+        self.forget_allocation();
+        success_response(&response?)?;
+        Ok(())
+    }
+
+    async fn send_refresh(&mut self, lifetime: Option<Duration>) -> Result<Bytes, ClientError> {
+        let mut request = MessageBuilder::request(MessageMethod::REFRESH)
+            .build()
+            .expect("a Refresh request can always be built");
+        add_lifetime(&mut request, lifetime);
+        let response = self.send(&request).await?;
+        if is_allocation_mismatch(&response) {
+            self.forget_allocation();
+        }
+        Ok(response)
+    }
+
+    /// Forgets the allocation, and the permissions and channels that were made for it.
+    pub(crate) fn forget_allocation(&mut self) {
+        self.allocation = None;
+        self.permissions.clear();
+        self.channels.clear();
+    }
+
+    /// Creates (or refreshes) permissions for peers with the given IP addresses, so that the
+    /// server relays datagrams from them. Only the IP address of a peer matters, not its port.
+    ///
+    /// Fails with [ClientError::NoAllocation] if no allocation has been made.
+    pub async fn create_permission(&mut self, peers: &[IpAddr]) -> Result<(), ClientError> {
+        self.allocation.ok_or(ClientError::NoAllocation)?;
+        let sent = Instant::now();
+        let response = self
+            .send_rebuilt(|| {
+                let mut request = MessageBuilder::request(MessageMethod::CREATE_PERMISSION)
+                    .build()
+                    .expect("a CreatePermission request can always be built");
+                let tx_id = request.header().tx_id;
+                for peer in peers {
+                    let peer = SocketAddr::new(*peer, 0);
+                    request
+                        .add_attribute(XOR_PEER_ADDRESS, &XorMappedAddress::encoder(peer, tx_id));
+                }
+                request
+            })
+            .await?;
+        if is_allocation_mismatch(&response) {
+            self.forget_allocation();
+        }
+        success_response(&response)?;
+        for peer in peers {
+            self.permissions.insert(*peer, sent + PERMISSION_LIFETIME);
+        }
+        Ok(())
+    }
+
+    /// Binds a channel to a peer (or refreshes its binding), returning the channel number. The
+    /// peer keeps the number it was first given for as long as the allocation lasts.
+    ///
+    /// Fails with [ClientError::NoAllocation] if no allocation has been made, and with
+    /// [ClientError::NoChannelNumber] if every channel number has been bound to another peer.
+    pub async fn bind_channel(&mut self, peer: SocketAddr) -> Result<u16, ClientError> {
+        self.allocation.ok_or(ClientError::NoAllocation)?;
+        // A number is not given to another peer after its binding expires, so that datagrams that
+        // are still in flight are not taken to come from the wrong peer.
+        let number = match self.channels.get(&peer) {
+            Some(channel) => channel.number,
+            None => {
+                let next = *CHANNEL_NUMBERS.start() as usize + self.channels.len();
+                u16::try_from(next)
+                    .ok()
+                    .filter(|number| CHANNEL_NUMBERS.contains(number))
+                    .ok_or(ClientError::NoChannelNumber)?
+            }
+        };
+        let sent = Instant::now();
+        let response = self
+            .send_rebuilt(|| {
+                let builder = MessageBuilder::request(MessageMethod::CHANNEL_BIND);
+                let tx_id = builder.header().tx_id;
+                builder
+                    .attribute(CHANNEL_NUMBER, &(u32::from(number) << 16))
+                    .attribute(XOR_PEER_ADDRESS, &XorMappedAddress::encoder(peer, tx_id))
+                    .build()
+                    .expect("a ChannelBind request can always be built")
+            })
+            .await?;
+        if is_allocation_mismatch(&response) {
+            self.forget_allocation();
+        }
+        success_response(&response)?;
+        let expires_at = sent + CHANNEL_LIFETIME;
+        self.channels.insert(peer, Channel { number, expires_at });
+        self.permissions
+            .insert(peer.ip(), sent + PERMISSION_LIFETIME);
+        Ok(number)
+    }
+
+    /// Relays `data` to `peer`, in a ChannelData message if a channel is bound to the peer, or
+    /// else in a Send indication. The server drops it unless the client has a permission for the
+    /// peer.
+    ///
+    /// Fails with [ClientError::NoAllocation] if no allocation has been made.
+    pub async fn send_to(&self, data: &[u8], peer: SocketAddr) -> Result<(), ClientError> {
+        self.allocation.ok_or(ClientError::NoAllocation)?;
+        let packet = match self.channel(peer) {
+            Some(number) => encode_channel_data(number, data),
+            None => encode_send_indication(peer, data),
+        };
+        self.client.socket().send(&packet).await?;
+        Ok(())
+    }
+
+    /// Waits for a payload relayed from a peer in a Data indication or a ChannelData message,
+    /// copying it into `buf` and returning its length and the address of the peer. A payload
+    /// longer than `buf` is truncated. Other datagrams, and ChannelData messages for channels that
+    /// have not been bound, are discarded.
+    pub async fn recv_from(&self, buf: &mut [u8]) -> Result<(usize, SocketAddr), ClientError> {
+        let mut packet = [0; MAX_RESPONSE_BYTES];
+        loop {
+            let len = self.client.socket().recv(&mut packet).await?;
+            let relayed = match decode_channel_data(&packet[..len]) {
+                Some((number, data)) => self.channel_peer(number).map(|peer| (peer, data)),
+                None => decode_data_indication(&packet[..len]),
+            };
+            if let Some((peer, data)) = relayed {
+                let len = data.len().min(buf.len());
+                buf[..len].copy_from_slice(&data[..len]);
+                return Ok((len, peer));
+            }
+        }
+    }
+
+    /// Returns the peer that a channel number is bound to.
+    fn channel_peer(&self, number: u16) -> Option<SocketAddr> {
+        self.channels
+            .iter()
+            .find(|(_, channel)| channel.number == number)
+            .map(|(peer, _)| *peer)
+    }
+
+    /// Sends an authenticated request like [send](Self::send), but building it again for each
+    /// attempt, as its XOR-PEER-ADDRESS attributes are encoded with its transaction ID.
+    async fn send_rebuilt(
+        &mut self,
+        build: impl Fn() -> StunMessage,
+    ) -> Result<Bytes, ClientError> {
+        let mut retries = 0;
+        loop {
+            let request = self.auth.sign_in_place(&build());
+            let response = self.client.send_request(request).await?;
+            let message = StunDecoder::new(&response)?;
+            if self.auth.handle_response(&message) && retries < self.auth.max_retries() {
+                retries += 1;
+                continue;
+            }
+            return Ok(response);
+        }
+    }
+
+    /// Sends an authenticated request, returning the response.
+    pub(crate) async fn send(&mut self, request: &StunMessage) -> Result<Bytes, ClientError> {
+        self.client
+            .send_authenticated(request, &mut self.auth)
+            .await
+    }
+}
+
+/// Encodes a Send indication, which asks the TURN server to relay `data` from the relayed address
+/// to `peer`.
+pub fn encode_send_indication(peer: SocketAddr, data: &[u8]) -> Bytes {
+    let builder = MessageBuilder::indication(MessageMethod::SEND);
+    let tx_id = builder.header().tx_id;
+    builder
+        .attribute(XOR_PEER_ADDRESS, &XorMappedAddress::encoder(peer, tx_id))
+        .attribute(DATA, &Data { payload: data })
+        .to_bytes()
+        .expect("a Send indication can always be encoded")
+}
+
+/// Decodes a Data indication, in which the TURN server relays a datagram that a peer sent to the
+/// relayed address, returning the address of the peer and the datagram. Returns `None` if the
+/// packet is not a valid Data indication.
+pub fn decode_data_indication(packet: &[u8]) -> Option<(SocketAddr, &[u8])> {
+    let message = StunDecoder::new(packet).ok()?;
+    if message.class() != MessageClass::Indication || message.method() != MessageMethod::DATA {
+        return None;
+    }
+    let peer = message
+        .get_attribute(
+            XOR_PEER_ADDRESS,
+            &XorMappedAddress::decoder(message.tx_id()),
+        )?
+        .ok()?;
+    let data = message.get_attribute(DATA, &DataDecoder)?.ok()?;
+    Some((peer, data.payload))
+}
+
+/// Encodes a ChannelData message, which relays `data` to or from the peer that the channel is
+/// bound to.
+///
+/// The message is not padded to a multiple of 4 bytes, which is only needed over TCP and TLS.
+///
+/// # Panics
+///
+/// If `data` is longer than 65535 bytes.
+pub fn encode_channel_data(channel: u16, data: &[u8]) -> Bytes {
+    let len = u16::try_from(data.len()).expect("ChannelData is at most 65535 bytes long");
+    let mut packet = BytesMut::with_capacity(CHANNEL_DATA_HEADER + data.len());
+    packet.put_u16(channel);
+    packet.put_u16(len);
+    packet.put_slice(data);
+    packet.freeze()
+}
+
+/// Decodes a ChannelData message, returning the channel number and the data, without any padding.
+/// Returns `None` if the packet is not a ChannelData message, e.g. because it is a STUN message.
+pub fn decode_channel_data(packet: &[u8]) -> Option<(u16, &[u8])> {
+    let header = packet.get(..CHANNEL_DATA_HEADER)?;
+    let channel = u16::from_be_bytes([header[0], header[1]]);
+    let len = usize::from(u16::from_be_bytes([header[2], header[3]]));
+    // The first two bits of a ChannelData message are 0b01, and those of a STUN message are 0b00.
+    if channel & 0xC000 != 0x4000 {
+        return None;
+    }
+    let data = packet.get(CHANNEL_DATA_HEADER..CHANNEL_DATA_HEADER + len)?;
+    Some((channel, data))
+}
+
+/// Adds a LIFETIME attribute to a request, if a lifetime is given.
+fn add_lifetime(request: &mut StunMessage, lifetime: Option<Duration>) {
+    if let Some(lifetime) = lifetime {
+        let seconds = u32::try_from(lifetime.as_secs()).unwrap_or(u32::MAX);
+        request.add_attribute(LIFETIME, &seconds);
+    }
+}
+
+/// Reads the LIFETIME attribute of a response.
+fn lifetime(message: &StunDecoder) -> Duration {
+    match message.get_attribute(LIFETIME, &U32Decoder) {
+        Some(Ok(seconds)) => Duration::from_secs(seconds.into()),
+        _ => DEFAULT_LIFETIME,
+    }
+}
+
+/// Returns true if a response is a 437 (Allocation Mismatch) error response.
+fn is_allocation_mismatch(response: &[u8]) -> bool {
+    matches!(
+        success_response(response),
+        Err(ClientError::ErrorResponse { code: 437, .. })
+    )
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::async_client::tests::{Immediate, Loopback};
+    use crate::tests::fast_config;
+    use futures_executor::block_on;
+    use stunne_protocol::attributes::types::NONCE;
+    use stunne_protocol::encodings::{ErrorCode, Utf8Decoder};
+    use stunne_protocol::{LongTermKey, MessageClass, MessageHeader};
+
+    pub(crate) const RELAYED: &str = "192.0.2.10:49152";
+    pub(crate) const MAPPED: &str = "192.0.2.1:32853";
+
+    /// Answers a request as a TURN server with the realm "realm", where "user" has the password
+    /// "password". Allocations last for the lifetime asked for, or 600 seconds.
+    pub(crate) fn turn_server(request: &[u8]) -> Option<Bytes> {
+        let message = StunDecoder::new(request).unwrap();
+        let header = MessageHeader::peek(request).unwrap();
+        if message.class() != MessageClass::Request {
+            return None;
+        }
+        let key = LongTermKey::new("user", "realm", "password");
+        if message.verify_integrity(key.as_bytes()).is_err() {
+            let response = MessageBuilder::new(header.error_response())
+                .error_code(ErrorCode::UNAUTHORIZED)
+                .realm("realm")
+                .nonce("nonce")
+                .to_bytes()
+                .unwrap();
+            return Some(response);
+        }
+        let lifetime = message
+            .get_attribute(LIFETIME, &U32Decoder)
+            .map_or(600, Result::unwrap);
+        let mut response = MessageBuilder::new(header.success_response());
+        if message.method() == MessageMethod::ALLOCATE {
+            let tx_id = header.tx_id;
+            response = response
+                .attribute(
+                    XOR_RELAYED_ADDRESS,
+                    &XorMappedAddress::encoder(RELAYED.parse().unwrap(), tx_id),
+                )
+                .xor_mapped_address(MAPPED.parse().unwrap());
+        }
+        if message.method() == MessageMethod::ALLOCATE || message.method() == MessageMethod::REFRESH
+        {
+            response = response.attribute(LIFETIME, &lifetime);
+        }
+        Some(response.to_bytes().unwrap())
+    }
+
+    pub(crate) fn turn_client(
+        respond: impl Fn(&[u8]) -> Option<Bytes> + 'static,
+    ) -> TurnClient<Loopback, Immediate> {
+        let client = StunClient::with_timer(Loopback::new(respond), Immediate, fast_config());
+        TurnClient::new(client, LongTermAuth::new("user", "password"))
+    }
+
+    #[test]
+    fn test_allocate() {
+        let mut turn = turn_client(turn_server).with_lifetime(Duration::from_secs(1200));
+        assert!(matches!(
+            block_on(turn.refresh()),
+            Err(ClientError::NoAllocation)
+        ));
+        let allocation = block_on(turn.allocate()).unwrap();
+        assert_eq!(allocation.relayed_address, RELAYED.parse().unwrap());
+        assert_eq!(allocation.mapped_address, Some(MAPPED.parse().unwrap()));
+        assert_eq!(allocation.lifetime, Duration::from_secs(1200));
+        assert_eq!(turn.relayed_address(), Some(allocation.relayed_address));
+        assert_eq!(turn.auth().realm(), Some("realm"));
+
+        assert_eq!(block_on(turn.refresh()).unwrap(), Duration::from_secs(1200));
+        let request = turn.client().socket().last_sent();
+        let request = StunDecoder::new(&request).unwrap();
+        assert_eq!(request.method(), MessageMethod::REFRESH);
+
+        block_on(turn.deallocate()).unwrap();
+        assert_eq!(turn.allocation(), None);
+        let request = turn.client().socket().last_sent();
+        let request = StunDecoder::new(&request).unwrap();
+        let lifetime = request.get_attribute(LIFETIME, &U32Decoder);
+        assert_eq!(lifetime.map(Result::unwrap), Some(0));
+    }
+
+    #[test]
+    fn test_allocation_mismatch() {
+        // A server that has lost the allocation, e.g. after a restart.
+        let mut turn = turn_client(|request| {
+            let message = StunDecoder::new(request).unwrap();
+            if message.method() == MessageMethod::REFRESH
+                && message.get_attribute(NONCE, &Utf8Decoder).is_some()
+            {
+                let header = MessageHeader::peek(request).unwrap();
+                let response = MessageBuilder::new(header.error_response())
+                    .error_code(ErrorCode::ALLOCATION_MISMATCH)
+                    .to_bytes()
+                    .unwrap();
+                return Some(response);
+            }
+            turn_server(request)
+        });
+        block_on(turn.allocate()).unwrap();
+        let result = block_on(turn.refresh());
+        assert!(matches!(
+            result,
+            Err(ClientError::ErrorResponse { code: 437, .. })
+        ));
+        assert_eq!(turn.allocation(), None);
+    }
+
+    #[test]
+    fn test_unauthorized() {
+        let mut turn = turn_client(turn_server);
+        turn.auth = LongTermAuth::new("user", "wrong");
+        let result = block_on(turn.allocate());
+        assert!(matches!(
+            result,
+            Err(ClientError::ErrorResponse { code: 401, .. })
+        ));
+        assert_eq!(turn.allocation(), None);
+    }
+
+    #[test]
+    fn test_create_permission() {
+        let mut turn = turn_client(turn_server);
+        let peers: [IpAddr; 2] = [
+            "198.51.100.1".parse().unwrap(),
+            "2001:db8::1".parse().unwrap(),
+        ];
+        assert!(matches!(
+            block_on(turn.create_permission(&peers)),
+            Err(ClientError::NoAllocation)
+        ));
+        block_on(turn.allocate()).unwrap();
+        block_on(turn.create_permission(&peers)).unwrap();
+        assert!(turn.has_permission(peers[0]));
+        assert!(turn.has_permission(peers[1]));
+        assert!(!turn.has_permission("198.51.100.2".parse().unwrap()));
+
+        let request = turn.client().socket().last_sent();
+        let request = StunDecoder::new(&request).unwrap();
+        assert_eq!(request.method(), MessageMethod::CREATE_PERMISSION);
+        let decoder = XorMappedAddress::decoder(request.tx_id());
+        let addresses: Vec<_> = request
+            .get_all(XOR_PEER_ADDRESS, &decoder)
+            .map(|address| address.unwrap().ip())
+            .collect();
+        assert_eq!(addresses, peers);
+
+        block_on(turn.deallocate()).unwrap();
+        assert!(!turn.has_permission(peers[0]));
+    }
+
+    /// Answers like [turn_server], but as if the peers echo everything relayed to them, which is
+    /// relayed back the way it was sent.
+    fn echo_server(request: &[u8]) -> Option<Bytes> {
+        if decode_channel_data(request).is_some() {
+            return Some(Bytes::copy_from_slice(request));
+        }
+        let message = StunDecoder::new(request).unwrap();
+        if message.class() != MessageClass::Indication {
+            return turn_server(request);
+        }
+        let decoder = XorMappedAddress::decoder(message.tx_id());
+        let peer = message.get_attribute(XOR_PEER_ADDRESS, &decoder)?.unwrap();
+        let data = message.get_attribute(DATA, &DataDecoder)?.unwrap();
+        let builder = MessageBuilder::indication(MessageMethod::DATA);
+        let tx_id = builder.header().tx_id;
+        let indication = builder
+            .attribute(XOR_PEER_ADDRESS, &XorMappedAddress::encoder(peer, tx_id))
+            .attribute(DATA, &data)
+            .to_bytes()
+            .unwrap();
+        Some(indication)
+    }
+
+    #[test]
+    fn test_send_and_receive() {
+        let mut turn = turn_client(echo_server);
+        let peer: SocketAddr = "198.51.100.1:5000".parse().unwrap();
+        assert!(matches!(
+            block_on(turn.send_to(b"hello", peer)),
+            Err(ClientError::NoAllocation)
+        ));
+        block_on(turn.allocate()).unwrap();
+        block_on(turn.create_permission(&[peer.ip()])).unwrap();
+        block_on(turn.send_to(b"hello", peer)).unwrap();
+        let mut buf = [0; 16];
+        let (len, source) = block_on(turn.recv_from(&mut buf)).unwrap();
+        assert_eq!((&buf[..len], source), (&b"hello"[..], peer));
+    }
+
+    #[test]
+    fn test_data_indication() {
+        let peer: SocketAddr = "[2001:db8::1]:5000".parse().unwrap();
+        let send = encode_send_indication(peer, b"hello");
+        let message = StunDecoder::new(&send).unwrap();
+        assert_eq!(message.class(), MessageClass::Indication);
+        assert_eq!(message.method(), MessageMethod::SEND);
+        // Only Data indications are relayed from peers.
+        assert_eq!(decode_data_indication(&send), None);
+        assert_eq!(decode_data_indication(b"hello"), None);
+
+        let builder = MessageBuilder::indication(MessageMethod::DATA);
+        let tx_id = builder.header().tx_id;
+        let data = builder
+            .attribute(XOR_PEER_ADDRESS, &XorMappedAddress::encoder(peer, tx_id))
+            .attribute(DATA, &Data { payload: b"hello" })
+            .to_bytes()
+            .unwrap();
+        assert_eq!(decode_data_indication(&data), Some((peer, &b"hello"[..])));
+    }
+
+    #[test]
+    fn test_bind_channel() {
+        let mut turn = turn_client(echo_server);
+        let (a, b): (SocketAddr, SocketAddr) = (
+            "198.51.100.1:5000".parse().unwrap(),
+            "[2001:db8::1]:5000".parse().unwrap(),
+        );
+        assert!(matches!(
+            block_on(turn.bind_channel(a)),
+            Err(ClientError::NoAllocation)
+        ));
+        block_on(turn.allocate()).unwrap();
+        assert_eq!(block_on(turn.bind_channel(a)).unwrap(), 0x4000);
+        assert_eq!(block_on(turn.bind_channel(b)).unwrap(), 0x4001);
+        let request = turn.client().socket().last_sent();
+        let request = StunDecoder::new(&request).unwrap();
+        assert_eq!(request.method(), MessageMethod::CHANNEL_BIND);
+        let number = request.get_attribute(CHANNEL_NUMBER, &U32Decoder);
+        assert_eq!(number.map(Result::unwrap), Some(0x4001_0000));
+        let peer = request.get_attribute(
+            XOR_PEER_ADDRESS,
+            &XorMappedAddress::decoder(request.tx_id()),
+        );
+        assert_eq!(peer.map(Result::unwrap), Some(b));
+
+        // Binding again refreshes the channel.
+        assert_eq!(block_on(turn.bind_channel(a)).unwrap(), 0x4000);
+        assert_eq!(turn.channel(a), Some(0x4000));
+        assert!(turn.has_permission(a.ip()));
+
+        block_on(turn.send_to(b"hello", b)).unwrap();
+        assert_eq!(
+            decode_channel_data(&turn.client().socket().last_sent()),
+            Some((0x4001, &b"hello"[..]))
+        );
+        let mut buf = [0; 16];
+        let (len, source) = block_on(turn.recv_from(&mut buf)).unwrap();
+        assert_eq!((&buf[..len], source), (&b"hello"[..], b));
+
+        block_on(turn.deallocate()).unwrap();
+        assert_eq!(turn.channel(a), None);
+    }
+
+    #[test]
+    fn test_channel_data() {
+        let packet = encode_channel_data(0x4123, b"hello");
+        assert_eq!(&packet[..4], &[0x41, 0x23, 0, 5]);
+        assert_eq!(decode_channel_data(&packet), Some((0x4123, &b"hello"[..])));
+        // Padding is ignored, but a truncated message is not decoded.
+        let padded = [&packet[..], &[0; 3]].concat();
+        assert_eq!(decode_channel_data(&padded), Some((0x4123, &b"hello"[..])));
+        assert_eq!(decode_channel_data(&packet[..8]), None);
+
+        let send = encode_send_indication("198.51.100.1:5000".parse().unwrap(), b"hello");
+        assert_eq!(decode_channel_data(&send), None);
+    }
+}
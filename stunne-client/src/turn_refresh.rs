@@ -0,0 +1,402 @@
+use crate::runtime::block_on;
+use crate::turn::{CHANNEL_LIFETIME, PERMISSION_LIFETIME};
+use crate::{AsyncDatagram, ClientError, Timer, TurnClient};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// What happened at a tick of a [TurnRefresher].
+#[derive(Debug)]
+pub enum TurnEvent {
+    /// The allocation was refreshed, and now has the given lifetime.
+    AllocationRefreshed {
+        /// The lifetime given by the server.
+        lifetime: Duration,
+    },
+
+    /// The permissions for peers with these IP addresses were refreshed.
+    PermissionsRefreshed {
+        /// The IP addresses of the peers.
+        peers: Vec<IpAddr>,
+    },
+
+    /// The channel bound to a peer was refreshed.
+    ChannelRefreshed {
+        /// The address of the peer.
+        peer: SocketAddr,
+
+        /// The channel number.
+        number: u16,
+    },
+
+    /// The allocation was lost, with its permissions and channels, either because the server no
+    /// longer has it (a 437 (Allocation Mismatch) response) or because it expired before it could
+    /// be refreshed. A new allocation must be made with [TurnClient::allocate].
+    AllocationLost {
+        /// The error of the refresh that failed.
+        error: ClientError,
+    },
+}
+
+/// Something that a [TurnRefresher] refreshes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Refresh {
+    Allocation,
+    Permissions(Vec<IpAddr>),
+    Channel(SocketAddr),
+}
+
+/// Keeps the allocation of a [TurnClient] alive, with its permissions and channels, by refreshing
+/// each of them shortly before it expires.
+///
+/// Each call to [tick](Self::tick) waits until the next refresh is due and sends it, returning a
+/// [TurnEvent]. Requests are authenticated like those of the [TurnClient], so that a 401
+/// (Unauthorized) or 438 (Stale Nonce) response is answered by signing the request again. A
+/// refresh that fails while the allocation is still alive (e.g. because the request timed out)
+/// is returned as an error, and retried after a [retry interval](Self::with_retry_interval), so
+/// the caller may keep ticking after an error. If the allocation is lost, a
+/// [TurnEvent::AllocationLost] is returned, and ticks fail with [ClientError::NoAllocation] until
+/// a new allocation is made.
+///
+/// As [TurnClient::recv_from] cannot be waited on while a tick holds the refresher, an
+/// application that receives relayed data can instead wait for whichever comes first of
+/// [next_refresh](Self::next_refresh) and a datagram, and then call
+/// [refresh_next](Self::refresh_next). Without a runtime, [tick_blocking](Self::tick_blocking)
+/// runs a tick on the current thread.
+///
+/// ```no_run
+/// # #[cfg(feature = "tokio")]
+/// # async fn run() -> Result<(), stunne_client::ClientError> {
+/// use stunne_client::{LongTermAuth, StunClient, TurnClient, TurnEvent, TurnRefresher};
+/// use tokio::net::UdpSocket;
+///
+/// let socket = UdpSocket::bind("0.0.0.0:0").await?;
+/// socket.connect("turn.example.org:3478").await?;
+/// let mut turn = TurnClient::new(StunClient::new(socket), LongTermAuth::new("user", "password"));
+/// turn.allocate().await?;
+/// let mut refresher = TurnRefresher::new(turn);
+/// loop {
+///     match refresher.tick().await {
+///         Ok(TurnEvent::AllocationLost { error }) => {
+///             println!("Allocation lost: {}", error);
+///             refresher.turn_mut().allocate().await?;
+///         }
+///         Ok(_) => {}
+///         Err(error) => println!("Refresh failed: {}", error),
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TurnRefresher<S, T> {
+    turn: TurnClient<S, T>,
+    margin: Duration,
+    retry_interval: Duration,
+    not_before: Option<Instant>,
+}
+
+impl<S: AsyncDatagram, T: Timer> TurnRefresher<S, T> {
+    /// The default time before expiry at which refreshes are sent, as recommended by RFC 8656.
+    pub const DEFAULT_MARGIN: Duration = Duration::from_secs(60);
+
+    /// The default time after a failed refresh at which it is retried.
+    pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Create a refresher for the allocation, permissions and channels of `turn`, including
+    /// those that are made later.
+    pub fn new(turn: TurnClient<S, T>) -> Self {
+        Self {
+            turn,
+            margin: Self::DEFAULT_MARGIN,
+            retry_interval: Self::DEFAULT_RETRY_INTERVAL,
+            not_before: None,
+        }
+    }
+
+    /// Sets how long before expiry refreshes are sent. Refreshes of anything whose lifetime is
+    /// shorter than twice the margin are sent halfway through its lifetime instead.
+    pub fn with_margin(mut self, margin: Duration) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets how long after a failed refresh it is retried.
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Returns the TURN client.
+    pub fn turn(&self) -> &TurnClient<S, T> {
+        &self.turn
+    }
+
+    /// Returns the TURN client, e.g. to make permissions or a new allocation.
+    pub fn turn_mut(&mut self) -> &mut TurnClient<S, T> {
+        &mut self.turn
+    }
+
+    /// Consumes the refresher, returning the TURN client.
+    pub fn into_turn(self) -> TurnClient<S, T> {
+        self.turn
+    }
+
+    /// Returns when the next refresh is due, or `None` if there is no allocation.
+    pub fn next_refresh(&self) -> Option<Instant> {
+        let (due, _) = self.next()?;
+        Some(
+            self.not_before
+                .map_or(due, |not_before| due.max(not_before)),
+        )
+    }
+
+    /// Waits until the next refresh is due and sends it.
+    ///
+    /// Fails with [ClientError::NoAllocation], without waiting, if there is no allocation.
+    pub async fn tick(&mut self) -> Result<TurnEvent, ClientError> {
+        let due = self.next_refresh().ok_or(ClientError::NoAllocation)?;
+        self.turn.client().timer().sleep_until(due).await;
+        self.refresh_next().await
+    }
+
+    /// Runs [tick](Self::tick) to completion on the current thread, for a client whose socket and
+    /// timer do not need a runtime (e.g. ones that block the thread).
+    pub fn tick_blocking(&mut self) -> Result<TurnEvent, ClientError> {
+        block_on(self.tick())
+    }
+
+    /// Sends the next refresh without waiting for it to be due.
+    ///
+    /// Fails with [ClientError::NoAllocation] if there is no allocation.
+    pub async fn refresh_next(&mut self) -> Result<TurnEvent, ClientError> {
+        let (due, refresh) = self.next().ok_or(ClientError::NoAllocation)?;
+        let result = match refresh {
+            Refresh::Allocation => self
+                .turn
+                .refresh()
+                .await
+                .map(|lifetime| TurnEvent::AllocationRefreshed { lifetime }),
+            Refresh::Permissions(peers) => self
+                .turn
+                .create_permission(&peers)
+                .await
+                .map(|()| TurnEvent::PermissionsRefreshed { peers }),
+            Refresh::Channel(peer) => self
+                .turn
+                .bind_channel(peer)
+                .await
+                .map(|number| TurnEvent::ChannelRefreshed { peer, number }),
+        };
+        let error = match result {
+            Ok(event) => {
+                self.not_before = None;
+                return Ok(event);
+            }
+            Err(error) => error,
+        };
+        let now = Instant::now();
+        match self.turn.allocation() {
+            // Forgotten after a 437 (Allocation Mismatch) response.
+            None => {}
+            Some(allocation) if allocation.expires_at <= now => self.turn.forget_allocation(),
+            Some(_) => {
+                self.not_before = Some(now.max(due) + self.retry_interval);
+                return Err(error);
+            }
+        }
+        self.not_before = None;
+        Ok(TurnEvent::AllocationLost { error })
+    }
+
+    /// Returns the refresh that is due first, and when.
+    fn next(&self) -> Option<(Instant, Refresh)> {
+        let allocation = self.turn.allocation()?;
+        let mut next = (
+            self.refresh_at(allocation.expires_at, allocation.lifetime),
+            Refresh::Allocation,
+        );
+        // Permissions are refreshed together, as soon as one of them is due.
+        let permissions = self
+            .turn
+            .permissions()
+            .map(|(peer, expires_at)| (self.refresh_at(expires_at, PERMISSION_LIFETIME), peer));
+        let (due, peers): (Vec<_>, Vec<_>) = permissions.unzip();
+        if let Some(due) = due.into_iter().min() {
+            if due < next.0 {
+                next = (due, Refresh::Permissions(peers));
+            }
+        }
+        let channels = self
+            .turn
+            .channels()
+            .map(|(peer, expires_at)| (self.refresh_at(expires_at, CHANNEL_LIFETIME), peer));
+        if let Some((due, peer)) = channels.min() {
+            if due < next.0 {
+                next = (due, Refresh::Channel(peer));
+            }
+        }
+        Some(next)
+    }
+
+    /// Returns when something with the given lifetime, which expires at `expires_at`, is
+    /// refreshed.
+    fn refresh_at(&self, expires_at: Instant, lifetime: Duration) -> Instant {
+        expires_at - self.margin.min(lifetime / 2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::turn::tests::{turn_client, turn_server};
+    use bytes::Bytes;
+    use futures_executor::block_on;
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use stunne_protocol::attributes::types::NONCE;
+    use stunne_protocol::encodings::{ErrorCode, Utf8Decoder};
+    use stunne_protocol::{MessageBuilder, MessageHeader, MessageMethod, StunDecoder};
+
+    /// Answers like [turn_server], but with the given error to requests of the given method.
+    fn failing_server(
+        method: MessageMethod,
+        code: ErrorCode<'static>,
+    ) -> (Rc<Cell<bool>>, impl Fn(&[u8]) -> Option<Bytes>) {
+        let failing = Rc::new(Cell::new(false));
+        let respond = {
+            let failing = failing.clone();
+            move |request: &[u8]| {
+                let message = StunDecoder::new(request).unwrap();
+                if failing.get() && message.method() == method {
+                    let header = MessageHeader::peek(request).unwrap();
+                    let response = MessageBuilder::new(header.error_response())
+                        .error_code(code)
+                        .to_bytes()
+                        .unwrap();
+                    return Some(response);
+                }
+                turn_server(request)
+            }
+        };
+        (failing, respond)
+    }
+
+    #[test]
+    fn test_refresh_schedule() {
+        let mut turn = turn_client(turn_server).with_lifetime(Duration::from_secs(1200));
+        let peer: SocketAddr = "198.51.100.1:5000".parse().unwrap();
+        block_on(async {
+            turn.allocate().await.unwrap();
+            turn.bind_channel(peer).await.unwrap();
+            turn.create_permission(&["198.51.100.2".parse().unwrap()])
+                .await
+                .unwrap();
+        });
+        let mut refresher = TurnRefresher::new(turn);
+        let remaining = refresher.next_refresh().unwrap() - Instant::now();
+        assert!(remaining <= Duration::from_secs(240));
+        assert!(remaining > Duration::from_secs(235));
+
+        // The permissions are due before the channel, whose binding also made a permission.
+        let event = block_on(refresher.tick()).unwrap();
+        let TurnEvent::PermissionsRefreshed { mut peers } = event else {
+            panic!("unexpected event: {:?}", event);
+        };
+        peers.sort();
+        let expected: [IpAddr; 2] = [
+            "198.51.100.1".parse().unwrap(),
+            "198.51.100.2".parse().unwrap(),
+        ];
+        assert_eq!(peers, expected);
+    }
+
+    #[test]
+    fn test_short_lifetime() {
+        let mut turn = turn_client(turn_server).with_lifetime(Duration::from_secs(100));
+        block_on(turn.allocate()).unwrap();
+        let mut refresher = TurnRefresher::new(turn);
+        // Refreshed halfway through its lifetime, as it is shorter than twice the margin.
+        let remaining = refresher.next_refresh().unwrap() - Instant::now();
+        assert!(remaining <= Duration::from_secs(50));
+        assert!(remaining > Duration::from_secs(45));
+        assert!(matches!(
+            refresher.tick_blocking().unwrap(),
+            TurnEvent::AllocationRefreshed { lifetime } if lifetime == Duration::from_secs(100)
+        ));
+    }
+
+    #[test]
+    fn test_stale_nonce() {
+        // A server that has replaced the nonce it gave for the allocation.
+        let mut turn = turn_client(|request| {
+            let message = StunDecoder::new(request).unwrap();
+            let nonce = message.get_attribute(NONCE, &Utf8Decoder);
+            if message.method() == MessageMethod::REFRESH
+                && nonce.is_some_and(|nonce| nonce.unwrap() == "nonce")
+            {
+                let header = MessageHeader::peek(request).unwrap();
+                let response = MessageBuilder::new(header.error_response())
+                    .error_code(ErrorCode::STALE_NONCE)
+                    .nonce("new nonce")
+                    .to_bytes()
+                    .unwrap();
+                return Some(response);
+            }
+            turn_server(request)
+        });
+        block_on(turn.allocate()).unwrap();
+        let mut refresher = TurnRefresher::new(turn);
+        assert!(matches!(
+            block_on(refresher.tick()).unwrap(),
+            TurnEvent::AllocationRefreshed { .. }
+        ));
+        assert_eq!(refresher.turn().auth().nonce(), Some("new nonce"));
+    }
+
+    #[test]
+    fn test_retry() {
+        let (failing, respond) = failing_server(MessageMethod::REFRESH, ErrorCode::SERVER_ERROR);
+        let mut turn = turn_client(respond);
+        block_on(turn.allocate()).unwrap();
+        let mut refresher = TurnRefresher::new(turn).with_retry_interval(Duration::from_secs(30));
+        let due = refresher.next_refresh().unwrap();
+
+        failing.set(true);
+        let result = block_on(refresher.tick());
+        assert!(matches!(
+            result,
+            Err(ClientError::ErrorResponse { code: 500, .. })
+        ));
+        assert!(refresher.turn().allocation().is_some());
+        assert!(refresher.next_refresh().unwrap() >= due + Duration::from_secs(30));
+
+        failing.set(false);
+        assert!(matches!(
+            block_on(refresher.tick()).unwrap(),
+            TurnEvent::AllocationRefreshed { .. }
+        ));
+        assert!(refresher.next_refresh().unwrap() < due + Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_allocation_lost() {
+        let (failing, respond) =
+            failing_server(MessageMethod::REFRESH, ErrorCode::ALLOCATION_MISMATCH);
+        let mut turn = turn_client(respond);
+        block_on(turn.allocate()).unwrap();
+        let mut refresher = TurnRefresher::new(turn);
+
+        failing.set(true);
+        let event = block_on(refresher.tick()).unwrap();
+        assert!(matches!(
+            event,
+            TurnEvent::AllocationLost {
+                error: ClientError::ErrorResponse { code: 437, .. }
+            }
+        ));
+        assert_eq!(refresher.next_refresh(), None);
+        assert!(matches!(
+            block_on(refresher.tick()),
+            Err(ClientError::NoAllocation)
+        ));
+    }
+}
@@ -0,0 +1,242 @@
+use std::fmt;
+use std::io;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+use std::str::FromStr;
+
+/// The scheme of a [StunUri].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Scheme {
+    /// `stun:`, for STUN over UDP or TCP.
+    Stun,
+    /// `stuns:`, for STUN over TLS or DTLS.
+    Stuns,
+}
+
+impl Scheme {
+    /// Returns the port used when a URI does not give one: 3478, or 5349 for `stuns:`.
+    pub fn default_port(self) -> u16 {
+        match self {
+            Scheme::Stun => 3478,
+            Scheme::Stuns => 5349,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Scheme::Stun => "stun",
+            Scheme::Stuns => "stuns",
+        }
+    }
+}
+
+/// Gives the reason that a [StunUri] could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UriError {
+    /// The scheme is missing, or is neither `stun` nor `stuns`.
+    InvalidScheme,
+
+    /// The host is empty, is an invalid IPv6 literal, or contains characters that are not
+    /// allowed in a host name.
+    InvalidHost,
+
+    /// The port is empty or is not a number from 0 to 65535.
+    InvalidPort,
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UriError::InvalidScheme => f.write_str("scheme is not stun or stuns"),
+            UriError::InvalidHost => f.write_str("invalid host"),
+            UriError::InvalidPort => f.write_str("invalid port"),
+        }
+    }
+}
+
+impl std::error::Error for UriError {}
+
+/// A STUN server given as a `stun:` or `stuns:` URI, as defined by RFC 7064 and used in the
+/// configuration of ICE servers.
+///
+/// The host is a host name, an IPv4 address, or an IPv6 address in brackets, optionally followed
+/// by a port; the scheme is case-insensitive.
+///
+/// ```
+/// # use stunne_client::{Scheme, StunUri};
+/// let uri: StunUri = "stun:[2001:db8::1]".parse().unwrap();
+/// assert_eq!(uri.scheme(), Scheme::Stun);
+/// assert_eq!(uri.host(), "2001:db8::1");
+/// assert_eq!(uri.port(), 3478);
+///
+/// let uri: StunUri = "stuns:stun.example.org:443".parse().unwrap();
+/// assert_eq!(uri.port(), 443);
+/// assert_eq!(uri.to_string(), "stuns:stun.example.org:443");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StunUri {
+    scheme: Scheme,
+    host: String,
+    port: Option<u16>,
+}
+
+impl StunUri {
+    /// Returns the scheme of the URI.
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Returns the host of the URI. IPv6 addresses are returned without brackets.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the host as an IP address, if it is not a host name.
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.host.parse().ok()
+    }
+
+    /// Returns the port of the URI, or the default port of its scheme if it does not give one.
+    pub fn port(&self) -> u16 {
+        self.port.unwrap_or_else(|| self.scheme.default_port())
+    }
+
+    /// Returns the URI of the same host with another scheme, on the default port of that scheme.
+    #[cfg(feature = "tls")]
+    pub(crate) fn with_scheme(&self, scheme: Scheme) -> Self {
+        Self {
+            scheme,
+            host: self.host.clone(),
+            port: None,
+        }
+    }
+}
+
+impl FromStr for StunUri {
+    type Err = UriError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = s.split_once(':').ok_or(UriError::InvalidScheme)?;
+        let scheme = if scheme.eq_ignore_ascii_case("stun") {
+            Scheme::Stun
+        } else if scheme.eq_ignore_ascii_case("stuns") {
+            Scheme::Stuns
+        } else {
+            return Err(UriError::InvalidScheme);
+        };
+
+        let (host, port) = if let Some(literal) = rest.strip_prefix('[') {
+            let (address, rest) = literal.split_once(']').ok_or(UriError::InvalidHost)?;
+            let address: Ipv6Addr = address.parse().map_err(|_| UriError::InvalidHost)?;
+            let port = match rest {
+                "" => None,
+                _ => Some(rest.strip_prefix(':').ok_or(UriError::InvalidPort)?),
+            };
+            (address.to_string(), port)
+        } else {
+            let (host, port) = match rest.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (rest, None),
+            };
+            if host.is_empty() || !host.bytes().all(is_host_char) {
+                return Err(UriError::InvalidHost);
+            }
+            (host.to_string(), port)
+        };
+
+        let port = match port {
+            Some(port) if !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()) => {
+                Some(port.parse().map_err(|_| UriError::InvalidPort)?)
+            }
+            Some(_) => return Err(UriError::InvalidPort),
+            None => None,
+        };
+
+        Ok(Self { scheme, host, port })
+    }
+}
+
+/// Returns whether a byte may appear in a host name (the `reg-name` of RFC 3986): unreserved
+/// characters, sub-delimiters, and percent-encoded octets.
+fn is_host_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=%".contains(&b)
+}
+
+impl fmt::Display for StunUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:", self.scheme.as_str())?;
+        if self.host.contains(':') {
+            write!(f, "[{}]", self.host)?;
+        } else {
+            f.write_str(&self.host)?;
+        }
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+impl ToSocketAddrs for StunUri {
+    type Iter = std::vec::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        (self.host.as_str(), self.port()).to_socket_addrs()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<StunUri, UriError> {
+        s.parse()
+    }
+
+    #[test]
+    fn test_parse() {
+        let uri = parse("stun:stun.example.org").unwrap();
+        assert_eq!(uri.scheme(), Scheme::Stun);
+        assert_eq!(uri.host(), "stun.example.org");
+        assert_eq!(uri.ip(), None);
+        assert_eq!(uri.port(), 3478);
+
+        let uri = parse("STUNS:192.0.2.1").unwrap();
+        assert_eq!(uri.scheme(), Scheme::Stuns);
+        assert_eq!(uri.ip(), Some("192.0.2.1".parse().unwrap()));
+        assert_eq!(uri.port(), 5349);
+
+        let uri = parse("stun:[2001:DB8::1]:19302").unwrap();
+        assert_eq!(uri.host(), "2001:db8::1");
+        assert_eq!(uri.port(), 19302);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(parse("stun.example.org"), Err(UriError::InvalidScheme));
+        assert_eq!(parse("turn:example.org"), Err(UriError::InvalidScheme));
+        assert_eq!(parse("stun:"), Err(UriError::InvalidHost));
+        assert_eq!(parse("stun://example.org"), Err(UriError::InvalidHost));
+        assert_eq!(parse("stun:[2001:db8::1"), Err(UriError::InvalidHost));
+        assert_eq!(parse("stun:[example.org]"), Err(UriError::InvalidHost));
+        assert_eq!(parse("stun:2001:db8::1"), Err(UriError::InvalidPort));
+        assert_eq!(parse("stun:[::1]3478"), Err(UriError::InvalidPort));
+        assert_eq!(parse("stun:example.org:"), Err(UriError::InvalidPort));
+        assert_eq!(parse("stun:example.org:65536"), Err(UriError::InvalidPort));
+        assert_eq!(parse("stun:example.org:+1"), Err(UriError::InvalidPort));
+    }
+
+    #[test]
+    fn test_display() {
+        for uri in ["stun:example.org", "stuns:[::1]:443", "stun:192.0.2.1:3478"] {
+            assert_eq!(parse(uri).unwrap().to_string(), uri);
+        }
+        assert_eq!(parse("Stun:[::1]").unwrap().to_string(), "stun:[::1]");
+    }
+
+    #[test]
+    fn test_to_socket_addrs() {
+        let uri = parse("stun:[::1]").unwrap();
+        let addresses: Vec<_> = uri.to_socket_addrs().unwrap().collect();
+        assert_eq!(addresses, ["[::1]:3478".parse().unwrap()]);
+    }
+}
@@ -0,0 +1,55 @@
+//! Introduces pairs of peers to each other, for the `rendezvous-peer` example.
+//!
+//! Each peer connects over TCP and sends a line `<session> <mapped address>`. Once two peers have
+//! given the same session, each is sent a line with the mapped address of the other, and both
+//! connections are closed.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::Duration;
+
+/// How long a peer has to send its registration, as connections are handled one at a time.
+const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn main() -> std::io::Result<()> {
+    let address: SocketAddr = std::env::args()
+        .nth(1)
+        .expect("Must provide address and port to listen on (e.g., '0.0.0.0:4000')")
+        .parse()
+        .expect("Address one is not a valid address");
+
+    let listener = TcpListener::bind(address)?;
+    println!("Listening on {}", listener.local_addr()?);
+    let mut waiting: HashMap<String, (TcpStream, SocketAddr)> = HashMap::new();
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        let Some((session, mapped_address)) = read_registration(&stream) else {
+            println!("Ignoring invalid registration from {}", stream.peer_addr()?);
+            continue;
+        };
+        println!("Peer {} joined session {:?}", mapped_address, session);
+        match waiting.remove(&session) {
+            Some((mut other, other_address)) => {
+                println!("Introducing {} and {}", mapped_address, other_address);
+                // A peer that has gone away only fails its own introduction.
+                let _ = writeln!(stream, "{}", other_address);
+                let _ = writeln!(other, "{}", mapped_address);
+            }
+            None => {
+                waiting.insert(session, (stream, mapped_address));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the line that a peer registers with, giving up after [REGISTRATION_TIMEOUT] so that a
+/// peer that never sends it does not hold up the others.
+fn read_registration(stream: &TcpStream) -> Option<(String, SocketAddr)> {
+    stream.set_read_timeout(Some(REGISTRATION_TIMEOUT)).ok()?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    let (session, mapped_address) = line.trim().split_once(' ')?;
+    Some((session.to_string(), mapped_address.parse().ok()?))
+}
@@ -0,0 +1,65 @@
+//! Connects directly to another peer through the NATs in between, using the
+//! `rendezvous-coordinator` example to exchange mapped addresses.
+//!
+//! Run two peers with the same session name, e.g. on two different networks:
+//!
+//! ```text
+//! rendezvous-peer coordinator.example.org:4000 stun.example.org my-session
+//! ```
+//!
+//! Each peer learns the address that its NAT maps its UDP socket to from the STUN server, sends it
+//! to the coordinator, and receives the address of the other peer in return. Both peers then punch
+//! a hole through their NATs towards each other, and exchange a greeting directly.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::time::Duration;
+use stunne_client::{get_mapped_address_from, punch_hole, ClientConfig};
+use stunne_protocol::is_stun;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(3);
+
+fn main() -> std::io::Result<()> {
+    let mut args = std::env::args().skip(1);
+    let (Some(coordinator), Some(stun_server), Some(session)) =
+        (args.next(), args.next(), args.next())
+    else {
+        panic!("Must provide the coordinator's address, a STUN server, and a session name");
+    };
+
+    let config = ClientConfig::default();
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    let mapped_address = get_mapped_address_from(&socket, &stun_server, &config)
+        .expect("Could not learn the mapped address from the STUN server");
+    println!("Mapped address: {}", mapped_address);
+
+    println!("Waiting for another peer to join session {:?}...", session);
+    let mut coordinator = TcpStream::connect(coordinator)?;
+    writeln!(coordinator, "{} {}", session, mapped_address)?;
+    let mut line = String::new();
+    BufReader::new(coordinator).read_line(&mut line)?;
+    let peer: SocketAddr = line
+        .trim()
+        .parse()
+        .expect("The coordinator did not send the address of a peer");
+    println!("Peer address: {}", peer);
+
+    let socket = punch_hole(socket, peer, &config).expect("Could not reach the peer");
+    println!("Connected directly to {}", peer);
+
+    socket.send(format!("Hello from {}!", mapped_address).as_bytes())?;
+    socket.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut buf = [0; 1024];
+    // Late requests and responses of the hole punching may still arrive.
+    loop {
+        let len = socket
+            .recv(&mut buf)
+            .expect("Timeout while waiting for the peer's greeting");
+        if !is_stun(&buf[..len]) {
+            println!("Received: {}", String::from_utf8_lossy(&buf[..len]));
+            break;
+        }
+    }
+
+    Ok(())
+}
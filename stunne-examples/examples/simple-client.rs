@@ -1,124 +1,406 @@
-use bytes::BytesMut;
-use std::net::UdpSocket;
-use std::time::Duration;
-use stunne_protocol::encodings::{
-    ChangeRequest, ChangeRequestDecoder, MappedAddress, Utf8Decoder, XorMappedAddress,
+use bytes::{Bytes, BytesMut};
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+use stunne_protocol::attributes::types::{
+    CHANGE_REQUEST, OTHER_ADDRESS, PADDING, RESPONSE_ORIGIN, RESPONSE_PORT, SOFTWARE,
+    XOR_MAPPED_ADDRESS,
 };
+use stunne_protocol::encodings::{ChangeRequest, Data, MappedAddressDecoder, XorMappedAddress};
 use stunne_protocol::*;
 
 const READ_TIMEOUT: Duration = Duration::from_secs(3);
 
-const XOR_MAPPED_ADDRESS: u16 = 0x0020;
-const XOR_MAPPED_ADDRESS_TEXT: &str = "XOR-MAPPED-ADDRESS";
+const USAGE: &str = "\
+Usage: simple-client <server> [OPTIONS]
 
-const MAPPED_ADDRESS: u16 = 0x0001;
-const MAPPED_ADDRESS_TEXT: &str = "MAPPED-ADDRESS";
+Options:
+  --change-ip             Ask the server to respond from its alternate IP address (RFC 5780).
+  --change-port           Ask the server to respond from its alternate port (RFC 5780).
+  --response-port N       Ask the server to send the response to local port N, which is bound
+                          to receive it (RFC 5780).
+  --padding N             Add a PADDING attribute of N bytes (at most 65535), for checking
+                          whether fragmented requests get through (RFC 5780).
+  --attr TYPE=KIND:VALUE  Add an attribute to the request. TYPE is a number, either decimal or
+                          hexadecimal with a 0x prefix. KIND is `string`, for a UTF-8 value, or
+                          `hex`, for a value given as hexadecimal digits.
+                          e.g. --attr 0x8022=string:foo --attr 0x0003=hex:00000002
+  --watch INTERVAL        Query the server repeatedly from the same socket, printing the mapped
+                          address each time and highlighting when it changes. INTERVAL is a
+                          number of seconds, or a number followed by `ms`, `s` or `m`.";
 
-const RESPONSE_ORIGIN: u16 = 0x802B;
-const RESPONSE_ORIGIN_TEXT: &str = "RESPONSE-ORIGIN";
+struct Args {
+    server: String,
+    change_request: ChangeRequest,
+    response_port: Option<u16>,
+    padding: Option<usize>,
+    attributes: Vec<(u16, Vec<u8>)>,
+    watch: Option<Duration>,
+}
 
-const OTHER_ADDRESS: u16 = 0x802C;
-const OTHER_ADDRESS_TEXT: &str = "OTHER-ADDRESS";
+fn parse_args() -> Result<Args, String> {
+    let mut server = None;
+    let mut change_request = ChangeRequest {
+        change_ip: false,
+        change_port: false,
+    };
+    let mut response_port = None;
+    let mut padding = None;
+    let mut attributes = Vec::new();
+    let mut watch = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--change-ip" {
+            change_request.change_ip = true;
+        } else if arg == "--change-port" {
+            change_request.change_port = true;
+        } else if arg == "--response-port" {
+            let port = args.next().ok_or("--response-port requires a value")?;
+            match port.parse() {
+                Ok(0) | Err(_) => return Err(format!("invalid port {:?}", port)),
+                Ok(port) => response_port = Some(port),
+            }
+        } else if arg == "--padding" {
+            let len = args.next().ok_or("--padding requires a value")?;
+            // The length of an attribute's value is a 16-bit field.
+            let len: u16 = len
+                .parse()
+                .map_err(|_| format!("invalid length {:?}", len))?;
+            padding = Some(usize::from(len));
+        } else if arg == "--attr" {
+            let attribute = args.next().ok_or("--attr requires a value")?;
+            attributes.push(parse_attribute(&attribute)?);
+        } else if let Some(attribute) = arg.strip_prefix("--attr=") {
+            attributes.push(parse_attribute(attribute)?);
+        } else if arg == "--watch" {
+            let interval = args.next().ok_or("--watch requires a value")?;
+            watch = Some(parse_interval(&interval)?);
+        } else if let Some(interval) = arg.strip_prefix("--watch=") {
+            watch = Some(parse_interval(interval)?);
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option {}", arg));
+        } else if server.replace(arg).is_some() {
+            return Err("only one server can be given".to_string());
+        }
+    }
+    Ok(Args {
+        server: server.ok_or("the address of a server must be given")?,
+        change_request,
+        response_port,
+        padding,
+        attributes,
+        watch,
+    })
+}
 
-const SOFTWARE: u16 = 0x8022;
-const SOFTWARE_TEXT: &str = "SOFTWARE";
+/// Parses an attribute given as `TYPE=KIND:VALUE`.
+fn parse_attribute(s: &str) -> Result<(u16, Vec<u8>), String> {
+    let invalid = |reason: &str| format!("invalid attribute {:?}: {}", s, reason);
+    let (attribute_type, value) = s.split_once('=').ok_or_else(|| invalid("missing `=`"))?;
+    let attribute_type = match attribute_type.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16),
+        None => attribute_type.parse(),
+    }
+    .map_err(|_| invalid("the type is not a 16-bit number"))?;
+    let value = match value.split_once(':') {
+        Some(("string", value)) => value.as_bytes().to_vec(),
+        Some(("hex", value)) => parse_hex(value).ok_or_else(|| invalid("invalid hex value"))?,
+        _ => return Err(invalid("the value must start with `string:` or `hex:`")),
+    };
+    if value.len() > usize::from(u16::MAX) {
+        return Err(invalid("the value is longer than 65535 bytes"));
+    }
+    Ok((attribute_type, value))
+}
 
-const CHANGE_REQUEST: u16 = 0x0003;
-const CHANGE_REQUEST_TEXT: &str = "CHANGE-REQUEST";
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
 
-const UNKNOWN_TEXT: &str = "UNKNOWN";
+/// Parses an interval given as a number of seconds, or as a number followed by a unit.
+fn parse_interval(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid interval {:?}", s);
+    let (value, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let value: u64 = value.parse().map_err(|_| invalid())?;
+    let interval = match unit {
+        "ms" => Duration::from_millis(value),
+        "s" => Duration::from_secs(value),
+        "m" => value
+            .checked_mul(60)
+            .map(Duration::from_secs)
+            .ok_or_else(invalid)?,
+        _ => return Err(invalid()),
+    };
+    if interval.is_zero() {
+        return Err("the interval must not be zero".to_string());
+    }
+    Ok(interval)
+}
 
-fn main() -> std::io::Result<()> {
-    let address = std::env::args()
-        .nth(1)
-        .expect("Must provide one argument: address of server");
-    let socket = UdpSocket::bind("0.0.0.0:0")?;
-    socket.connect(address)?;
+fn main() -> io::Result<()> {
+    let args = parse_args().unwrap_or_else(|e| {
+        eprintln!("Error: {}\n\n{}", e, USAGE);
+        std::process::exit(2);
+    });
+    let sockets = Sockets::bind(&args)?;
+    match args.watch {
+        Some(interval) => watch(&sockets, &args, interval),
+        None => query(&sockets, &args),
+    }
+}
 
-    let buf = BytesMut::with_capacity(1024);
-    let bytes = StunEncoder::new(buf)
-        .encode_header(MessageHeader {
-            class: MessageClass::Request,
-            method: MessageMethod::BINDING,
-            tx_id: TransactionId::random(),
+/// The socket that requests are sent from, and the one that responses are received on, which is
+/// another socket if a RESPONSE-PORT is requested. The sockets are not connected, so that
+/// responses from the alternate address of the server are received too.
+struct Sockets {
+    socket: UdpSocket,
+    responses: Option<UdpSocket>,
+    server: SocketAddr,
+}
+
+impl Sockets {
+    fn bind(args: &Args) -> io::Result<Self> {
+        let server = args.server.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "the server has no addresses")
+        })?;
+        let unspecified = match server {
+            SocketAddr::V4(_) => "0.0.0.0",
+            SocketAddr::V6(_) => "::",
+        };
+        let socket = UdpSocket::bind((unspecified, 0))?;
+        let responses = match args.response_port {
+            Some(port) => Some(UdpSocket::bind((unspecified, port))?),
+            None => None,
+        };
+        Ok(Self {
+            socket,
+            responses,
+            server,
         })
-        .add_attribute(
-            CHANGE_REQUEST,
-            &ChangeRequest {
-                change_ip: false,
-                change_port: true,
+    }
+
+    fn send(&self, bytes: &[u8]) -> io::Result<()> {
+        self.socket.send_to(bytes, self.server).map(drop)
+    }
+
+    fn receiver(&self) -> &UdpSocket {
+        self.responses.as_ref().unwrap_or(&self.socket)
+    }
+}
+
+/// Returns a new Binding request, and its header.
+fn encode_request(args: &Args) -> (MessageHeader, Bytes) {
+    let request = MessageHeader {
+        class: MessageClass::Request,
+        method: MessageMethod::BINDING,
+        tx_id: TransactionId::random(),
+    };
+    let buf = BytesMut::with_capacity(1024);
+    let mut encoder = StunEncoder::new(buf)
+        .encode_header(request.clone())
+        .add_attribute(SOFTWARE, &"stunne");
+    let change_request = args.change_request;
+    if change_request.change_ip || change_request.change_port {
+        encoder = encoder.add_attribute(CHANGE_REQUEST, &change_request);
+    }
+    if let Some(port) = args.response_port {
+        // The port, followed by two reserved bytes.
+        encoder = encoder.add_attribute(RESPONSE_PORT, &(u32::from(port) << 16));
+    }
+    if let Some(len) = args.padding {
+        encoder = encoder.add_attribute(
+            PADDING,
+            &Data {
+                payload: &vec![0; len],
             },
-        )
-        .add_attribute(SOFTWARE, &"stunne")
+        );
+    }
+    // Attributes given on the command line are added as-is, after those of the example.
+    let bytes = args
+        .attributes
+        .iter()
+        .fold(encoder, |encoder, (attribute_type, value)| {
+            encoder.add_attribute(*attribute_type, &Data { payload: value })
+        })
         .finish();
-    socket.send(bytes.as_ref())?;
+    (request, bytes)
+}
+
+fn decode_options() -> DecodeOptions {
+    DecodeOptions {
+        allow_classic: true,
+        ..DecodeOptions::lenient()
+    }
+}
+
+fn mapped_address(msg: &StunDecoder) -> Option<Result<SocketAddr, String>> {
+    let decoder = XorMappedAddress::decoder(msg.tx_id());
+    msg.get_attribute(XOR_MAPPED_ADDRESS, &decoder)
+        .map(|address| address.map_err(|e| format!("{:?}", e)))
+}
+
+/// Sends a single request, and prints the response in full.
+fn query(sockets: &Sockets, args: &Args) -> io::Result<()> {
+    let (request, bytes) = encode_request(args);
+    sockets.send(bytes.as_ref())?;
     println!("Waiting for response...");
 
-    let mut incoming_buf = [0; 1024];
+    let mut incoming_buf = [0; 1500];
+    let socket = sockets.receiver();
     socket.set_read_timeout(Some(READ_TIMEOUT))?;
-    let amt = socket
-        .recv(&mut incoming_buf)
-        .expect("Timeout while waiting for response");
-    let msg = StunDecoder::new(&incoming_buf[0..amt]).unwrap();
+    let (amt, source) = match socket.recv_from(&mut incoming_buf) {
+        Err(e) if sockets.responses.is_some() && is_timeout(&e) => {
+            // A server that does not support RESPONSE-PORT responds to the port of the request.
+            sockets.socket.set_nonblocking(true)?;
+            let received = sockets
+                .socket
+                .recv_from(&mut incoming_buf)
+                .expect("Timeout while waiting for response");
+            println!("The server ignored RESPONSE-PORT");
+            received
+        }
+        received => received.expect("Timeout while waiting for response"),
+    };
+    let msg = StunDecoder::with_options(&incoming_buf[0..amt], decode_options()).unwrap();
 
     println!();
-    println!("## Header ##");
-    println!("* Class:  {:?}", msg.class());
-    println!("* Method: {:?}", msg.method());
-    println!("* Tx ID:  {:?}", msg.tx_id());
+    println!("Received from {}", source);
+    print!("{}", msg.report());
+    if !msg.header().is_response_to(&request) {
+        println!("Warning: the message is not a response to the request");
+    }
     println!();
-    println!("## Attributes ##");
-    for attribute in msg.attributes() {
-        match attribute {
-            Ok(attr) => {
-                print!(
-                    "* {: <20}",
-                    match attr.attribute_type() {
-                        XOR_MAPPED_ADDRESS => XOR_MAPPED_ADDRESS_TEXT,
-                        MAPPED_ADDRESS => MAPPED_ADDRESS_TEXT,
-                        RESPONSE_ORIGIN => RESPONSE_ORIGIN_TEXT,
-                        OTHER_ADDRESS => OTHER_ADDRESS_TEXT,
-                        CHANGE_REQUEST => CHANGE_REQUEST_TEXT,
-                        SOFTWARE => SOFTWARE_TEXT,
-                        _ => UNKNOWN_TEXT,
-                    }
-                );
+    match mapped_address(&msg) {
+        Some(Ok(address)) => println!("Reflexive address: {}", address),
+        Some(Err(e)) => println!("Could not read reflexive address: {}", e),
+        None => println!("Server did not return a reflexive address"),
+    }
+    interpret(&msg, args, sockets.server, source);
 
-                match attr.attribute_type() {
-                    MAPPED_ADDRESS => {
-                        let decoder = MappedAddress::decoder();
-                        println!("{:?}", attr.decode(&decoder));
-                    }
-                    XOR_MAPPED_ADDRESS => {
-                        let decoder = XorMappedAddress::decoder(msg.header().tx_id);
-                        println!("{:?}", attr.decode(&decoder));
-                    }
-                    RESPONSE_ORIGIN => {
-                        let decoder = MappedAddress::decoder();
-                        println!("{:?}", attr.decode(&decoder));
-                    }
-                    OTHER_ADDRESS => {
-                        let decoder = MappedAddress::decoder();
-                        println!("{:?}", attr.decode(&decoder));
-                    }
-                    CHANGE_REQUEST => {
-                        let decoder = ChangeRequestDecoder::default();
-                        println!("{:?}", attr.decode(&decoder));
-                    }
-                    SOFTWARE => {
-                        let decoder = Utf8Decoder::default();
-                        println!("{:?}", attr.decode(&decoder));
+    Ok(())
+}
+
+/// Explains the RFC 5780 attributes of a response, and whether the server did what was asked.
+fn interpret(msg: &StunDecoder, args: &Args, server: SocketAddr, source: SocketAddr) {
+    let origin = msg.get_attribute(RESPONSE_ORIGIN, &MappedAddressDecoder);
+    let other = msg.get_attribute(OTHER_ADDRESS, &MappedAddressDecoder);
+    match other {
+        Some(Ok(other)) => println!(
+            "The server supports NAT behavior discovery; its alternate address is {}",
+            other
+        ),
+        _ => println!(
+            "The server gave no OTHER-ADDRESS, so it does not support NAT behavior discovery"
+        ),
+    }
+    if let Some(Ok(origin)) = origin {
+        if origin != source {
+            println!(
+                "The response was sent from {}, but received from {}: there is a NAT in front \
+                 of the server",
+                origin, source
+            );
+        }
+    }
+
+    let ChangeRequest {
+        change_ip,
+        change_port,
+    } = args.change_request;
+    if change_ip || change_port {
+        let honored = (source.ip() != server.ip()) == change_ip
+            && (source.port() != server.port()) == change_port;
+        if honored {
+            println!("The server responded from the address asked for by CHANGE-REQUEST");
+        } else {
+            println!(
+                "The server ignored CHANGE-REQUEST: it responded from {}, having been sent the \
+                 request at {}",
+                source, server
+            );
+        }
+    }
+}
+
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+/// Sends a request every `interval` from the same socket, printing a line for each response and
+/// marking the lines where the mapped address, or only its port, changed.
+fn watch(sockets: &Sockets, args: &Args, interval: Duration) -> ! {
+    let start = Instant::now();
+    let mut previous: Option<SocketAddr> = None;
+    let mut next = start;
+    loop {
+        let (request, bytes) = encode_request(args);
+        let elapsed = start.elapsed().as_secs_f64();
+        // Errors (e.g. ICMP port unreachable) are reported, but do not stop the monitor.
+        let result = sockets
+            .send(bytes.as_ref())
+            .and_then(|_| receive(sockets.receiver(), &request));
+        match result {
+            Ok(Some(Ok(address))) => {
+                let change = match previous {
+                    Some(previous) if previous.ip() != address.ip() => {
+                        format!("  <-- CHANGED from {}", previous)
                     }
-                    _ => {
-                        println!("{:?}", attr);
+                    Some(previous) if previous.port() != address.port() => {
+                        format!("  <-- PORT CHANGED from {}", previous.port())
                     }
+                    _ => String::new(),
                 };
+                println!("[{:>9.1}s] {}{}", elapsed, address, change);
+                previous = Some(address);
             }
-            Err(e) => {
-                println!("Error reading attribute: {:#?}", e);
-            }
+            Ok(Some(Err(e))) => println!("[{:>9.1}s] no mapped address: {}", elapsed, e),
+            Ok(None) => println!("[{:>9.1}s] no response", elapsed),
+            Err(e) => println!("[{:>9.1}s] error: {}", elapsed, e),
         }
+        next += interval;
+        thread::sleep(next.saturating_duration_since(Instant::now()));
     }
+}
 
-    Ok(())
+/// Waits for the response to `request`, ignoring any other message (e.g. a late response to an
+/// earlier request). Returns None if none arrives in time.
+fn receive(
+    socket: &UdpSocket,
+    request: &MessageHeader,
+) -> io::Result<Option<Result<SocketAddr, String>>> {
+    let deadline = Instant::now() + READ_TIMEOUT;
+    let mut incoming_buf = [0; 1500];
+    loop {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        if timeout.is_zero() {
+            return Ok(None);
+        }
+        socket.set_read_timeout(Some(timeout))?;
+        let amt = match socket.recv(&mut incoming_buf) {
+            Ok(amt) => amt,
+            Err(e) if is_timeout(&e) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let Ok(msg) = StunDecoder::with_options(&incoming_buf[0..amt], decode_options()) else {
+            continue;
+        };
+        if msg.header().is_response_to(request) {
+            let address = mapped_address(&msg)
+                .unwrap_or_else(|| Err("the server did not return one".to_string()));
+            return Ok(Some(address));
+        }
+    }
 }
@@ -1,12 +1,10 @@
 use bytes::BytesMut;
 use std::net::SocketAddr;
 use std::net::UdpSocket;
+use stunne_protocol::attributes::types::{MAPPED_ADDRESS, SOFTWARE, XOR_MAPPED_ADDRESS};
 use stunne_protocol::encodings::{MappedAddress, XorMappedAddress};
-use stunne_protocol::{MessageClass, MessageHeader, MessageMethod, StunDecoder, StunEncoder};
-
-const SOFTWARE: u16 = 0x8022;
-const XOR_MAPPED_ADDRESS: u16 = 0x0020;
-const MAPPED_ADDRESS: u16 = 0x0001;
+use stunne_protocol::ext::SocketAddrExt;
+use stunne_protocol::{is_stun, MessageClass, MessageMethod, StunDecoder, StunEncoder};
 
 fn main() -> std::io::Result<()> {
     let address: SocketAddr = std::env::args()
@@ -16,30 +14,28 @@ fn main() -> std::io::Result<()> {
         .expect("Address one is not a valid address");
 
     let socket = UdpSocket::bind(address)?;
+    let (response_origin_type, response_origin) = socket.local_addr()?.as_response_origin();
     let mut buf = [0; 1024];
 
     loop {
         let (bytes, origin) = socket.recv_from(&mut buf).expect("Error reading");
-        let msg = StunDecoder::new(&buf[0..=bytes]).unwrap();
-        match (msg.class(), msg.method()) {
-            (MessageClass::Request, MessageMethod::BINDING) => {
-                let response_buf = BytesMut::with_capacity(1024);
-                let bytes = StunEncoder::new(response_buf)
-                    .encode_header(MessageHeader {
-                        class: MessageClass::SuccessResponse,
-                        method: MessageMethod::BINDING,
-                        tx_id: msg.tx_id(),
-                    })
-                    .add_attribute(MAPPED_ADDRESS, &MappedAddress::encoder(origin))
-                    .add_attribute(
-                        XOR_MAPPED_ADDRESS,
-                        &XorMappedAddress::encoder(origin, msg.tx_id()),
-                    )
-                    .add_attribute(SOFTWARE, &"stunne-server")
-                    .finish();
-                socket.send_to(bytes.as_ref(), origin)?;
-            }
-            _ => {}
+        if !is_stun(&buf[..bytes]) {
+            continue;
+        }
+        let msg = StunDecoder::new(&buf[..bytes]).unwrap();
+        if let (MessageClass::Request, MessageMethod::BINDING) = (msg.class(), msg.method()) {
+            let response_buf = BytesMut::with_capacity(1024);
+            let bytes = StunEncoder::new(response_buf)
+                .encode_header(msg.header().success_response())
+                .add_attribute(MAPPED_ADDRESS, &MappedAddress::encoder(origin))
+                .add_attribute(
+                    XOR_MAPPED_ADDRESS,
+                    &XorMappedAddress::encoder(origin, msg.tx_id()),
+                )
+                .add_attribute(response_origin_type, &response_origin)
+                .add_attribute(SOFTWARE, &"stunne-server")
+                .finish();
+            socket.send_to(bytes.as_ref(), origin)?;
         }
     }
 }
@@ -0,0 +1,141 @@
+// smoltcp only provides TUN interfaces on Linux and Android.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn main() -> std::io::Result<()> {
+    tun::main()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn main() {
+    eprintln!("smoltcp-client needs a TUN interface, which is only supported on Linux and Android");
+    std::process::exit(1);
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod tun {
+    use smoltcp::iface::{Config, Interface, SocketSet};
+    use smoltcp::phy::{wait, Device, Medium, TunTapInterface};
+    use smoltcp::socket::udp;
+    use smoltcp::time::Instant;
+    use smoltcp::wire::{HardwareAddress, IpCidr, IpEndpoint, Ipv4Address};
+    use std::io;
+    use std::net::SocketAddr;
+    use std::os::unix::io::AsRawFd;
+    use stunne_protocol::*;
+
+    const USAGE: &str = "\
+Usage: smoltcp-client <tun> <address/prefix> <gateway> <server>
+
+Discovers the public address of a smoltcp network stack, in the way an embedded device would,
+using a TUN interface in place of the device's network hardware. <server> is the IPv4 address and
+port of a STUN server, as smoltcp does not resolve names here.
+
+e.g., as root, with NAT from the TUN interface to the Internet:
+  ip tuntap add name tun0 mode tun user $USER
+  ip link set tun0 up
+  ip addr add 192.168.69.100/24 dev tun0
+  iptables -t nat -A POSTROUTING -s 192.168.69.0/24 -j MASQUERADE
+  sysctl net.ipv4.ip_forward=1
+then:
+  smoltcp-client tun0 192.168.69.1/24 192.168.69.100 198.51.100.1:3478";
+
+    /// The port of the UDP socket that the Binding request is sent from.
+    const LOCAL_PORT: u16 = 49152;
+
+    struct Args {
+        tun: String,
+        address: IpCidr,
+        gateway: Ipv4Address,
+        server: IpEndpoint,
+    }
+
+    fn parse_args() -> Result<Args, String> {
+        let args: Vec<_> = std::env::args().skip(1).collect();
+        let [tun, address, gateway, server] = &args[..] else {
+            return Err("expected 4 arguments".to_string());
+        };
+        Ok(Args {
+            tun: tun.clone(),
+            address: address
+                .parse()
+                .map_err(|_| format!("invalid address: {}", address))?,
+            gateway: gateway
+                .parse()
+                .map_err(|_| format!("invalid gateway: {}", gateway))?,
+            server: server
+                .parse()
+                .map_err(|_| format!("invalid server: {}", server))?,
+        })
+    }
+
+    pub fn main() -> io::Result<()> {
+        let args = parse_args().unwrap_or_else(|e| {
+            eprintln!("Error: {}\n\n{}", e, USAGE);
+            std::process::exit(2);
+        });
+        let mut device = TunTapInterface::new(&args.tun, Medium::Ip)?;
+        let fd = device.as_raw_fd();
+        let mut iface = Interface::new(
+            Config::new(HardwareAddress::Ip),
+            &mut device,
+            Instant::now(),
+        );
+        iface.update_ip_addrs(|addresses| {
+            addresses
+                .push(args.address)
+                .expect("an interface has room for one address");
+        });
+        iface
+            .routes_mut()
+            .add_default_ipv4_route(args.gateway)
+            .expect("the route table has room for the default route");
+
+        // On a device, the transaction ID would come from the hardware RNG.
+        let tx_id = TransactionId::random_with(&mut rand::rngs::OsRng);
+        match discover(&mut iface, &mut device, args.server, tx_id, |delay| {
+            wait(fd, delay)
+        })? {
+            Some(address) => println!("Public address: {}", address),
+            None => println!("No public address: the server did not answer, or sent an error"),
+        }
+        Ok(())
+    }
+
+    /// Sends a Binding request to `server` and returns the mapped address of the response, as the
+    /// firmware of a device would. Only `Instant::now` and `sleep`, which waits for a packet or
+    /// until the given delay has passed, depend on the platform.
+    fn discover<D: Device>(
+        iface: &mut Interface,
+        device: &mut D,
+        server: IpEndpoint,
+        tx_id: TransactionId,
+        mut sleep: impl FnMut(Option<smoltcp::time::Duration>) -> io::Result<()>,
+    ) -> io::Result<Option<SocketAddr>> {
+        let mut sockets = SocketSet::new(Vec::new());
+        let buffer = || udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 1500]);
+        let mut socket = udp::Socket::new(buffer(), buffer());
+        socket
+            .bind(LOCAL_PORT)
+            .expect("the port is neither zero nor bound already");
+        let handle = sockets.add(socket);
+
+        let config = TransactionConfig::default();
+        let mut binding = SmoltcpTransaction::binding(server, tx_id, config, Instant::now());
+        loop {
+            let now = Instant::now();
+            iface.poll(now, device, &mut sockets);
+            if binding.poll(sockets.get_mut(handle), now).is_some() {
+                break;
+            }
+            // Send the request now, rather than after sleeping.
+            iface.poll(now, device, &mut sockets);
+
+            let retransmission = binding.poll_at().map(|at| at - now);
+            let delay = match iface.poll_delay(now, &sockets) {
+                Some(delay) => Some(retransmission.map_or(delay, |r| r.min(delay))),
+                None => retransmission,
+            };
+            sleep(delay)?;
+        }
+        Ok(binding.mapped_address())
+    }
+}
@@ -0,0 +1,215 @@
+use crate::attributes::types::*;
+use crate::attributes::StunAttribute;
+use crate::encodings::{
+    AttributeDecoder, ChangeRequest, ChangeRequestDecoder, ErrorCode, ErrorCodeDecoder,
+    Fingerprint, FingerprintDecoder, MappedAddressDecoder, MessageIntegrity,
+    MessageIntegrityDecoder, SoftwareDecoder, U32Decoder, U64Decoder, XorMappedAddressDecoder,
+};
+use crate::TransactionId;
+use core::fmt;
+use core::net::SocketAddr;
+
+/// A decoded attribute of one of the commonly used types, as yielded by
+/// [StunDecoder::typed_attributes](crate::StunDecoder::typed_attributes).
+///
+/// Values are borrowed from the message where possible, so decoding does not allocate. Attributes
+/// of any other type are returned as [Unknown](Self::Unknown), with their raw value, to be decoded
+/// by the application if needed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KnownAttribute<'a> {
+    MappedAddress(SocketAddr),
+    /// The address, already XORed with the magic cookie and transaction ID.
+    XorMappedAddress(SocketAddr),
+    ChangeRequest(ChangeRequest),
+    MessageIntegrity(MessageIntegrity),
+    ErrorCode(ErrorCode<'a>),
+    Lifetime(u32),
+    /// The address, already XORed with the magic cookie and transaction ID.
+    XorPeerAddress(SocketAddr),
+    /// The address, already XORed with the magic cookie and transaction ID.
+    XorRelayedAddress(SocketAddr),
+    Priority(u32),
+    UseCandidate,
+    Software(&'a str),
+    AlternateServer(SocketAddr),
+    Fingerprint(Fingerprint),
+    IceControlled(u64),
+    IceControlling(u64),
+    ResponseOrigin(SocketAddr),
+    OtherAddress(SocketAddr),
+
+    /// An attribute of a type not listed above.
+    Unknown {
+        attribute_type: u16,
+        /// The raw value of the attribute, excluding any padding.
+        data: &'a [u8],
+    },
+}
+
+/// Gives the reason that the value of a known attribute type could not be decoded by
+/// [KnownAttribute::decode]. The specific error can be found by decoding the attribute with the
+/// decoder for its type.
+#[derive(Debug, PartialEq, Eq)]
+pub struct InvalidAttributeValue {
+    /// The type of the attribute whose value is invalid.
+    pub attribute_type: u16,
+}
+
+impl fmt::Display for InvalidAttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {} value",
+            DisplayAttributeType(self.attribute_type)
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidAttributeValue {}
+
+impl<'a> KnownAttribute<'a> {
+    /// Decodes the attribute, using the transaction ID of its message to decode XORed addresses.
+    pub fn decode(
+        attribute: &StunAttribute<'a>,
+        tx_id: TransactionId,
+    ) -> Result<Self, InvalidAttributeValue> {
+        let attribute_type = attribute.attribute_type();
+        let data = attribute.data();
+        let xor_address = || XorMappedAddressDecoder::new(tx_id).decode(data).ok();
+        let known = match attribute_type {
+            MAPPED_ADDRESS => MappedAddressDecoder
+                .decode(data)
+                .ok()
+                .map(Self::MappedAddress),
+            XOR_MAPPED_ADDRESS => xor_address().map(Self::XorMappedAddress),
+            CHANGE_REQUEST => ChangeRequestDecoder
+                .decode(data)
+                .ok()
+                .map(Self::ChangeRequest),
+            MESSAGE_INTEGRITY => MessageIntegrityDecoder
+                .decode(data)
+                .ok()
+                .map(Self::MessageIntegrity),
+            ERROR_CODE => ErrorCodeDecoder.decode(data).ok().map(Self::ErrorCode),
+            LIFETIME => U32Decoder.decode(data).ok().map(Self::Lifetime),
+            XOR_PEER_ADDRESS => xor_address().map(Self::XorPeerAddress),
+            XOR_RELAYED_ADDRESS => xor_address().map(Self::XorRelayedAddress),
+            PRIORITY => U32Decoder.decode(data).ok().map(Self::Priority),
+            USE_CANDIDATE => data.is_empty().then_some(Self::UseCandidate),
+            SOFTWARE => SoftwareDecoder
+                .decode(data)
+                .ok()
+                .map(|software| Self::Software(software.as_str())),
+            ALTERNATE_SERVER => MappedAddressDecoder
+                .decode(data)
+                .ok()
+                .map(Self::AlternateServer),
+            FINGERPRINT => FingerprintDecoder.decode(data).ok().map(Self::Fingerprint),
+            ICE_CONTROLLED => U64Decoder.decode(data).ok().map(Self::IceControlled),
+            ICE_CONTROLLING => U64Decoder.decode(data).ok().map(Self::IceControlling),
+            RESPONSE_ORIGIN => MappedAddressDecoder
+                .decode(data)
+                .ok()
+                .map(Self::ResponseOrigin),
+            OTHER_ADDRESS => MappedAddressDecoder
+                .decode(data)
+                .ok()
+                .map(Self::OtherAddress),
+            _ => Some(Self::Unknown {
+                attribute_type,
+                data,
+            }),
+        };
+        known.ok_or(InvalidAttributeValue { attribute_type })
+    }
+
+    /// Returns the type of the attribute.
+    pub fn attribute_type(&self) -> u16 {
+        match self {
+            Self::MappedAddress(_) => MAPPED_ADDRESS,
+            Self::XorMappedAddress(_) => XOR_MAPPED_ADDRESS,
+            Self::ChangeRequest(_) => CHANGE_REQUEST,
+            Self::MessageIntegrity(_) => MESSAGE_INTEGRITY,
+            Self::ErrorCode(_) => ERROR_CODE,
+            Self::Lifetime(_) => LIFETIME,
+            Self::XorPeerAddress(_) => XOR_PEER_ADDRESS,
+            Self::XorRelayedAddress(_) => XOR_RELAYED_ADDRESS,
+            Self::Priority(_) => PRIORITY,
+            Self::UseCandidate => USE_CANDIDATE,
+            Self::Software(_) => SOFTWARE,
+            Self::AlternateServer(_) => ALTERNATE_SERVER,
+            Self::Fingerprint(_) => FINGERPRINT,
+            Self::IceControlled(_) => ICE_CONTROLLED,
+            Self::IceControlling(_) => ICE_CONTROLLING,
+            Self::ResponseOrigin(_) => RESPONSE_ORIGIN,
+            Self::OtherAddress(_) => OTHER_ADDRESS,
+            Self::Unknown { attribute_type, .. } => *attribute_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::AttributeError;
+    use crate::{MessageBuilder, StunDecoder};
+
+    #[test]
+    fn test_typed_attributes() {
+        let address: SocketAddr = "192.0.2.1:3478".parse().unwrap();
+        let bytes = MessageBuilder::binding_request()
+            .mapped_address(address)
+            .xor_mapped_address(address)
+            .error_code(ErrorCode::ROLE_CONFLICT)
+            .attribute(PRIORITY, &0x6e0001ffu32)
+            .attribute(ICE_CONTROLLING, &0x1234u64)
+            .attribute(USE_CANDIDATE, &"")
+            .attribute(0xC001, &"vendor")
+            .to_bytes()
+            .unwrap();
+        let message = StunDecoder::new(&bytes).unwrap();
+
+        let attributes: Vec<_> = message.typed_attributes().map(Result::unwrap).collect();
+        assert_eq!(
+            attributes,
+            [
+                KnownAttribute::MappedAddress(address),
+                KnownAttribute::XorMappedAddress(address),
+                KnownAttribute::ErrorCode(ErrorCode::ROLE_CONFLICT),
+                KnownAttribute::Priority(0x6e0001ff),
+                KnownAttribute::IceControlling(0x1234),
+                KnownAttribute::UseCandidate,
+                KnownAttribute::Unknown {
+                    attribute_type: 0xC001,
+                    data: b"vendor",
+                },
+            ]
+        );
+        for (attribute, typed) in message.attributes().zip(attributes) {
+            assert_eq!(attribute.unwrap().attribute_type(), typed.attribute_type());
+        }
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let bytes = MessageBuilder::binding_request()
+            .attribute(PRIORITY, &"invalid")
+            .attribute(SOFTWARE, &"valid")
+            .to_bytes()
+            .unwrap();
+        let message = StunDecoder::new(&bytes).unwrap();
+
+        let mut attributes = message.typed_attributes();
+        assert_eq!(
+            attributes.next(),
+            Some(Err(AttributeError::Decode(InvalidAttributeValue {
+                attribute_type: PRIORITY
+            })))
+        );
+        assert_eq!(
+            attributes.next(),
+            Some(Ok(KnownAttribute::Software("valid")))
+        );
+    }
+}
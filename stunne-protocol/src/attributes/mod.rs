@@ -0,0 +1,458 @@
+use crate::encodings::AttributeDecoder;
+use crate::errors::MessageDecodeError;
+use crate::utils::padding_for_attribute_length;
+use crate::DecodeOptions;
+use core::iter::FusedIterator;
+
+mod known;
+#[cfg(feature = "alloc")]
+mod registry;
+pub mod types;
+
+pub use known::{InvalidAttributeValue, KnownAttribute};
+#[cfg(feature = "alloc")]
+pub use registry::{AnyValue, DecodedAttribute, DecoderRegistry};
+
+#[derive(Debug, Copy, Clone)]
+pub struct StunAttribute<'a> {
+    attribute_type: u16,
+    data: &'a [u8],
+}
+
+impl<'a> StunAttribute<'a> {
+    pub(crate) fn new(attribute_type: u16, data: &'a [u8]) -> Self {
+        Self {
+            attribute_type,
+            data,
+        }
+    }
+
+    pub fn attribute_type(&self) -> u16 {
+        self.attribute_type
+    }
+
+    /// Returns the raw bytes of the attribute's value, excluding any padding.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Returns the length of the attribute's value once padded to a 32-bit boundary. This is the
+    /// number of bytes the value occupies in the message, not counting the 4-byte attribute
+    /// header.
+    pub fn padded_length(&self) -> usize {
+        self.data.len() + padding_for_attribute_length(self.data.len())
+    }
+
+    pub fn decode<T: AttributeDecoder<'a>>(&self, decoder: &T) -> Result<T::Item, T::Error> {
+        decoder.decode(self.data)
+    }
+}
+
+/// Iterates over the attributes of a message (see [attributes()](crate::StunDecoder::attributes)).
+///
+/// The iterator is cheap to clone, so the attributes can be scanned several times (e.g., once
+/// for each attribute type of interest) without decoding the header again.
+#[derive(Debug, Clone)]
+pub struct StunAttributeIterator<'a> {
+    pub(crate) data: &'a [u8],
+    pub(crate) options: DecodeOptions,
+    /// Offset of `data` from the start of the message, used to give context to errors.
+    pub(crate) offset: usize,
+}
+
+const ATTRIBUTE_TYPE_LENGTH_BYTES: usize = 4;
+
+/// Iterates over the bytes representing attributes, yielding a `StunAttribute` for each attribute
+/// found.
+///
+/// If at any point in the iteration some problem is discovered (e.g., the byte stream ends early),
+/// then an error is returned. Any subsequent call to `next()` will return `None` after such an
+/// error.
+impl<'a> Iterator for StunAttributeIterator<'a> {
+    type Item = Result<StunAttribute<'a>, MessageDecodeError>;
+
+    /// Every attribute takes at least four bytes, so the number of bytes remaining gives an upper
+    /// bound on the number of attributes.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.data.len() {
+            0 => (0, Some(0)),
+            len => (1, Some(len.div_ceil(ATTRIBUTE_TYPE_LENGTH_BYTES))),
+        }
+    }
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "tracing")]
+        let offset = self.offset;
+        let attribute = self.decode_next()?;
+        #[cfg(feature = "tracing")]
+        match &attribute {
+            Ok(attribute) => tracing::trace!(
+                offset,
+                attribute_type = %types::DisplayAttributeType(attribute.attribute_type),
+                length = attribute.data.len(),
+                "decoded STUN attribute"
+            ),
+            Err(error) => tracing::debug!(offset, %error, "failed to decode STUN attribute"),
+        }
+        Some(attribute)
+    }
+}
+
+/// Once the iterator has returned `None` (including after an error), it always returns `None`.
+impl FusedIterator for StunAttributeIterator<'_> {}
+
+impl<'a> StunAttributeIterator<'a> {
+    fn decode_next(&mut self) -> Option<Result<StunAttribute<'a>, MessageDecodeError>> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        if self.data.len() < ATTRIBUTE_TYPE_LENGTH_BYTES {
+            let error = MessageDecodeError::UnexpectedEndOfData {
+                offset: self.offset,
+                attribute_type: None,
+                needed: ATTRIBUTE_TYPE_LENGTH_BYTES,
+                available: self.data.len(),
+            };
+            self.data = &self.data[0..0];
+            return Some(Err(error));
+        }
+
+        let (attribute_header, remaining) = self.data.split_at(ATTRIBUTE_TYPE_LENGTH_BYTES);
+        let type_bytes = &attribute_header[0..=1];
+        let length_bytes = &attribute_header[2..=3];
+
+        let attribute_type = u16::from_be_bytes(type_bytes.try_into().unwrap());
+        let data_length: usize = u16::from_be_bytes(length_bytes.try_into().unwrap()).into();
+        let mut padded_data_length = data_length + padding_for_attribute_length(data_length);
+        let data_offset = self.offset + ATTRIBUTE_TYPE_LENGTH_BYTES;
+
+        if remaining.len() < padded_data_length {
+            // A final attribute whose padding is missing entirely may be tolerated.
+            if !self.options.allow_missing_final_padding || remaining.len() != data_length {
+                self.data = &self.data[0..0];
+                return Some(Err(MessageDecodeError::UnexpectedEndOfData {
+                    offset: data_offset,
+                    attribute_type: Some(attribute_type),
+                    needed: padded_data_length,
+                    available: remaining.len(),
+                }));
+            }
+            padded_data_length = data_length;
+        }
+
+        let (attribute_data, remaining) = remaining.split_at(padded_data_length);
+        let (data, padding) = attribute_data.split_at(data_length);
+        if !self.options.allow_nonzero_padding && padding.iter().any(|&byte| byte != 0) {
+            self.data = &self.data[0..0];
+            return Some(Err(MessageDecodeError::NonZeroPadding {
+                offset: data_offset + data_length,
+                attribute_type,
+            }));
+        }
+        self.data = remaining;
+        self.offset = data_offset + padded_data_length;
+
+        Some(Ok(StunAttribute {
+            attribute_type,
+            data,
+        }))
+    }
+
+    pub fn from_bytes(data: &'a [u8]) -> Self {
+        Self::with_options(data, DecodeOptions::default())
+    }
+
+    /// Iterate over the bytes representing attributes, using the given options to decide which
+    /// quirks of the encoding to tolerate (see [DecodeOptions]).
+    ///
+    /// The offsets in any errors returned are relative to the start of `data`.
+    pub fn with_options(data: &'a [u8], options: DecodeOptions) -> Self {
+        Self {
+            data,
+            options,
+            offset: 0,
+        }
+    }
+
+    /// Returns the bytes of the attributes that have not been iterated over yet. After an error,
+    /// this is empty.
+    pub fn remaining_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_bytes() {
+        let bytes: [u8; 0] = [];
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_single_attribute() {
+        #[rustfmt::skip]
+        let bytes: [u8; 8] = [
+            1, 5, // Type
+            0, 4, // Length. Note that data must be padded to fit 32 bits, so four bytes
+                  // is the smaller possible data format.
+            1, 2, 3, 4 // Data
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next();
+        assert!(matches!(
+            first,
+            Some(Ok(StunAttribute {
+                attribute_type: 0x0105,
+                data: &[1, 2, 3, 4]
+            }))
+        ));
+
+        let second = iter.next();
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn test_longer_attribute() {
+        #[rustfmt::skip]
+        let bytes: [u8; 12] = [
+            0, 1,  // Type,
+            0, 8, // Length
+            1, 2, 3, 4, 5, 6, 7, 8
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next();
+        assert!(matches!(
+            first,
+            Some(Ok(StunAttribute {
+                attribute_type: 1,
+                data: &[1, 2, 3, 4, 5, 6, 7, 8]
+            }))
+        ));
+
+        let second = iter.next();
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn test_multiple_attributes() {
+        #[rustfmt::skip]
+        let bytes: [u8; 20] = [
+            0, 1,  // Type,
+            0, 4, // Length
+            1, 2, 3, 4,
+
+            0, 2,  // Type,
+            0, 8, // Length
+            5, 6, 7, 8, 9, 10, 11, 12
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next();
+        assert!(matches!(
+            first,
+            Some(Ok(StunAttribute {
+                attribute_type: 1,
+                data: &[1, 2, 3, 4]
+            }))
+        ));
+
+        let second = iter.next();
+        assert!(matches!(
+            second,
+            Some(Ok(StunAttribute {
+                attribute_type: 2,
+                data: &[5, 6, 7, 8, 9, 10, 11, 12]
+            }))
+        ));
+
+        let third = iter.next();
+        assert!(third.is_none());
+    }
+
+    #[test]
+    fn test_error_when_not_enough_data_for_attribute_type_or_value() {
+        #[rustfmt::skip]
+        let bytes: [u8; 3] = [
+            0, 1, // Type,
+            0,    // What the? The length is cut off?
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next();
+        assert!(matches!(
+            first,
+            Some(Err(MessageDecodeError::UnexpectedEndOfData {
+                offset: 0,
+                attribute_type: None,
+                needed: 4,
+                available: 3,
+            }))
+        ));
+
+        let second = iter.next();
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn test_error_when_not_enough_data_for_attribute_length() {
+        #[rustfmt::skip]
+        let bytes: [u8; 8] = [
+            0, 1, // Type
+            0, 8, // This attribute should be 8 bytes
+            1, 2, 3, 4 // However, there is only four bytes here. Thus, an error
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next();
+        assert!(matches!(
+            first,
+            Some(Err(MessageDecodeError::UnexpectedEndOfData {
+                offset: 4,
+                attribute_type: Some(1),
+                needed: 8,
+                available: 4,
+            }))
+        ));
+
+        let second = iter.next();
+        assert!(second.is_none());
+    }
+
+    #[test]
+    fn test_can_iterate_over_attribute_with_padding() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Type
+            0, 7, // The attribute is 7 bytes. However, that means that the padding should be eight
+                  // bytes.
+            1, 2, 3, 4, 5, 6, 7, 0, // This final zero is the padding, and should not be in slice
+                                    // for the decoding process
+            0, 2, // The next type
+            0, 8, // This attribute has 8 bytes in its value; no padding needed
+            1, 2, 3, 4, 5, 6, 7, 8 // Data
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        let first = iter.next().unwrap().unwrap();
+        assert_eq!(first.attribute_type, 0x01);
+        assert_eq!(first.data, &[1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(first.padded_length(), 8);
+
+        let second = iter.next().unwrap().unwrap();
+        assert_eq!(second.attribute_type, 0x02);
+        assert_eq!(second.data, &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(second.padded_length(), 8);
+
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_nonzero_padding() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Type
+            0, 3, // Length
+            1, 2, 3, 0xff, // Data with non-zero padding
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        assert_eq!(iter.next().unwrap().unwrap().data(), &[1, 2, 3]);
+
+        let mut iter = StunAttributeIterator::with_options(&bytes, DecodeOptions::strict());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(MessageDecodeError::NonZeroPadding {
+                offset: 7,
+                attribute_type: 1,
+            }))
+        ));
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_missing_final_padding() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Type
+            0, 3, // Length
+            1, 2, 3, // Data without padding
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        assert!(matches!(
+            iter.next(),
+            Some(Err(MessageDecodeError::UnexpectedEndOfData {
+                needed: 4,
+                ..
+            }))
+        ));
+
+        let mut iter = StunAttributeIterator::with_options(&bytes, DecodeOptions::lenient());
+        assert_eq!(iter.next().unwrap().unwrap().data(), &[1, 2, 3]);
+        assert!(iter.next().is_none());
+
+        // Only the padding may be missing, not the data itself.
+        let mut iter = StunAttributeIterator::with_options(&bytes[..6], DecodeOptions::lenient());
+        assert!(matches!(
+            iter.next(),
+            Some(Err(MessageDecodeError::UnexpectedEndOfData {
+                available: 2,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_clone_and_size_hint() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, 0, 4, 1, 2, 3, 4,
+            0, 2, 0, 0,
+            0, 3, 0, 8, // Truncated
+        ];
+
+        let mut iter = StunAttributeIterator::from_bytes(&bytes);
+        assert_eq!(iter.size_hint(), (1, Some(4)));
+        assert_eq!(iter.next().unwrap().unwrap().attribute_type(), 1);
+        assert_eq!(iter.remaining_bytes(), &bytes[8..]);
+
+        let types: Vec<_> = iter
+            .clone()
+            .map(|a| a.map(|a| a.attribute_type()))
+            .collect();
+        assert_eq!(types.len(), 2);
+        assert_eq!(types[0], Ok(2));
+        assert!(types[1].is_err());
+
+        assert_eq!(iter.next().unwrap().unwrap().attribute_type(), 2);
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.remaining_bytes().is_empty());
+        assert_eq!(iter.size_hint(), (0, Some(0)));
+        assert!(iter.next().is_none());
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn test_data_accessor() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Type
+            0, 5, // Length
+            1, 2, 3, 4, 5, 0, 0, 0, // Data with padding
+        ];
+
+        let attribute = StunAttributeIterator::from_bytes(&bytes)
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(attribute.data(), &[1, 2, 3, 4, 5]);
+        assert_eq!(attribute.padded_length(), 8);
+    }
+}
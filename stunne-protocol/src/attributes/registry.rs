@@ -0,0 +1,244 @@
+use crate::attributes::StunAttribute;
+use crate::encodings::AttributeDecoder;
+use crate::errors::MessageDecodeError;
+use crate::StunDecoder;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use core::any::Any;
+use core::fmt;
+
+/// A value whose concrete type is only known at runtime, as returned by a [DecoderRegistry].
+///
+/// Every `'static` type implementing `Debug` is a value, so values can always be printed. To get
+/// the concrete value back, use [downcast_ref](Self::downcast_ref).
+pub trait AnyValue: Any + fmt::Debug + Send + Sync {
+    /// Returns the value as `Any`, so that it can be downcast.
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + fmt::Debug + Send + Sync> AnyValue for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl dyn AnyValue {
+    /// Returns the value as a `T`, or `None` if it is of a different type.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+type ErasedDecoder =
+    Box<dyn Fn(&[u8]) -> Result<Box<dyn AnyValue>, Box<dyn AnyValue>> + Send + Sync>;
+
+/// An attribute of a message, along with its value as decoded by a [DecoderRegistry].
+#[derive(Debug)]
+pub struct DecodedAttribute<'a> {
+    /// The attribute, as found in the message.
+    pub attribute: StunAttribute<'a>,
+
+    /// The result of decoding the attribute's value, or `None` if no decoder is registered for
+    /// the attribute type. The error is the error returned by the decoder.
+    pub value: Option<Result<Box<dyn AnyValue>, Box<dyn AnyValue>>>,
+}
+
+/// Maps attribute types to decoders that are chosen at runtime.
+///
+/// An application registers a decoder for each attribute type it handles, including any
+/// vendor-specific types, and then decodes every attribute of a message with
+/// [decode_all](Self::decode_all), rather than matching on the attribute type wherever messages
+/// are consumed.
+///
+/// Decoded values must be owned (`'static`), so that they can be returned as an [AnyValue].
+/// Decoders that borrow from the message (e.g., [SoftwareDecoder](crate::encodings::SoftwareDecoder))
+/// can be registered with [register_fn](Self::register_fn), converting the value to an owned
+/// type.
+///
+/// ```
+/// # use std::net::SocketAddr;
+/// # use stunne_protocol::{attributes::{types::*, DecoderRegistry}, encodings::*, *};
+/// let mut registry = DecoderRegistry::new();
+/// registry
+///     .register(MAPPED_ADDRESS, MappedAddressDecoder)
+///     .register_fn(SOFTWARE, |buf| {
+///         SoftwareDecoder.decode(buf).map(|software| software.as_str().to_owned())
+///     });
+///
+/// # let address: SocketAddr = "192.0.2.1:3478".parse().unwrap();
+/// let bytes = MessageBuilder::binding_request()
+///     .software("Widget, Inc.")
+///     .mapped_address(address)
+///     .to_bytes()
+///     .unwrap();
+/// let message = StunDecoder::new(&bytes).unwrap();
+///
+/// for decoded in registry.decode_all(&message) {
+///     let decoded = decoded.unwrap();
+///     let value = decoded.value.unwrap().unwrap();
+///     match decoded.attribute.attribute_type() {
+///         SOFTWARE => assert_eq!(value.downcast_ref::<String>().unwrap(), "Widget, Inc."),
+///         MAPPED_ADDRESS => assert_eq!(value.downcast_ref::<SocketAddr>(), Some(&address)),
+///         _ => unreachable!(),
+///     }
+/// }
+/// ```
+#[derive(Default)]
+pub struct DecoderRegistry {
+    decoders: BTreeMap<u16, ErasedDecoder>,
+}
+
+impl DecoderRegistry {
+    /// Create a registry without any decoders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the decoder for the attribute type, replacing any decoder already registered
+    /// for it.
+    pub fn register<D, T, E>(&mut self, attribute_type: u16, decoder: D) -> &mut Self
+    where
+        D: for<'buf> AttributeDecoder<'buf, Item = T, Error = E> + Send + Sync + 'static,
+        T: AnyValue,
+        E: AnyValue,
+    {
+        self.register_fn(attribute_type, move |buf| decoder.decode(buf))
+    }
+
+    /// Registers a function that decodes the value of the attribute type, replacing any decoder
+    /// already registered for it.
+    pub fn register_fn<F, T, E>(&mut self, attribute_type: u16, decode: F) -> &mut Self
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+        T: AnyValue,
+        E: AnyValue,
+    {
+        let decoder: ErasedDecoder = Box::new(move |buf| match decode(buf) {
+            Ok(value) => Ok(Box::new(value)),
+            Err(error) => Err(Box::new(error)),
+        });
+        self.decoders.insert(attribute_type, decoder);
+        self
+    }
+
+    /// Removes the decoder for the attribute type, returning true if one was registered.
+    pub fn unregister(&mut self, attribute_type: u16) -> bool {
+        self.decoders.remove(&attribute_type).is_some()
+    }
+
+    /// Returns true if a decoder is registered for the attribute type.
+    pub fn contains(&self, attribute_type: u16) -> bool {
+        self.decoders.contains_key(&attribute_type)
+    }
+
+    /// Decodes the value of a single attribute, or returns `None` if no decoder is registered
+    /// for its type.
+    pub fn decode(
+        &self,
+        attribute: &StunAttribute<'_>,
+    ) -> Option<Result<Box<dyn AnyValue>, Box<dyn AnyValue>>> {
+        let decoder = self.decoders.get(&attribute.attribute_type())?;
+        Some(decoder(attribute.data()))
+    }
+
+    /// Returns an iterator that decodes every attribute of the message, in order, with the
+    /// decoder registered for its type.
+    ///
+    /// As with [StunDecoder::attributes], if the attributes of the message cannot be decoded, the
+    /// error is yielded and the iteration ends.
+    pub fn decode_all<'r, 'a>(
+        &'r self,
+        message: &StunDecoder<'a>,
+    ) -> impl Iterator<Item = Result<DecodedAttribute<'a>, MessageDecodeError>> + 'r
+    where
+        'a: 'r,
+    {
+        message.attributes().map(move |attribute| {
+            let attribute = attribute?;
+            let value = self.decode(&attribute);
+            Ok(DecodedAttribute { attribute, value })
+        })
+    }
+}
+
+impl fmt::Debug for DecoderRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecoderRegistry")
+            .field("attribute_types", &self.decoders.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::{ChangeRequest, ChangeRequestDecoder, U32Decoder, Utf8Decoder};
+    use crate::MessageBuilder;
+    use alloc::string::String;
+    use core::str::Utf8Error;
+
+    #[test]
+    fn test_decode_all() {
+        let mut registry = DecoderRegistry::new();
+        registry
+            .register(0xC001, U32Decoder)
+            .register(0x0003, ChangeRequestDecoder)
+            .register_fn(0xC002, |buf| Utf8Decoder.decode(buf).map(String::from));
+
+        let bytes = MessageBuilder::binding_request()
+            .attribute(0xC001, &7u32)
+            .change_request(true, false)
+            .attribute(0xC002, &"text")
+            .attribute(0xC003, &"unregistered")
+            .to_bytes()
+            .unwrap();
+        let message = StunDecoder::new(&bytes).unwrap();
+
+        let decoded: Vec<_> = registry
+            .decode_all(&message)
+            .map(|decoded| decoded.unwrap())
+            .collect();
+        assert_eq!(decoded.len(), 4);
+
+        let value = decoded[0].value.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(value.downcast_ref::<u32>(), Some(&7));
+        let value = decoded[1].value.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(
+            value.downcast_ref::<ChangeRequest>(),
+            Some(&ChangeRequest {
+                change_ip: true,
+                change_port: false,
+            })
+        );
+        let value = decoded[2].value.as_ref().unwrap().as_ref().unwrap();
+        assert_eq!(value.downcast_ref::<String>().unwrap(), "text");
+        assert!(decoded[3].value.is_none());
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let mut registry = DecoderRegistry::new();
+        registry.register_fn(0xC002, |buf| Utf8Decoder.decode(buf).map(String::from));
+
+        let bytes = MessageBuilder::binding_request()
+            .attribute(0xC002, &0xffffffffu32)
+            .to_bytes()
+            .unwrap();
+        let message = StunDecoder::new(&bytes).unwrap();
+        let attribute = message.attributes().next().unwrap().unwrap();
+
+        let error = registry.decode(&attribute).unwrap().unwrap_err();
+        assert!(error.downcast_ref::<Utf8Error>().is_some());
+        assert!(error.downcast_ref::<String>().is_none());
+    }
+
+    #[test]
+    fn test_unregister() {
+        let mut registry = DecoderRegistry::new();
+        registry.register(0xC001, U32Decoder);
+        assert!(registry.contains(0xC001));
+        assert!(registry.unregister(0xC001));
+        assert!(!registry.contains(0xC001));
+        assert!(!registry.unregister(0xC001));
+    }
+}
@@ -0,0 +1,246 @@
+//! Attribute type numbers registered with IANA in the [STUN Attributes registry][].
+//!
+//! Types in the range 0x0000-0x7FFF are comprehension-required, meaning that an agent that does
+//! not understand the attribute cannot process the message. Types in the range 0x8000-0xFFFF are
+//! comprehension-optional, and may be safely ignored.
+//!
+//! [STUN Attributes registry]: https://www.iana.org/assignments/stun-parameters/stun-parameters.xhtml#stun-parameters-4
+use core::fmt;
+
+// Comprehension-required attributes.
+
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.1)
+pub const MAPPED_ADDRESS: u16 = 0x0001;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.2)
+pub const CHANGE_REQUEST: u16 = 0x0003;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.3)
+pub const USERNAME: u16 = 0x0006;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.4)
+pub const MESSAGE_INTEGRITY: u16 = 0x0008;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.6)
+pub const ERROR_CODE: u16 = 0x0009;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.9)
+pub const UNKNOWN_ATTRIBUTES: u16 = 0x000A;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.1)
+pub const CHANNEL_NUMBER: u16 = 0x000C;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.2)
+pub const LIFETIME: u16 = 0x000D;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.3)
+pub const XOR_PEER_ADDRESS: u16 = 0x0012;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.4)
+pub const DATA: u16 = 0x0013;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.7)
+pub const REALM: u16 = 0x0014;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.8)
+pub const NONCE: u16 = 0x0015;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.5)
+pub const XOR_RELAYED_ADDRESS: u16 = 0x0016;
+/// [RFC 8656](https://datatracker.ietf.org/doc/html/rfc8656#section-18.1)
+pub const REQUESTED_ADDRESS_FAMILY: u16 = 0x0017;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.6)
+pub const EVEN_PORT: u16 = 0x0018;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.7)
+pub const REQUESTED_TRANSPORT: u16 = 0x0019;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.8)
+pub const DONT_FRAGMENT: u16 = 0x001A;
+/// [RFC 7635](https://datatracker.ietf.org/doc/html/rfc7635#section-6.2)
+pub const ACCESS_TOKEN: u16 = 0x001B;
+/// [RFC 8489](https://datatracker.ietf.org/doc/html/rfc8489#section-14.6)
+pub const MESSAGE_INTEGRITY_SHA256: u16 = 0x001C;
+/// [RFC 8489](https://datatracker.ietf.org/doc/html/rfc8489#section-14.12)
+pub const PASSWORD_ALGORITHM: u16 = 0x001D;
+/// [RFC 8489](https://datatracker.ietf.org/doc/html/rfc8489#section-14.4)
+pub const USERHASH: u16 = 0x001E;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.2)
+pub const XOR_MAPPED_ADDRESS: u16 = 0x0020;
+/// [RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-14.9)
+pub const RESERVATION_TOKEN: u16 = 0x0022;
+/// [RFC 8445](https://datatracker.ietf.org/doc/html/rfc8445#section-7.1.1)
+pub const PRIORITY: u16 = 0x0024;
+/// [RFC 8445](https://datatracker.ietf.org/doc/html/rfc8445#section-7.1.2)
+pub const USE_CANDIDATE: u16 = 0x0025;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.4)
+pub const PADDING: u16 = 0x0026;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.5)
+pub const RESPONSE_PORT: u16 = 0x0027;
+/// [RFC 6062](https://datatracker.ietf.org/doc/html/rfc6062#section-6.2.1)
+pub const CONNECTION_ID: u16 = 0x002A;
+
+// Comprehension-optional attributes.
+
+/// [RFC 8656](https://datatracker.ietf.org/doc/html/rfc8656#section-18.2)
+pub const ADDITIONAL_ADDRESS_FAMILY: u16 = 0x8000;
+/// [RFC 8656](https://datatracker.ietf.org/doc/html/rfc8656#section-18.3)
+pub const ADDRESS_ERROR_CODE: u16 = 0x8001;
+/// [RFC 8489](https://datatracker.ietf.org/doc/html/rfc8489#section-14.11)
+pub const PASSWORD_ALGORITHMS: u16 = 0x8002;
+/// [RFC 8489](https://datatracker.ietf.org/doc/html/rfc8489#section-14.16)
+pub const ALTERNATE_DOMAIN: u16 = 0x8003;
+/// [RFC 8656](https://datatracker.ietf.org/doc/html/rfc8656#section-18.4)
+pub const ICMP: u16 = 0x8004;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.10)
+pub const SOFTWARE: u16 = 0x8022;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.11)
+pub const ALTERNATE_SERVER: u16 = 0x8023;
+/// [RFC 7982](https://datatracker.ietf.org/doc/html/rfc7982#section-3.1)
+pub const TRANSACTION_TRANSMIT_COUNTER: u16 = 0x8025;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.6)
+pub const CACHE_TIMEOUT: u16 = 0x8027;
+/// [RFC 5389](https://datatracker.ietf.org/doc/html/rfc5389#section-15.5)
+pub const FINGERPRINT: u16 = 0x8028;
+/// [RFC 8445](https://datatracker.ietf.org/doc/html/rfc8445#section-7.1.3)
+pub const ICE_CONTROLLED: u16 = 0x8029;
+/// [RFC 8445](https://datatracker.ietf.org/doc/html/rfc8445#section-7.1.3)
+pub const ICE_CONTROLLING: u16 = 0x802A;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.3)
+pub const RESPONSE_ORIGIN: u16 = 0x802B;
+/// [RFC 5780](https://datatracker.ietf.org/doc/html/rfc5780#section-7.4)
+pub const OTHER_ADDRESS: u16 = 0x802C;
+/// [RFC 6679](https://datatracker.ietf.org/doc/html/rfc6679#section-7.2.2)
+pub const ECN_CHECK: u16 = 0x802D;
+/// [RFC 7635](https://datatracker.ietf.org/doc/html/rfc7635#section-6.1)
+pub const THIRD_PARTY_AUTHORIZATION: u16 = 0x802E;
+/// [RFC 8016](https://datatracker.ietf.org/doc/html/rfc8016#section-3.1)
+pub const MOBILITY_TICKET: u16 = 0x8030;
+
+/// Returns the name of the attribute type as registered with IANA (e.g., `"SOFTWARE"` for
+/// 0x8022), or `None` if the type is not one of the constants in this module.
+pub fn attribute_name(attribute_type: u16) -> Option<&'static str> {
+    let name = match attribute_type {
+        MAPPED_ADDRESS => "MAPPED-ADDRESS",
+        CHANGE_REQUEST => "CHANGE-REQUEST",
+        USERNAME => "USERNAME",
+        MESSAGE_INTEGRITY => "MESSAGE-INTEGRITY",
+        ERROR_CODE => "ERROR-CODE",
+        UNKNOWN_ATTRIBUTES => "UNKNOWN-ATTRIBUTES",
+        CHANNEL_NUMBER => "CHANNEL-NUMBER",
+        LIFETIME => "LIFETIME",
+        XOR_PEER_ADDRESS => "XOR-PEER-ADDRESS",
+        DATA => "DATA",
+        REALM => "REALM",
+        NONCE => "NONCE",
+        XOR_RELAYED_ADDRESS => "XOR-RELAYED-ADDRESS",
+        REQUESTED_ADDRESS_FAMILY => "REQUESTED-ADDRESS-FAMILY",
+        EVEN_PORT => "EVEN-PORT",
+        REQUESTED_TRANSPORT => "REQUESTED-TRANSPORT",
+        DONT_FRAGMENT => "DONT-FRAGMENT",
+        ACCESS_TOKEN => "ACCESS-TOKEN",
+        MESSAGE_INTEGRITY_SHA256 => "MESSAGE-INTEGRITY-SHA256",
+        PASSWORD_ALGORITHM => "PASSWORD-ALGORITHM",
+        USERHASH => "USERHASH",
+        XOR_MAPPED_ADDRESS => "XOR-MAPPED-ADDRESS",
+        RESERVATION_TOKEN => "RESERVATION-TOKEN",
+        PRIORITY => "PRIORITY",
+        USE_CANDIDATE => "USE-CANDIDATE",
+        PADDING => "PADDING",
+        RESPONSE_PORT => "RESPONSE-PORT",
+        CONNECTION_ID => "CONNECTION-ID",
+        ADDITIONAL_ADDRESS_FAMILY => "ADDITIONAL-ADDRESS-FAMILY",
+        ADDRESS_ERROR_CODE => "ADDRESS-ERROR-CODE",
+        PASSWORD_ALGORITHMS => "PASSWORD-ALGORITHMS",
+        ALTERNATE_DOMAIN => "ALTERNATE-DOMAIN",
+        ICMP => "ICMP",
+        SOFTWARE => "SOFTWARE",
+        ALTERNATE_SERVER => "ALTERNATE-SERVER",
+        TRANSACTION_TRANSMIT_COUNTER => "TRANSACTION_TRANSMIT_COUNTER",
+        CACHE_TIMEOUT => "CACHE-TIMEOUT",
+        FINGERPRINT => "FINGERPRINT",
+        ICE_CONTROLLED => "ICE-CONTROLLED",
+        ICE_CONTROLLING => "ICE-CONTROLLING",
+        RESPONSE_ORIGIN => "RESPONSE-ORIGIN",
+        OTHER_ADDRESS => "OTHER-ADDRESS",
+        ECN_CHECK => "ECN-CHECK",
+        THIRD_PARTY_AUTHORIZATION => "THIRD-PARTY-AUTHORIZATION",
+        MOBILITY_TICKET => "MOBILITY-TICKET",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Wraps an attribute type so that it displays as its IANA name, falling back to the hexadecimal
+/// value for types that are not known.
+///
+/// ```
+/// # use stunne_protocol::attributes::types::DisplayAttributeType;
+/// assert_eq!(DisplayAttributeType(0x8022).to_string(), "SOFTWARE");
+/// assert_eq!(DisplayAttributeType(0xC001).to_string(), "0xC001");
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DisplayAttributeType(pub u16);
+
+impl fmt::Display for DisplayAttributeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match attribute_name(self.0) {
+            Some(name) => f.pad(name),
+            None => {
+                const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+                let mut hex = *b"0x0000";
+                for (i, digit) in hex[2..].iter_mut().enumerate() {
+                    *digit = HEX_DIGITS[usize::from(self.0 >> (12 - 4 * i)) & 0xF];
+                }
+                f.pad(core::str::from_utf8(&hex).unwrap())
+            }
+        }
+    }
+}
+
+/// Returns true if the attribute type is in the comprehension-required range.
+pub fn is_comprehension_required(attribute_type: u16) -> bool {
+    attribute_type < 0x8000
+}
+
+/// Returns true if a message may contain more than one attribute of the type.
+///
+/// Of the types in this module, only XOR-PEER-ADDRESS (e.g., when creating permissions for
+/// several peers) and XOR-RELAYED-ADDRESS (when allocating both an IPv4 and an IPv6 address) may
+/// be repeated. As nothing is known about other types, they are assumed to be repeatable.
+pub fn is_repeatable(attribute_type: u16) -> bool {
+    match attribute_type {
+        XOR_PEER_ADDRESS | XOR_RELAYED_ADDRESS => true,
+        _ => attribute_name(attribute_type).is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_comprehension_required() {
+        assert!(is_comprehension_required(MAPPED_ADDRESS));
+        assert!(is_comprehension_required(0x7FFF));
+        assert!(!is_comprehension_required(0x8000));
+        assert!(!is_comprehension_required(SOFTWARE));
+    }
+
+    #[test]
+    fn test_is_repeatable() {
+        assert!(!is_repeatable(XOR_MAPPED_ADDRESS));
+        assert!(!is_repeatable(FINGERPRINT));
+        assert!(is_repeatable(XOR_PEER_ADDRESS));
+        assert!(is_repeatable(0xC001));
+    }
+
+    #[test]
+    fn test_attribute_name() {
+        assert_eq!(attribute_name(MAPPED_ADDRESS), Some("MAPPED-ADDRESS"));
+        assert_eq!(attribute_name(0x0020), Some("XOR-MAPPED-ADDRESS"));
+        assert_eq!(
+            attribute_name(MESSAGE_INTEGRITY_SHA256),
+            Some("MESSAGE-INTEGRITY-SHA256")
+        );
+        assert_eq!(attribute_name(0x8022), Some("SOFTWARE"));
+        assert_eq!(attribute_name(0x0000), None);
+        assert_eq!(attribute_name(0xC001), None);
+    }
+
+    #[test]
+    fn test_display_attribute_type() {
+        assert_eq!(DisplayAttributeType(ERROR_CODE).to_string(), "ERROR-CODE");
+        assert_eq!(DisplayAttributeType(0x0b).to_string(), "0x000B");
+        assert_eq!(
+            format!("{:<12}|", DisplayAttributeType(DATA)),
+            "DATA        |"
+        );
+    }
+}
@@ -0,0 +1,373 @@
+use crate::attributes::types::{
+    is_comprehension_required, CHANGE_REQUEST, ERROR_CODE, MAPPED_ADDRESS, NONCE, SOFTWARE,
+    UNKNOWN_ATTRIBUTES, XOR_MAPPED_ADDRESS,
+};
+#[cfg(feature = "std")]
+use crate::attributes::types::{REALM, USERNAME};
+use crate::encodings::{
+    AttributeEncoder, ChangeRequest, ErrorCode, MappedAddress, Nonce, Software, UnknownAttributes,
+    XorMappedAddress,
+};
+#[cfg(feature = "std")]
+use crate::encodings::{Realm, Username};
+use crate::errors::{BuildError, MessageDecodeError};
+use crate::{MessageClass, MessageHeader, MessageMethod, StunDecoder, StunMessage, TransactionId};
+use alloc::vec::Vec;
+use bytes::Bytes;
+use core::net::SocketAddr;
+
+/// Builds a [StunMessage] using the standard attribute types, so that typical messages can be
+/// created without tracking attribute type numbers and encoders by hand.
+///
+/// Attributes are added in the order the methods are called. Values that must be validated
+/// (e.g., SOFTWARE and USERNAME) are checked as they are added; the first error found is returned
+/// from [build](Self::build).
+///
+/// ```
+/// # use stunne_protocol::{attributes::types::SOFTWARE, encodings::SoftwareDecoder, *};
+/// let message = MessageBuilder::binding_request()
+///     .software("Widget, Inc.")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(message.header().method, MessageMethod::BINDING);
+/// let software = message.attribute(SOFTWARE).unwrap();
+/// assert_eq!(software.decode(&SoftwareDecoder).unwrap().as_str(), "Widget, Inc.");
+/// ```
+pub struct MessageBuilder {
+    message: StunMessage,
+    error: Option<BuildError>,
+}
+
+impl MessageBuilder {
+    /// Create a builder for a message with the given header.
+    pub fn new(header: MessageHeader) -> Self {
+        Self {
+            message: StunMessage::new(header),
+            error: None,
+        }
+    }
+
+    /// Create a builder for a request of the given method, using a random transaction ID.
+    #[cfg(feature = "std")]
+    pub fn request(method: MessageMethod) -> Self {
+        Self::new(MessageHeader {
+            class: MessageClass::Request,
+            method,
+            tx_id: TransactionId::random(),
+        })
+    }
+
+    /// Create a builder for an indication of the given method, using a random transaction ID.
+    #[cfg(feature = "std")]
+    pub fn indication(method: MessageMethod) -> Self {
+        Self::new(MessageHeader {
+            class: MessageClass::Indication,
+            method,
+            tx_id: TransactionId::random(),
+        })
+    }
+
+    /// Create a builder for a Binding request, using a random transaction ID.
+    #[cfg(feature = "std")]
+    pub fn binding_request() -> Self {
+        Self::request(MessageMethod::BINDING)
+    }
+
+    /// Create a builder for a successful response to the Binding request with the given
+    /// transaction ID.
+    pub fn binding_success_response(tx_id: TransactionId) -> Self {
+        Self::new(MessageHeader {
+            class: MessageClass::SuccessResponse,
+            method: MessageMethod::BINDING,
+            tx_id,
+        })
+    }
+
+    /// Create a builder for the 420 (Unknown Attribute) error response that [RFC 5389][] requires
+    /// when a request contains comprehension-required attributes that the server does not
+    /// understand.
+    ///
+    /// Every comprehension-required attribute type in the request that is not in `known` is
+    /// listed in the UNKNOWN-ATTRIBUTES attribute of the response, once each, in the order they
+    /// first appear. Returns `None` if there are no such attributes, or if the message is not a
+    /// request (unknown attributes in indications and responses are not reported back).
+    ///
+    /// ```
+    /// # use stunne_protocol::{attributes::types::*, *};
+    /// # let bytes = MessageBuilder::binding_request()
+    /// #     .attribute(0x0024, &1u32)
+    /// #     .software("client")
+    /// #     .to_bytes()
+    /// #     .unwrap();
+    /// let request = StunDecoder::new(&bytes).unwrap();
+    /// let response = MessageBuilder::unknown_attributes_response(&request, &[SOFTWARE])
+    ///     .unwrap()
+    ///     .expect("the request contains the unknown PRIORITY attribute");
+    /// let bytes = response.software("server").to_bytes().unwrap();
+    /// ```
+    ///
+    /// [RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-7.3.1
+    pub fn unknown_attributes_response(
+        request: &StunDecoder,
+        known: &[u16],
+    ) -> Result<Option<Self>, MessageDecodeError> {
+        if !request.class().is_request() {
+            return Ok(None);
+        }
+
+        let mut unknown = Vec::new();
+        for attribute in request.attributes() {
+            let attribute_type = attribute?.attribute_type();
+            if is_comprehension_required(attribute_type)
+                && !known.contains(&attribute_type)
+                && !unknown.contains(&attribute_type)
+            {
+                unknown.push(attribute_type);
+            }
+        }
+        if unknown.is_empty() {
+            return Ok(None);
+        }
+
+        let builder = Self::new(request.header().error_response())
+            .error_code(ErrorCode::UNKNOWN_ATTRIBUTE)
+            .attribute(
+                UNKNOWN_ATTRIBUTES,
+                &UnknownAttributes {
+                    attribute_types: &unknown,
+                },
+            );
+        Ok(Some(builder))
+    }
+
+    /// Returns the header of the message being built.
+    pub fn header(&self) -> &MessageHeader {
+        self.message.header()
+    }
+
+    /// Adds an attribute of any type, encoded with the given encoder.
+    pub fn attribute<T: AttributeEncoder>(mut self, attribute_type: u16, encoder: &T) -> Self {
+        self.message.add_attribute(attribute_type, encoder);
+        self
+    }
+
+    /// Adds a SOFTWARE attribute.
+    pub fn software(self, value: &str) -> Self {
+        match Software::new(value) {
+            Ok(software) => self.attribute(SOFTWARE, &software),
+            Err(e) => self.fail(BuildError::Software(e)),
+        }
+    }
+
+    /// Adds a USERNAME attribute.
+    #[cfg(feature = "std")]
+    pub fn username(self, value: &str) -> Self {
+        match Username::new(value) {
+            Ok(username) => self.attribute(USERNAME, &username),
+            Err(e) => self.fail(BuildError::Username(e)),
+        }
+    }
+
+    /// Adds a REALM attribute.
+    #[cfg(feature = "std")]
+    pub fn realm(self, value: &str) -> Self {
+        match Realm::new(value) {
+            Ok(realm) => self.attribute(REALM, &realm),
+            Err(e) => self.fail(BuildError::Realm(e)),
+        }
+    }
+
+    /// Adds a NONCE attribute.
+    pub fn nonce(self, value: &str) -> Self {
+        match Nonce::new(value) {
+            Ok(nonce) => self.attribute(NONCE, &nonce),
+            Err(e) => self.fail(BuildError::Nonce(e)),
+        }
+    }
+
+    /// Adds an ERROR-CODE attribute, e.g. one of the [ErrorCode] constants.
+    pub fn error_code(self, error: ErrorCode) -> Self {
+        self.attribute(ERROR_CODE, &error)
+    }
+
+    /// Adds a MAPPED-ADDRESS attribute.
+    pub fn mapped_address(self, addr: SocketAddr) -> Self {
+        self.attribute(MAPPED_ADDRESS, &MappedAddress::encoder(addr))
+    }
+
+    /// Adds an XOR-MAPPED-ADDRESS attribute, obfuscated using the message's transaction ID.
+    pub fn xor_mapped_address(self, addr: SocketAddr) -> Self {
+        let tx_id = self.message.header().tx_id;
+        self.attribute(XOR_MAPPED_ADDRESS, &XorMappedAddress::encoder(addr, tx_id))
+    }
+
+    /// Adds a CHANGE-REQUEST attribute.
+    pub fn change_request(self, change_ip: bool, change_port: bool) -> Self {
+        self.attribute(
+            CHANGE_REQUEST,
+            &ChangeRequest {
+                change_ip,
+                change_port,
+            },
+        )
+    }
+
+    /// Finish building, returning the message or the first error encountered.
+    pub fn build(self) -> Result<StunMessage, BuildError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.message),
+        }
+    }
+
+    /// Finish building, and encode the message into bytes ready to be sent to a socket.
+    pub fn to_bytes(self) -> Result<Bytes, BuildError> {
+        self.build().map(|message| message.to_bytes())
+    }
+
+    fn fail(mut self, error: BuildError) -> Self {
+        self.error.get_or_insert(error);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::{
+        ErrorCodeDecoder, SoftwareEncodeError, UnknownAttributesDecoder, UsernameEncodeError,
+        Utf8Decoder,
+    };
+
+    #[test]
+    fn test_binding_request() {
+        let addr: SocketAddr = "192.0.2.1:32853".parse().unwrap();
+        let bytes = MessageBuilder::binding_request()
+            .software("test")
+            .change_request(false, true)
+            .xor_mapped_address(addr)
+            .to_bytes()
+            .unwrap();
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(message.class(), MessageClass::Request);
+        assert_eq!(message.method(), MessageMethod::BINDING);
+
+        let types: Vec<_> = message
+            .attributes()
+            .map(|attribute| attribute.unwrap().attribute_type())
+            .collect();
+        assert_eq!(types, vec![SOFTWARE, CHANGE_REQUEST, XOR_MAPPED_ADDRESS]);
+
+        let decoder = XorMappedAddress::decoder(message.tx_id());
+        let decoded = message.get_attribute(XOR_MAPPED_ADDRESS, &decoder);
+        assert_eq!(decoded.unwrap().unwrap(), addr);
+    }
+
+    #[test]
+    fn test_success_response() {
+        let tx_id = TransactionId::random();
+        let message = MessageBuilder::binding_success_response(tx_id)
+            .username("user")
+            .attribute(0x8050, &"custom")
+            .build()
+            .unwrap();
+
+        assert_eq!(message.header().class, MessageClass::SuccessResponse);
+        assert_eq!(message.header().tx_id, tx_id);
+        assert_eq!(
+            message.attribute(0x8050).unwrap().decode(&Utf8Decoder),
+            Ok("custom")
+        );
+    }
+
+    #[test]
+    fn test_error_response() {
+        let request = MessageBuilder::binding_request().build().unwrap();
+        let message = MessageBuilder::new(request.header().error_response())
+            .error_code(ErrorCode::BAD_REQUEST)
+            .build()
+            .unwrap();
+
+        assert_eq!(message.header().class, MessageClass::ErrorResponse);
+        assert_eq!(
+            message
+                .attribute(ERROR_CODE)
+                .unwrap()
+                .decode(&ErrorCodeDecoder),
+            Ok(ErrorCode::BAD_REQUEST)
+        );
+    }
+
+    #[test]
+    fn test_unknown_attributes_response() {
+        let bytes = MessageBuilder::binding_request()
+            .attribute(0x0024, &1u32)
+            .software("test")
+            .attribute(0x7f01, &"unknown")
+            .attribute(0x8050, &"optional")
+            .attribute(0x0024, &2u32)
+            .change_request(false, true)
+            .to_bytes()
+            .unwrap();
+        let request = StunDecoder::new(&bytes).unwrap();
+
+        let response = MessageBuilder::unknown_attributes_response(&request, &[CHANGE_REQUEST])
+            .unwrap()
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(response.header().is_response_to(request.header()));
+        assert_eq!(response.header().class, MessageClass::ErrorResponse);
+        assert_eq!(
+            response
+                .attribute(ERROR_CODE)
+                .unwrap()
+                .decode(&ErrorCodeDecoder),
+            Ok(ErrorCode::UNKNOWN_ATTRIBUTE)
+        );
+        assert_eq!(
+            response
+                .attribute(UNKNOWN_ATTRIBUTES)
+                .unwrap()
+                .decode(&UnknownAttributesDecoder),
+            Ok(vec![0x0024, 0x7f01])
+        );
+
+        let known = [0x0024, 0x7f01, CHANGE_REQUEST];
+        let response = MessageBuilder::unknown_attributes_response(&request, &known).unwrap();
+        assert!(response.is_none());
+    }
+
+    #[test]
+    fn test_unknown_attributes_in_indication() {
+        let bytes = MessageBuilder::indication(MessageMethod::BINDING)
+            .attribute(0x0024, &1u32)
+            .to_bytes()
+            .unwrap();
+        let indication = StunDecoder::new(&bytes).unwrap();
+        let response = MessageBuilder::unknown_attributes_response(&indication, &[]).unwrap();
+        assert!(response.is_none());
+    }
+
+    #[test]
+    fn test_first_error_is_returned() {
+        let result = MessageBuilder::binding_request()
+            .software(&"a".repeat(128))
+            .username(&"a".repeat(513))
+            .build();
+        assert_eq!(
+            result,
+            Err(BuildError::Software(SoftwareEncodeError::TooLong))
+        );
+
+        let result = MessageBuilder::binding_request()
+            .username(&"a".repeat(513))
+            .build();
+        assert_eq!(
+            result,
+            Err(BuildError::Username(UsernameEncodeError::TooLong))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::errors::MessageDecodeError;
+use crate::stream::{split_message, StreamItem};
+use crate::StunMessage;
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Gives the reason that a [StunCodec] could not read or write a message.
+#[derive(Debug)]
+pub enum CodecError {
+    /// The underlying transport failed.
+    Io(std::io::Error),
+
+    /// The header of the next message on the stream was invalid. As the boundaries of messages
+    /// can no longer be found, the stream should be closed.
+    Decode(MessageDecodeError),
+}
+
+impl std::fmt::Display for CodecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CodecError::Io(e) => write!(f, "I/O error: {}", e),
+            CodecError::Decode(e) => write!(f, "failed to decode message header: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CodecError::Io(e) => Some(e),
+            CodecError::Decode(e) => Some(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for CodecError {
+    fn from(other: std::io::Error) -> Self {
+        CodecError::Io(other)
+    }
+}
+
+/// A [tokio_util codec](tokio_util::codec) for sending and receiving STUN messages over a stream
+/// transport (e.g., TCP or TLS), using the same reassembly logic as
+/// [StreamDecoder](crate::StreamDecoder).
+///
+/// Each decoded item is the bytes of a single complete message, ready to be given to
+/// [StunDecoder](crate::StunDecoder). Messages can be sent either as already-encoded bytes, or as
+/// a [StunMessage].
+///
+/// ```ignore
+/// let mut framed = tokio_util::codec::Framed::new(tcp_stream, StunCodec);
+/// while let Some(bytes) = framed.next().await {
+///     let message = StunDecoder::new(&bytes?)?;
+///     // ...
+/// }
+/// ```
+#[derive(Debug, Default, Copy, Clone)]
+pub struct StunCodec;
+
+impl Decoder for StunCodec {
+    type Item = Bytes;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match split_message(src).map_err(CodecError::Decode)? {
+            StreamItem::Message(bytes) => Ok(Some(bytes)),
+            StreamItem::NeedMoreBytes(needed) => {
+                src.reserve(needed);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl Encoder<Bytes> for StunCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: Bytes, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+impl Encoder<&StunMessage> for StunCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: &StunMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        self.encode(item.to_bytes(), dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageBuilder, StunDecoder};
+
+    #[test]
+    fn test_round_trip() {
+        let first = MessageBuilder::binding_request()
+            .software("first")
+            .build()
+            .unwrap();
+        let second = MessageBuilder::binding_request()
+            .software("second")
+            .build()
+            .unwrap();
+
+        let mut buf = BytesMut::new();
+        StunCodec.encode(&first, &mut buf).unwrap();
+        StunCodec.encode(second.to_bytes(), &mut buf).unwrap();
+
+        let mut partial = buf.split_to(10);
+        assert!(StunCodec.decode(&mut partial).unwrap().is_none());
+        partial.unsplit(buf);
+        let mut buf = partial;
+
+        let decoded = StunCodec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded, first.to_bytes());
+        let decoded = StunCodec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(StunMessage::from_bytes(&decoded).unwrap(), second);
+        assert!(StunCodec.decode(&mut buf).unwrap().is_none());
+        assert!(StunDecoder::new(&decoded).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut buf = BytesMut::from(&[0xff; 20][..]);
+        assert!(matches!(
+            StunCodec.decode(&mut buf),
+            Err(CodecError::Decode(MessageDecodeError::NonZeroStartingBits))
+        ));
+    }
+}
@@ -0,0 +1,126 @@
+use core::fmt;
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// The number of bytes in the largest key (SHA-256).
+const MAX_KEY_BYTES: usize = 32;
+
+/// A key for the [long-term credential mechanism][], derived from a username, realm and password.
+///
+/// The key is used with the MESSAGE-INTEGRITY APIs (e.g.,
+/// [verify_integrity](crate::StunDecoder::verify_integrity)), through [as_bytes](Self::as_bytes).
+/// The key is overwritten with zeros when dropped, and is not shown by its `Debug` output.
+///
+/// The username, realm and password are used as given. RFC 5389 requires the realm and password
+/// to have been processed with SASLprep, and RFC 8489 with OpaqueString; otherwise, non-ASCII
+/// credentials may produce a different key than the peer's. The `text` module (available with the
+/// `std` feature) implements both profiles.
+///
+/// ```
+/// # use stunne_protocol::LongTermKey;
+/// let key = LongTermKey::new("user", "realm", "pass");
+/// assert_eq!(key.as_bytes().len(), 16);
+/// ```
+///
+/// [long-term credential mechanism]: https://datatracker.ietf.org/doc/html/rfc8489#section-9.2.2
+#[derive(Clone)]
+pub struct LongTermKey {
+    bytes: [u8; MAX_KEY_BYTES],
+    len: usize,
+}
+
+impl LongTermKey {
+    /// Derive the key as MD5(username ":" realm ":" password), as used by RFC 5389 and by the
+    /// MD5 password algorithm of RFC 8489.
+    pub fn new(username: &str, realm: &str, password: &str) -> Self {
+        Self::derive::<Md5>(username, realm, password)
+    }
+
+    /// Derive the key as SHA-256(username ":" realm ":" password), as used by the SHA-256
+    /// password algorithm of RFC 8489.
+    pub fn sha256(username: &str, realm: &str, password: &str) -> Self {
+        Self::derive::<Sha256>(username, realm, password)
+    }
+
+    fn derive<D: Digest>(username: &str, realm: &str, password: &str) -> Self {
+        let mut digest = D::new()
+            .chain_update(username)
+            .chain_update(":")
+            .chain_update(realm)
+            .chain_update(":")
+            .chain_update(password)
+            .finalize();
+
+        let mut key = Self {
+            bytes: [0; MAX_KEY_BYTES],
+            len: digest.len(),
+        };
+        key.bytes[..key.len].copy_from_slice(&digest);
+        digest.zeroize();
+        key
+    }
+
+    /// Returns the bytes of the key.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+impl AsRef<[u8]> for LongTermKey {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl Drop for LongTermKey {
+    fn drop(&mut self) {
+        self.bytes.zeroize();
+    }
+}
+
+impl fmt::Debug for LongTermKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LongTermKey").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md5_key() {
+        // The credentials of the long-term credential test vector of RFC 5769, section 2.4.
+        let key = LongTermKey::new(
+            "\u{30de}\u{30c8}\u{30ea}\u{30c3}\u{30af}\u{30b9}",
+            "example.org",
+            "TheMatrIX",
+        );
+        #[rustfmt::skip]
+        let expected = [
+            0xe8, 0xca, 0x7a, 0xd5, 0x9d, 0x5e, 0xb0, 0x51,
+            0x8e, 0x31, 0x29, 0x11, 0xd2, 0xda, 0xb2, 0xa9,
+        ];
+        assert_eq!(key.as_bytes(), &expected);
+    }
+
+    #[test]
+    fn test_sha256_key() {
+        let key = LongTermKey::sha256("user", "realm", "pass");
+        #[rustfmt::skip]
+        let expected = [
+            0x07, 0xe9, 0x34, 0x11, 0x7a, 0xbd, 0x40, 0x83,
+            0x6e, 0x7c, 0x63, 0x29, 0xb5, 0x47, 0x31, 0xb2,
+            0xb2, 0xd2, 0xa5, 0xf9, 0xa7, 0x1f, 0x54, 0x49,
+            0x22, 0xd7, 0x5e, 0x07, 0x30, 0xd8, 0x25, 0x1b,
+        ];
+        assert_eq!(key.as_bytes(), &expected);
+    }
+
+    #[test]
+    fn test_debug_hides_key() {
+        let key = LongTermKey::new("user", "realm", "pass");
+        assert_eq!(format!("{:?}", key), "LongTermKey { .. }");
+    }
+}
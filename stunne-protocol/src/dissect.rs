@@ -0,0 +1,260 @@
+use crate::attributes::types::DisplayAttributeType;
+use crate::attributes::{KnownAttribute, StunAttribute};
+use crate::report::{AttributeValue, Hex};
+use crate::utils::{decode_message_type, padding_for_attribute_length};
+use crate::{TransactionId, ATTRIBUTE_HEADER_BYTES, MAGIC_COOKIE, STUN_HEADER_BYTES};
+use core::fmt;
+
+/// The number of bytes shown on each row of a dissection.
+const BYTES_PER_ROW: usize = 8;
+
+/// Returns an annotated hexdump of the bytes of a message, for debugging interoperability
+/// problems byte by byte.
+///
+/// Each field of the header and of every attribute (type, length, value and padding) is shown on
+/// its own row, next to its offset and raw bytes. The message does not need to be valid: the
+/// dissection annotates as much of the buffer as it can, and marks any field that is cut short.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// let tx_id: TransactionId = "b7e7a701bc34d686fa87dfae".parse().unwrap();
+/// let bytes = MessageBuilder::new(MessageHeader {
+///     class: MessageClass::Request,
+///     method: MessageMethod::BINDING,
+///     tx_id,
+/// })
+/// .software("hi")
+/// .to_bytes()
+/// .unwrap();
+/// assert_eq!(
+///     dissect(&bytes).to_string(),
+///     "\
+/// 0000  00 01                    Message type: Request BINDING
+/// 0002  00 08                    Message length: 8
+/// 0004  21 12 a4 42              Magic cookie
+/// 0008  b7 e7 a7 01 bc 34 d6 86  Transaction ID: b7e7a701bc34d686fa87dfae
+/// 0010  fa 87 df ae
+/// 0014  80 22                    Attribute type: SOFTWARE
+/// 0016  00 02                    Attribute length: 2
+/// 0018  68 69                    Attribute value: \"hi\"
+/// 001a  00 00                    Padding
+/// "
+/// );
+/// ```
+pub fn dissect(buf: &[u8]) -> Dissection<'_> {
+    Dissection { buf }
+}
+
+/// An annotated hexdump of a message, as returned by [dissect].
+#[derive(Debug, Copy, Clone)]
+pub struct Dissection<'a> {
+    buf: &'a [u8],
+}
+
+impl fmt::Display for Dissection<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let buf = self.buf;
+        let Some(header) = buf.get(..STUN_HEADER_BYTES) else {
+            return row(
+                f,
+                0,
+                buf,
+                format_args!("Header (truncated: {} of 20 bytes)", buf.len()),
+            );
+        };
+
+        match decode_message_type([header[0], header[1]]) {
+            Ok((class, method)) if header[0] & 0b1100_0000 == 0 => row(
+                f,
+                0,
+                &header[0..2],
+                format_args!("Message type: {:?} {}", class, method),
+            )?,
+            _ => row(f, 0, &header[0..2], format_args!("Message type: invalid"))?,
+        }
+        let length = usize::from(u16::from_be_bytes([header[2], header[3]]));
+        let end = STUN_HEADER_BYTES + length;
+        match end.checked_sub(buf.len()) {
+            Some(missing) if missing > 0 => row(
+                f,
+                2,
+                &header[2..4],
+                format_args!(
+                    "Message length: {} (exceeds the buffer by {} bytes)",
+                    length, missing
+                ),
+            )?,
+            _ => row(
+                f,
+                2,
+                &header[2..4],
+                format_args!("Message length: {}", length),
+            )?,
+        }
+        match header[4..8] == MAGIC_COOKIE {
+            true => row(f, 4, &header[4..8], format_args!("Magic cookie"))?,
+            false => row(f, 4, &header[4..8], format_args!("Magic cookie: invalid"))?,
+        }
+        row(
+            f,
+            8,
+            &header[8..],
+            format_args!("Transaction ID: {}", Hex(&header[8..])),
+        )?;
+
+        let tx_id = TransactionId::from_bytes(header[8..].try_into().unwrap());
+        let end = end.min(buf.len());
+        let mut offset = STUN_HEADER_BYTES;
+        while offset < end {
+            let Some(attribute_header) = buf.get(offset..offset + ATTRIBUTE_HEADER_BYTES) else {
+                row(
+                    f,
+                    offset,
+                    &buf[offset..end],
+                    format_args!("Attribute header (truncated)"),
+                )?;
+                break;
+            };
+            let attribute_type = u16::from_be_bytes([attribute_header[0], attribute_header[1]]);
+            let value_length = usize::from(u16::from_be_bytes([
+                attribute_header[2],
+                attribute_header[3],
+            ]));
+            row(
+                f,
+                offset,
+                &attribute_header[0..2],
+                format_args!("Attribute type: {}", DisplayAttributeType(attribute_type)),
+            )?;
+            row(
+                f,
+                offset + 2,
+                &attribute_header[2..4],
+                format_args!("Attribute length: {}", value_length),
+            )?;
+
+            offset += ATTRIBUTE_HEADER_BYTES;
+            let value_end = offset + value_length;
+            if value_end > end {
+                return row(
+                    f,
+                    offset,
+                    &buf[offset..end],
+                    format_args!(
+                        "Attribute value (truncated: {} of {} bytes)",
+                        end - offset,
+                        value_length
+                    ),
+                );
+            }
+            let value = &buf[offset..value_end];
+            let attribute = StunAttribute::new(attribute_type, value);
+            match KnownAttribute::decode(&attribute, tx_id) {
+                Ok(KnownAttribute::Unknown { .. }) => {
+                    row(f, offset, value, format_args!("Attribute value"))?
+                }
+                Ok(known) => row(
+                    f,
+                    offset,
+                    value,
+                    format_args!("Attribute value: {}", AttributeValue(&known)),
+                )?,
+                Err(_) => row(f, offset, value, format_args!("Attribute value: invalid"))?,
+            }
+
+            offset = value_end;
+            let padding_end = (offset + padding_for_attribute_length(value_length)).min(end);
+            row(
+                f,
+                offset,
+                &buf[offset..padding_end],
+                format_args!("Padding"),
+            )?;
+            offset = padding_end;
+        }
+
+        row(f, end, &buf[end..], format_args!("Trailing bytes"))
+    }
+}
+
+/// Writes the bytes starting at the given offset, wrapped over as many rows as needed, with the
+/// label on the first row. Nothing is written if there are no bytes.
+fn row(
+    f: &mut fmt::Formatter<'_>,
+    offset: usize,
+    bytes: &[u8],
+    label: fmt::Arguments<'_>,
+) -> fmt::Result {
+    for (i, chunk) in bytes.chunks(BYTES_PER_ROW).enumerate() {
+        write!(f, "{:04x} ", offset + i * BYTES_PER_ROW)?;
+        for byte in chunk {
+            write!(f, " {:02x}", byte)?;
+        }
+        if i == 0 {
+            let padding = 3 * (BYTES_PER_ROW - chunk.len());
+            write!(f, "{:padding$}  {}", "", label)?;
+        }
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_vectors::sample_ipv4_response;
+
+    #[test]
+    fn test_sample_ipv4_response() {
+        assert_eq!(
+            dissect(&sample_ipv4_response::BYTES).to_string(),
+            "\
+0000  01 01                    Message type: SuccessResponse BINDING
+0002  00 3c                    Message length: 60
+0004  21 12 a4 42              Magic cookie
+0008  b7 e7 a7 01 bc 34 d6 86  Transaction ID: b7e7a701bc34d686fa87dfae
+0010  fa 87 df ae
+0014  80 22                    Attribute type: SOFTWARE
+0016  00 0b                    Attribute length: 11
+0018  74 65 73 74 20 76 65 63  Attribute value: \"test vector\"
+0020  74 6f 72
+0023  20                       Padding
+0024  00 20                    Attribute type: XOR-MAPPED-ADDRESS
+0026  00 08                    Attribute length: 8
+0028  00 01 a1 47 e1 12 a6 43  Attribute value: 192.0.2.1:32853
+0030  00 08                    Attribute type: MESSAGE-INTEGRITY
+0032  00 14                    Attribute length: 20
+0034  2b 91 f5 99 fd 9e 90 c3  Attribute value: 2b91f599fd9e90c38c7489f92af9ba53f06be7d7
+003c  8c 74 89 f9 2a f9 ba 53
+0044  f0 6b e7 d7
+0048  80 28                    Attribute type: FINGERPRINT
+004a  00 04                    Attribute length: 4
+004c  c0 7d 4c 96              Attribute value: 0xc07d4c96
+"
+        );
+    }
+
+    #[test]
+    fn test_truncated() {
+        let bytes = &sample_ipv4_response::BYTES;
+        assert_eq!(
+            dissect(&bytes[..6]).to_string(),
+            "0000  01 01 00 3c 21 12        Header (truncated: 6 of 20 bytes)\n"
+        );
+
+        let dissection = dissect(&bytes[..30]).to_string();
+        assert!(dissection.contains("Message length: 60 (exceeds the buffer by 50 bytes)\n"));
+        assert!(dissection.ends_with(
+            "\
+0018  74 65 73 74 20 76        Attribute value (truncated: 6 of 11 bytes)
+"
+        ));
+
+        let mut bytes = bytes.to_vec();
+        bytes[41] = 0x03;
+        bytes.extend_from_slice(&[0xff; 2]);
+        let dissection = dissect(&bytes).to_string();
+        assert!(dissection.contains("\n0028  00 03 a1 47 e1 12 a6 43  Attribute value: invalid\n"));
+        assert!(dissection.ends_with("\n0050  ff ff                    Trailing bytes\n"));
+    }
+}
@@ -0,0 +1,502 @@
+use crate::attributes::types::{FINGERPRINT, MESSAGE_INTEGRITY};
+use crate::attributes::StunAttributeIterator;
+use crate::encodings::fingerprint::FINGERPRINT_BYTES;
+use crate::encodings::message_integrity::MESSAGE_INTEGRITY_BYTES;
+use crate::encodings::{AttributeEncoder, Fingerprint, MessageIntegrity};
+use crate::errors::{EncodeError, MessageDecodeError};
+use crate::utils::{check_attribute_order, is_trailing_attribute, padding_for_attribute_length};
+use crate::DecodeOptions;
+use crate::{MessageHeader, ATTRIBUTE_HEADER_BYTES, PADDING_VALUE, STUN_HEADER_BYTES};
+use bytes::{BufMut, Bytes, BytesMut};
+
+/// Provides an interface that can be used to dynamically encode a stun datagram into a supplied
+/// buffer.
+///
+/// See example usage in [crate documentation](crate).
+///
+/// Note that the encoder is designed to continually reserve more bytes from the
+/// [BytesMut](bytes::BytesMut) buffer value as needed. If it needs to do this, it will allocate a
+/// new buffer, which can come with some performance penalty. These additional allocations can be
+/// avoided if the buffer is given a sane starting capacity. For example...
+///
+/// ```
+/// # use bytes::BytesMut;
+/// let mut buffer = BytesMut::with_capacity(1024);
+/// ```
+///
+/// An encoder using the above buffer now no longer need to allocate memory so long as the number
+/// of encoded bytes does not go above 1024. See the [BytesMut] documentation for more info.
+///
+/// To encode many messages (e.g., the responses of a busy server) without allocating a buffer
+/// for each of them, finish each message with
+/// [finish_and_reset](StunAttributeEncoder::finish_and_reset), which returns an encoder for the
+/// next message that reuses the buffer.
+pub struct StunEncoder {
+    buf: BytesMut,
+}
+
+impl StunEncoder {
+    /// Create the encoder with the given buffer.
+    pub fn new(buf: BytesMut) -> StunEncoder {
+        Self { buf }
+    }
+
+    /// Associates the given header information to be written to the buffer.
+    ///
+    /// Note that there is no guarantee that the header is written when this method is called, as
+    /// it's impossible to know the length of the data (which must be written alongside the header
+    /// data). Instead, it is more likely that the header will be written AFTER the attributes have
+    /// been written to the buffer, during the [finish](StunAttributeEncoder::finish) method.
+    pub fn encode_header(mut self, header: MessageHeader) -> StunAttributeEncoder {
+        self.buf.reserve(STUN_HEADER_BYTES);
+        let data_buf = self.buf.split_off(STUN_HEADER_BYTES);
+        StunAttributeEncoder {
+            header_buf: self.buf,
+            buf: data_buf,
+            next_attribute_byte: 0,
+            header,
+            trailer: None,
+        }
+    }
+
+    /// Continues encoding a message that has already been encoded into the buffer, so that more
+    /// attributes can be appended to it.
+    ///
+    /// This is useful for code that does not own the original encoder, such as a proxy inserting
+    /// an attribute, or a layer that adds a MESSAGE-INTEGRITY or FINGERPRINT attribute to a message
+    /// built elsewhere. The length in the header is rewritten when the message is finished.
+    ///
+    /// The buffer must contain exactly one message; the header is validated in the same way as
+    /// [StunDecoder::new](crate::StunDecoder::new).
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use stunne_protocol::*;
+    /// let bytes = MessageBuilder::binding_request()
+    ///     .software("Widget, Inc.")
+    ///     .to_bytes()
+    ///     .unwrap();
+    ///
+    /// let bytes = StunEncoder::new(BytesMut::from(&bytes[..]))
+    ///     .resume()
+    ///     .unwrap()
+    ///     .finish_with_fingerprint();
+    /// assert_eq!(StunDecoder::new(&bytes).unwrap().attributes().count(), 2);
+    /// ```
+    pub fn resume(mut self) -> Result<StunAttributeEncoder, MessageDecodeError> {
+        if self.buf.len() < STUN_HEADER_BYTES {
+            return Err(MessageDecodeError::header_too_short(self.buf.len()));
+        }
+        let header_buf = self.buf[..STUN_HEADER_BYTES].try_into().unwrap();
+        let (header, length) = MessageHeader::decode_with_length(header_buf)?;
+        let declared = usize::from(length);
+        let available = self.buf.len() - STUN_HEADER_BYTES;
+        if declared > available {
+            return Err(MessageDecodeError::LengthExceedsSlice {
+                declared,
+                available,
+            });
+        }
+        if declared < available {
+            return Err(MessageDecodeError::SliceExceedsLength {
+                declared,
+                available,
+            });
+        }
+
+        // The header is written again when the message is finished.
+        let data_buf = self.buf.split_off(STUN_HEADER_BYTES);
+        self.buf.clear();
+        let trailer = StunAttributeIterator::with_options(&data_buf, DecodeOptions::lenient())
+            .map_while(Result::ok)
+            .map(|attribute| attribute.attribute_type())
+            .filter(|attribute_type| is_trailing_attribute(*attribute_type))
+            .last();
+        Ok(StunAttributeEncoder {
+            header_buf: self.buf,
+            next_attribute_byte: data_buf.len(),
+            buf: data_buf,
+            header,
+            trailer,
+        })
+    }
+}
+
+pub struct StunAttributeEncoder {
+    header_buf: BytesMut,
+    buf: BytesMut,
+    next_attribute_byte: usize,
+    header: MessageHeader,
+    /// The last MESSAGE-INTEGRITY, MESSAGE-INTEGRITY-SHA256 or FINGERPRINT attribute added.
+    trailer: Option<u16>,
+}
+
+impl StunAttributeEncoder {
+    /// Adds an attribute, encoded with the given encoder.
+    ///
+    /// The order of attributes is not checked, so that any message can be built (e.g., to test
+    /// how a peer handles invalid messages). Nor are the lengths checked: a value longer than
+    /// 65535 bytes, or a message whose attributes are longer than that, will have its length
+    /// truncated. See [try_add_attribute](Self::try_add_attribute) for a variant that enforces
+    /// the order required by the RFCs, and the limits on lengths.
+    pub fn add_attribute<T: AttributeEncoder>(mut self, attribute_type: u16, encoder: &T) -> Self {
+        self.put_attribute(attribute_type, encoder);
+        self
+    }
+
+    /// Appends an attribute, returning the length of its value.
+    fn put_attribute<T: AttributeEncoder>(&mut self, attribute_type: u16, encoder: &T) -> usize {
+        // No need for reservation here.
+        // By default, `next_attribute_byte` is zero, so this will not panic.
+        // After the first attribute is created, `next_attribute_byte` will point to the byte where
+        // the writing of bytes has already advanced to, and thus presumed to have been reserved.
+        let mut attribute_header = self.buf.split_off(self.next_attribute_byte);
+        attribute_header.reserve(ATTRIBUTE_HEADER_BYTES);
+        attribute_header.reserve(ATTRIBUTE_HEADER_BYTES);
+
+        let mut attribute_data = attribute_header.split_off(ATTRIBUTE_HEADER_BYTES);
+        encoder.encode(&mut attribute_data);
+        let attribute_length = attribute_data.len();
+
+        // Add additional padding onto the attribute value if necessary
+        let padding_length = padding_for_attribute_length(attribute_length);
+        attribute_data.reserve(padding_length);
+        attribute_data.put_bytes(PADDING_VALUE, padding_length);
+
+        // Write to the attribute "header"
+        attribute_header.put_u16(attribute_type);
+        attribute_header.put_u16(attribute_length as u16);
+
+        // Put all of the split items back together again.
+        attribute_header.unsplit(attribute_data);
+        self.buf.unsplit(attribute_header);
+        self.next_attribute_byte += ATTRIBUTE_HEADER_BYTES + attribute_length + padding_length;
+        if is_trailing_attribute(attribute_type) {
+            self.trailer = Some(attribute_type);
+        }
+        attribute_length
+    }
+
+    /// Adds an attribute as with [add_attribute](Self::add_attribute), unless it may not follow
+    /// the attributes already in the message, its value is too long for the attribute's length
+    /// field, or the message would be too long for the header's length field.
+    ///
+    /// Only MESSAGE-INTEGRITY-SHA256 and FINGERPRINT may follow MESSAGE-INTEGRITY, only
+    /// FINGERPRINT may follow MESSAGE-INTEGRITY-SHA256, and FINGERPRINT must be the last
+    /// attribute; any other attribute would not be protected by the MESSAGE-INTEGRITY attribute,
+    /// or would be ignored by the receiver.
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use stunne_protocol::{attributes::types::*, errors::EncodeError, *};
+    /// let result = StunEncoder::new(BytesMut::new())
+    ///     .encode_header(MessageHeader {
+    ///         class: MessageClass::Request,
+    ///         method: MessageMethod::BINDING,
+    ///         tx_id: TransactionId::random(),
+    ///     })
+    ///     .add_message_integrity(b"secret")
+    ///     .try_add_attribute(SOFTWARE, &"Widget, Inc.");
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(EncodeError::InvalidOrder { attribute_type: SOFTWARE, preceding: MESSAGE_INTEGRITY })
+    /// ));
+    /// ```
+    pub fn try_add_attribute<T: AttributeEncoder>(
+        mut self,
+        attribute_type: u16,
+        encoder: &T,
+    ) -> Result<Self, EncodeError> {
+        check_attribute_order(self.trailer, attribute_type).map_err(|preceding| {
+            EncodeError::InvalidOrder {
+                attribute_type,
+                preceding,
+            }
+        })?;
+        let length = self.put_attribute(attribute_type, encoder);
+        if length > usize::from(u16::MAX) {
+            return Err(EncodeError::AttributeTooLarge {
+                attribute_type,
+                length,
+            });
+        }
+        self.check_size()
+    }
+
+    /// Adds a MESSAGE-INTEGRITY attribute, containing an HMAC-SHA1 of the message encoded so far
+    /// using the given key.
+    ///
+    /// As [described in RFC 5389][], the HMAC covers the header and all attributes that precede
+    /// the MESSAGE-INTEGRITY attribute. The length field of the header used in the calculation is
+    /// adjusted to point to the end of the MESSAGE-INTEGRITY attribute, even though it is not
+    /// necessarily the final attribute (e.g., a FINGERPRINT attribute may follow it).
+    ///
+    /// [described in RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.4
+    pub fn add_message_integrity(self, key: &[u8]) -> Self {
+        let length = self.buf.len() + ATTRIBUTE_HEADER_BYTES + MESSAGE_INTEGRITY_BYTES;
+        let header = self.header.to_bytes_with_length(length as u16);
+
+        let integrity = MessageIntegrity::calculate(key, &[&header, &self.buf]);
+        self.add_attribute(MESSAGE_INTEGRITY, &integrity)
+    }
+
+    /// Adds a FINGERPRINT attribute as the final attribute of the message, and then finishes the
+    /// message as with [finish](Self::finish).
+    ///
+    /// The fingerprint is the CRC-32 of the entire message up to (but excluding) the FINGERPRINT
+    /// attribute, XORed with 0x5354554e, as [described in RFC 5389][]. The header length used in
+    /// the calculation includes the FINGERPRINT attribute.
+    ///
+    /// [described in RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.5
+    pub fn finish_with_fingerprint(self) -> Bytes {
+        self.add_fingerprint().finish()
+    }
+
+    fn add_fingerprint(self) -> Self {
+        let length = self.buf.len() + ATTRIBUTE_HEADER_BYTES + FINGERPRINT_BYTES;
+        let header = self.header.to_bytes_with_length(length as u16);
+
+        let fingerprint = Fingerprint::calculate(&[&header, &self.buf]);
+        self.add_attribute(FINGERPRINT, &fingerprint)
+    }
+
+    /// Finishes the message as with [finish_with_fingerprint](Self::finish_with_fingerprint),
+    /// unless the message would be too long for the header's length field.
+    pub fn try_finish_with_fingerprint(self) -> Result<Bytes, EncodeError> {
+        let size = self.size() + ATTRIBUTE_HEADER_BYTES + FINGERPRINT_BYTES;
+        check_length(size)?;
+        Ok(self.finish_with_fingerprint())
+    }
+
+    /// Finishes the message as with [finish](Self::finish), unless the message is too long for
+    /// the header's length field (e.g., because attributes were added with
+    /// [add_attribute](Self::add_attribute), which does not check the length).
+    pub fn try_finish(self) -> Result<Bytes, EncodeError> {
+        Ok(self.check_size()?.finish())
+    }
+
+    /// Returns the number of bytes in the message encoded so far, including the header.
+    pub fn size(&self) -> usize {
+        STUN_HEADER_BYTES + self.buf.len()
+    }
+
+    fn check_size(self) -> Result<Self, EncodeError> {
+        check_length(self.size())?;
+        Ok(self)
+    }
+
+    pub fn finish(self) -> Bytes {
+        self.into_message().freeze()
+    }
+
+    /// Finishes the message as with [finish](Self::finish), but returns the buffer rather than
+    /// freezing it, so that it can be reused once the message has been sent (e.g., by giving it
+    /// back to a [BufferPool](crate::BufferPool)).
+    pub fn finish_mut(self) -> BytesMut {
+        self.into_message()
+    }
+
+    /// Finishes the message as with [finish](Self::finish), also returning an encoder for the
+    /// next message that reuses the buffer of this one.
+    ///
+    /// The encoder can use the capacity of the buffer that was not taken by the message straight
+    /// away. The capacity taken by the message is reclaimed once the returned bytes (and any
+    /// clones of them) have been dropped and the encoder needs more space, so a server that sends
+    /// each response before encoding the next will reuse the same allocation for every message.
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use stunne_protocol::*;
+    /// let mut encoder = StunEncoder::new(BytesMut::with_capacity(1024));
+    /// for _ in 0..1000 {
+    ///     let (bytes, next) = encoder
+    ///         .encode_header(MessageHeader {
+    ///             class: MessageClass::SuccessResponse,
+    ///             method: MessageMethod::BINDING,
+    ///             tx_id: TransactionId::random(),
+    ///         })
+    ///         .add_attribute(attributes::types::SOFTWARE, &"Widget, Inc.")
+    ///         .finish_and_reset();
+    ///     encoder = next;
+    ///     // socket.send(&bytes)?;
+    /// #   drop(bytes);
+    /// }
+    /// ```
+    pub fn finish_and_reset(self) -> (Bytes, StunEncoder) {
+        let mut buf = self.into_message();
+        let message = buf.split().freeze();
+        (message, StunEncoder { buf })
+    }
+
+    /// Finishes the message as with [finish_with_fingerprint](Self::finish_with_fingerprint),
+    /// also returning an encoder for the next message as with
+    /// [finish_and_reset](Self::finish_and_reset).
+    pub fn finish_with_fingerprint_and_reset(self) -> (Bytes, StunEncoder) {
+        self.add_fingerprint().finish_and_reset()
+    }
+
+    /// Discards the message encoded so far, returning an encoder that reuses its buffer, with the
+    /// capacity of the buffer retained.
+    pub fn reset(mut self) -> StunEncoder {
+        self.header_buf.unsplit(self.buf);
+        self.header_buf.clear();
+        StunEncoder {
+            buf: self.header_buf,
+        }
+    }
+
+    /// Writes the header, returning the buffer containing the encoded message.
+    fn into_message(mut self) -> BytesMut {
+        self.header
+            .encode_with_length(&mut self.header_buf, self.buf.len() as u16);
+        self.header_buf.unsplit(self.buf);
+        self.header_buf
+    }
+}
+
+/// Returns an error if a message of `size` bytes, including the header, is too long for the
+/// header's length field.
+fn check_length(size: usize) -> Result<(), EncodeError> {
+    let length = size - STUN_HEADER_BYTES;
+    if length > usize::from(u16::MAX) {
+        return Err(EncodeError::LengthOverflow { length });
+    }
+    Ok(())
+}
+
+/// An encoder that limits the encoded message (including the header) to a maximum size, e.g. the
+/// size of the buffers of a [BufferPool](crate::BufferPool).
+///
+/// A message sent over UDP should fit in a single datagram, as [described in RFC 8489][] (e.g.,
+/// 548 bytes for IPv4, or 1280 bytes for IPv6, when the path MTU is unknown). Unlike
+/// [StunEncoder], only fallible methods are given to add attributes, and each of them returns a
+/// [MessageTooLarge](EncodeError::MessageTooLarge) error rather than growing the message past the
+/// limit, so a finished message never exceeds it.
+///
+/// ```
+/// # use bytes::BytesMut;
+/// # use stunne_protocol::{attributes::types::SOFTWARE, errors::EncodeError, *};
+/// let result = LimitedEncoder::new(BytesMut::new(), 32)
+///     .encode_header(MessageHeader {
+///         class: MessageClass::Request,
+///         method: MessageMethod::BINDING,
+///         tx_id: TransactionId::random(),
+///     })
+///     .try_add_attribute(SOFTWARE, &"Widget, Inc.");
+/// assert!(matches!(
+///     result,
+///     Err(EncodeError::MessageTooLarge { size: 36, max_size: 32 })
+/// ));
+/// ```
+///
+/// [described in RFC 8489]: https://datatracker.ietf.org/doc/html/rfc8489#section-6.1
+pub struct LimitedEncoder {
+    encoder: StunEncoder,
+    max_size: usize,
+}
+
+impl LimitedEncoder {
+    /// Create the encoder with the given buffer, limiting the encoded message to `max_size` bytes.
+    pub fn new(buf: BytesMut, max_size: usize) -> LimitedEncoder {
+        Self {
+            encoder: StunEncoder::new(buf),
+            max_size,
+        }
+    }
+
+    /// Returns the maximum size of the message, including the header.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// Associates the given header information to be written to the buffer, as with
+    /// [StunEncoder::encode_header].
+    pub fn encode_header(self, header: MessageHeader) -> LimitedAttributeEncoder {
+        LimitedAttributeEncoder {
+            encoder: self.encoder.encode_header(header),
+            max_size: self.max_size,
+        }
+    }
+}
+
+/// Adds attributes to a message started by [LimitedEncoder::encode_header], failing rather than
+/// exceeding the maximum size of the message.
+pub struct LimitedAttributeEncoder {
+    encoder: StunAttributeEncoder,
+    max_size: usize,
+}
+
+impl LimitedAttributeEncoder {
+    /// Adds an attribute as with [StunAttributeEncoder::try_add_attribute], unless the message
+    /// would exceed its maximum size.
+    pub fn try_add_attribute<T: AttributeEncoder>(
+        self,
+        attribute_type: u16,
+        encoder: &T,
+    ) -> Result<Self, EncodeError> {
+        let max_size = self.max_size;
+        let encoder = self.encoder.try_add_attribute(attribute_type, encoder)?;
+        check_max_size(encoder.size(), max_size)?;
+        Ok(Self { encoder, max_size })
+    }
+
+    /// Adds a MESSAGE-INTEGRITY attribute as with
+    /// [StunAttributeEncoder::add_message_integrity], unless the message would exceed its maximum
+    /// size.
+    pub fn try_add_message_integrity(self, key: &[u8]) -> Result<Self, EncodeError> {
+        let size = self.size() + ATTRIBUTE_HEADER_BYTES + MESSAGE_INTEGRITY_BYTES;
+        check_length(size)?;
+        check_max_size(size, self.max_size)?;
+        Ok(Self {
+            encoder: self.encoder.add_message_integrity(key),
+            max_size: self.max_size,
+        })
+    }
+
+    /// Finishes the message as with [StunAttributeEncoder::finish_with_fingerprint], unless the
+    /// message would exceed its maximum size.
+    pub fn try_finish_with_fingerprint(self) -> Result<Bytes, EncodeError> {
+        let size = self.size() + ATTRIBUTE_HEADER_BYTES + FINGERPRINT_BYTES;
+        check_max_size(size, self.max_size)?;
+        self.encoder.try_finish_with_fingerprint()
+    }
+
+    /// Returns the number of bytes in the message encoded so far, including the header.
+    pub fn size(&self) -> usize {
+        self.encoder.size()
+    }
+
+    /// Finishes the message as with [StunAttributeEncoder::finish]. Every attribute has been
+    /// checked as it was added, so the message does not exceed its maximum size.
+    pub fn finish(self) -> Bytes {
+        self.encoder.finish()
+    }
+
+    /// Finishes the message as with [StunAttributeEncoder::finish_mut].
+    pub fn finish_mut(self) -> BytesMut {
+        self.encoder.finish_mut()
+    }
+
+    /// Finishes the message as with [StunAttributeEncoder::finish_and_reset], returning an
+    /// encoder for the next message with the same maximum size.
+    pub fn finish_and_reset(self) -> (Bytes, LimitedEncoder) {
+        let (message, encoder) = self.encoder.finish_and_reset();
+        let max_size = self.max_size;
+        (message, LimitedEncoder { encoder, max_size })
+    }
+
+    /// Discards the message encoded so far, as with [StunAttributeEncoder::reset].
+    pub fn reset(self) -> LimitedEncoder {
+        LimitedEncoder {
+            encoder: self.encoder.reset(),
+            max_size: self.max_size,
+        }
+    }
+}
+
+fn check_max_size(size: usize, max_size: usize) -> Result<(), EncodeError> {
+    if size > max_size {
+        return Err(EncodeError::MessageTooLarge { size, max_size });
+    }
+    Ok(())
+}
@@ -1,5 +1,5 @@
-use crate::encodings::{AttributeDecoder, AttributeEncoder};
-use bytes::{BufMut, BytesMut};
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
 
 const CHANGE_IP: u32 = 0b100;
 const CHANGE_PORT: u32 = 0b10;
@@ -10,16 +10,34 @@ pub enum ChangeRequestDecodeError {
     InvalidDataSize,
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+impl fmt::Display for ChangeRequestDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChangeRequestDecodeError::UnexpectedEndOfData => {
+                f.write_str("CHANGE-REQUEST value is shorter than four bytes")
+            }
+            ChangeRequestDecodeError::InvalidDataSize => {
+                f.write_str("CHANGE-REQUEST value is longer than four bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ChangeRequestDecodeError {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ChangeRequest {
     pub change_ip: bool,
     pub change_port: bool,
 }
 
-impl AttributeEncoder for ChangeRequest {
-    fn encode(&self, dst: &mut BytesMut) {
-        dst.reserve(4);
+impl AttributeSliceEncoder for ChangeRequest {
+    fn encoded_len(&self) -> usize {
+        CHANGE_REQUEST_BYTES
+    }
 
+    fn encode_to_slice(&self, dst: &mut [u8]) {
         let mut value = 0;
         if self.change_ip {
             value += CHANGE_IP;
@@ -29,7 +47,7 @@ impl AttributeEncoder for ChangeRequest {
             value += CHANGE_PORT;
         }
 
-        dst.put_u32(value);
+        dst.copy_from_slice(&value.to_be_bytes());
     }
 }
 
@@ -64,6 +82,8 @@ impl AttributeDecoder<'_> for ChangeRequestDecoder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
 
     fn test_encoding(expected_cr: ChangeRequest, expected_bytes: &[u8]) {
         // Use a zero capacity to ensure that capacity will grow as necessary.
@@ -77,8 +97,8 @@ mod tests {
             expected_cr
         );
 
-        let decoder = ChangeRequestDecoder::default();
-        let result = decoder.decode(&expected_bytes);
+        let decoder = ChangeRequestDecoder;
+        let result = decoder.decode(expected_bytes);
         match result {
             Ok(cr) => {
                 assert_eq!(
@@ -136,7 +156,7 @@ mod tests {
 
     #[test]
     fn test_unexpected_end_of_data() {
-        let decoder = ChangeRequestDecoder::default();
+        let decoder = ChangeRequestDecoder;
         let examples = [vec![], vec![0], vec![0, 0], vec![0, 0, 0]];
 
         for example in examples {
@@ -152,7 +172,7 @@ mod tests {
 
     #[test]
     fn test_invalid_data_size_with_larger_than_necessary_slice() {
-        let decoder = ChangeRequestDecoder::default();
+        let decoder = ChangeRequestDecoder;
         let examples = [
             vec![0, 0, 0, 0, 0],
             vec![0, 0, 0, 0, 0, 0, 0],
@@ -0,0 +1,85 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
+
+const CONNECTION_ID_BYTES: usize = 4;
+
+/// Gives the reason that a CONNECTION-ID attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConnectionIdDecodeError {
+    /// The attribute value was not exactly four bytes.
+    InvalidDataSize,
+}
+
+impl fmt::Display for ConnectionIdDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionIdDecodeError::InvalidDataSize => {
+                f.write_str("CONNECTION-ID value is not four bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConnectionIdDecodeError {}
+
+/// The value of a [CONNECTION-ID][] attribute, which uniquely identifies a peer data connection
+/// on a TURN server using TCP allocations.
+///
+/// [CONNECTION-ID]: https://datatracker.ietf.org/doc/html/rfc6062#section-6.2.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionId(pub u32);
+
+impl AttributeSliceEncoder for ConnectionId {
+    fn encoded_len(&self) -> usize {
+        CONNECTION_ID_BYTES
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.0.to_be_bytes());
+    }
+}
+
+#[derive(Default)]
+pub struct ConnectionIdDecoder;
+
+impl AttributeDecoder<'_> for ConnectionIdDecoder {
+    type Item = ConnectionId;
+    type Error = ConnectionIdDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let bytes = buf
+            .try_into()
+            .map_err(|_| ConnectionIdDecodeError::InvalidDataSize)?;
+        Ok(ConnectionId(u32::from_be_bytes(bytes)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encoding() {
+        let mut buf = BytesMut::with_capacity(0);
+        ConnectionId(0x1234_5678).encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[0x12, 0x34, 0x56, 0x78]);
+
+        assert_eq!(
+            ConnectionIdDecoder.decode(&buf),
+            Ok(ConnectionId(0x1234_5678))
+        );
+    }
+
+    #[test]
+    fn test_invalid_data_size() {
+        for size in [0, 3, 5, 8] {
+            assert_eq!(
+                ConnectionIdDecoder.decode(&vec![0; size]),
+                Err(ConnectionIdDecodeError::InvalidDataSize)
+            );
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::convert::Infallible;
+
+/// The value of a TURN [DATA][] attribute: an opaque payload being relayed to or from a peer.
+///
+/// The payload is encoded as-is; any padding needed to align the attribute is added by the
+/// message encoder, and removed before the value is given to the decoder.
+///
+/// [DATA]: https://datatracker.ietf.org/doc/html/rfc5766#section-14.4
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Data<'a> {
+    pub payload: &'a [u8],
+}
+
+impl AttributeSliceEncoder for Data<'_> {
+    fn encoded_len(&self) -> usize {
+        self.payload.len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(self.payload);
+    }
+}
+
+#[derive(Default)]
+pub struct DataDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for DataDecoder {
+    type Item = Data<'buf>;
+    type Error = Infallible;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        Ok(Data { payload: buf })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::types::DATA;
+    use crate::encodings::AttributeEncoder;
+    use crate::{
+        MessageClass, MessageHeader, MessageMethod, StunDecoder, StunEncoder, TransactionId,
+    };
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encoding() {
+        let data = Data {
+            payload: &[1, 2, 3, 4, 5],
+        };
+        let mut buf = BytesMut::with_capacity(0);
+        data.encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[1, 2, 3, 4, 5]);
+
+        assert_eq!(DataDecoder.decode(&buf), Ok(data));
+    }
+
+    #[test]
+    fn test_padding_is_not_part_of_payload() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Indication,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(DATA, &Data { payload: &[9; 7] })
+            .finish();
+        assert_eq!(&bytes[20..24], &[0x00, 0x13, 0, 7]);
+        assert_eq!(&bytes[24..], &[9, 9, 9, 9, 9, 9, 9, 0]);
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        let attribute = message.attributes().next().unwrap().unwrap();
+        assert_eq!(attribute.decode(&DataDecoder).unwrap().payload, &[9; 7]);
+    }
+}
@@ -0,0 +1,407 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
+use core::str::{from_utf8, Utf8Error};
+
+/// RFC 5389 requires the reason phrase to be "less than 128 characters".
+const REASON_CHARACTER_LIMIT: usize = 128;
+
+/// RFC 5389 notes that 127 characters "can be as long as 763 bytes".
+const REASON_BYTE_LIMIT: usize = 763;
+
+/// Number of bytes before the reason phrase: two reserved bytes, the class, and the number.
+const ERROR_CODE_HEADER_BYTES: usize = 4;
+
+/// Gives the reason that a value could not be used as an ERROR-CODE attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorCodeEncodeError {
+    /// The code was not in the range 300..=699.
+    InvalidCode,
+
+    /// The reason phrase was 128 characters or longer, or longer than 763 bytes.
+    ReasonTooLong,
+}
+
+impl fmt::Display for ErrorCodeEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCodeEncodeError::InvalidCode => f.write_str("error code must be from 300 to 699"),
+            ErrorCodeEncodeError::ReasonTooLong => f.write_str(
+                "error reason phrase must be less than 128 characters and at most 763 bytes",
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ErrorCodeEncodeError {}
+
+/// Gives the reason that an ERROR-CODE attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ErrorCodeDecodeError {
+    /// The value was shorter than the four bytes holding the code.
+    UnexpectedEndOfData,
+
+    /// The class was not in the range 3..=6, or the number was greater than 99.
+    InvalidCode,
+
+    /// The reason phrase was not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+
+    /// The reason phrase was 128 characters or longer, or longer than 763 bytes.
+    ReasonTooLong,
+}
+
+impl fmt::Display for ErrorCodeDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorCodeDecodeError::UnexpectedEndOfData => {
+                f.write_str("ERROR-CODE value is shorter than four bytes")
+            }
+            ErrorCodeDecodeError::InvalidCode => f.write_str("error code must be from 300 to 699"),
+            ErrorCodeDecodeError::InvalidUtf8(e) => {
+                write!(f, "error reason phrase is not valid UTF-8: {}", e)
+            }
+            ErrorCodeDecodeError::ReasonTooLong => f.write_str(
+                "error reason phrase must be less than 128 characters and at most 763 bytes",
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ErrorCodeDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ErrorCodeDecodeError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The value of an [ERROR-CODE][] attribute: a numeric code from 300 to 699, and a reason
+/// phrase for a human to read.
+///
+/// Constants are provided for the codes registered with IANA, each with the reason phrase
+/// suggested by the RFC that defines it.
+///
+/// ```
+/// # use stunne_protocol::encodings::ErrorCode;
+/// let error = ErrorCode::STALE_NONCE;
+/// assert_eq!(error.code(), 438);
+/// assert_eq!(error.reason(), "Stale Nonce");
+/// assert!(error.is_retryable());
+///
+/// let error = ErrorCode::new(420, "Unknown Attribute: 0x0024").unwrap();
+/// assert_eq!(error, ErrorCode::UNKNOWN_ATTRIBUTE.with_reason("Unknown Attribute: 0x0024"));
+/// ```
+///
+/// [ERROR-CODE]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.6
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ErrorCode<'a> {
+    code: u16,
+    reason: &'a str,
+}
+
+impl ErrorCode<'static> {
+    /// The client should contact the server given in the ALTERNATE-SERVER attribute.
+    pub const TRY_ALTERNATE: Self = Self::registered(300, "Try Alternate");
+
+    /// The request was malformed.
+    pub const BAD_REQUEST: Self = Self::registered(400, "Bad Request");
+
+    /// The request did not contain the correct credentials.
+    pub const UNAUTHORIZED: Self = Self::registered(401, "Unauthorized");
+
+    /// The request was valid, but cannot be performed due to administrative restrictions.
+    pub const FORBIDDEN: Self = Self::registered(403, "Forbidden");
+
+    /// The server did not understand a comprehension-required attribute in the request.
+    pub const UNKNOWN_ATTRIBUTE: Self = Self::registered(420, "Unknown Attribute");
+
+    /// A TURN request was received for an allocation that does not match the 5-tuple.
+    pub const ALLOCATION_MISMATCH: Self = Self::registered(437, "Allocation Mismatch");
+
+    /// The NONCE used by the client was no longer valid.
+    pub const STALE_NONCE: Self = Self::registered(438, "Stale Nonce");
+
+    /// The server does not support the address family requested by a TURN client.
+    pub const ADDRESS_FAMILY_NOT_SUPPORTED: Self =
+        Self::registered(440, "Address Family not Supported");
+
+    /// The credentials in a TURN request do not match those used to create the allocation.
+    pub const WRONG_CREDENTIALS: Self = Self::registered(441, "Wrong Credentials");
+
+    /// The TURN server does not support the requested transport protocol.
+    pub const UNSUPPORTED_TRANSPORT_PROTOCOL: Self =
+        Self::registered(442, "Unsupported Transport Protocol");
+
+    /// The peer address is of a different family than the TURN allocation.
+    pub const PEER_ADDRESS_FAMILY_MISMATCH: Self =
+        Self::registered(443, "Peer Address Family Mismatch");
+
+    /// A TURN connection to the peer already exists.
+    pub const CONNECTION_ALREADY_EXISTS: Self = Self::registered(446, "Connection Already Exists");
+
+    /// The TURN server could not connect to the peer.
+    pub const CONNECTION_TIMEOUT_OR_FAILURE: Self =
+        Self::registered(447, "Connection Timeout or Failure");
+
+    /// No more TURN allocations can be created for the user at this time.
+    pub const ALLOCATION_QUOTA_REACHED: Self = Self::registered(486, "Allocation Quota Reached");
+
+    /// Both ICE agents believe they are in the same role.
+    pub const ROLE_CONFLICT: Self = Self::registered(487, "Role Conflict");
+
+    /// The server suffered a temporary error.
+    pub const SERVER_ERROR: Self = Self::registered(500, "Server Error");
+
+    /// The TURN server cannot create the allocation due to a lack of capacity.
+    pub const INSUFFICIENT_CAPACITY: Self = Self::registered(508, "Insufficient Capacity");
+
+    const fn registered(code: u16, reason: &'static str) -> Self {
+        Self { code, reason }
+    }
+
+    /// Returns the error code with the given number, using the reason phrase of the registered
+    /// code if there is one, or an empty reason phrase otherwise.
+    pub fn from_code(code: u16) -> Result<Self, ErrorCodeEncodeError> {
+        Self::new(code, default_reason(code).unwrap_or(""))
+    }
+}
+
+impl<'a> ErrorCode<'a> {
+    pub fn new(code: u16, reason: &'a str) -> Result<Self, ErrorCodeEncodeError> {
+        if !(300..=699).contains(&code) {
+            return Err(ErrorCodeEncodeError::InvalidCode);
+        }
+        if reason.len() > REASON_BYTE_LIMIT || reason.chars().count() >= REASON_CHARACTER_LIMIT {
+            return Err(ErrorCodeEncodeError::ReasonTooLong);
+        }
+        Ok(Self { code, reason })
+    }
+
+    /// Returns the same code with a different reason phrase, e.g. to give more detail about the
+    /// error.
+    ///
+    /// As the code is already known to be valid, this cannot fail; a reason phrase that is too
+    /// long is truncated to the limit instead.
+    pub fn with_reason<'b>(&self, reason: &'b str) -> ErrorCode<'b> {
+        let mut end = reason.len().min(REASON_BYTE_LIMIT);
+        if let Some((index, _)) = reason.char_indices().nth(REASON_CHARACTER_LIMIT - 1) {
+            end = end.min(index);
+        }
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        ErrorCode {
+            code: self.code,
+            reason: &reason[..end],
+        }
+    }
+
+    /// The numeric code (e.g., 401).
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    /// The hundreds digit of the code (e.g., 4 for 401).
+    pub fn class(&self) -> u8 {
+        (self.code / 100) as u8
+    }
+
+    /// The code modulo 100 (e.g., 1 for 401).
+    pub fn number(&self) -> u8 {
+        (self.code % 100) as u8
+    }
+
+    pub fn reason(&self) -> &'a str {
+        self.reason
+    }
+
+    /// Returns true if the client may succeed by retrying the request after acting on the error:
+    /// contacting the alternate server (300), providing credentials (401), using the new nonce
+    /// (438), switching ICE roles (487), or waiting for the server to recover (500, 508).
+    pub fn is_retryable(&self) -> bool {
+        matches!(self.code, 300 | 401 | 438 | 487 | 500 | 508)
+    }
+}
+
+/// Returns the reason phrase for a code registered with IANA, as suggested by the RFC that
+/// defines it.
+pub fn default_reason(code: u16) -> Option<&'static str> {
+    let registered = match code {
+        300 => ErrorCode::TRY_ALTERNATE,
+        400 => ErrorCode::BAD_REQUEST,
+        401 => ErrorCode::UNAUTHORIZED,
+        403 => ErrorCode::FORBIDDEN,
+        420 => ErrorCode::UNKNOWN_ATTRIBUTE,
+        437 => ErrorCode::ALLOCATION_MISMATCH,
+        438 => ErrorCode::STALE_NONCE,
+        440 => ErrorCode::ADDRESS_FAMILY_NOT_SUPPORTED,
+        441 => ErrorCode::WRONG_CREDENTIALS,
+        442 => ErrorCode::UNSUPPORTED_TRANSPORT_PROTOCOL,
+        443 => ErrorCode::PEER_ADDRESS_FAMILY_MISMATCH,
+        446 => ErrorCode::CONNECTION_ALREADY_EXISTS,
+        447 => ErrorCode::CONNECTION_TIMEOUT_OR_FAILURE,
+        486 => ErrorCode::ALLOCATION_QUOTA_REACHED,
+        487 => ErrorCode::ROLE_CONFLICT,
+        500 => ErrorCode::SERVER_ERROR,
+        508 => ErrorCode::INSUFFICIENT_CAPACITY,
+        _ => return None,
+    };
+    Some(registered.reason)
+}
+
+impl fmt::Display for ErrorCode<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code, self.reason)
+    }
+}
+
+impl AttributeSliceEncoder for ErrorCode<'_> {
+    fn encoded_len(&self) -> usize {
+        ERROR_CODE_HEADER_BYTES + self.reason.len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        let (header, reason) = dst.split_at_mut(ERROR_CODE_HEADER_BYTES);
+        header.copy_from_slice(&[0, 0, self.class(), self.number()]);
+        reason.copy_from_slice(self.reason.as_bytes());
+    }
+}
+
+#[derive(Default)]
+pub struct ErrorCodeDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for ErrorCodeDecoder {
+    type Item = ErrorCode<'buf>;
+    type Error = ErrorCodeDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        if buf.len() < ERROR_CODE_HEADER_BYTES {
+            return Err(ErrorCodeDecodeError::UnexpectedEndOfData);
+        }
+
+        let (header, reason) = buf.split_at(ERROR_CODE_HEADER_BYTES);
+        let class = header[2] & 0b111;
+        let number = header[3];
+        if !(3..=6).contains(&class) || number > 99 {
+            return Err(ErrorCodeDecodeError::InvalidCode);
+        }
+
+        if reason.len() > REASON_BYTE_LIMIT {
+            return Err(ErrorCodeDecodeError::ReasonTooLong);
+        }
+        let reason = from_utf8(reason).map_err(ErrorCodeDecodeError::InvalidUtf8)?;
+        if reason.chars().count() >= REASON_CHARACTER_LIMIT {
+            return Err(ErrorCodeDecodeError::ReasonTooLong);
+        }
+
+        Ok(ErrorCode {
+            code: u16::from(class) * 100 + u16::from(number),
+            reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encoding() {
+        let mut buf = BytesMut::with_capacity(0);
+        ErrorCode::UNAUTHORIZED.encode(&mut buf);
+
+        #[rustfmt::skip]
+        let expected = [
+            0, 0, // Reserved
+            4, 1, // Class and number
+            b'U', b'n', b'a', b'u', b't', b'h', b'o', b'r', b'i', b'z', b'e', b'd',
+        ];
+        assert_eq!(buf.as_ref(), &expected);
+        assert_eq!(
+            ErrorCodeDecoder.decode(&expected),
+            Ok(ErrorCode::UNAUTHORIZED)
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        assert_eq!(
+            ErrorCode::new(420, "Unknown Attribute"),
+            Ok(ErrorCode::UNKNOWN_ATTRIBUTE)
+        );
+        assert_eq!(
+            ErrorCode::new(299, ""),
+            Err(ErrorCodeEncodeError::InvalidCode)
+        );
+        assert_eq!(
+            ErrorCode::new(700, ""),
+            Err(ErrorCodeEncodeError::InvalidCode)
+        );
+        assert_eq!(
+            ErrorCode::new(400, &"a".repeat(128)),
+            Err(ErrorCodeEncodeError::ReasonTooLong)
+        );
+
+        assert_eq!(ErrorCode::from_code(438), Ok(ErrorCode::STALE_NONCE));
+        assert_eq!(ErrorCode::from_code(699).unwrap().reason(), "");
+    }
+
+    #[test]
+    fn test_with_reason() {
+        let error = ErrorCode::BAD_REQUEST.with_reason("Missing USERNAME");
+        assert_eq!(error.code(), 400);
+        assert_eq!(error.reason(), "Missing USERNAME");
+
+        let long = "𓄁".repeat(200);
+        let error = ErrorCode::BAD_REQUEST.with_reason(&long);
+        assert_eq!(error.reason().chars().count(), 127);
+        assert!(ErrorCode::new(error.code(), error.reason()).is_ok());
+    }
+
+    #[test]
+    fn test_accessors() {
+        let error = ErrorCode::ROLE_CONFLICT;
+        assert_eq!(error.class(), 4);
+        assert_eq!(error.number(), 87);
+        assert!(error.is_retryable());
+        assert!(!ErrorCode::BAD_REQUEST.is_retryable());
+        assert_eq!(error.to_string(), "487 Role Conflict");
+
+        assert_eq!(default_reason(508), Some("Insufficient Capacity"));
+        assert_eq!(default_reason(499), None);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(
+            ErrorCodeDecoder.decode(&[0, 0, 4]),
+            Err(ErrorCodeDecodeError::UnexpectedEndOfData)
+        );
+        assert_eq!(
+            ErrorCodeDecoder.decode(&[0, 0, 2, 0]),
+            Err(ErrorCodeDecodeError::InvalidCode)
+        );
+        assert_eq!(
+            ErrorCodeDecoder.decode(&[0, 0, 4, 100]),
+            Err(ErrorCodeDecodeError::InvalidCode)
+        );
+        assert!(matches!(
+            ErrorCodeDecoder.decode(&[0, 0, 4, 0, 0xff]),
+            Err(ErrorCodeDecodeError::InvalidUtf8(_))
+        ));
+
+        let mut value = vec![0, 0, 4, 0];
+        value.extend_from_slice("a".repeat(127).as_bytes());
+        assert!(ErrorCodeDecoder.decode(&value).is_ok());
+        value.push(b'a');
+        assert_eq!(
+            ErrorCodeDecoder.decode(&value),
+            Err(ErrorCodeDecodeError::ReasonTooLong)
+        );
+    }
+}
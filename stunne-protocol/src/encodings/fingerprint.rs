@@ -0,0 +1,266 @@
+use crate::attributes::types::FINGERPRINT;
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use crate::errors::FingerprintError;
+use crate::{StunDecoder, ATTRIBUTE_HEADER_BYTES};
+use core::fmt;
+
+/// Number of bytes in the value of a FINGERPRINT attribute.
+pub(crate) const FINGERPRINT_BYTES: usize = 4;
+
+/// The value XORed with the CRC-32 of the message to produce the fingerprint.
+const FINGERPRINT_XOR: u32 = 0x5354_554e;
+
+/// Gives the reason that a FINGERPRINT attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum FingerprintDecodeError {
+    /// The attribute value was not exactly four bytes.
+    InvalidDataSize,
+}
+
+impl fmt::Display for FingerprintDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FingerprintDecodeError::InvalidDataSize => {
+                f.write_str("FINGERPRINT value is not four bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FingerprintDecodeError {}
+
+/// The value carried in a [FINGERPRINT][] attribute.
+///
+/// Users will typically not create this value themselves, as the CRC must be calculated over the
+/// encoded message. Instead, see
+/// [finish_with_fingerprint](crate::StunAttributeEncoder::finish_with_fingerprint).
+///
+/// [FINGERPRINT]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.5
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub value: u32,
+}
+
+impl Fingerprint {
+    /// Calculate the fingerprint (the CRC-32 XORed with 0x5354554e) of the given byte slices, in
+    /// order.
+    pub(crate) fn calculate(data: &[&[u8]]) -> Self {
+        let mut hasher = crc32fast::Hasher::new();
+        for bytes in data {
+            hasher.update(bytes);
+        }
+        Self {
+            value: hasher.finalize() ^ FINGERPRINT_XOR,
+        }
+    }
+}
+
+impl AttributeSliceEncoder for Fingerprint {
+    fn encoded_len(&self) -> usize {
+        FINGERPRINT_BYTES
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.value.to_be_bytes());
+    }
+}
+
+/// Verifies the FINGERPRINT attributes of received messages, for demultiplexing STUN from other
+/// protocols on hot paths such as media servers.
+///
+/// The CRC-32 is computed with [crc32fast][], which uses the carry-less multiplication (PCLMULQDQ)
+/// instructions on x86 and the CRC-32 instructions on AArch64 where the CPU supports them, and a
+/// table-driven implementation processing 16 bytes at a time otherwise. With the `std` feature,
+/// the CPU is checked at runtime; without it, the target features the crate was compiled with
+/// decide. A verifier makes this selection once, when it is created, rather than once per
+/// message, so it should be kept and reused (it is cheap to clone and can be shared between
+/// threads).
+///
+/// ```
+/// # use bytes::BytesMut;
+/// # use stunne_protocol::{encodings::FingerprintVerifier, errors::FingerprintError, *};
+/// # let header = MessageHeader {
+/// #     class: MessageClass::Request,
+/// #     method: MessageMethod::BINDING,
+/// #     tx_id: TransactionId::random(),
+/// # };
+/// let verifier = FingerprintVerifier::new();
+/// let signed = StunEncoder::new(BytesMut::new())
+///     .encode_header(header.clone())
+///     .finish_with_fingerprint();
+/// assert_eq!(verifier.verify(&signed), Ok(()));
+///
+/// let unsigned = StunEncoder::new(BytesMut::new())
+///     .encode_header(header)
+///     .finish();
+/// let results: Vec<_> = verifier.verify_batch([&signed[..], &unsigned[..]]).collect();
+/// assert_eq!(results, [Ok(()), Err(FingerprintError::MissingAttribute)]);
+/// ```
+///
+/// [crc32fast]: https://docs.rs/crc32fast
+#[derive(Debug, Clone)]
+pub struct FingerprintVerifier {
+    /// A hasher with no input, cloned for each message.
+    hasher: crc32fast::Hasher,
+}
+
+impl Default for FingerprintVerifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FingerprintVerifier {
+    /// Create a verifier using the fastest CRC-32 implementation available.
+    pub fn new() -> Self {
+        Self {
+            hasher: crc32fast::Hasher::new(),
+        }
+    }
+
+    /// Decodes the message and verifies its FINGERPRINT attribute, which must be the last
+    /// attribute of the message.
+    pub fn verify(&self, message: &[u8]) -> Result<(), FingerprintError> {
+        let message = StunDecoder::new(message).map_err(FingerprintError::Decode)?;
+        self.verify_decoded(&message)
+    }
+
+    /// Verifies the FINGERPRINT attribute of a message that has already been decoded.
+    pub fn verify_decoded(&self, message: &StunDecoder<'_>) -> Result<(), FingerprintError> {
+        let mut last = None;
+        for attribute in message.attributes() {
+            last = Some(attribute.map_err(FingerprintError::Decode)?);
+        }
+        let attribute = last
+            .filter(|attribute| attribute.attribute_type() == FINGERPRINT)
+            .ok_or(FingerprintError::MissingAttribute)?;
+        let fingerprint = attribute
+            .decode(&FingerprintDecoder)
+            .map_err(FingerprintError::InvalidAttribute)?;
+
+        // The FINGERPRINT attribute is last, and its value needs no padding.
+        let attribute_buf = message.attribute_buf;
+        let covered_attributes =
+            &attribute_buf[..attribute_buf.len() - ATTRIBUTE_HEADER_BYTES - FINGERPRINT_BYTES];
+        let mut hasher = self.hasher.clone();
+        hasher.update(message.header_buf);
+        hasher.update(covered_attributes);
+        match hasher.finalize() ^ FINGERPRINT_XOR == fingerprint.value {
+            true => Ok(()),
+            false => Err(FingerprintError::Mismatch),
+        }
+    }
+
+    /// Verifies the FINGERPRINT attribute of each of the messages, as with
+    /// [verify](Self::verify), yielding the results in order.
+    pub fn verify_batch<'a, I>(
+        &'a self,
+        messages: I,
+    ) -> impl Iterator<Item = Result<(), FingerprintError>> + 'a
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+        I::IntoIter: 'a,
+    {
+        messages.into_iter().map(|message| self.verify(message))
+    }
+}
+
+#[derive(Default)]
+pub struct FingerprintDecoder;
+
+impl AttributeDecoder<'_> for FingerprintDecoder {
+    type Item = Fingerprint;
+    type Error = FingerprintDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let bytes = buf
+            .try_into()
+            .map_err(|_| FingerprintDecodeError::InvalidDataSize)?;
+        Ok(Fingerprint {
+            value: u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_calculate() {
+        // The CRC-32 of "123456789" is 0xcbf43926.
+        let fingerprint = Fingerprint::calculate(&[b"1234", b"56789"]);
+        assert_eq!(fingerprint.value, 0xcbf4_3926 ^ 0x5354_554e);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let fingerprint = Fingerprint { value: 0x0102_0304 };
+        let mut buf = BytesMut::with_capacity(0);
+        fingerprint.encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[1, 2, 3, 4]);
+
+        assert_eq!(FingerprintDecoder.decode(&buf), Ok(fingerprint));
+    }
+
+    #[test]
+    fn test_verify() {
+        use crate::errors::MessageDecodeError;
+        use crate::test_vectors::{sample_ipv4_response, sample_ipv6_response, sample_request};
+
+        let verifier = FingerprintVerifier::new();
+        let samples = [
+            &sample_request::BYTES[..],
+            &sample_ipv4_response::BYTES[..],
+            &sample_ipv6_response::BYTES[..],
+        ];
+        assert!(verifier.verify_batch(samples).all(|result| result.is_ok()));
+
+        let mut corrupted = sample_request::BYTES;
+        corrupted[30] ^= 1;
+        assert_eq!(verifier.verify(&corrupted), Err(FingerprintError::Mismatch));
+
+        // Without the FINGERPRINT attribute, the MESSAGE-INTEGRITY attribute is last.
+        let mut truncated = sample_request::BYTES[..100].to_vec();
+        truncated[3] -= 8;
+        assert_eq!(
+            verifier.verify(&truncated),
+            Err(FingerprintError::MissingAttribute)
+        );
+
+        // A FINGERPRINT attribute with an eight-byte value.
+        let mut long = sample_request::BYTES.to_vec();
+        long.extend_from_slice(&[0; 4]);
+        long[3] += 4;
+        long[103] = 8;
+        assert_eq!(
+            verifier.verify(&long),
+            Err(FingerprintError::InvalidAttribute(
+                FingerprintDecodeError::InvalidDataSize
+            ))
+        );
+
+        assert!(matches!(
+            verifier.verify(&sample_request::BYTES[..50]),
+            Err(FingerprintError::Decode(
+                MessageDecodeError::LengthExceedsSlice { .. }
+            ))
+        ));
+
+        let message = StunDecoder::new(&sample_ipv4_response::BYTES).unwrap();
+        assert_eq!(message.verify_fingerprint(), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_data_size() {
+        for size in [0, 3, 5, 8] {
+            assert_eq!(
+                FingerprintDecoder.decode(&vec![0; size]),
+                Err(FingerprintDecodeError::InvalidDataSize)
+            );
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
+
+/// Gives the reason that an integer attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IntegerDecodeError {
+    /// The attribute value was not exactly the size of the integer being decoded (e.g., four bytes
+    /// for a u32).
+    InvalidDataSize,
+}
+
+impl fmt::Display for IntegerDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegerDecodeError::InvalidDataSize => {
+                f.write_str("value is not the size of the integer")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IntegerDecodeError {}
+
+/// Encodes the integer as a big-endian u32 (e.g., LIFETIME or PRIORITY).
+impl AttributeSliceEncoder for u32 {
+    fn encoded_len(&self) -> usize {
+        4
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+/// Encodes the integer as a big-endian u64 (e.g., the tie-breaker of ICE-CONTROLLING).
+impl AttributeSliceEncoder for u64 {
+    fn encoded_len(&self) -> usize {
+        8
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.to_be_bytes());
+    }
+}
+
+/// Decodes an attribute whose value is a big-endian u32.
+#[derive(Default)]
+pub struct U32Decoder;
+
+impl AttributeDecoder<'_> for U32Decoder {
+    type Item = u32;
+    type Error = IntegerDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let bytes = buf
+            .try_into()
+            .map_err(|_| IntegerDecodeError::InvalidDataSize)?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+}
+
+/// Decodes an attribute whose value is a big-endian u64.
+#[derive(Default)]
+pub struct U64Decoder;
+
+impl AttributeDecoder<'_> for U64Decoder {
+    type Item = u64;
+    type Error = IntegerDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let bytes = buf
+            .try_into()
+            .map_err(|_| IntegerDecodeError::InvalidDataSize)?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_u32() {
+        let mut buf = BytesMut::with_capacity(0);
+        0x6e00_01ffu32.encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[0x6e, 0x00, 0x01, 0xff]);
+        assert_eq!(U32Decoder.decode(&buf), Ok(0x6e00_01ff));
+    }
+
+    #[test]
+    fn test_u64() {
+        let mut buf = BytesMut::with_capacity(0);
+        0x932f_f9b1_5126_3b36u64.encode(&mut buf);
+        assert_eq!(
+            buf.as_ref(),
+            &[0x93, 0x2f, 0xf9, 0xb1, 0x51, 0x26, 0x3b, 0x36]
+        );
+        assert_eq!(U64Decoder.decode(&buf), Ok(0x932f_f9b1_5126_3b36));
+    }
+
+    #[test]
+    fn test_invalid_data_size() {
+        for size in [0, 3, 5, 8] {
+            assert_eq!(
+                U32Decoder.decode(&vec![0; size]),
+                Err(IntegerDecodeError::InvalidDataSize)
+            );
+        }
+
+        for size in [0, 4, 7, 9, 16] {
+            assert_eq!(
+                U64Decoder.decode(&vec![0; size]),
+                Err(IntegerDecodeError::InvalidDataSize)
+            );
+        }
+    }
+}
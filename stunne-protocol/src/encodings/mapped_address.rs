@@ -1,8 +1,8 @@
-use super::{AttributeDecoder, AttributeEncoder};
+use super::{AttributeDecoder, AttributeSliceEncoder};
 use crate::utils::xor;
 use crate::TransactionId;
-use bytes::{BufMut, BytesMut};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::fmt;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 
 pub struct MappedAddress;
 
@@ -12,7 +12,7 @@ impl MappedAddress {
     }
 
     pub fn decoder() -> MappedAddressDecoder {
-        MappedAddressDecoder::default()
+        MappedAddressDecoder
     }
 }
 
@@ -38,22 +38,26 @@ impl MappedAddressEncoder {
     }
 }
 
-impl AttributeEncoder for MappedAddressEncoder {
-    fn encode(&self, dst: &mut BytesMut) {
+impl AttributeSliceEncoder for MappedAddressEncoder {
+    fn encoded_len(&self) -> usize {
+        match self.addr {
+            SocketAddr::V4(_) => MAPPED_ADDRESS_HEADER_BYTES + IPV4_BYTE_LENGTH,
+            SocketAddr::V6(_) => MAPPED_ADDRESS_HEADER_BYTES + IPV6_BYTE_LENGTH,
+        }
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        let (header, address) = dst.split_at_mut(MAPPED_ADDRESS_HEADER_BYTES);
+        header[0] = 0;
+        header[2..4].copy_from_slice(&self.addr.port().to_be_bytes());
         match self.addr {
             SocketAddr::V4(addr) => {
-                dst.reserve(8);
-                dst.put_u8(0);
-                dst.put_u8(IPV4_FAMILY);
-                dst.put_u16(addr.port());
-                dst.extend_from_slice(&addr.ip().octets());
+                header[1] = IPV4_FAMILY;
+                address.copy_from_slice(&addr.ip().octets());
             }
             SocketAddr::V6(addr) => {
-                dst.reserve(20);
-                dst.put_u8(0);
-                dst.put_u8(IPV6_FAMILY);
-                dst.put_u16(addr.port());
-                dst.extend_from_slice(&addr.ip().octets());
+                header[1] = IPV6_FAMILY;
+                address.copy_from_slice(&addr.ip().octets());
             }
         }
     }
@@ -88,6 +92,23 @@ pub enum MappedAddressDecodeError {
     UnexpectedEndOfSlice,
 }
 
+impl fmt::Display for MappedAddressDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MappedAddressDecodeError::NonZeroFirstByte => {
+                f.write_str("first byte of the address is not zero")
+            }
+            MappedAddressDecodeError::UnknownFamily => f.write_str("unknown address family"),
+            MappedAddressDecodeError::UnexpectedEndOfSlice => {
+                f.write_str("value is not the size of the address")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MappedAddressDecodeError {}
+
 /// Number of bytes to read the zero byte, family byte, and port.
 const MAPPED_ADDRESS_HEADER_BYTES: usize = 4;
 
@@ -115,10 +136,9 @@ impl XorMappedAddressEncoder {
     pub fn new(addr: SocketAddr, tx_id: TransactionId) -> Self {
         Self { addr, tx_id }
     }
-}
 
-impl AttributeEncoder for XorMappedAddressEncoder {
-    fn encode(&self, dst: &mut BytesMut) {
+    /// Returns an encoder for the address once XORed with the magic cookie and transaction ID.
+    fn xored(&self) -> MappedAddressEncoder {
         let processed_ip = match self.addr.ip() {
             IpAddr::V4(ip) => {
                 let mut octets = ip.octets();
@@ -137,7 +157,17 @@ impl AttributeEncoder for XorMappedAddressEncoder {
         let processed_port = self.addr.port() ^ MAGIC_COOKIE_MSB;
 
         let processed_address = SocketAddr::new(processed_ip, processed_port);
-        MappedAddressEncoder::new(processed_address).encode(dst);
+        MappedAddressEncoder::new(processed_address)
+    }
+}
+
+impl AttributeSliceEncoder for XorMappedAddressEncoder {
+    fn encoded_len(&self) -> usize {
+        self.xored().encoded_len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        self.xored().encode_to_slice(dst);
     }
 }
 
@@ -213,6 +243,8 @@ fn parse_mapped_address(bytes: &[u8]) -> Result<SocketAddr, MappedAddressDecodeE
 #[cfg(test)]
 mod test_mapped_address {
     use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
 
     macro_rules! test_address {
         ($addr:expr, $bytes:expr) => {{
@@ -311,7 +343,7 @@ mod test_mapped_address {
         ];
 
         assert!(matches!(
-            MappedAddressDecoder::default().decode(&bytes),
+            MappedAddressDecoder.decode(&bytes),
             Err(MappedAddressDecodeError::UnknownFamily)
         ));
     }
@@ -326,14 +358,14 @@ mod test_mapped_address {
             0x01, 0x02, 0x03, 0x04 // 1.2.3.4
         ];
         assert!(matches!(
-            MappedAddressDecoder::default().decode(&bytes),
+            MappedAddressDecoder.decode(&bytes),
             Err(MappedAddressDecodeError::NonZeroFirstByte)
         ));
     }
 
     #[test]
     fn test_parse_mapped_address_invalid_number_of_bytes() {
-        let decoder = MappedAddressDecoder::default();
+        let decoder = MappedAddressDecoder;
         #[rustfmt::skip]
         let test_cases = [
             vec![],
@@ -373,6 +405,8 @@ mod test_mapped_address {
 #[cfg(test)]
 mod test_xor_mapped_address {
     use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
 
     fn test_address(addr: &str, tx_id: TransactionId, expected_bytes: &[u8]) {
         // Use a zero capacity to ensure that capacity will grow as necessary.
@@ -390,7 +424,7 @@ mod test_xor_mapped_address {
         );
 
         let decoder = XorMappedAddressDecoder::new(tx_id);
-        let result = decoder.decode(&expected_bytes);
+        let result = decoder.decode(expected_bytes);
         match result {
             Ok(addr) => {
                 assert_eq!(
@@ -535,7 +569,7 @@ mod test_xor_mapped_address {
         for test_case in &test_cases {
             assert!(
                 matches!(
-                    decoder.decode(&test_case),
+                    decoder.decode(test_case),
                     Err(MappedAddressDecodeError::UnexpectedEndOfSlice)
                 ),
                 "\n\nTest case {:?} failed\n",
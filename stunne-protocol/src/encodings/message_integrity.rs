@@ -0,0 +1,127 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+/// Number of bytes in an HMAC-SHA1 value.
+pub(crate) const MESSAGE_INTEGRITY_BYTES: usize = 20;
+
+/// Gives the reason that a MESSAGE-INTEGRITY attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MessageIntegrityDecodeError {
+    /// The attribute value was not exactly 20 bytes.
+    InvalidDataSize,
+}
+
+impl fmt::Display for MessageIntegrityDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageIntegrityDecodeError::InvalidDataSize => {
+                f.write_str("MESSAGE-INTEGRITY value is not 20 bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MessageIntegrityDecodeError {}
+
+/// The HMAC-SHA1 value carried in a [MESSAGE-INTEGRITY][] attribute.
+///
+/// Users will typically not create this value themselves, as the HMAC must be calculated over the
+/// encoded message. Instead, see
+/// [add_message_integrity](crate::StunAttributeEncoder::add_message_integrity).
+///
+/// [MESSAGE-INTEGRITY]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.4
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MessageIntegrity {
+    pub hmac: [u8; MESSAGE_INTEGRITY_BYTES],
+}
+
+impl MessageIntegrity {
+    /// Calculate the HMAC-SHA1 of the given byte slices, in order, using the given key.
+    pub(crate) fn calculate(key: &[u8], data: &[&[u8]]) -> Self {
+        Self {
+            hmac: hmac(key, data).finalize().into_bytes().into(),
+        }
+    }
+
+    /// Checks, in constant time, that this value is the HMAC-SHA1 of the given byte slices.
+    pub(crate) fn verify(&self, key: &[u8], data: &[&[u8]]) -> bool {
+        hmac(key, data).verify_slice(&self.hmac).is_ok()
+    }
+}
+
+fn hmac(key: &[u8], data: &[&[u8]]) -> Hmac<Sha1> {
+    let mut mac = Hmac::<Sha1>::new_from_slice(key).expect("HMAC can take a key of any length");
+    for bytes in data {
+        mac.update(bytes);
+    }
+    mac
+}
+
+impl AttributeSliceEncoder for MessageIntegrity {
+    fn encoded_len(&self) -> usize {
+        MESSAGE_INTEGRITY_BYTES
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(&self.hmac);
+    }
+}
+
+#[derive(Default)]
+pub struct MessageIntegrityDecoder;
+
+impl AttributeDecoder<'_> for MessageIntegrityDecoder {
+    type Item = MessageIntegrity;
+    type Error = MessageIntegrityDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let hmac = buf
+            .try_into()
+            .map_err(|_| MessageIntegrityDecodeError::InvalidDataSize)?;
+        Ok(MessageIntegrity { hmac })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_calculate() {
+        // Test case 2 from RFC 2202
+        let integrity =
+            MessageIntegrity::calculate(b"Jefe", &[b"what do ya want ", b"for nothing?"]);
+        assert_eq!(
+            integrity.hmac,
+            [
+                0xef, 0xfc, 0xdf, 0x6a, 0xe5, 0xeb, 0x2f, 0xa2, 0xd2, 0x74, 0x16, 0xd5, 0xf1, 0x84,
+                0xdf, 0x9c, 0x25, 0x9a, 0x7c, 0x79
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        let integrity = MessageIntegrity { hmac: [7; 20] };
+        let mut buf = BytesMut::with_capacity(0);
+        integrity.encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[7; 20]);
+
+        assert_eq!(MessageIntegrityDecoder.decode(&buf), Ok(integrity));
+    }
+
+    #[test]
+    fn test_invalid_data_size() {
+        for size in [0, 19, 21, 32] {
+            assert_eq!(
+                MessageIntegrityDecoder.decode(&vec![0; size]),
+                Err(MessageIntegrityDecodeError::InvalidDataSize)
+            );
+        }
+    }
+}
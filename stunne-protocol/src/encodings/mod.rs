@@ -1,19 +1,89 @@
 mod change_request;
+mod connection_id;
+mod data;
+mod error_code;
+pub(crate) mod fingerprint;
+mod integers;
 mod mapped_address;
+pub(crate) mod message_integrity;
+mod nonce;
+mod password_algorithms;
+mod quoted_string;
+#[cfg(feature = "alloc")]
+mod realm;
+mod software;
+mod unknown_attributes;
+#[cfg(feature = "alloc")]
+mod username;
 
-use bytes::{BufMut, BytesMut};
-use std::str::{from_utf8, Utf8Error};
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+#[cfg(feature = "alloc")]
+use bytes::BytesMut;
+use core::str::{from_utf8, Utf8Error};
 
-pub use change_request::{ChangeRequest, ChangeRequestDecoder};
+pub use change_request::{ChangeRequest, ChangeRequestDecodeError, ChangeRequestDecoder};
+pub use connection_id::{ConnectionId, ConnectionIdDecodeError, ConnectionIdDecoder};
+pub use data::{Data, DataDecoder};
+pub use error_code::{
+    default_reason, ErrorCode, ErrorCodeDecodeError, ErrorCodeDecoder, ErrorCodeEncodeError,
+};
+pub use fingerprint::{
+    Fingerprint, FingerprintDecodeError, FingerprintDecoder, FingerprintVerifier,
+};
+pub use integers::{IntegerDecodeError, U32Decoder, U64Decoder};
 pub use mapped_address::{
-    MappedAddress, MappedAddressDecoder, MappedAddressEncoder, XorMappedAddress,
-    XorMappedAddressDecoder, XorMappedAddressEncoder,
+    MappedAddress, MappedAddressDecodeError, MappedAddressDecoder, MappedAddressEncoder,
+    XorMappedAddress, XorMappedAddressDecoder, XorMappedAddressEncoder,
+};
+pub use message_integrity::{
+    MessageIntegrity, MessageIntegrityDecodeError, MessageIntegrityDecoder,
+};
+#[cfg(feature = "alloc")]
+pub use nonce::NonceDecoder;
+pub use nonce::{Nonce, NonceEncodeError};
+#[cfg(feature = "alloc")]
+pub use password_algorithms::PasswordAlgorithmsDecoder;
+pub use password_algorithms::{
+    PasswordAlgorithm, PasswordAlgorithmDecodeError, PasswordAlgorithmDecoder, PasswordAlgorithms,
 };
+pub use quoted_string::QuotedStringDecodeError;
+#[cfg(feature = "alloc")]
+pub use realm::{Realm, RealmDecoder, RealmEncodeError};
+pub use software::{Software, SoftwareDecodeError, SoftwareDecoder, SoftwareEncodeError};
+#[cfg(feature = "alloc")]
+pub use unknown_attributes::UnknownAttributesDecoder;
+pub use unknown_attributes::{UnknownAttributes, UnknownAttributesDecodeError};
+#[cfg(feature = "alloc")]
+pub use username::{Username, UsernameDecodeError, UsernameDecoder, UsernameEncodeError};
 
+#[cfg(feature = "alloc")]
 pub trait AttributeEncoder {
     fn encode(&self, dst: &mut BytesMut);
 }
 
+/// Encodes an attribute value into a buffer provided by the caller, without allocating.
+///
+/// This is the encoding used by [SliceEncoder](crate::SliceEncoder), which is available without
+/// the `alloc` feature. Every type implementing this trait also implements [AttributeEncoder].
+pub trait AttributeSliceEncoder {
+    /// Returns the number of bytes in the encoded value, not counting any padding.
+    fn encoded_len(&self) -> usize;
+
+    /// Writes the encoded value into `dst`, which is exactly [encoded_len](Self::encoded_len)
+    /// bytes long.
+    fn encode_to_slice(&self, dst: &mut [u8]);
+}
+
+#[cfg(feature = "alloc")]
+impl<T: AttributeSliceEncoder> AttributeEncoder for T {
+    fn encode(&self, dst: &mut BytesMut) {
+        let start = dst.len();
+        dst.resize(start + self.encoded_len(), 0);
+        self.encode_to_slice(&mut dst[start..]);
+    }
+}
+
 pub trait AttributeDecoder<'buf> {
     type Item;
     type Error;
@@ -21,10 +91,27 @@ pub trait AttributeDecoder<'buf> {
     fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error>;
 }
 
-impl AttributeEncoder for &str {
+impl AttributeSliceEncoder for &str {
+    fn encoded_len(&self) -> usize {
+        self.len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(self.as_bytes());
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AttributeEncoder for String {
     fn encode(&self, dst: &mut BytesMut) {
-        dst.reserve(self.len());
-        dst.put(self.as_bytes());
+        self.as_str().encode(dst);
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AttributeEncoder for Cow<'_, str> {
+    fn encode(&self, dst: &mut BytesMut) {
+        self.as_ref().encode(dst);
     }
 }
 
@@ -43,6 +130,7 @@ impl<'buf> AttributeDecoder<'buf> for Utf8Decoder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytes::BytesMut;
 
     fn test_encoding(expected_string: &str, expected_bytes: &[u8]) {
         // Use zero capacity to ensure that more capacity is reserved if needed.
@@ -50,7 +138,7 @@ mod tests {
         expected_string.encode(&mut buf);
         assert_eq!(&buf, expected_bytes);
 
-        let actual_string = Utf8Decoder::default().decode(expected_bytes).unwrap();
+        let actual_string = Utf8Decoder.decode(expected_bytes).unwrap();
         assert_eq!(actual_string, expected_string);
     }
 
@@ -60,10 +148,25 @@ mod tests {
         test_encoding("𓄁", &[0xf0, 0x93, 0x84, 0x81]);
     }
 
+    #[test]
+    fn test_owned_string_encoding() {
+        let mut buf = BytesMut::with_capacity(0);
+        String::from("test").encode(&mut buf);
+        assert_eq!(&buf, &[0x74, 0x65, 0x73, 0x74][..]);
+
+        let mut buf = BytesMut::with_capacity(0);
+        Cow::<str>::Owned(String::from("𓄁")).encode(&mut buf);
+        assert_eq!(&buf, &[0xf0, 0x93, 0x84, 0x81][..]);
+
+        let mut buf = BytesMut::with_capacity(0);
+        Cow::Borrowed("test").encode(&mut buf);
+        assert_eq!(&buf, &[0x74, 0x65, 0x73, 0x74][..]);
+    }
+
     #[test]
     fn test_invalid_utf8_encoding() {
         const INVALID_UTF8_BYTES: [u8; 1] = [0xf0];
-        let result = Utf8Decoder::default().decode(&INVALID_UTF8_BYTES);
+        let result = Utf8Decoder.decode(&INVALID_UTF8_BYTES);
         assert!(matches!(result, Err(Utf8Error { .. })));
     }
 }
@@ -0,0 +1,149 @@
+use crate::encodings::quoted_string::{is_valid_unescaped, is_within_limits, QuotedValue};
+use crate::encodings::AttributeSliceEncoder;
+#[cfg(feature = "alloc")]
+use crate::encodings::{
+    quoted_string::{check_escaped, QuotedStringDecodeError},
+    AttributeDecoder,
+};
+#[cfg(feature = "alloc")]
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Gives the reason that a value could not be used as a NONCE attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum NonceEncodeError {
+    /// The value was 128 characters or longer.
+    TooLong,
+
+    /// The value contained characters that cannot be represented in a quoted-string (e.g., a
+    /// newline).
+    InvalidCharacters,
+}
+
+impl fmt::Display for NonceEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NonceEncodeError::TooLong => f.write_str("NONCE must be less than 128 characters"),
+            NonceEncodeError::InvalidCharacters => {
+                f.write_str("NONCE contains characters that cannot be quoted")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NonceEncodeError {}
+
+/// The value of a [NONCE][] attribute.
+///
+/// The value given to [new](Self::new) is unescaped; any double-quotes or backslashes are escaped
+/// when encoded. A value decoded by [NonceDecoder] is kept as received, so that it is echoed to
+/// the server byte for byte, and is unescaped for display. Values are equal if their unescaped
+/// values are.
+///
+/// [NONCE]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.8
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Nonce<'a> {
+    value: QuotedValue<&'a str>,
+}
+
+impl<'a> Nonce<'a> {
+    pub fn new(value: &'a str) -> Result<Self, NonceEncodeError> {
+        if !is_valid_unescaped(value) {
+            return Err(NonceEncodeError::InvalidCharacters);
+        }
+        if !is_within_limits(value) {
+            return Err(NonceEncodeError::TooLong);
+        }
+        Ok(Self {
+            value: QuotedValue::Unescaped(value),
+        })
+    }
+
+    /// Returns the unescaped nonce.
+    #[cfg(feature = "alloc")]
+    pub fn unescaped(&self) -> Cow<'_, str> {
+        self.value.unescaped()
+    }
+}
+
+/// Displays the unescaped nonce.
+impl fmt::Display for Nonce<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl AttributeSliceEncoder for Nonce<'_> {
+    fn encoded_len(&self) -> usize {
+        self.value.encoded_len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        self.value.encode_to_slice(dst);
+    }
+}
+
+/// Decodes a NONCE attribute, checking that it is a valid quoted-string.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+pub struct NonceDecoder;
+
+#[cfg(feature = "alloc")]
+impl<'buf> AttributeDecoder<'buf> for NonceDecoder {
+    type Item = Nonce<'buf>;
+    type Error = QuotedStringDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        Ok(Nonce {
+            value: QuotedValue::Escaped(check_escaped(buf)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    fn test_encoding(nonce: &str, expected_bytes: &[u8]) {
+        let mut buf = BytesMut::with_capacity(0);
+        Nonce::new(nonce).unwrap().encode(&mut buf);
+        assert_eq!(buf.as_ref(), expected_bytes);
+
+        let decoded = NonceDecoder.decode(expected_bytes).unwrap();
+        assert_eq!(decoded.to_string(), nonce);
+        assert_eq!(decoded, Nonce::new(nonce).unwrap());
+    }
+
+    #[test]
+    fn test_valid_nonces() {
+        test_encoding(
+            "f//499k954d6OL34oL9FSTvy64sA",
+            b"f//499k954d6OL34oL9FSTvy64sA",
+        );
+        test_encoding(r"a\b", br"a\\b");
+    }
+
+    #[test]
+    fn test_echo() {
+        // Characters may be escaped needlessly, and must be echoed as received.
+        let received = br#"\a\"b"#;
+        let decoded = NonceDecoder.decode(received).unwrap();
+        assert_eq!(decoded.unescaped(), r#"a"b"#);
+        assert_eq!(decoded, Nonce::new(r#"a"b"#).unwrap());
+        let mut buf = BytesMut::with_capacity(0);
+        decoded.encode(&mut buf);
+        assert_eq!(buf.as_ref(), received);
+    }
+
+    #[test]
+    fn test_invalid_nonces() {
+        assert_eq!(
+            Nonce::new("carriage\rreturn"),
+            Err(NonceEncodeError::InvalidCharacters)
+        );
+        assert_eq!(Nonce::new(&"a".repeat(128)), Err(NonceEncodeError::TooLong));
+    }
+}
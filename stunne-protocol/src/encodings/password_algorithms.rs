@@ -0,0 +1,269 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use crate::utils::padding_for_attribute_length;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Number of bytes to read the algorithm number and the parameters length.
+const PASSWORD_ALGORITHM_HEADER_BYTES: usize = 4;
+
+/// Gives the reason that a PASSWORD-ALGORITHM or PASSWORD-ALGORITHMS attribute's value could not
+/// be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PasswordAlgorithmDecodeError {
+    /// The value ended before an algorithm's header or parameters could be read.
+    UnexpectedEndOfData,
+
+    /// A PASSWORD-ALGORITHM attribute contained data beyond its single algorithm.
+    InvalidDataSize,
+}
+
+impl fmt::Display for PasswordAlgorithmDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordAlgorithmDecodeError::UnexpectedEndOfData => {
+                f.write_str("unexpected end of password algorithm data")
+            }
+            PasswordAlgorithmDecodeError::InvalidDataSize => {
+                f.write_str("PASSWORD-ALGORITHM value contains more than one algorithm")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PasswordAlgorithmDecodeError {}
+
+/// A password algorithm and its parameters, as used by the [PASSWORD-ALGORITHM][] and
+/// [PASSWORD-ALGORITHMS][] attributes of RFC 8489.
+///
+/// When encoded, the parameters are padded to a multiple of four bytes.
+///
+/// [PASSWORD-ALGORITHM]: https://datatracker.ietf.org/doc/html/rfc8489#section-14.12
+/// [PASSWORD-ALGORITHMS]: https://datatracker.ietf.org/doc/html/rfc8489#section-14.11
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PasswordAlgorithm<'a> {
+    pub algorithm: u16,
+    pub parameters: &'a [u8],
+}
+
+impl PasswordAlgorithm<'static> {
+    /// The MD5 algorithm, which takes no parameters.
+    pub const MD5: Self = PasswordAlgorithm {
+        algorithm: 0x0001,
+        parameters: &[],
+    };
+
+    /// The SHA-256 algorithm, which takes no parameters.
+    pub const SHA256: Self = PasswordAlgorithm {
+        algorithm: 0x0002,
+        parameters: &[],
+    };
+}
+
+impl AttributeSliceEncoder for PasswordAlgorithm<'_> {
+    fn encoded_len(&self) -> usize {
+        PASSWORD_ALGORITHM_HEADER_BYTES
+            + self.parameters.len()
+            + padding_for_attribute_length(self.parameters.len())
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        let (header, remaining) = dst.split_at_mut(PASSWORD_ALGORITHM_HEADER_BYTES);
+        header[0..2].copy_from_slice(&self.algorithm.to_be_bytes());
+        header[2..4].copy_from_slice(&(self.parameters.len() as u16).to_be_bytes());
+        let (parameters, padding) = remaining.split_at_mut(self.parameters.len());
+        parameters.copy_from_slice(self.parameters);
+        padding.fill(0);
+    }
+}
+
+/// Splits the first algorithm off of the buffer, returning it alongside the remaining bytes.
+fn parse_password_algorithm(
+    buf: &[u8],
+) -> Result<(PasswordAlgorithm<'_>, &[u8]), PasswordAlgorithmDecodeError> {
+    if buf.len() < PASSWORD_ALGORITHM_HEADER_BYTES {
+        return Err(PasswordAlgorithmDecodeError::UnexpectedEndOfData);
+    }
+
+    let (header, remaining) = buf.split_at(PASSWORD_ALGORITHM_HEADER_BYTES);
+    let algorithm = u16::from_be_bytes(header[0..=1].try_into().unwrap());
+    let parameters_length: usize = u16::from_be_bytes(header[2..=3].try_into().unwrap()).into();
+    let padded_length = parameters_length + padding_for_attribute_length(parameters_length);
+
+    // The padding of the final parameters may have been left off, as the attribute itself is
+    // padded anyways.
+    if remaining.len() < parameters_length {
+        return Err(PasswordAlgorithmDecodeError::UnexpectedEndOfData);
+    }
+    let (parameters, remaining) = remaining.split_at(parameters_length);
+    let remaining = &remaining[(padded_length - parameters_length).min(remaining.len())..];
+
+    Ok((
+        PasswordAlgorithm {
+            algorithm,
+            parameters,
+        },
+        remaining,
+    ))
+}
+
+/// Decodes a PASSWORD-ALGORITHM attribute.
+#[derive(Default)]
+pub struct PasswordAlgorithmDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for PasswordAlgorithmDecoder {
+    type Item = PasswordAlgorithm<'buf>;
+    type Error = PasswordAlgorithmDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        let (algorithm, remaining) = parse_password_algorithm(buf)?;
+        if !remaining.is_empty() {
+            return Err(PasswordAlgorithmDecodeError::InvalidDataSize);
+        }
+        Ok(algorithm)
+    }
+}
+
+/// The value of a [PASSWORD-ALGORITHMS][] attribute: the list of algorithms a server supports,
+/// in order of preference.
+///
+/// [PASSWORD-ALGORITHMS]: https://datatracker.ietf.org/doc/html/rfc8489#section-14.11
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PasswordAlgorithms<'a> {
+    pub algorithms: &'a [PasswordAlgorithm<'a>],
+}
+
+impl AttributeSliceEncoder for PasswordAlgorithms<'_> {
+    fn encoded_len(&self) -> usize {
+        self.algorithms.iter().map(|a| a.encoded_len()).sum()
+    }
+
+    fn encode_to_slice(&self, mut dst: &mut [u8]) {
+        for algorithm in self.algorithms {
+            let (current, remaining) = dst.split_at_mut(algorithm.encoded_len());
+            algorithm.encode_to_slice(current);
+            dst = remaining;
+        }
+    }
+}
+
+/// Decodes a PASSWORD-ALGORITHMS attribute into the list of algorithms it contains.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+pub struct PasswordAlgorithmsDecoder;
+
+#[cfg(feature = "alloc")]
+impl<'buf> AttributeDecoder<'buf> for PasswordAlgorithmsDecoder {
+    type Item = Vec<PasswordAlgorithm<'buf>>;
+    type Error = PasswordAlgorithmDecodeError;
+
+    fn decode(&self, mut buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        let mut algorithms = Vec::new();
+        while !buf.is_empty() {
+            let (algorithm, remaining) = parse_password_algorithm(buf)?;
+            algorithms.push(algorithm);
+            buf = remaining;
+        }
+        Ok(algorithms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_password_algorithm() {
+        let mut buf = BytesMut::with_capacity(0);
+        PasswordAlgorithm::SHA256.encode(&mut buf);
+        assert_eq!(buf.as_ref(), &[0x00, 0x02, 0x00, 0x00]);
+
+        assert_eq!(
+            PasswordAlgorithmDecoder.decode(&buf),
+            Ok(PasswordAlgorithm::SHA256)
+        );
+    }
+
+    #[test]
+    fn test_password_algorithm_with_parameters() {
+        let algorithm = PasswordAlgorithm {
+            algorithm: 0x1234,
+            parameters: &[1, 2, 3, 4, 5],
+        };
+
+        let mut buf = BytesMut::with_capacity(0);
+        algorithm.encode(&mut buf);
+
+        #[rustfmt::skip]
+        let expected = [
+            0x12, 0x34, // Algorithm
+            0x00, 0x05, // Parameters length
+            1, 2, 3, 4, 5, 0, 0, 0, // Parameters, plus padding
+        ];
+        assert_eq!(buf.as_ref(), &expected);
+        assert_eq!(PasswordAlgorithmDecoder.decode(&expected), Ok(algorithm));
+
+        // The padding of the parameters may be left off.
+        assert_eq!(
+            PasswordAlgorithmDecoder.decode(&expected[..9]),
+            Ok(algorithm)
+        );
+    }
+
+    #[test]
+    fn test_password_algorithm_errors() {
+        for example in [&[][..], &[0, 1, 0], &[0, 1, 0, 4, 1, 2, 3]] {
+            assert_eq!(
+                PasswordAlgorithmDecoder.decode(example),
+                Err(PasswordAlgorithmDecodeError::UnexpectedEndOfData)
+            );
+        }
+
+        assert_eq!(
+            PasswordAlgorithmDecoder.decode(&[0, 1, 0, 0, 0, 2, 0, 0]),
+            Err(PasswordAlgorithmDecodeError::InvalidDataSize)
+        );
+    }
+
+    #[test]
+    fn test_password_algorithms() {
+        let algorithms = [
+            PasswordAlgorithm::MD5,
+            PasswordAlgorithm {
+                algorithm: 0x1234,
+                parameters: &[9, 8],
+            },
+            PasswordAlgorithm::SHA256,
+        ];
+
+        let mut buf = BytesMut::with_capacity(0);
+        PasswordAlgorithms {
+            algorithms: &algorithms,
+        }
+        .encode(&mut buf);
+
+        #[rustfmt::skip]
+        let expected = [
+            0x00, 0x01, 0x00, 0x00, // MD5
+            0x12, 0x34, 0x00, 0x02, 9, 8, 0, 0, // Unknown algorithm, with padded parameters
+            0x00, 0x02, 0x00, 0x00, // SHA-256
+        ];
+        assert_eq!(buf.as_ref(), &expected);
+        assert_eq!(
+            PasswordAlgorithmsDecoder.decode(&expected),
+            Ok(algorithms.to_vec())
+        );
+    }
+
+    #[test]
+    fn test_password_algorithms_errors() {
+        assert_eq!(PasswordAlgorithmsDecoder.decode(&[]), Ok(vec![]));
+        assert_eq!(
+            PasswordAlgorithmsDecoder.decode(&[0, 1, 0, 0, 0, 2]),
+            Err(PasswordAlgorithmDecodeError::UnexpectedEndOfData)
+        );
+    }
+}
@@ -0,0 +1,311 @@
+//! Helpers for attributes (REALM and NONCE) whose values follow the `quoted-string` grammar of
+//! [RFC 3261][], minus the surrounding quotes.
+//!
+//! Within such a value, a double-quote or backslash may only appear when escaped by a preceding
+//! backslash (a `quoted-pair`). Values given to the encoders in this crate are unescaped, and are
+//! escaped when written. Decoders return the value as received, which is encoded again byte for
+//! byte, and only unescaped for display: a client echoes the REALM and NONCE of a server, which
+//! may have escaped characters that need not be escaped (e.g., `\a`).
+//!
+//! [RFC 3261]: https://datatracker.ietf.org/doc/html/rfc3261#section-25.1
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, string::String};
+use core::fmt::{self, Write};
+#[cfg(feature = "alloc")]
+use core::str::from_utf8;
+use core::str::Utf8Error;
+
+/// RFC 5389 limits both REALM and NONCE to "less than 128 characters".
+pub(crate) const QUOTED_STRING_CHARACTER_LIMIT: usize = 128;
+
+/// RFC 5389 notes that 127 characters "can be as long as 763 bytes".
+pub(crate) const QUOTED_STRING_BYTE_LIMIT: usize = 763;
+
+/// Gives the reason that a REALM or NONCE attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum QuotedStringDecodeError {
+    /// The value was not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+
+    /// The value contained a double-quote that was not escaped, or a control character that is
+    /// not allowed by the grammar.
+    InvalidCharacter,
+
+    /// The value ended with a backslash, or a backslash escaped a character that cannot be
+    /// escaped.
+    InvalidEscape,
+
+    /// The unescaped value was 128 characters or longer, or the value was longer than 763 bytes.
+    TooLong,
+}
+
+impl fmt::Display for QuotedStringDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotedStringDecodeError::InvalidUtf8(e) => write!(f, "value is not valid UTF-8: {}", e),
+            QuotedStringDecodeError::InvalidCharacter => {
+                f.write_str("value contains an unescaped double-quote or control character")
+            }
+            QuotedStringDecodeError::InvalidEscape => {
+                f.write_str("value contains an invalid escape")
+            }
+            QuotedStringDecodeError::TooLong => {
+                f.write_str("value must be less than 128 characters and at most 763 bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for QuotedStringDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QuotedStringDecodeError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Returns true if the character may appear in the value without being escaped (RFC 3261's
+/// `qdtext`, where linear whitespace is limited to spaces and tabs).
+fn is_qdtext(c: char) -> bool {
+    matches!(c, ' ' | '\t' | '\x21' | '\x23'..='\x5B' | '\x5D'..='\x7E') || !c.is_ascii()
+}
+
+/// Returns true if the character may be escaped with a backslash (RFC 3261's `quoted-pair`).
+#[cfg(feature = "alloc")]
+fn is_escapable(c: char) -> bool {
+    matches!(c, '\x00'..='\x09' | '\x0B'..='\x0C' | '\x0E'..='\x7F')
+}
+
+/// Returns true if the unescaped value can be represented as a quoted-string.
+pub(crate) fn is_valid_unescaped(value: &str) -> bool {
+    value.chars().all(|c| is_qdtext(c) || c == '"' || c == '\\')
+}
+
+/// Returns true if the unescaped value fits within the RFC 5389 length limits.
+pub(crate) fn is_within_limits(value: &str) -> bool {
+    value.chars().count() < QUOTED_STRING_CHARACTER_LIMIT
+        && escaped_len(value) <= QUOTED_STRING_BYTE_LIMIT
+}
+
+/// Length in bytes of the value once escaped.
+pub(crate) fn escaped_len(value: &str) -> usize {
+    value.len() + value.chars().filter(|c| matches!(c, '"' | '\\')).count()
+}
+
+/// Writes the value into `dst` with double-quotes and backslashes escaped. The value is assumed
+/// to have been checked with [is_valid_unescaped], and `dst` must be [escaped_len] bytes long.
+pub(crate) fn escape_to_slice(value: &str, dst: &mut [u8]) {
+    let mut written = 0;
+    for &byte in value.as_bytes() {
+        if matches!(byte, b'"' | b'\\') {
+            dst[written] = b'\\';
+            written += 1;
+        }
+        dst[written] = byte;
+        written += 1;
+    }
+}
+
+/// A REALM or NONCE value, either as given by the application (unescaped), or as received
+/// (escaped).
+#[derive(Debug, Clone)]
+pub(crate) enum QuotedValue<S> {
+    Unescaped(S),
+    /// Only values decoded by [NonceDecoder](super::NonceDecoder) or
+    /// [RealmDecoder](super::RealmDecoder), which require the `alloc` feature, are escaped.
+    #[cfg(feature = "alloc")]
+    Escaped(S),
+}
+
+impl<S: AsRef<str>> QuotedValue<S> {
+    pub(crate) fn encoded_len(&self) -> usize {
+        match self {
+            QuotedValue::Unescaped(value) => escaped_len(value.as_ref()),
+            #[cfg(feature = "alloc")]
+            QuotedValue::Escaped(value) => value.as_ref().len(),
+        }
+    }
+
+    pub(crate) fn encode_to_slice(&self, dst: &mut [u8]) {
+        match self {
+            QuotedValue::Unescaped(value) => escape_to_slice(value.as_ref(), dst),
+            #[cfg(feature = "alloc")]
+            QuotedValue::Escaped(value) => dst.copy_from_slice(value.as_ref().as_bytes()),
+        }
+    }
+
+    /// Returns the characters of the unescaped value. An escaped value is assumed to have been
+    /// checked when it was decoded.
+    fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        let (value, escaped) = match self {
+            QuotedValue::Unescaped(value) => (value.as_ref(), false),
+            #[cfg(feature = "alloc")]
+            QuotedValue::Escaped(value) => (value.as_ref(), true),
+        };
+        let mut chars = value.chars();
+        core::iter::from_fn(move || match chars.next()? {
+            '\\' if escaped => chars.next(),
+            c => Some(c),
+        })
+    }
+
+    /// Returns the unescaped value.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn unescaped(&self) -> Cow<'_, str> {
+        match self {
+            QuotedValue::Unescaped(value) => Cow::Borrowed(value.as_ref()),
+            QuotedValue::Escaped(value) => unescape_unlimited(value.as_ref().as_bytes())
+                .expect("the value was checked when decoded"),
+        }
+    }
+}
+
+/// Values are equal if they are once unescaped, however they are encoded.
+impl<S: AsRef<str>> PartialEq for QuotedValue<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.chars().eq(other.chars())
+    }
+}
+
+impl<S: AsRef<str>> Eq for QuotedValue<S> {}
+
+/// Displays the unescaped value.
+impl<S: AsRef<str>> fmt::Display for QuotedValue<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chars().try_for_each(|c| f.write_char(c))
+    }
+}
+
+/// Checks that the bytes of an attribute are a quoted-string value within the RFC 5389 length
+/// limits, returning the value as received.
+#[cfg(feature = "alloc")]
+pub(crate) fn check_escaped(buf: &[u8]) -> Result<&str, QuotedStringDecodeError> {
+    unescape(buf)?;
+    Ok(from_utf8(buf).expect("the value was checked to be UTF-8"))
+}
+
+/// Decodes the bytes of a quoted-string value, removing any escaping, and checks that it fits
+/// within the RFC 5389 length limits.
+#[cfg(feature = "alloc")]
+pub(crate) fn unescape(buf: &[u8]) -> Result<Cow<'_, str>, QuotedStringDecodeError> {
+    if buf.len() > QUOTED_STRING_BYTE_LIMIT {
+        return Err(QuotedStringDecodeError::TooLong);
+    }
+    let value = unescape_unlimited(buf)?;
+    if value.chars().count() >= QUOTED_STRING_CHARACTER_LIMIT {
+        return Err(QuotedStringDecodeError::TooLong);
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "alloc")]
+fn unescape_unlimited(buf: &[u8]) -> Result<Cow<'_, str>, QuotedStringDecodeError> {
+    let value = from_utf8(buf).map_err(QuotedStringDecodeError::InvalidUtf8)?;
+    if !value.contains('\\') {
+        return match value.chars().all(is_qdtext) {
+            true => Ok(Cow::Borrowed(value)),
+            false => Err(QuotedStringDecodeError::InvalidCharacter),
+        };
+    }
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if is_escapable(escaped) => unescaped.push(escaped),
+                _ => return Err(QuotedStringDecodeError::InvalidEscape),
+            },
+            c if is_qdtext(c) => unescaped.push(c),
+            _ => return Err(QuotedStringDecodeError::InvalidCharacter),
+        }
+    }
+    Ok(Cow::Owned(unescaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn escape(value: &str) -> String {
+        let mut buf = vec![0; escaped_len(value)];
+        escape_to_slice(value, &mut buf);
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("example.org"), "example.org");
+        assert_eq!(escape(r#"say "hi""#), r#"say \"hi\""#);
+        assert_eq!(escape(r"back\slash"), r"back\\slash");
+        assert_eq!(escape("𓄁\""), "𓄁\\\"");
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert!(matches!(
+            unescape(b"example.org"),
+            Ok(Cow::Borrowed("example.org"))
+        ));
+        assert_eq!(unescape(br#"say \"hi\""#).unwrap(), r#"say "hi""#);
+        assert_eq!(unescape(br"back\\slash").unwrap(), r"back\slash");
+        assert_eq!(unescape(br"\a\b").unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_unescape_errors() {
+        assert_eq!(
+            unescape(br#"unescaped " quote"#),
+            Err(QuotedStringDecodeError::InvalidCharacter)
+        );
+        assert_eq!(
+            unescape(b"new\nline"),
+            Err(QuotedStringDecodeError::InvalidCharacter)
+        );
+        assert_eq!(
+            unescape(br"trailing\"),
+            Err(QuotedStringDecodeError::InvalidEscape)
+        );
+        assert_eq!(
+            unescape(b"bad \\\xC3\xA9 escape"),
+            Err(QuotedStringDecodeError::InvalidEscape)
+        );
+        assert!(matches!(
+            unescape(&[0xf0]),
+            Err(QuotedStringDecodeError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_unescape_limits() {
+        assert!(unescape("a".repeat(127).as_bytes()).is_ok());
+        assert!(unescape(r"\a".repeat(127).as_bytes()).is_ok());
+        assert_eq!(
+            unescape("a".repeat(128).as_bytes()),
+            Err(QuotedStringDecodeError::TooLong)
+        );
+        assert_eq!(
+            unescape("𓄁".repeat(127).as_bytes())
+                .unwrap()
+                .chars()
+                .count(),
+            127
+        );
+        assert_eq!(
+            unescape(r"\a".repeat(382).as_bytes()),
+            Err(QuotedStringDecodeError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        assert!(is_within_limits(&"a".repeat(127)));
+        assert!(!is_within_limits(&"a".repeat(128)));
+
+        // The limit is in characters, not bytes.
+        assert!(is_within_limits(&"𓄁".repeat(127)));
+        assert!(is_within_limits(&"\"".repeat(127)));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::encodings::quoted_string::{check_escaped, QuotedStringDecodeError, QuotedValue};
+#[cfg(feature = "std")]
+use crate::encodings::quoted_string::{is_valid_unescaped, is_within_limits};
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use alloc::borrow::Cow;
+use core::fmt;
+
+/// Gives the reason that a value could not be used as a REALM attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RealmEncodeError {
+    /// The value was 128 characters or longer.
+    TooLong,
+
+    /// The value contained characters that are prohibited by SASLprep, or that cannot be
+    /// represented in a quoted-string (e.g., a newline).
+    InvalidCharacters,
+}
+
+impl fmt::Display for RealmEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RealmEncodeError::TooLong => f.write_str("REALM must be less than 128 characters"),
+            RealmEncodeError::InvalidCharacters => {
+                f.write_str("REALM contains characters that are prohibited or cannot be quoted")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RealmEncodeError {}
+
+/// The value of a [REALM][] attribute.
+///
+/// The value given to [new](Self::new) is unescaped; any double-quotes or backslashes are escaped
+/// when encoded. As with [Username](super::Username), the value is processed with SASLprep when
+/// created. A value decoded by [RealmDecoder] is kept as received, so that it is echoed to the
+/// server byte for byte, and is unescaped for display. Values are equal if their unescaped values
+/// are.
+///
+/// [REALM]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.7
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Realm<'a> {
+    value: QuotedValue<Cow<'a, str>>,
+}
+
+impl<'a> Realm<'a> {
+    /// Requires the `std` feature, as SASLprep is not available without it.
+    #[cfg(feature = "std")]
+    pub fn new(value: &'a str) -> Result<Self, RealmEncodeError> {
+        let value =
+            crate::text::saslprep(value).map_err(|_| RealmEncodeError::InvalidCharacters)?;
+        if !is_valid_unescaped(&value) {
+            return Err(RealmEncodeError::InvalidCharacters);
+        }
+        if !is_within_limits(&value) {
+            return Err(RealmEncodeError::TooLong);
+        }
+        Ok(Self {
+            value: QuotedValue::Unescaped(value),
+        })
+    }
+
+    /// Returns the unescaped realm (after SASLprep processing, if given to [new](Self::new)).
+    pub fn unescaped(&self) -> Cow<'_, str> {
+        self.value.unescaped()
+    }
+}
+
+/// Displays the unescaped realm.
+impl fmt::Display for Realm<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl AttributeSliceEncoder for Realm<'_> {
+    fn encoded_len(&self) -> usize {
+        self.value.encoded_len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        self.value.encode_to_slice(dst);
+    }
+}
+
+/// Decodes a REALM attribute, checking that it is a valid quoted-string.
+#[derive(Default)]
+pub struct RealmDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for RealmDecoder {
+    type Item = Realm<'buf>;
+    type Error = QuotedStringDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        Ok(Realm {
+            value: QuotedValue::Escaped(Cow::Borrowed(check_escaped(buf)?)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    fn test_encoding(realm: &str, expected_bytes: &[u8]) {
+        let mut buf = BytesMut::with_capacity(0);
+        Realm::new(realm).unwrap().encode(&mut buf);
+        assert_eq!(buf.as_ref(), expected_bytes);
+
+        let decoded = RealmDecoder.decode(expected_bytes).unwrap();
+        assert_eq!(decoded.to_string(), realm);
+        assert_eq!(decoded, Realm::new(realm).unwrap());
+    }
+
+    #[test]
+    fn test_valid_realms() {
+        test_encoding("example.org", b"example.org");
+        test_encoding(r#"the "best" realm"#, br#"the \"best\" realm"#);
+    }
+
+    #[test]
+    fn test_echo() {
+        // The realm is echoed as received, without SASLprep or re-escaping.
+        let received = "\\e\u{00AD}xample.org".as_bytes();
+        let decoded = RealmDecoder.decode(received).unwrap();
+        assert_eq!(decoded.unescaped(), "e\u{00AD}xample.org");
+        let mut buf = BytesMut::with_capacity(0);
+        decoded.encode(&mut buf);
+        assert_eq!(buf.as_ref(), received);
+    }
+
+    #[test]
+    fn test_invalid_realms() {
+        assert_eq!(
+            Realm::new("line\nbreak"),
+            Err(RealmEncodeError::InvalidCharacters)
+        );
+        assert_eq!(Realm::new(&"a".repeat(128)), Err(RealmEncodeError::TooLong));
+    }
+}
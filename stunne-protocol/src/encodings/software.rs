@@ -0,0 +1,164 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use core::fmt;
+use core::str::{from_utf8, Utf8Error};
+
+/// RFC 5389 requires SOFTWARE to be "less than 128 characters".
+const SOFTWARE_CHARACTER_LIMIT: usize = 128;
+
+/// RFC 5389 notes that 127 characters "can be as long as 763 bytes".
+const SOFTWARE_BYTE_LIMIT: usize = 763;
+
+/// Gives the reason that a value could not be used as a SOFTWARE attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SoftwareEncodeError {
+    /// The value was 128 characters or longer, or longer than 763 bytes.
+    TooLong,
+}
+
+impl fmt::Display for SoftwareEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoftwareEncodeError::TooLong => {
+                f.write_str("SOFTWARE must be less than 128 characters and at most 763 bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SoftwareEncodeError {}
+
+/// Gives the reason that a SOFTWARE attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SoftwareDecodeError {
+    /// The value was not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+
+    /// The value was 128 characters or longer, or longer than 763 bytes.
+    TooLong,
+}
+
+impl fmt::Display for SoftwareDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoftwareDecodeError::InvalidUtf8(e) => write!(f, "SOFTWARE is not valid UTF-8: {}", e),
+            SoftwareDecodeError::TooLong => {
+                f.write_str("SOFTWARE must be less than 128 characters and at most 763 bytes")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SoftwareDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SoftwareDecodeError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a [SOFTWARE][] attribute, which describes the software being used by the agent
+/// sending the message.
+///
+/// [SOFTWARE]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.10
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Software<'a> {
+    value: &'a str,
+}
+
+impl<'a> Software<'a> {
+    pub fn new(value: &'a str) -> Result<Self, SoftwareEncodeError> {
+        if !is_within_limits(value) {
+            return Err(SoftwareEncodeError::TooLong);
+        }
+        Ok(Self { value })
+    }
+
+    pub fn as_str(&self) -> &'a str {
+        self.value
+    }
+}
+
+impl AttributeSliceEncoder for Software<'_> {
+    fn encoded_len(&self) -> usize {
+        self.value.len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(self.value.as_bytes());
+    }
+}
+
+fn is_within_limits(value: &str) -> bool {
+    value.len() <= SOFTWARE_BYTE_LIMIT && value.chars().count() < SOFTWARE_CHARACTER_LIMIT
+}
+
+/// Decodes a SOFTWARE attribute, rejecting values longer than RFC 5389 allows.
+#[derive(Default)]
+pub struct SoftwareDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for SoftwareDecoder {
+    type Item = Software<'buf>;
+    type Error = SoftwareDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        if buf.len() > SOFTWARE_BYTE_LIMIT {
+            return Err(SoftwareDecodeError::TooLong);
+        }
+        let value = from_utf8(buf).map_err(SoftwareDecodeError::InvalidUtf8)?;
+        if !is_within_limits(value) {
+            return Err(SoftwareDecodeError::TooLong);
+        }
+        Ok(Software { value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encoding() {
+        let mut buf = BytesMut::with_capacity(0);
+        let software = Software::new("stunne").unwrap();
+        software.encode(&mut buf);
+        assert_eq!(buf.as_ref(), b"stunne");
+
+        assert_eq!(SoftwareDecoder.decode(b"stunne"), Ok(software));
+    }
+
+    #[test]
+    fn test_length_limit() {
+        assert!(Software::new(&"a".repeat(127)).is_ok());
+        assert!(Software::new(&"𓄁".repeat(127)).is_ok());
+        assert_eq!(
+            Software::new(&"a".repeat(128)),
+            Err(SoftwareEncodeError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_decode_length_limit() {
+        assert!(SoftwareDecoder.decode("𓄁".repeat(127).as_bytes()).is_ok());
+        assert_eq!(
+            SoftwareDecoder.decode("a".repeat(128).as_bytes()),
+            Err(SoftwareDecodeError::TooLong)
+        );
+        assert_eq!(
+            SoftwareDecoder.decode(&[0xff; 764]),
+            Err(SoftwareDecodeError::TooLong)
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        assert!(matches!(
+            SoftwareDecoder.decode(&[0xf0]),
+            Err(SoftwareDecodeError::InvalidUtf8(_))
+        ));
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(feature = "alloc")]
+use crate::encodings::AttributeDecoder;
+use crate::encodings::AttributeSliceEncoder;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Gives the reason that an UNKNOWN-ATTRIBUTES attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UnknownAttributesDecodeError {
+    /// The value was not a whole number of two-byte attribute types.
+    InvalidDataSize,
+}
+
+impl fmt::Display for UnknownAttributesDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownAttributesDecodeError::InvalidDataSize => {
+                f.write_str("value is not a whole number of attribute types")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UnknownAttributesDecodeError {}
+
+/// The value of an [UNKNOWN-ATTRIBUTES][] attribute: the comprehension-required attribute types
+/// in a request that the server did not understand.
+///
+/// [UNKNOWN-ATTRIBUTES]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.9
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UnknownAttributes<'a> {
+    pub attribute_types: &'a [u16],
+}
+
+impl AttributeSliceEncoder for UnknownAttributes<'_> {
+    fn encoded_len(&self) -> usize {
+        self.attribute_types.len() * 2
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        for (chunk, attribute_type) in dst.chunks_exact_mut(2).zip(self.attribute_types) {
+            chunk.copy_from_slice(&attribute_type.to_be_bytes());
+        }
+    }
+}
+
+/// Decodes an UNKNOWN-ATTRIBUTES attribute into the list of attribute types it contains.
+#[cfg(feature = "alloc")]
+#[derive(Default)]
+pub struct UnknownAttributesDecoder;
+
+#[cfg(feature = "alloc")]
+impl AttributeDecoder<'_> for UnknownAttributesDecoder {
+    type Item = Vec<u16>;
+    type Error = UnknownAttributesDecodeError;
+
+    fn decode(&self, buf: &[u8]) -> Result<Self::Item, Self::Error> {
+        let chunks = buf.chunks_exact(2);
+        if !chunks.remainder().is_empty() {
+            return Err(UnknownAttributesDecodeError::InvalidDataSize);
+        }
+        Ok(chunks
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_unknown_attributes() {
+        let mut buf = BytesMut::with_capacity(0);
+        UnknownAttributes {
+            attribute_types: &[0x0024, 0x7f01, 0x0003],
+        }
+        .encode(&mut buf);
+
+        let expected = [0x00, 0x24, 0x7f, 0x01, 0x00, 0x03];
+        assert_eq!(buf.as_ref(), &expected);
+        assert_eq!(
+            UnknownAttributesDecoder.decode(&expected),
+            Ok(vec![0x0024, 0x7f01, 0x0003])
+        );
+    }
+
+    #[test]
+    fn test_invalid_data_size() {
+        assert_eq!(
+            UnknownAttributesDecoder.decode(&[0x00, 0x24, 0x7f]),
+            Err(UnknownAttributesDecodeError::InvalidDataSize)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use crate::encodings::{AttributeDecoder, AttributeSliceEncoder};
+use alloc::borrow::Cow;
+use core::fmt;
+use core::str::{from_utf8, Utf8Error};
+
+/// RFC 5389 requires the USERNAME value to be "a UTF-8 encoded sequence of less than 513 bytes".
+const USERNAME_BYTE_LIMIT: usize = 513;
+
+/// Gives the reason that a value could not be used as a USERNAME attribute.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UsernameEncodeError {
+    /// The value, after being processed by SASLprep, was 513 bytes or longer.
+    TooLong,
+
+    /// The value contained characters that are prohibited by the SASLprep profile (e.g., control
+    /// characters), or violated its bidirectional text rules.
+    InvalidCharacters,
+}
+
+impl fmt::Display for UsernameEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsernameEncodeError::TooLong => f.write_str("USERNAME must be less than 513 bytes"),
+            UsernameEncodeError::InvalidCharacters => {
+                f.write_str("USERNAME contains characters prohibited by SASLprep")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UsernameEncodeError {}
+
+/// Gives the reason that a USERNAME attribute's value could not be decoded.
+#[derive(Debug, PartialEq, Eq)]
+pub enum UsernameDecodeError {
+    /// The value was not valid UTF-8.
+    InvalidUtf8(Utf8Error),
+
+    /// The value was 513 bytes or longer.
+    TooLong,
+}
+
+impl fmt::Display for UsernameDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UsernameDecodeError::InvalidUtf8(e) => write!(f, "USERNAME is not valid UTF-8: {}", e),
+            UsernameDecodeError::TooLong => f.write_str("USERNAME must be less than 513 bytes"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for UsernameDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            UsernameDecodeError::InvalidUtf8(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// The value of a [USERNAME][] attribute.
+///
+/// The username is processed through the [SASLprep][] profile when it is created, so the value
+/// that is encoded may differ slightly from the value supplied (e.g., non-ASCII spaces are mapped
+/// to a regular space). Creation fails if the processed value is not a valid username.
+///
+/// [USERNAME]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.3
+/// [SASLprep]: https://datatracker.ietf.org/doc/html/rfc4013
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Username<'a> {
+    value: Cow<'a, str>,
+}
+
+impl<'a> Username<'a> {
+    /// Requires the `std` feature, as SASLprep is not available without it.
+    #[cfg(feature = "std")]
+    pub fn new(value: &'a str) -> Result<Self, UsernameEncodeError> {
+        let value =
+            crate::text::saslprep(value).map_err(|_| UsernameEncodeError::InvalidCharacters)?;
+        if value.len() >= USERNAME_BYTE_LIMIT {
+            return Err(UsernameEncodeError::TooLong);
+        }
+        Ok(Self { value })
+    }
+
+    /// Returns the username as it will be encoded (i.e., after SASLprep processing).
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
+impl AttributeSliceEncoder for Username<'_> {
+    fn encoded_len(&self) -> usize {
+        self.value.len()
+    }
+
+    fn encode_to_slice(&self, dst: &mut [u8]) {
+        dst.copy_from_slice(self.value.as_bytes());
+    }
+}
+
+/// Decodes a USERNAME attribute, rejecting values longer than RFC 5389 allows.
+#[derive(Default)]
+pub struct UsernameDecoder;
+
+impl<'buf> AttributeDecoder<'buf> for UsernameDecoder {
+    type Item = &'buf str;
+    type Error = UsernameDecodeError;
+
+    fn decode(&self, buf: &'buf [u8]) -> Result<Self::Item, Self::Error> {
+        if buf.len() >= USERNAME_BYTE_LIMIT {
+            return Err(UsernameDecodeError::TooLong);
+        }
+        from_utf8(buf).map_err(UsernameDecodeError::InvalidUtf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::AttributeEncoder;
+    use bytes::BytesMut;
+
+    #[test]
+    fn test_encoding() {
+        let mut buf = BytesMut::with_capacity(0);
+        Username::new("user").unwrap().encode(&mut buf);
+        assert_eq!(buf.as_ref(), b"user");
+
+        assert_eq!(UsernameDecoder.decode(b"user"), Ok("user"));
+    }
+
+    #[test]
+    fn test_saslprep_is_applied() {
+        // A non-ASCII space (U+00A0) is mapped to a regular space, while a soft hyphen (U+00AD) is
+        // mapped to nothing.
+        let username = Username::new("us\u{00A0}er\u{00AD}").unwrap();
+        assert_eq!(username.as_str(), "us er");
+
+        let mut buf = BytesMut::with_capacity(0);
+        username.encode(&mut buf);
+        assert_eq!(buf.as_ref(), b"us er");
+    }
+
+    #[test]
+    fn test_prohibited_characters() {
+        assert_eq!(
+            Username::new("user\u{0007}"),
+            Err(UsernameEncodeError::InvalidCharacters)
+        );
+    }
+
+    #[test]
+    fn test_length_limit() {
+        let longest = "a".repeat(512);
+        assert!(Username::new(&longest).is_ok());
+
+        let too_long = "a".repeat(513);
+        assert_eq!(Username::new(&too_long), Err(UsernameEncodeError::TooLong));
+
+        assert_eq!(UsernameDecoder.decode(longest.as_bytes()), Ok(&longest[..]));
+        assert_eq!(
+            UsernameDecoder.decode(too_long.as_bytes()),
+            Err(UsernameDecodeError::TooLong)
+        );
+    }
+}
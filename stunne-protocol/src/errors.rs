@@ -1,3 +1,12 @@
+use crate::attributes::types::DisplayAttributeType;
+use crate::encodings::{FingerprintDecodeError, MessageIntegrityDecodeError};
+#[cfg(feature = "alloc")]
+use crate::encodings::{
+    NonceEncodeError, RealmEncodeError, SoftwareEncodeError, UsernameEncodeError,
+};
+use crate::{TransactionId, STUN_HEADER_BYTES};
+use core::fmt;
+
 /// This error occurs whenever an attempt to decode a message fails due to the message having an
 /// invalid format.
 #[derive(Debug, PartialEq, Eq)]
@@ -20,5 +29,624 @@ pub enum MessageDecodeError {
     /// The data provided to the decoder was not large enough to perform the current operation
     /// (e.g., decoding the header, or if occurring while decoding an attribute, the data was not
     /// able to decode the entire attribute.
-    UnexpectedEndOfData,
+    UnexpectedEndOfData {
+        /// The offset of the header, attribute header, or attribute value that could not be
+        /// decoded, from the start of the message.
+        offset: usize,
+
+        /// The type of the attribute whose value was cut short, if the attribute header itself
+        /// could be decoded.
+        attribute_type: Option<u16>,
+
+        /// The number of bytes needed from `offset` onwards (for an attribute value, including
+        /// its padding).
+        needed: usize,
+
+        /// The number of bytes that were available from `offset` onwards.
+        available: usize,
+    },
+
+    /// The message length in the header was not a multiple of four. As every attribute is padded
+    /// to a multiple of four bytes, the attributes of such a message cannot be aligned correctly.
+    UnalignedLength {
+        /// The message length given in the header.
+        declared: usize,
+    },
+
+    /// The message length in the header is larger than the data following the header.
+    LengthExceedsSlice {
+        /// The message length given in the header.
+        declared: usize,
+
+        /// The number of bytes following the header.
+        available: usize,
+    },
+
+    /// The data following the header is larger than the message length in the header.
+    SliceExceedsLength {
+        /// The message length given in the header.
+        declared: usize,
+
+        /// The number of bytes following the header.
+        available: usize,
+    },
+
+    /// The padding following an attribute's value contained non-zero bytes.
+    NonZeroPadding {
+        /// The offset of the padding, from the start of the message.
+        offset: usize,
+
+        /// The type of the attribute whose padding is invalid.
+        attribute_type: u16,
+    },
+
+    /// An attribute that may appear at most once in a message appeared again (see
+    /// [check_duplicates](crate::StunDecoder::check_duplicates)).
+    DuplicateAttribute {
+        /// The offset of the repeated attribute, from the start of the message.
+        offset: usize,
+
+        /// The type of the repeated attribute.
+        attribute_type: u16,
+    },
+}
+
+impl MessageDecodeError {
+    /// The error returned when there are too few bytes to contain a message header.
+    pub(crate) fn header_too_short(available: usize) -> Self {
+        MessageDecodeError::UnexpectedEndOfData {
+            offset: 0,
+            attribute_type: None,
+            needed: STUN_HEADER_BYTES,
+            available,
+        }
+    }
+}
+
+impl fmt::Display for MessageDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageDecodeError::NonZeroStartingBits => {
+                f.write_str("message does not start with two zero bits")
+            }
+            MessageDecodeError::InvalidMagicCookie => {
+                f.write_str("message does not contain the magic cookie")
+            }
+            MessageDecodeError::InvalidMessageClass => f.write_str("invalid message class"),
+            MessageDecodeError::InvalidMessageMethod => f.write_str("invalid message method"),
+            MessageDecodeError::UnexpectedEndOfData {
+                offset,
+                attribute_type,
+                needed,
+                available,
+            } => {
+                write!(f, "unexpected end of data at offset {}", offset)?;
+                if let Some(attribute_type) = attribute_type {
+                    write!(f, " in {}", DisplayAttributeType(*attribute_type))?;
+                }
+                write!(f, ": needed {} bytes, but {} available", needed, available)
+            }
+            MessageDecodeError::UnalignedLength { declared } => write!(
+                f,
+                "message length in the header ({}) is not a multiple of four",
+                declared
+            ),
+            MessageDecodeError::LengthExceedsSlice {
+                declared,
+                available,
+            } => write!(
+                f,
+                "message length in the header ({}) exceeds the data given ({})",
+                declared, available
+            ),
+            MessageDecodeError::SliceExceedsLength {
+                declared,
+                available,
+            } => write!(
+                f,
+                "data given ({}) exceeds the message length in the header ({})",
+                available, declared
+            ),
+            MessageDecodeError::NonZeroPadding {
+                offset,
+                attribute_type,
+            } => write!(
+                f,
+                "padding of {} at offset {} contains non-zero bytes",
+                DisplayAttributeType(*attribute_type),
+                offset
+            ),
+            MessageDecodeError::DuplicateAttribute {
+                offset,
+                attribute_type,
+            } => write!(
+                f,
+                "{} at offset {} appears more than once",
+                DisplayAttributeType(*attribute_type),
+                offset
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MessageDecodeError {}
+
+/// Gives the reason that a [TransactionId](crate::TransactionId) could not be created from a
+/// byte slice or parsed from a string.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransactionIdError {
+    /// The value was not 12 bytes long, or when parsing, was not 24 characters long.
+    InvalidLength,
+
+    /// When parsing, the value contained a character that was not a hexadecimal digit.
+    InvalidCharacter,
+}
+
+impl fmt::Display for TransactionIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionIdError::InvalidLength => {
+                f.write_str("transaction ID must be 12 bytes (24 hexadecimal digits)")
+            }
+            TransactionIdError::InvalidCharacter => {
+                f.write_str("transaction ID contains a character that is not a hexadecimal digit")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransactionIdError {}
+
+/// Gives the reason that a message's MESSAGE-INTEGRITY attribute could not be verified.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// The message does not contain a MESSAGE-INTEGRITY attribute.
+    MissingAttribute,
+
+    /// The value of the MESSAGE-INTEGRITY attribute could not be decoded.
+    InvalidAttribute(MessageIntegrityDecodeError),
+
+    /// The attributes of the message could not be decoded up to the MESSAGE-INTEGRITY attribute.
+    Decode(MessageDecodeError),
+
+    /// The HMAC in the MESSAGE-INTEGRITY attribute does not match the one calculated from the
+    /// message. Either the key was incorrect, or the message was tampered with.
+    Mismatch,
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::MissingAttribute => {
+                f.write_str("message does not contain a MESSAGE-INTEGRITY attribute")
+            }
+            IntegrityError::InvalidAttribute(e) => {
+                write!(f, "invalid MESSAGE-INTEGRITY attribute: {}", e)
+            }
+            IntegrityError::Decode(e) => write!(f, "failed to decode message: {}", e),
+            IntegrityError::Mismatch => f.write_str("MESSAGE-INTEGRITY does not match the message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for IntegrityError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IntegrityError::InvalidAttribute(e) => Some(e),
+            IntegrityError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Gives the reason that a message's FINGERPRINT attribute could not be verified (see
+/// [FingerprintVerifier](crate::encodings::FingerprintVerifier)).
+#[derive(Debug, PartialEq, Eq)]
+pub enum FingerprintError {
+    /// The last attribute of the message is not a FINGERPRINT attribute.
+    MissingAttribute,
+
+    /// The value of the FINGERPRINT attribute could not be decoded.
+    InvalidAttribute(FingerprintDecodeError),
+
+    /// The message or its attributes could not be decoded.
+    Decode(MessageDecodeError),
+
+    /// The CRC in the FINGERPRINT attribute does not match the one calculated from the message.
+    /// The message was corrupted, or is not a STUN message at all.
+    Mismatch,
+}
+
+impl fmt::Display for FingerprintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FingerprintError::MissingAttribute => {
+                f.write_str("message does not end with a FINGERPRINT attribute")
+            }
+            FingerprintError::InvalidAttribute(e) => {
+                write!(f, "invalid FINGERPRINT attribute: {}", e)
+            }
+            FingerprintError::Decode(e) => write!(f, "failed to decode message: {}", e),
+            FingerprintError::Mismatch => f.write_str("FINGERPRINT does not match the message"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FingerprintError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FingerprintError::InvalidAttribute(e) => Some(e),
+            FingerprintError::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Gives the reason that a specific attribute could not be retrieved from a message (see
+/// [get_attribute](crate::StunDecoder::get_attribute)).
+#[derive(Debug, PartialEq, Eq)]
+pub enum AttributeError<E> {
+    /// The attributes of the message could not be decoded up to the requested attribute.
+    Message(MessageDecodeError),
+
+    /// The requested attribute was found, but its value could not be decoded.
+    Decode(E),
+}
+
+impl<E: fmt::Display> fmt::Display for AttributeError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeError::Message(e) => write!(f, "failed to decode message: {}", e),
+            AttributeError::Decode(e) => write!(f, "failed to decode attribute: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: std::error::Error + 'static> std::error::Error for AttributeError<E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AttributeError::Message(e) => Some(e),
+            AttributeError::Decode(e) => Some(e),
+        }
+    }
+}
+
+/// Gives the reason that a [MessageBuilder](crate::MessageBuilder) could not build a message.
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The value given for the SOFTWARE attribute was invalid.
+    Software(SoftwareEncodeError),
+
+    /// The value given for the USERNAME attribute was invalid.
+    Username(UsernameEncodeError),
+
+    /// The value given for the REALM attribute was invalid.
+    Realm(RealmEncodeError),
+
+    /// The value given for the NONCE attribute was invalid.
+    Nonce(NonceEncodeError),
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Software(e) => write!(f, "invalid SOFTWARE: {}", e),
+            BuildError::Username(e) => write!(f, "invalid USERNAME: {}", e),
+            BuildError::Realm(e) => write!(f, "invalid REALM: {}", e),
+            BuildError::Nonce(e) => write!(f, "invalid NONCE: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BuildError::Software(e) => Some(e),
+            BuildError::Username(e) => Some(e),
+            BuildError::Realm(e) => Some(e),
+            BuildError::Nonce(e) => Some(e),
+        }
+    }
+}
+
+/// Gives the reason that an attribute could not be added by
+/// [try_add_attribute](crate::StunAttributeEncoder::try_add_attribute), or that a message could
+/// not be finished by [try_finish](crate::StunAttributeEncoder::try_finish).
+#[cfg(feature = "alloc")]
+#[derive(Debug, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The attribute may not follow an attribute already in the message. Only
+    /// MESSAGE-INTEGRITY-SHA256 and FINGERPRINT may follow MESSAGE-INTEGRITY, only FINGERPRINT may
+    /// follow MESSAGE-INTEGRITY-SHA256, and FINGERPRINT must be the last attribute.
+    InvalidOrder {
+        /// The type of the attribute being added.
+        attribute_type: u16,
+
+        /// The type of the attribute that it may not follow.
+        preceding: u16,
+    },
+
+    /// The value of the attribute is longer than can be given in the attribute's 16-bit length
+    /// field.
+    AttributeTooLarge {
+        /// The type of the attribute being added.
+        attribute_type: u16,
+
+        /// The length of the attribute's value.
+        length: usize,
+    },
+
+    /// The attributes of the message are longer than can be given in the 16-bit length field of
+    /// the header.
+    LengthOverflow {
+        /// The length of the attributes, including padding.
+        length: usize,
+    },
+
+    /// The message would be larger than the maximum size of a
+    /// [LimitedEncoder](crate::LimitedEncoder).
+    MessageTooLarge {
+        /// The size of the message, including the header.
+        size: usize,
+
+        /// The maximum size of the message.
+        max_size: usize,
+    },
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodeError::InvalidOrder {
+                attribute_type,
+                preceding,
+            } => write_invalid_order(f, *attribute_type, *preceding),
+            EncodeError::AttributeTooLarge {
+                attribute_type,
+                length,
+            } => write_attribute_too_large(f, *attribute_type, *length),
+            EncodeError::LengthOverflow { length } => write_length_overflow(f, *length),
+            EncodeError::MessageTooLarge { size, max_size } => write!(
+                f,
+                "message of {} bytes exceeds the maximum of {} bytes",
+                size, max_size
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Gives the reason that a [SliceEncoder](crate::SliceEncoder) could not encode a message.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SliceEncodeError {
+    /// The buffer given to the encoder is too small to hold the message.
+    BufferTooSmall,
+
+    /// The attribute may not follow an attribute already in the message (e.g., any attribute after
+    /// FINGERPRINT).
+    InvalidOrder {
+        /// The type of the attribute being added.
+        attribute_type: u16,
+
+        /// The type of the attribute that it may not follow.
+        preceding: u16,
+    },
+
+    /// The value of the attribute is longer than can be given in the attribute's 16-bit length
+    /// field.
+    AttributeTooLarge {
+        /// The type of the attribute being added.
+        attribute_type: u16,
+
+        /// The length of the attribute's value.
+        length: usize,
+    },
+
+    /// The attributes of the message would be longer than can be given in the 16-bit length
+    /// field of the header.
+    LengthOverflow {
+        /// The length of the attributes, including padding.
+        length: usize,
+    },
+}
+
+impl fmt::Display for SliceEncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SliceEncodeError::BufferTooSmall => {
+                f.write_str("buffer is too small to hold the message")
+            }
+            SliceEncodeError::InvalidOrder {
+                attribute_type,
+                preceding,
+            } => write_invalid_order(f, *attribute_type, *preceding),
+            SliceEncodeError::AttributeTooLarge {
+                attribute_type,
+                length,
+            } => write_attribute_too_large(f, *attribute_type, *length),
+            SliceEncodeError::LengthOverflow { length } => write_length_overflow(f, *length),
+        }
+    }
+}
+
+fn write_invalid_order(
+    f: &mut fmt::Formatter<'_>,
+    attribute_type: u16,
+    preceding: u16,
+) -> fmt::Result {
+    write!(
+        f,
+        "{} may not follow {}",
+        DisplayAttributeType(attribute_type),
+        DisplayAttributeType(preceding)
+    )
+}
+
+fn write_attribute_too_large(
+    f: &mut fmt::Formatter<'_>,
+    attribute_type: u16,
+    length: usize,
+) -> fmt::Result {
+    write!(
+        f,
+        "{} value of {} bytes exceeds the maximum attribute length",
+        DisplayAttributeType(attribute_type),
+        length
+    )
+}
+
+fn write_length_overflow(f: &mut fmt::Formatter<'_>, length: usize) -> fmt::Result {
+    write!(
+        f,
+        "attributes of {} bytes exceed the maximum message length",
+        length
+    )
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SliceEncodeError {}
+
+/// Gives the reason that a message could not be read from a stream by
+/// [OwnedDecoder::read_from](crate::OwnedDecoder::read_from).
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadError {
+    /// The underlying reader failed, or the stream ended before a whole message was read.
+    Io(std::io::Error),
+
+    /// The message was invalid. If the header could not be decoded, the boundaries of messages
+    /// can no longer be found, and the stream should be closed.
+    Decode(MessageDecodeError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "I/O error: {}", e),
+            ReadError::Decode(e) => write!(f, "failed to decode message: {}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::Decode(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for ReadError {
+    fn from(other: std::io::Error) -> Self {
+        ReadError::Io(other)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<MessageDecodeError> for ReadError {
+    fn from(other: MessageDecodeError) -> Self {
+        ReadError::Decode(other)
+    }
+}
+
+/// Gives the reason that a transaction could not be started by a
+/// [TransactionManager](crate::TransactionManager).
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransactionError {
+    /// The header of the request could not be decoded.
+    Decode(MessageDecodeError),
+
+    /// A transaction with the same transaction ID is already in progress.
+    DuplicateTransactionId(TransactionId),
+}
+
+impl fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionError::Decode(e) => write!(f, "failed to decode request: {}", e),
+            TransactionError::DuplicateTransactionId(tx_id) => {
+                write!(f, "transaction {} is already in progress", tx_id)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransactionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransactionError::Decode(e) => Some(e),
+            TransactionError::DuplicateTransactionId(_) => None,
+        }
+    }
+}
+
+impl From<MessageDecodeError> for TransactionError {
+    fn from(other: MessageDecodeError) -> Self {
+        TransactionError::Decode(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::MappedAddressDecodeError;
+    use std::error::Error;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            MessageDecodeError::InvalidMagicCookie.to_string(),
+            "message does not contain the magic cookie"
+        );
+        assert_eq!(
+            IntegrityError::Decode(MessageDecodeError::header_too_short(4)).to_string(),
+            "failed to decode message: unexpected end of data at offset 0: needed 20 bytes, but \
+             4 available"
+        );
+        assert_eq!(
+            MessageDecodeError::UnexpectedEndOfData {
+                offset: 24,
+                attribute_type: Some(0x8022),
+                needed: 8,
+                available: 3,
+            }
+            .to_string(),
+            "unexpected end of data at offset 24 in SOFTWARE: needed 8 bytes, but 3 available"
+        );
+        assert_eq!(
+            AttributeError::Decode(MappedAddressDecodeError::UnknownFamily).to_string(),
+            "failed to decode attribute: unknown address family"
+        );
+    }
+
+    #[test]
+    fn test_boxed_error() {
+        fn decode() -> Result<(), Box<dyn Error>> {
+            Err(AttributeError::Decode(
+                MappedAddressDecodeError::NonZeroFirstByte,
+            ))?
+        }
+
+        let error = decode().unwrap_err();
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            "first byte of the address is not zero"
+        );
+    }
 }
@@ -1,11 +1,41 @@
-use std::net::SocketAddr;
+use core::net::SocketAddr;
 
+use crate::attributes::types::{ALTERNATE_SERVER, OTHER_ADDRESS, RESPONSE_ORIGIN};
 use crate::encodings::{MappedAddressEncoder, XorMappedAddressEncoder};
 use crate::TransactionId;
 
 pub trait SocketAddrExt {
     fn as_mapped_address(&self) -> MappedAddressEncoder;
     fn as_xor_mapped_address(&self, tx_id: TransactionId) -> XorMappedAddressEncoder;
+
+    /// Returns the attribute type and encoder for a [RESPONSE-ORIGIN][] attribute containing
+    /// this address.
+    ///
+    /// ```
+    /// # use std::net::SocketAddr;
+    /// # use stunne_protocol::{ext::SocketAddrExt, *};
+    /// let address: SocketAddr = "192.0.2.1:3478".parse().unwrap();
+    /// let (attribute_type, encoder) = address.as_response_origin();
+    /// let message = MessageBuilder::binding_request()
+    ///     .attribute(attribute_type, &encoder)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [RESPONSE-ORIGIN]: https://datatracker.ietf.org/doc/html/rfc5780#section-7.3
+    fn as_response_origin(&self) -> (u16, MappedAddressEncoder);
+
+    /// Returns the attribute type and encoder for an [OTHER-ADDRESS][] attribute containing this
+    /// address.
+    ///
+    /// [OTHER-ADDRESS]: https://datatracker.ietf.org/doc/html/rfc5780#section-7.4
+    fn as_other_address(&self) -> (u16, MappedAddressEncoder);
+
+    /// Returns the attribute type and encoder for an [ALTERNATE-SERVER][] attribute containing
+    /// this address.
+    ///
+    /// [ALTERNATE-SERVER]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.11
+    fn as_alternate_server(&self) -> (u16, MappedAddressEncoder);
 }
 
 impl SocketAddrExt for SocketAddr {
@@ -16,4 +46,43 @@ impl SocketAddrExt for SocketAddr {
     fn as_xor_mapped_address(&self, tx_id: TransactionId) -> XorMappedAddressEncoder {
         XorMappedAddressEncoder::new(*self, tx_id)
     }
+
+    fn as_response_origin(&self) -> (u16, MappedAddressEncoder) {
+        (RESPONSE_ORIGIN, self.as_mapped_address())
+    }
+
+    fn as_other_address(&self) -> (u16, MappedAddressEncoder) {
+        (OTHER_ADDRESS, self.as_mapped_address())
+    }
+
+    fn as_alternate_server(&self) -> (u16, MappedAddressEncoder) {
+        (ALTERNATE_SERVER, self.as_mapped_address())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::MappedAddressDecoder;
+    use crate::{MessageBuilder, StunDecoder};
+
+    #[test]
+    fn test_address_attributes() {
+        let address: SocketAddr = "192.0.2.1:3478".parse().unwrap();
+        let mut builder = MessageBuilder::binding_request();
+        for (attribute_type, encoder) in [
+            address.as_response_origin(),
+            address.as_other_address(),
+            address.as_alternate_server(),
+        ] {
+            builder = builder.attribute(attribute_type, &encoder);
+        }
+        let bytes = builder.to_bytes().unwrap();
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        for attribute_type in [RESPONSE_ORIGIN, OTHER_ADDRESS, ALTERNATE_SERVER] {
+            let decoded = message.get_attribute(attribute_type, &MappedAddressDecoder);
+            assert_eq!(decoded.unwrap().unwrap(), address);
+        }
+    }
 }
@@ -1,7 +1,8 @@
 use crate::errors::MessageDecodeError;
 use crate::utils::{decode_message_type, encode_message_type};
 use crate::{MessageClass, MessageMethod, TransactionId, MAGIC_COOKIE, STUN_HEADER_BYTES};
-use bytes::{BufMut, BytesMut};
+#[cfg(feature = "alloc")]
+use bytes::BytesMut;
 
 /// Represents contextual values in a STUN header.
 ///
@@ -12,6 +13,8 @@ use bytes::{BufMut, BytesMut};
 ///
 /// [defined in RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-6
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct MessageHeader {
     pub class: MessageClass,
     pub method: MessageMethod,
@@ -19,29 +22,150 @@ pub struct MessageHeader {
 }
 
 impl MessageHeader {
+    /// Returns the header for a successful response to this message, with the same method and
+    /// transaction ID.
+    ///
+    /// ```
+    /// # use stunne_protocol::*;
+    /// let request = MessageHeader {
+    ///     class: MessageClass::Request,
+    ///     method: MessageMethod::BINDING,
+    ///     tx_id: TransactionId::random(),
+    /// };
+    /// let response = request.success_response();
+    /// assert_eq!(response.class, MessageClass::SuccessResponse);
+    /// assert_eq!(response.method, request.method);
+    /// assert_eq!(response.tx_id, request.tx_id);
+    /// ```
+    pub fn success_response(&self) -> MessageHeader {
+        self.response(MessageClass::SuccessResponse)
+    }
+
+    /// Returns the header for an error response to this message, with the same method and
+    /// transaction ID.
+    pub fn error_response(&self) -> MessageHeader {
+        self.response(MessageClass::ErrorResponse)
+    }
+
+    /// Returns true if this is the header of a response (either success or error) to the request
+    /// with the given header; that is, the method and transaction ID match.
+    ///
+    /// ```
+    /// # use stunne_protocol::*;
+    /// let request = MessageHeader {
+    ///     class: MessageClass::Request,
+    ///     method: MessageMethod::BINDING,
+    ///     tx_id: TransactionId::random(),
+    /// };
+    /// assert!(request.error_response().is_response_to(&request));
+    /// assert!(!request.is_response_to(&request));
+    /// ```
+    pub fn is_response_to(&self, request: &MessageHeader) -> bool {
+        self.class.is_response()
+            && request.class.is_request()
+            && self.method == request.method
+            && self.tx_id == request.tx_id
+    }
+
+    /// Decodes only the header at the start of a packet, without checking the attributes that
+    /// follow it.
+    ///
+    /// This is cheaper than creating a [StunDecoder](crate::StunDecoder), for applications (e.g.,
+    /// servers demultiplexing packets by transaction ID) that only need to route a message. The
+    /// header is validated as by [StunDecoder::new](crate::StunDecoder::new), except that the
+    /// length in the header is not compared against the length of the packet.
+    ///
+    /// ```
+    /// # use stunne_protocol::*;
+    /// let bytes = MessageBuilder::binding_request().software("example").to_bytes().unwrap();
+    /// let header = MessageHeader::peek(&bytes).unwrap();
+    /// assert_eq!(header.class, MessageClass::Request);
+    /// assert_eq!(header.method, MessageMethod::BINDING);
+    ///
+    /// // Only the header is needed.
+    /// assert_eq!(MessageHeader::peek(&bytes[..20]), Ok(header));
+    /// ```
+    pub fn peek(buf: &[u8]) -> Result<MessageHeader, MessageDecodeError> {
+        let header_buf: &[u8; STUN_HEADER_BYTES] = buf
+            .get(..STUN_HEADER_BYTES)
+            .and_then(|header_buf| header_buf.try_into().ok())
+            .ok_or(MessageDecodeError::header_too_short(buf.len()))?;
+        let (header, _) = Self::decode_with_length(header_buf)?;
+        Ok(header)
+    }
+
+    fn response(&self, class: MessageClass) -> MessageHeader {
+        MessageHeader {
+            class,
+            method: self.method,
+            tx_id: self.tx_id,
+        }
+    }
+
     /// Encodes the header into a buffer. Note that the header includes a length, but we will not
     /// have the ability to write the length currently since we don't know what it is.
+    #[cfg(feature = "alloc")]
     pub(crate) fn encode_with_length(&self, buf: &mut BytesMut, data_length: u16) {
-        buf.reserve(STUN_HEADER_BYTES);
-        buf.extend_from_slice(&encode_message_type(self.class, self.method));
-        buf.put_u16(data_length);
-        buf.extend_from_slice(&MAGIC_COOKIE);
-        buf.extend_from_slice(self.tx_id.as_ref());
+        buf.extend_from_slice(&self.to_bytes_with_length(data_length));
+    }
+
+    /// Encodes the header with the given length of the attribute data.
+    pub(crate) fn to_bytes_with_length(&self, data_length: u16) -> [u8; STUN_HEADER_BYTES] {
+        let mut buf = [0; STUN_HEADER_BYTES];
+        buf[0..2].copy_from_slice(&encode_message_type(self.class, self.method));
+        buf[2..4].copy_from_slice(&data_length.to_be_bytes());
+        buf[4..8].copy_from_slice(&MAGIC_COOKIE);
+        buf[8..20].copy_from_slice(self.tx_id.as_ref());
+        buf
     }
 
     /// Decodes the header from a packet. Returns information in the header, including the length
     /// of the attribute size separately.
+    ///
+    /// As every attribute is padded to a multiple of four bytes, the length must also be a
+    /// multiple of four.
     pub(crate) fn decode_with_length(
         buf: &[u8; STUN_HEADER_BYTES],
     ) -> Result<(MessageHeader, u16), MessageDecodeError> {
-        if (buf[0] & 0b1100_0000) != 0 {
-            return Err(MessageDecodeError::NonZeroStartingBits);
+        let (header, length) = Self::decode_unaligned_with_length(buf)?;
+
+        if length % 4 != 0 {
+            return Err(MessageDecodeError::UnalignedLength {
+                declared: length.into(),
+            });
         }
 
+        Ok((header, length))
+    }
+
+    /// Decodes the header as with [decode_with_length](Self::decode_with_length), but allowing a
+    /// length that is not a multiple of four, as sent by agents that leave off the padding of the
+    /// final attribute.
+    pub(crate) fn decode_unaligned_with_length(
+        buf: &[u8; STUN_HEADER_BYTES],
+    ) -> Result<(MessageHeader, u16), MessageDecodeError> {
+        let decoded = Self::decode_classic_with_length(buf)?;
+
         if buf[4..8] != MAGIC_COOKIE {
             return Err(MessageDecodeError::InvalidMagicCookie);
         }
 
+        Ok(decoded)
+    }
+
+    /// Decodes the header as with [decode_with_length](Self::decode_with_length), but without
+    /// requiring the magic cookie, as is the case for [RFC 3489][] messages. The bytes where the
+    /// magic cookie would be are treated as part of the 128-bit transaction ID, of which only the
+    /// last 96 bits are returned in the header.
+    ///
+    /// [RFC 3489]: https://datatracker.ietf.org/doc/html/rfc3489#section-11.1
+    pub(crate) fn decode_classic_with_length(
+        buf: &[u8; STUN_HEADER_BYTES],
+    ) -> Result<(MessageHeader, u16), MessageDecodeError> {
+        if (buf[0] & 0b1100_0000) != 0 {
+            return Err(MessageDecodeError::NonZeroStartingBits);
+        }
+
         let (class, method) = decode_message_type(buf[0..=1].try_into().unwrap())?;
         let length = u16::from_be_bytes(buf[2..=3].try_into().unwrap());
         let tx_id = TransactionId::from_bytes(buf[8..20].try_into().unwrap());
@@ -130,12 +254,12 @@ mod tests {
                 method: MessageMethod::BINDING,
                 tx_id: TransactionId::from_bytes(&[10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21]),
             },
-            u16::MAX,
+            0xfffc,
             [
                 // Type
                 0x00, 0x01,
-                // Length
-                0xff, 0xff,
+                // Length (the largest multiple of four)
+                0xff, 0xfc,
                 // Magic cookie
                 0x21, 0x12, 0xa4, 0x42,
                 // TX id
@@ -165,6 +289,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_classic() {
+        #[rustfmt::skip]
+        let bytes = [
+            // Type
+            0x01, 0x01,
+            // Length
+            0, 12,
+            // The first 32 bits of a 128-bit RFC 3489 transaction ID
+            0xde, 0xad, 0xbe, 0xef,
+            // The remaining 96 bits
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11,
+        ];
+
+        assert!(matches!(
+            MessageHeader::decode_with_length(&bytes),
+            Err(MessageDecodeError::InvalidMagicCookie)
+        ));
+
+        let (header, length) = MessageHeader::decode_classic_with_length(&bytes).unwrap();
+        assert_eq!(header.class, MessageClass::SuccessResponse);
+        assert_eq!(header.method, MessageMethod::BINDING);
+        assert_eq!(header.tx_id.as_ref(), &bytes[8..]);
+        assert_eq!(length, 12);
+    }
+
+    #[test]
+    fn test_unaligned_length() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Binding Request
+            0, 6, // Message Length
+            0x21, 0x12, 0xA4, 0x42, // Cookie
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, // Transaction ID
+        ];
+        assert_eq!(
+            MessageHeader::decode_with_length(&bytes),
+            Err(MessageDecodeError::UnalignedLength { declared: 6 })
+        );
+
+        let (_, length) = MessageHeader::decode_unaligned_with_length(&bytes).unwrap();
+        assert_eq!(length, 6);
+    }
+
+    #[test]
+    fn test_peek() {
+        #[rustfmt::skip]
+        let bytes = [
+            0x01, 0x13, // Error response, ALLOCATE
+            0, 8, // Message Length
+            0x21, 0x12, 0xA4, 0x42, // Cookie
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, // Transaction ID
+        ];
+        let expected = MessageHeader {
+            class: MessageClass::ErrorResponse,
+            method: MessageMethod::ALLOCATE,
+            tx_id: TransactionId::from_bytes(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+        };
+        assert_eq!(MessageHeader::peek(&bytes), Ok(expected.clone()));
+
+        let mut longer = [0; 32];
+        longer[..20].copy_from_slice(&bytes);
+        assert_eq!(MessageHeader::peek(&longer), Ok(expected));
+
+        assert_eq!(
+            MessageHeader::peek(&bytes[..19]),
+            Err(MessageDecodeError::header_too_short(19))
+        );
+        let mut invalid = bytes;
+        invalid[4] = 0;
+        assert_eq!(
+            MessageHeader::peek(&invalid),
+            Err(MessageDecodeError::InvalidMagicCookie)
+        );
+    }
+
+    #[test]
+    fn test_responses() {
+        let request = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::CONNECT,
+            tx_id: TransactionId::from_bytes(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]),
+        };
+
+        assert_eq!(
+            request.success_response(),
+            MessageHeader {
+                class: MessageClass::SuccessResponse,
+                ..request.clone()
+            }
+        );
+        assert_eq!(
+            request.error_response(),
+            MessageHeader {
+                class: MessageClass::ErrorResponse,
+                ..request.clone()
+            }
+        );
+
+        assert!(request.success_response().is_response_to(&request));
+        let other_transaction = MessageHeader {
+            tx_id: TransactionId::from_bytes(&[1; 12]),
+            ..request.success_response()
+        };
+        assert!(!other_transaction.is_response_to(&request));
+        let other_method = MessageHeader {
+            method: MessageMethod::BINDING,
+            ..request.success_response()
+        };
+        assert!(!other_method.is_response_to(&request));
+    }
+
     #[test]
     fn test_non_zero_msbs() {
         #[rustfmt::skip]
@@ -228,7 +464,7 @@ mod tests {
 
         // Loop over every possible bit that could be flipped. Flip that bit and test.
         for x in 0..32 {
-            let mut test_bytes = bytes.clone();
+            let mut test_bytes = bytes;
             let change_mask = (1u32 << x).to_be_bytes();
             test_bytes[4] ^= change_mask[0];
             test_bytes[5] ^= change_mask[1];
@@ -0,0 +1,328 @@
+use crate::attributes::types::{ERROR_CODE, ICE_CONTROLLED, ICE_CONTROLLING};
+#[cfg(feature = "alloc")]
+use crate::attributes::types::{PRIORITY, USERNAME, USE_CANDIDATE};
+use crate::encodings::{ErrorCode, ErrorCodeDecoder, U64Decoder};
+use crate::{MessageClass, MessageMethod, StunDecoder};
+#[cfg(feature = "alloc")]
+use crate::{MessageHeader, StunEncoder, TransactionId};
+#[cfg(feature = "alloc")]
+use bytes::{Bytes, BytesMut};
+
+/// The role of an ICE agent (RFC 8445). The controlling agent decides which candidate pairs are
+/// used.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum IceRole {
+    Controlling,
+    Controlled,
+}
+
+impl IceRole {
+    /// Returns the type of the attribute that carries the tie-breaker of an agent in this role:
+    /// ICE-CONTROLLING or ICE-CONTROLLED.
+    pub fn attribute_type(self) -> u16 {
+        match self {
+            IceRole::Controlling => ICE_CONTROLLING,
+            IceRole::Controlled => ICE_CONTROLLED,
+        }
+    }
+
+    /// Returns the other role.
+    pub fn opposite(self) -> Self {
+        match self {
+            IceRole::Controlling => IceRole::Controlled,
+            IceRole::Controlled => IceRole::Controlling,
+        }
+    }
+
+    /// Returns the role of the agent that sent a check, from its ICE-CONTROLLING or
+    /// ICE-CONTROLLED attribute, with its tie-breaker, or `None` if it has neither attribute (or
+    /// it cannot be decoded).
+    pub fn of_request(request: &StunDecoder) -> Option<(IceRole, u64)> {
+        [IceRole::Controlling, IceRole::Controlled]
+            .into_iter()
+            .find_map(|role| {
+                let tie_breaker = request.get_attribute(role.attribute_type(), &U64Decoder)?;
+                Some((role, tie_breaker.ok()?))
+            })
+    }
+}
+
+/// How an agent resolves a role conflict found in a check that it received, as described in RFC
+/// 8445 section 7.3.1.1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum RoleConflict {
+    /// The agent keeps its role, and answers the check with a 487 (Role Conflict) error response.
+    Reject,
+
+    /// The agent switches to the given role, and then answers the check as usual.
+    Switch(IceRole),
+}
+
+/// Detects a role conflict in a check received by an agent with the given role and tie-breaker:
+/// the sender claims the same role. Returns how the conflict is resolved, or `None` if there is
+/// no conflict.
+///
+/// The agent with the larger tie-breaker becomes (or stays) controlling: a controlling agent
+/// with a tie-breaker at least as large as the sender's rejects the check, and otherwise becomes
+/// controlled; a controlled agent with a tie-breaker at least as large as the sender's becomes
+/// controlling, and otherwise rejects the check.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// # let bytes = IceCheck {
+/// #     local_ufrag: "l",
+/// #     remote_ufrag: "r",
+/// #     remote_password: "p",
+/// #     priority: 1,
+/// #     role: IceRole::Controlling,
+/// #     tie_breaker: 5,
+/// #     use_candidate: false,
+/// # }
+/// # .to_bytes();
+/// let check = StunDecoder::new(&bytes).unwrap();
+/// // The sender is also controlling, with the tie-breaker 5.
+/// assert_eq!(detect_role_conflict(&check, IceRole::Controlling, 9), Some(RoleConflict::Reject));
+/// assert_eq!(
+///     detect_role_conflict(&check, IceRole::Controlling, 1),
+///     Some(RoleConflict::Switch(IceRole::Controlled))
+/// );
+/// assert_eq!(detect_role_conflict(&check, IceRole::Controlled, 9), None);
+/// ```
+pub fn detect_role_conflict(
+    request: &StunDecoder,
+    role: IceRole,
+    tie_breaker: u64,
+) -> Option<RoleConflict> {
+    let (remote_role, remote_tie_breaker) = IceRole::of_request(request)?;
+    if remote_role != role {
+        return None;
+    }
+    let wins = tie_breaker >= remote_tie_breaker;
+    Some(match (role, wins) {
+        (IceRole::Controlling, true) | (IceRole::Controlled, false) => RoleConflict::Reject,
+        (IceRole::Controlling, false) => RoleConflict::Switch(IceRole::Controlled),
+        (IceRole::Controlled, true) => RoleConflict::Switch(IceRole::Controlling),
+    })
+}
+
+/// Returns true if a message is a 487 (Role Conflict) error response to a Binding request.
+pub fn is_role_conflict(response: &StunDecoder) -> bool {
+    response.class() == MessageClass::ErrorResponse
+        && response.method() == MessageMethod::BINDING
+        && matches!(
+            response.get_attribute(ERROR_CODE, &ErrorCodeDecoder),
+            Some(Ok(error)) if error.code() == ErrorCode::ROLE_CONFLICT.code()
+        )
+}
+
+/// Returns the role that an agent switches to after a check it sent in the role `sent_role` is
+/// answered with a 487 (Role Conflict) error response, as described in RFC 8445 section
+/// 7.2.5.1, or `None` if the response is not a role conflict. The agent then sends the check
+/// again, in its new role.
+pub fn role_after_conflict(response: &StunDecoder, sent_role: IceRole) -> Option<IceRole> {
+    is_role_conflict(response).then(|| sent_role.opposite())
+}
+
+/// An ICE connectivity check (RFC 8445 section 7.1.1), encoded as a Binding request in one call.
+///
+/// The attributes are added in the order that they must appear: USERNAME (the remote and local
+/// username fragments, separated by a colon), PRIORITY, ICE-CONTROLLING or ICE-CONTROLLED, then
+/// USE-CANDIDATE if the pair is nominated, and finally MESSAGE-INTEGRITY, keyed with the remote
+/// password, and FINGERPRINT.
+///
+/// ```
+/// # use stunne_protocol::{attributes::types::USERNAME, encodings::Utf8Decoder, *};
+/// let check = IceCheck {
+///     local_ufrag: "local",
+///     remote_ufrag: "remote",
+///     remote_password: "remote-password",
+///     priority: 1_862_270_975,
+///     role: IceRole::Controlling,
+///     tie_breaker: 0x1234,
+///     use_candidate: false,
+/// };
+/// let bytes = check.encode(TransactionId::random());
+///
+/// let message = StunDecoder::new(&bytes).unwrap();
+/// assert!(message.verify_integrity(b"remote-password").is_ok());
+/// assert!(message.verify_fingerprint().is_ok());
+/// let username = message.get_attribute(USERNAME, &Utf8Decoder).unwrap();
+/// assert_eq!(username, Ok("remote:local"));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct IceCheck<'a> {
+    /// The username fragment of the local agent, which sends the check.
+    pub local_ufrag: &'a str,
+
+    /// The username fragment of the remote agent, which answers the check.
+    pub remote_ufrag: &'a str,
+
+    /// The password of the remote agent, used as the short-term credential.
+    pub remote_password: &'a str,
+
+    /// The priority that the local agent would give a peer-reflexive candidate learned from
+    /// this check.
+    pub priority: u32,
+
+    /// The role of the local agent.
+    pub role: IceRole,
+
+    /// The tie-breaker of the local agent, used to resolve role conflicts.
+    pub tie_breaker: u64,
+
+    /// Whether the check nominates the pair, which only the controlling agent does.
+    pub use_candidate: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl IceCheck<'_> {
+    /// Encodes the check with the given transaction ID.
+    pub fn encode(&self, tx_id: TransactionId) -> Bytes {
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id,
+        };
+        let mut encoder = StunEncoder::new(BytesMut::new())
+            .encode_header(header)
+            .add_attribute(USERNAME, &self.username().as_str())
+            .add_attribute(PRIORITY, &self.priority)
+            .add_attribute(self.role.attribute_type(), &self.tie_breaker);
+        if self.use_candidate {
+            encoder = encoder.add_attribute(USE_CANDIDATE, &"");
+        }
+        encoder
+            .add_message_integrity(self.remote_password.as_bytes())
+            .finish_with_fingerprint()
+    }
+
+    /// Encodes the check with a random transaction ID.
+    #[cfg(feature = "std")]
+    pub fn to_bytes(&self) -> Bytes {
+        self.encode(TransactionId::random())
+    }
+
+    /// Returns the value of the USERNAME attribute: the remote and local username fragments,
+    /// separated by a colon.
+    pub fn username(&self) -> alloc::string::String {
+        alloc::format!("{}:{}", self.remote_ufrag, self.local_ufrag)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl core::fmt::Debug for IceCheck<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("IceCheck")
+            .field("local_ufrag", &self.local_ufrag)
+            .field("remote_ufrag", &self.remote_ufrag)
+            .field("priority", &self.priority)
+            .field("role", &self.role)
+            .field("tie_breaker", &self.tie_breaker)
+            .field("use_candidate", &self.use_candidate)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::attributes::types::{FINGERPRINT, MESSAGE_INTEGRITY};
+    use crate::encodings::{U32Decoder, Utf8Decoder};
+    use crate::StunDecoder;
+    use alloc::vec::Vec;
+
+    fn check() -> IceCheck<'static> {
+        IceCheck {
+            local_ufrag: "local",
+            remote_ufrag: "remote",
+            remote_password: "remote-password",
+            priority: 1_862_270_975,
+            role: IceRole::Controlled,
+            tie_breaker: 42,
+            use_candidate: false,
+        }
+    }
+
+    #[test]
+    fn test_attribute_order() {
+        let tx_id = TransactionId::random();
+        let bytes = IceCheck {
+            role: IceRole::Controlling,
+            use_candidate: true,
+            ..check()
+        }
+        .encode(tx_id);
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(message.tx_id(), tx_id);
+        assert_eq!(message.class(), MessageClass::Request);
+        assert_eq!(message.method(), MessageMethod::BINDING);
+        let types: Vec<_> = message
+            .attributes()
+            .map(|attribute| attribute.unwrap().attribute_type())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                USERNAME,
+                PRIORITY,
+                ICE_CONTROLLING,
+                USE_CANDIDATE,
+                MESSAGE_INTEGRITY,
+                FINGERPRINT
+            ]
+        );
+        assert_eq!(message.verify_integrity(b"remote-password"), Ok(()));
+        assert_eq!(message.verify_fingerprint(), Ok(()));
+        let priority = message.get_attribute(PRIORITY, &U32Decoder).unwrap();
+        assert_eq!(priority, Ok(1_862_270_975));
+    }
+
+    #[test]
+    fn test_controlled() {
+        let bytes = check().encode(TransactionId::random());
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert!(message
+            .get_attribute(ICE_CONTROLLING, &Utf8Decoder)
+            .is_none());
+        assert!(message
+            .get_attribute(ICE_CONTROLLED, &Utf8Decoder)
+            .is_some());
+        assert!(message.get_attribute(USE_CANDIDATE, &Utf8Decoder).is_none());
+        assert_eq!(check().username(), "remote:local");
+    }
+
+    #[test]
+    fn test_role_conflict() {
+        let bytes = check().encode(TransactionId::random());
+        let request = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(
+            IceRole::of_request(&request),
+            Some((IceRole::Controlled, 42))
+        );
+        let cases = [
+            (
+                IceRole::Controlled,
+                42,
+                Some(RoleConflict::Switch(IceRole::Controlling)),
+            ),
+            (IceRole::Controlled, 41, Some(RoleConflict::Reject)),
+            (IceRole::Controlling, 1, None),
+        ];
+        for (role, tie_breaker, expected) in cases {
+            assert_eq!(detect_role_conflict(&request, role, tie_breaker), expected);
+        }
+
+        let header = MessageHeader::peek(&bytes).unwrap();
+        let response = crate::MessageBuilder::new(header.error_response())
+            .error_code(ErrorCode::ROLE_CONFLICT)
+            .to_bytes()
+            .unwrap();
+        let response = StunDecoder::new(&response).unwrap();
+        assert_eq!(
+            role_after_conflict(&response, IceRole::Controlling),
+            Some(IceRole::Controlled)
+        );
+        assert_eq!(role_after_conflict(&request, IceRole::Controlling), None);
+    }
+}
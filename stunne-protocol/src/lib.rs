@@ -8,12 +8,10 @@
 //! ```
 //! use bytes::BytesMut;
 //! use stunne_protocol::{
-//!     encodings::Utf8Decoder, MessageClass, MessageHeader, MessageMethod, StunDecoder, StunEncoder,
-//!     TransactionId,
+//!     attributes::types::SOFTWARE, encodings::Utf8Decoder, MessageClass, MessageHeader,
+//!     MessageMethod, StunDecoder, StunEncoder, TransactionId,
 //! };
 //!
-//! const ATTRIBUTE_SOFTWARE: u16 = 0x8022;
-//!
 //! // Create a buffer and encode data to the buffer
 //! let buf = BytesMut::with_capacity(1000);
 //! let tx_id = TransactionId::random();
@@ -23,7 +21,7 @@
 //!         method: MessageMethod::BINDING,
 //!         tx_id
 //!     })
-//!     .add_attribute(ATTRIBUTE_SOFTWARE, &"Widget, Inc.")
+//!     .add_attribute(SOFTWARE, &"Widget, Inc.")
 //!     .finish();
 //!
 //! // `bytes` is a byte slice that can now be sent to a socket if desired.
@@ -32,35 +30,167 @@
 //! assert_eq!(message.method(), MessageMethod::BINDING);
 //! assert_eq!(message.tx_id(), tx_id);
 //! let attribute = message.attributes().next().unwrap().unwrap();
-//! assert_eq!(attribute.attribute_type(), ATTRIBUTE_SOFTWARE);
+//! assert_eq!(attribute.attribute_type(), SOFTWARE);
 //! assert_eq!(attribute.decode(&Utf8Decoder::default()).unwrap(), "Widget, Inc.");
 //! ```
-use rand::prelude::*;
+//!
+//! # Features
+//!
+//! * `std` (enabled by default): Without this feature, the crate is `no_std`. Generating random
+//!   transaction IDs, and the SASLprep processing required to create USERNAME and REALM values
+//!   (see [text]), are only available with this feature.
+//! * `alloc` (enabled by `std`): Provides [StunEncoder], [StunMessage], [MessageBuilder],
+//!   [StreamDecoder] and [StunTransaction], along with the decoders that must allocate to return
+//!   their values. Without this feature, messages are encoded into a fixed-size buffer using
+//!   [SliceEncoder], and decoded with [StunDecoder] as usual.
+//! * `tokio`: Provides `StunCodec`, a tokio_util codec for STUN over stream transports.
+//! * `smoltcp`: Provides `SmoltcpTransaction`, which runs a [StunTransaction] over a
+//!   [smoltcp](https://docs.rs/smoltcp) UDP socket, so that embedded targets with an allocator
+//!   can discover their public address.
+//! * `serde`: Implements `Serialize` and `Deserialize` for [MessageHeader] and its parts, and for
+//!   the owned message representations ([StunMessage] and [OwnedAttribute]).
+//! * `arbitrary`: Implements `Arbitrary` for [MessageHeader] and its parts, and for the owned
+//!   message representations, so that applications can fuzz their handling of messages.
+//! * `proptest`: Provides the `strategies` module, containing proptest strategies for generating
+//!   valid and corrupted messages.
+//! * `tracing`: Emits [tracing](https://docs.rs/tracing) spans and events while decoding
+//!   messages, for the header, each attribute and every error, so that decoding can be diagnosed
+//!   in production by enabling the `trace` or `debug` level.
+//! * `test-vectors`: Provides the `test_vectors` module, containing the test vectors of RFC 5769.
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-mod attributes;
+use rand::{CryptoRng, Rng, RngCore};
+
+pub mod attributes;
+#[cfg(feature = "alloc")]
+mod builder;
+#[cfg(feature = "tokio")]
+mod codec;
+mod credentials;
+mod dissect;
+#[cfg(feature = "alloc")]
+mod encoder;
 pub mod encodings;
 pub mod errors;
 pub mod ext;
 mod header;
+mod ice;
+#[cfg(feature = "alloc")]
+mod message;
+mod options;
+#[cfg(feature = "std")]
+mod pool;
+mod report;
+mod slice_encoder;
+#[cfg(feature = "smoltcp")]
+mod smoltcp_udp;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "proptest")]
+pub mod strategies;
+#[cfg(feature = "alloc")]
+mod stream;
+mod template;
+#[cfg(any(test, feature = "test-vectors"))]
+pub mod test_vectors;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "alloc")]
+mod transaction;
+#[cfg(feature = "std")]
+mod transaction_manager;
 mod utils;
 
-use attributes::StunAttributeIterator;
-use bytes::{BufMut, Bytes, BytesMut};
-use encodings::AttributeEncoder;
-use errors::MessageDecodeError;
+use attributes::types::{is_repeatable, MESSAGE_INTEGRITY, MESSAGE_INTEGRITY_SHA256};
+use attributes::{InvalidAttributeValue, KnownAttribute, StunAttribute, StunAttributeIterator};
+#[cfg(feature = "alloc")]
+pub use builder::MessageBuilder;
+#[cfg(feature = "tokio")]
+pub use codec::{CodecError, StunCodec};
+use core::fmt;
+use core::str::FromStr;
+pub use credentials::LongTermKey;
+pub use dissect::{dissect, Dissection};
+#[cfg(feature = "alloc")]
+pub use encoder::{LimitedAttributeEncoder, LimitedEncoder, StunAttributeEncoder, StunEncoder};
+use encodings::message_integrity::MESSAGE_INTEGRITY_BYTES;
+use encodings::{AttributeDecoder, FingerprintVerifier, MessageIntegrityDecoder};
+use errors::{
+    AttributeError, FingerprintError, IntegrityError, MessageDecodeError, TransactionIdError,
+};
 pub use header::MessageHeader;
+#[cfg(feature = "alloc")]
+pub use ice::IceCheck;
+pub use ice::{detect_role_conflict, is_role_conflict, role_after_conflict, IceRole, RoleConflict};
+#[cfg(feature = "alloc")]
+pub use message::{Divergence, OwnedAttribute, OwnedDecoder, StunMessage};
+pub use options::DecodeOptions;
+#[cfg(feature = "std")]
+pub use pool::BufferPool;
 use rand::distributions::{Distribution, Standard};
+pub use report::MessageReport;
+pub use slice_encoder::{SliceAttributeEncoder, SliceEncoder};
+#[cfg(feature = "smoltcp")]
+pub use smoltcp_udp::SmoltcpTransaction;
+#[cfg(feature = "std")]
+pub use stats::{DecodeErrorKind, DecodeStats};
+#[cfg(feature = "alloc")]
+pub use stream::{StreamDecoder, StreamItem};
+pub use template::MessageTemplate;
+#[cfg(feature = "alloc")]
+pub use transaction::{StunTransaction, Timestamp, TransactionConfig, TransactionOutcome};
+#[cfg(feature = "std")]
+pub use transaction_manager::{ResponseMatch, TransactionManager};
 
 /// Magic data that must be included in all STUN messages to clarify that the STUN message
 /// uses rfc5389, rather than the outdated rfc3489.
-static MAGIC_COOKIE: [u8; 4] = [0x21, 0x12, 0xA4, 0x42];
+const MAGIC_COOKIE: [u8; 4] = [0x21, 0x12, 0xA4, 0x42];
 
 const STUN_HEADER_BYTES: usize = 20;
 
+const PADDING_VALUE: u8 = 0;
+const ATTRIBUTE_HEADER_BYTES: usize = 4;
+
+/// Quickly checks whether a packet looks like a STUN message, without decoding it.
+///
+/// This is intended for applications that multiplex STUN with other protocols (e.g., RTP or DTLS)
+/// on a single socket, as [described in RFC 7983][]. The packet is considered to be STUN if it
+/// starts with two zero bits, contains the magic cookie, and has a message length that is a
+/// multiple of four and fits within the packet. A packet passing this check may still fail to
+/// decode.
+///
+/// ```
+/// # use stunne_protocol::is_stun;
+/// let binding_request = [0, 1, 0, 0, 0x21, 0x12, 0xA4, 0x42, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+/// assert!(is_stun(&binding_request));
+///
+/// // The first byte of a DTLS record is in the range 20..=63.
+/// let dtls = [22, 0xfe, 0xfd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+/// assert!(!is_stun(&dtls));
+/// ```
+///
+/// [described in RFC 7983]: https://datatracker.ietf.org/doc/html/rfc7983#section-7
+pub fn is_stun(buf: &[u8]) -> bool {
+    if buf.len() < STUN_HEADER_BYTES {
+        return false;
+    }
+
+    let length = usize::from(u16::from_be_bytes([buf[2], buf[3]]));
+    buf[0] & 0b1100_0000 == 0
+        && buf[4..8] == MAGIC_COOKIE
+        && length % 4 == 0
+        && STUN_HEADER_BYTES + length <= buf.len()
+}
+
 /// The class for a given STUN message, as [defined in RFC5839][].
 ///
 /// [defined in RFC5839]: https://datatracker.ietf.org/doc/html/rfc5389#section-6
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum MessageClass {
     /// Used by clients to request an operation from a server. The client would expect some response.
     Request,
@@ -72,6 +202,43 @@ pub enum MessageClass {
     ErrorResponse,
 }
 
+impl MessageClass {
+    /// Returns true for [Request](Self::Request) messages.
+    pub fn is_request(&self) -> bool {
+        *self == MessageClass::Request
+    }
+
+    /// Returns true for [Indication](Self::Indication) messages.
+    pub fn is_indication(&self) -> bool {
+        *self == MessageClass::Indication
+    }
+
+    /// Returns true for both [SuccessResponse](Self::SuccessResponse) and
+    /// [ErrorResponse](Self::ErrorResponse) messages.
+    pub fn is_response(&self) -> bool {
+        matches!(
+            self,
+            MessageClass::SuccessResponse | MessageClass::ErrorResponse
+        )
+    }
+
+    /// Returns true for [SuccessResponse](Self::SuccessResponse) messages.
+    pub fn is_success(&self) -> bool {
+        *self == MessageClass::SuccessResponse
+    }
+
+    /// Returns true for [ErrorResponse](Self::ErrorResponse) messages.
+    pub fn is_error(&self) -> bool {
+        *self == MessageClass::ErrorResponse
+    }
+
+    /// Returns true if the receiver of a message of this class is expected to send a response.
+    /// Only requests receive a response; indications and responses do not.
+    pub fn expects_response(&self) -> bool {
+        self.is_request()
+    }
+}
+
 impl From<MessageClass> for u16 {
     fn from(other: MessageClass) -> u16 {
         match other {
@@ -107,7 +274,17 @@ impl TryFrom<u16> for MessageClass {
 /// [defined in RFC5839]: https://datatracker.ietf.org/doc/html/rfc5389#section-6
 /// [Binding]: https://datatracker.ietf.org/doc/html/rfc5389#section-3
 /// [define their own methods]: https://datatracker.ietf.org/doc/html/rfc5389#section-18.1
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Both the `Debug` and `Display` output of a method is its name if it is a registered method
+/// (see [name](Self::name)), or its hexadecimal value otherwise.
+///
+/// ```
+/// # use stunne_protocol::MessageMethod;
+/// assert_eq!(MessageMethod::BINDING.to_string(), "BINDING");
+/// assert_eq!(MessageMethod::try_from_u16(0x123).unwrap().to_string(), "0x123");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(transparent))]
 pub struct MessageMethod(u16);
 
 impl MessageMethod {
@@ -129,6 +306,80 @@ impl MessageMethod {
     }
 
     pub const BINDING: Self = MessageMethod(1);
+
+    /// Used by a TURN client to create an allocation on the server, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-6).
+    pub const ALLOCATE: Self = MessageMethod(0x0003);
+
+    /// Used by a TURN client to refresh or delete an allocation, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-7).
+    pub const REFRESH: Self = MessageMethod(0x0004);
+
+    /// Indication used by a TURN client to send data to a peer, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-10).
+    pub const SEND: Self = MessageMethod(0x0006);
+
+    /// Indication used by a TURN server to relay data from a peer, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-10).
+    pub const DATA: Self = MessageMethod(0x0007);
+
+    /// Used by a TURN client to install permissions for peers, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-9).
+    pub const CREATE_PERMISSION: Self = MessageMethod(0x0008);
+
+    /// Used by a TURN client to bind a channel to a peer, as
+    /// [defined in RFC 5766](https://datatracker.ietf.org/doc/html/rfc5766#section-11).
+    pub const CHANNEL_BIND: Self = MessageMethod(0x0009);
+
+    /// Used by a TURN client to open a TCP connection to a peer through its allocation, as
+    /// [defined in RFC 6062](https://datatracker.ietf.org/doc/html/rfc6062#section-4.3).
+    pub const CONNECT: Self = MessageMethod(0x000A);
+
+    /// Used by a TURN client to associate a new data connection with a peer's TCP connection, as
+    /// [defined in RFC 6062](https://datatracker.ietf.org/doc/html/rfc6062#section-4.3).
+    pub const CONNECTION_BIND: Self = MessageMethod(0x000B);
+
+    /// Sent by a TURN server to indicate that a peer has opened a TCP connection to the client's
+    /// allocation, as [defined in RFC 6062](https://datatracker.ietf.org/doc/html/rfc6062#section-4.4).
+    pub const CONNECTION_ATTEMPT: Self = MessageMethod(0x000C);
+
+    /// Returns the name of the method if it is one of the methods registered with IANA in the
+    /// [STUN Methods registry][], or `None` otherwise.
+    ///
+    /// [STUN Methods registry]: https://www.iana.org/assignments/stun-parameters/stun-parameters.xhtml#stun-parameters-2
+    pub fn name(&self) -> Option<&'static str> {
+        match *self {
+            Self::BINDING => Some("BINDING"),
+            Self::ALLOCATE => Some("ALLOCATE"),
+            Self::REFRESH => Some("REFRESH"),
+            Self::SEND => Some("SEND"),
+            Self::DATA => Some("DATA"),
+            Self::CREATE_PERMISSION => Some("CREATE_PERMISSION"),
+            Self::CHANNEL_BIND => Some("CHANNEL_BIND"),
+            Self::CONNECT => Some("CONNECT"),
+            Self::CONNECTION_BIND => Some("CONNECTION_BIND"),
+            Self::CONNECTION_ATTEMPT => Some("CONNECTION_ATTEMPT"),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for MessageMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.pad(name),
+            None => write!(f, "0x{:03X}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for MessageMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "MessageMethod(0x{:03X})", self.0),
+        }
+    }
 }
 
 impl From<MessageMethod> for u16 {
@@ -145,6 +396,32 @@ impl TryFrom<u16> for MessageMethod {
     }
 }
 
+/// Deserializes the method from its number, rejecting values that do not fit in 12 bits.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for MessageMethod {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = u16::deserialize(deserializer)?;
+        Self::try_from_u16(value).map_err(|_| {
+            serde::de::Error::invalid_value(
+                serde::de::Unexpected::Unsigned(value.into()),
+                &"a method between 0 and 4095",
+            )
+        })
+    }
+}
+
+/// Generates methods that fit in 12 bits.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for MessageMethod {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(MessageMethod(u.int_in_range(0..=0xfff)?))
+    }
+
+    fn size_hint(depth: usize) -> (usize, Option<usize>) {
+        u16::size_hint(depth)
+    }
+}
+
 /// Represents the 96-bit value of the transaction ID for a STUN message.
 ///
 /// The transaction ID is a 96-bit identifier used to uniquely identify STUN transactions.
@@ -152,15 +429,47 @@ impl TryFrom<u16> for MessageMethod {
 /// Transaction ID in their responses to a client's requests.
 ///
 /// A Transaction ID SHOULD be generated in a cryptographically random way.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Transaction IDs are displayed as 24 lowercase hexadecimal digits, and can be parsed back from
+/// the same format:
+///
+/// ```
+/// # use stunne_protocol::TransactionId;
+/// let tx_id = TransactionId::from_bytes(&[0xb7, 0xe7, 0xa7, 1, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae]);
+/// assert_eq!(tx_id.to_string(), "b7e7a701bc34d686fa87dfae");
+/// assert_eq!("b7e7a701bc34d686fa87dfae".parse(), Ok(tx_id));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct TransactionId {
     bytes: [u8; 12],
 }
 
 impl TransactionId {
-    /// Generate a random transaction ID using Rand's thread_rng.
+    /// Generate a random transaction ID using Rand's thread_rng, which is a cryptographically
+    /// secure generator seeded by the operating system.
+    ///
+    /// Requires the `std` feature. Without it, a transaction ID can be generated from a
+    /// caller-supplied generator using [random_with](Self::random_with).
+    #[cfg(feature = "std")]
     pub fn random() -> Self {
-        thread_rng().gen()
+        Self::random_with(&mut rand::thread_rng())
+    }
+
+    /// Generate a random transaction ID using the given cryptographically secure random number
+    /// generator.
+    ///
+    /// ```
+    /// # use rand::SeedableRng;
+    /// # use stunne_protocol::TransactionId;
+    /// let mut rng = rand::rngs::StdRng::from_entropy();
+    /// let tx_id = TransactionId::random_with(&mut rng);
+    /// assert_ne!(tx_id, TransactionId::random_with(&mut rng));
+    /// ```
+    pub fn random_with<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
+        rng.gen()
     }
 
     pub fn from_bytes(bytes: &[u8; 12]) -> Self {
@@ -184,96 +493,46 @@ impl AsRef<[u8]> for TransactionId {
     }
 }
 
-/// Provides an interface that can be used to dynamically encode a stun datagram into a supplied
-/// buffer.
-///
-/// See example usage in [crate documentation](crate).
-///
-/// Note that the encoder is designed to continually reserve more bytes from the
-/// [BytesMut](bytes::BytesMut) buffer value as needed. If it needs to do this, it will allocate a
-/// new buffer, which can come with some performance penalty. These additional allocations can be
-/// avoided if the buffer is given a sane starting capacity. For example...
-///
-/// ```
-/// # use bytes::BytesMut;
-/// let mut buffer = BytesMut::with_capacity(1024);
-/// ```
-///
-/// An encoder using the above buffer now no longer need to allocate memory so long as the number
-/// of encoded bytes does not go above 1024. See the [BytesMut] documentation for more info.
-pub struct StunEncoder {
-    buf: BytesMut,
-}
+impl TryFrom<&[u8]> for TransactionId {
+    type Error = TransactionIdError;
 
-impl StunEncoder {
-    /// Create the encoder with the given buffer.
-    pub fn new(buf: BytesMut) -> StunEncoder {
-        Self { buf }
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let bytes = value
+            .try_into()
+            .map_err(|_| TransactionIdError::InvalidLength)?;
+        Ok(Self { bytes })
     }
+}
 
-    /// Associates the given header information to be written to the buffer.
-    ///
-    /// Note that there is no guarantee that the header is written when this method is called, as
-    /// it's impossible to know the length of the data (which must be written alongside the header
-    /// data). Instead, it is more likely that the header will be written AFTER the attributes have
-    /// been written to the buffer, during the [finish](StunAttributeEncoder::finish) method.
-    pub fn encode_header(mut self, header: MessageHeader) -> StunAttributeEncoder {
-        self.buf.reserve(STUN_HEADER_BYTES);
-        let data_buf = self.buf.split_off(STUN_HEADER_BYTES);
-        StunAttributeEncoder {
-            header_buf: self.buf,
-            buf: data_buf,
-            next_attribute_byte: 0,
-            header,
+impl fmt::Display for TransactionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.bytes {
+            write!(f, "{:02x}", byte)?;
         }
+        Ok(())
     }
 }
 
-const PADDING_VALUE: u8 = 0;
-const ATTRIBUTE_HEADER_BYTES: usize = 4;
+impl FromStr for TransactionId {
+    type Err = TransactionIdError;
 
-pub struct StunAttributeEncoder {
-    header_buf: BytesMut,
-    buf: BytesMut,
-    next_attribute_byte: usize,
-    header: MessageHeader,
-}
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 24 {
+            return Err(TransactionIdError::InvalidLength);
+        }
 
-impl StunAttributeEncoder {
-    pub fn add_attribute<T: AttributeEncoder>(mut self, attribute_type: u16, encoder: &T) -> Self {
-        // No need for reservation here.
-        // By default, `next_attribute_byte` is zero, so this will not panic.
-        // After the first attribute is created, `next_attribute_byte` will point to the byte where
-        // the writing of bytes has already advanced to, and thus presumed to have been reserved.
-        let mut attribute_header = self.buf.split_off(self.next_attribute_byte);
-        attribute_header.reserve(ATTRIBUTE_HEADER_BYTES);
-        attribute_header.reserve(ATTRIBUTE_HEADER_BYTES);
-
-        let mut attribute_data = attribute_header.split_off(ATTRIBUTE_HEADER_BYTES);
-        encoder.encode(&mut attribute_data);
-        let attribute_length = attribute_data.len();
-
-        // Add additional padding onto the attribute value if necessary
-        let padding_length = utils::padding_for_attribute_length(attribute_length);
-        attribute_data.reserve(padding_length);
-        attribute_data.put_bytes(PADDING_VALUE, padding_length);
-
-        // Write to the attribute "header"
-        attribute_header.put_u16(attribute_type);
-        attribute_header.put_u16(attribute_length as u16);
-
-        // Put all of the split items back together again.
-        attribute_header.unsplit(attribute_data);
-        self.buf.unsplit(attribute_header);
-        self.next_attribute_byte += ATTRIBUTE_HEADER_BYTES + attribute_length + padding_length;
-        self
-    }
-
-    pub fn finish(mut self) -> Bytes {
-        self.header
-            .encode_with_length(&mut self.header_buf, self.buf.len() as u16);
-        self.header_buf.unsplit(self.buf);
-        self.header_buf.freeze()
+        let mut bytes = [0; 12];
+        for (byte, digits) in bytes.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let mut value = 0;
+            for &digit in digits {
+                let digit = char::from(digit)
+                    .to_digit(16)
+                    .ok_or(TransactionIdError::InvalidCharacter)?;
+                value = value * 16 + digit as u8;
+            }
+            *byte = value;
+        }
+        Ok(Self { bytes })
     }
 }
 
@@ -282,7 +541,11 @@ impl StunAttributeEncoder {
 /// See example usage in [crate documentation](crate).
 pub struct StunDecoder<'a> {
     header: MessageHeader,
+    options: DecodeOptions,
+    classic: bool,
+    header_buf: &'a [u8; STUN_HEADER_BYTES],
     attribute_buf: &'a [u8],
+    trailing_buf: &'a [u8],
 }
 
 impl<'a> StunDecoder<'a> {
@@ -291,19 +554,96 @@ impl<'a> StunDecoder<'a> {
     /// This method will immediately try to parse the header from the byte slice. If it encounters
     /// an error in doing so, this will return a [MessageDecodeError].
     ///
-    /// Note that we do not perform any read past the header data here. If this method succeeds,
+    /// The length in the header must match the number of bytes following the header exactly;
+    /// otherwise, a [LengthExceedsSlice](MessageDecodeError::LengthExceedsSlice) or
+    /// [SliceExceedsLength](MessageDecodeError::SliceExceedsLength) error is returned. See
+    /// [with_options](Self::with_options) to relax this.
+    ///
+    /// Note that we do not perform any read of the attribute data here. If this method succeeds,
     /// it's still possible that an error might occur if the user were to continue decoding
     /// attributes (see [attributes()](Self::attributes()) below).
     pub fn new(buf: &'a [u8]) -> Result<Self, MessageDecodeError> {
+        Self::with_options(buf, DecodeOptions::default())
+    }
+
+    /// Create a new decoder as with [new](Self::new), using the given options to decide which
+    /// quirks of the encoding to tolerate (see [DecodeOptions]).
+    pub fn with_options(buf: &'a [u8], options: DecodeOptions) -> Result<Self, MessageDecodeError> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("decode_stun_header", length = buf.len()).entered();
+        let decoder = Self::decode(buf, options);
+        #[cfg(feature = "tracing")]
+        match &decoder {
+            Ok(decoder) => tracing::trace!(
+                class = ?decoder.class(),
+                method = %decoder.method(),
+                tx_id = %decoder.tx_id(),
+                classic = decoder.classic,
+                attribute_length = decoder.attribute_buf.len(),
+                trailing_bytes = decoder.trailing_buf.len(),
+                "decoded STUN header"
+            ),
+            Err(error) => tracing::debug!(%error, "failed to decode STUN header"),
+        }
+        decoder
+    }
+
+    /// Create a new decoder as with [with_options](Self::with_options), counting the message in
+    /// the given [DecodeStats].
+    ///
+    /// The attributes of the message are decoded up front so that they can be counted, along with
+    /// any error in them. Such an error is not returned here, but by [attributes()](Self::attributes)
+    /// as usual.
+    #[cfg(feature = "std")]
+    pub fn with_stats(
+        buf: &'a [u8],
+        options: DecodeOptions,
+        stats: &DecodeStats,
+    ) -> Result<Self, MessageDecodeError> {
+        let decoder = Self::with_options(buf, options);
+        match &decoder {
+            Ok(decoder) => stats.record_message(decoder),
+            Err(error) => stats.record_error(error),
+        }
+        decoder
+    }
+
+    fn decode(buf: &'a [u8], options: DecodeOptions) -> Result<Self, MessageDecodeError> {
         if buf.len() < STUN_HEADER_BYTES {
-            return Err(MessageDecodeError::UnexpectedEndOfData);
+            return Err(MessageDecodeError::header_too_short(buf.len()));
         }
         let (header_buf, attribute_buf) = buf.split_at(STUN_HEADER_BYTES);
         let header_buf: &[u8; STUN_HEADER_BYTES] = (header_buf).try_into().unwrap();
-        let (header, _attribute_length) = MessageHeader::decode_with_length(header_buf)?;
+        let classic = options.allow_classic && header_buf[4..8] != MAGIC_COOKIE;
+        let (header, attribute_length) = match classic {
+            true => MessageHeader::decode_classic_with_length(header_buf)?,
+            // A final attribute without padding leaves the length unaligned.
+            false if options.allow_missing_final_padding => {
+                MessageHeader::decode_unaligned_with_length(header_buf)?
+            }
+            false => MessageHeader::decode_with_length(header_buf)?,
+        };
+        let attribute_length = usize::from(attribute_length);
+        if attribute_length > attribute_buf.len() {
+            return Err(MessageDecodeError::LengthExceedsSlice {
+                declared: attribute_length,
+                available: attribute_buf.len(),
+            });
+        }
+        if attribute_length < attribute_buf.len() && !options.allow_trailing_bytes {
+            return Err(MessageDecodeError::SliceExceedsLength {
+                declared: attribute_length,
+                available: attribute_buf.len(),
+            });
+        }
+        let (attribute_buf, trailing_buf) = attribute_buf.split_at(attribute_length);
         Ok(Self {
             header,
+            options,
+            classic,
+            header_buf,
             attribute_buf,
+            trailing_buf,
         })
     }
 
@@ -327,6 +667,43 @@ impl<'a> StunDecoder<'a> {
         self.header.tx_id
     }
 
+    /// Returns true if the message was decoded as an [RFC 3489][] message, which lacks the magic
+    /// cookie. This can only happen if [allow_classic](DecodeOptions::allow_classic) was set.
+    ///
+    /// The 128-bit transaction ID of such a message is available from
+    /// [classic_tx_id](Self::classic_tx_id); [tx_id](Self::tx_id) only returns its last 96 bits.
+    ///
+    /// [RFC 3489]: https://datatracker.ietf.org/doc/html/rfc3489
+    pub fn is_classic(&self) -> bool {
+        self.classic
+    }
+
+    /// Returns the full 128-bit transaction ID of an [RFC 3489][] message, or `None` if the
+    /// message contains the magic cookie.
+    ///
+    /// [RFC 3489]: https://datatracker.ietf.org/doc/html/rfc3489#section-11.1
+    pub fn classic_tx_id(&self) -> Option<[u8; 16]> {
+        match self.classic {
+            true => Some(self.header_buf[4..].try_into().unwrap()),
+            false => None,
+        }
+    }
+
+    /// Returns any bytes in the buffer beyond the message length given in the header.
+    ///
+    /// These can only be present if [allow_trailing_bytes](DecodeOptions::allow_trailing_bytes)
+    /// was set, and are otherwise ignored by the decoder. A non-empty result may indicate that
+    /// several messages were coalesced into one datagram, or that the datagram is bogus.
+    pub fn trailing_bytes(&self) -> &'a [u8] {
+        self.trailing_buf
+    }
+
+    /// Returns a multi-line, human-readable report of the message, listing its header and each of
+    /// its attributes, for logging and debugging. See [MessageReport].
+    pub fn report(&self) -> MessageReport<'_, 'a> {
+        MessageReport::new(self)
+    }
+
     /// Returns an iterator that can be used to iterate over all of the attributes of the STUN
     /// message.
     ///
@@ -337,7 +714,267 @@ impl<'a> StunDecoder<'a> {
     /// the datagram encoded into the byte slice was incorrectly encoded.
     pub fn attributes(&self) -> StunAttributeIterator<'a> {
         StunAttributeIterator {
-            data: self.attribute_buf,
+            offset: STUN_HEADER_BYTES,
+            ..StunAttributeIterator::with_options(self.attribute_buf, self.options)
+        }
+    }
+
+    /// Returns an iterator that decodes every attribute of the message as a [KnownAttribute],
+    /// without needing to choose a decoder for each attribute type.
+    ///
+    /// Attributes of types that are not known are yielded as [KnownAttribute::Unknown]. As with
+    /// [attributes()](Self::attributes), if the attributes of the message cannot be decoded, an
+    /// [AttributeError::Message] is yielded and the iteration ends; if the value of a known
+    /// attribute type is invalid, an [AttributeError::Decode] is yielded for that attribute.
+    ///
+    /// ```
+    /// # use stunne_protocol::{attributes::KnownAttribute, *};
+    /// # let address = "192.0.2.1:3478".parse().unwrap();
+    /// let bytes = MessageBuilder::binding_success_response(TransactionId::random())
+    ///     .xor_mapped_address(address)
+    ///     .software("Widget, Inc.")
+    ///     .to_bytes()
+    ///     .unwrap();
+    /// let message = StunDecoder::new(&bytes).unwrap();
+    ///
+    /// let attributes: Vec<_> = message.typed_attributes().map(Result::unwrap).collect();
+    /// assert_eq!(
+    ///     attributes,
+    ///     [
+    ///         KnownAttribute::XorMappedAddress(address),
+    ///         KnownAttribute::Software("Widget, Inc."),
+    ///     ]
+    /// );
+    /// ```
+    pub fn typed_attributes(
+        &self,
+    ) -> impl Iterator<Item = Result<KnownAttribute<'a>, AttributeError<InvalidAttributeValue>>>
+    {
+        let tx_id = self.tx_id();
+        self.attributes().map(move |attribute| {
+            let attribute = attribute.map_err(AttributeError::Message)?;
+            KnownAttribute::decode(&attribute, tx_id).map_err(AttributeError::Decode)
+        })
+    }
+
+    /// Finds the first attribute of the given type and decodes it using the given decoder.
+    ///
+    /// Returns `None` if the message does not contain an attribute of the given type. If the
+    /// attributes of the message could not be decoded before a matching attribute was found, an
+    /// [AttributeError::Message] is returned.
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use stunne_protocol::{attributes::types::SOFTWARE, encodings::SoftwareDecoder, *};
+    /// # let bytes = StunEncoder::new(BytesMut::new())
+    /// #     .encode_header(MessageHeader {
+    /// #         class: MessageClass::Request,
+    /// #         method: MessageMethod::BINDING,
+    /// #         tx_id: TransactionId::random(),
+    /// #     })
+    /// #     .add_attribute(SOFTWARE, &"Widget, Inc.")
+    /// #     .finish();
+    /// let message = StunDecoder::new(&bytes).unwrap();
+    /// let software = message.get_attribute(SOFTWARE, &SoftwareDecoder);
+    /// assert_eq!(software.unwrap().unwrap().as_str(), "Widget, Inc.");
+    /// ```
+    pub fn get_attribute<T: AttributeDecoder<'a>>(
+        &self,
+        attribute_type: u16,
+        decoder: &T,
+    ) -> Option<Result<T::Item, AttributeError<T::Error>>> {
+        self.get_all(attribute_type, decoder).next()
+    }
+
+    /// Returns an iterator that decodes every attribute of the given type using the given decoder,
+    /// in the order they appear in the message.
+    ///
+    /// As with [attributes()](Self::attributes), if the attributes of the message cannot be
+    /// decoded, an [AttributeError::Message] is yielded and the iteration ends.
+    pub fn get_all<'d, T: AttributeDecoder<'a>>(
+        &self,
+        attribute_type: u16,
+        decoder: &'d T,
+    ) -> impl Iterator<Item = Result<T::Item, AttributeError<T::Error>>> + 'd
+    where
+        'a: 'd,
+    {
+        self.attributes()
+            .filter(move |attribute| match attribute {
+                Ok(attribute) => attribute.attribute_type() == attribute_type,
+                Err(_) => true,
+            })
+            .map(
+                move |attribute: Result<StunAttribute<'a>, MessageDecodeError>| {
+                    attribute
+                        .map_err(AttributeError::Message)?
+                        .decode(decoder)
+                        .map_err(AttributeError::Decode)
+                },
+            )
+    }
+
+    /// Checks that no attribute appears more than once, unless the attribute type may be repeated
+    /// (see [is_repeatable](attributes::types::is_repeatable)).
+    ///
+    /// The decoder itself yields every attribute, so an application that looks up an attribute
+    /// with [get_attribute](Self::get_attribute) uses the first occurrence and never sees the
+    /// others. Calling this first lets such messages be rejected instead.
+    ///
+    /// ```
+    /// # use stunne_protocol::{attributes::types::SOFTWARE, errors::MessageDecodeError, *};
+    /// let bytes = MessageBuilder::binding_request()
+    ///     .software("first")
+    ///     .software("second")
+    ///     .to_bytes()
+    ///     .unwrap();
+    /// let message = StunDecoder::new(&bytes).unwrap();
+    /// assert_eq!(
+    ///     message.check_duplicates(),
+    ///     Err(MessageDecodeError::DuplicateAttribute { offset: 32, attribute_type: SOFTWARE })
+    /// );
+    /// ```
+    pub fn check_duplicates(&self) -> Result<(), MessageDecodeError> {
+        let mut attributes = self.attributes();
+        let mut index = 0;
+        loop {
+            let offset = attributes.offset;
+            let attribute_type = match attributes.next() {
+                Some(attribute) => attribute?.attribute_type(),
+                None => return Ok(()),
+            };
+            if !is_repeatable(attribute_type)
+                && self
+                    .attributes()
+                    .take(index)
+                    .any(|earlier| earlier.is_ok_and(|a| a.attribute_type() == attribute_type))
+            {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(
+                    offset,
+                    attribute_type = %attributes::types::DisplayAttributeType(attribute_type),
+                    "duplicate STUN attribute"
+                );
+                return Err(MessageDecodeError::DuplicateAttribute {
+                    offset,
+                    attribute_type,
+                });
+            }
+            index += 1;
+        }
+    }
+
+    /// Verifies the MESSAGE-INTEGRITY attribute of the message using the given key.
+    ///
+    /// The HMAC is recalculated over the header and the attributes preceding the
+    /// MESSAGE-INTEGRITY attribute, with the header's length adjusted as [described in RFC
+    /// 5389][]. Any attributes after the MESSAGE-INTEGRITY attribute (e.g., FINGERPRINT) are not
+    /// covered by the HMAC, and so are ignored here.
+    ///
+    /// [described in RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.4
+    pub fn verify_integrity(&self, key: &[u8]) -> Result<(), IntegrityError> {
+        let mut attributes = self.attributes();
+        while let Some(attribute) = attributes.next() {
+            let attribute = attribute.map_err(IntegrityError::Decode)?;
+            if attribute.attribute_type() != MESSAGE_INTEGRITY {
+                continue;
+            }
+
+            let integrity = attribute
+                .decode(&MessageIntegrityDecoder)
+                .map_err(IntegrityError::InvalidAttribute)?;
+
+            // The iterator has advanced to just past the MESSAGE-INTEGRITY attribute.
+            let end_of_integrity = self.attribute_buf.len() - attributes.data.len();
+            let covered_attributes = &self.attribute_buf
+                [..end_of_integrity - ATTRIBUTE_HEADER_BYTES - MESSAGE_INTEGRITY_BYTES];
+
+            let mut header = *self.header_buf;
+            header[2..4].copy_from_slice(&(end_of_integrity as u16).to_be_bytes());
+
+            return match integrity.verify(key, &[&header, covered_attributes]) {
+                true => Ok(()),
+                false => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(
+                        offset = STUN_HEADER_BYTES + end_of_integrity - MESSAGE_INTEGRITY_BYTES,
+                        "STUN MESSAGE-INTEGRITY mismatch"
+                    );
+                    Err(IntegrityError::Mismatch)
+                }
+            };
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("STUN MESSAGE-INTEGRITY missing");
+        Err(IntegrityError::MissingAttribute)
+    }
+
+    /// Verifies the FINGERPRINT attribute of the message, which must be its last attribute.
+    ///
+    /// To verify many messages, a [FingerprintVerifier] can be reused instead.
+    pub fn verify_fingerprint(&self) -> Result<(), FingerprintError> {
+        FingerprintVerifier::new().verify_decoded(self)
+    }
+
+    /// Splits the attributes of the message at the first MESSAGE-INTEGRITY or
+    /// MESSAGE-INTEGRITY-SHA256 attribute, returning an iterator over the attributes covered by
+    /// its HMAC and an iterator over the attributes that follow it. The integrity attribute itself
+    /// is in neither.
+    ///
+    /// Apart from FINGERPRINT (and MESSAGE-INTEGRITY-SHA256 following MESSAGE-INTEGRITY), the
+    /// attributes following the integrity attribute [must be ignored][]: they could have been
+    /// added by anyone, without the key. An application that authenticates messages should only
+    /// read attributes from the first iterator.
+    ///
+    /// Returns `None` if the message has no integrity attribute. If the attributes could not be
+    /// decoded up to the integrity attribute, the error is returned.
+    ///
+    /// ```
+    /// # use bytes::BytesMut;
+    /// # use stunne_protocol::{attributes::types::*, *};
+    /// let bytes = MessageBuilder::binding_request()
+    ///     .software("Widget, Inc.")
+    ///     .to_bytes()
+    ///     .unwrap();
+    /// let bytes = StunEncoder::new(BytesMut::from(&bytes[..]))
+    ///     .resume()
+    ///     .unwrap()
+    ///     .add_message_integrity(b"secret")
+    ///     .add_attribute(USERNAME, &"mallory")
+    ///     .finish_with_fingerprint();
+    ///
+    /// let message = StunDecoder::new(&bytes).unwrap();
+    /// let (covered, after) = message.split_at_integrity().unwrap().unwrap();
+    /// let types = |attributes: attributes::StunAttributeIterator| {
+    ///     attributes.map(|a| a.unwrap().attribute_type()).collect::<Vec<_>>()
+    /// };
+    /// assert_eq!(types(covered), [SOFTWARE]);
+    /// assert_eq!(types(after), [USERNAME, FINGERPRINT]);
+    /// ```
+    ///
+    /// [must be ignored]: https://datatracker.ietf.org/doc/html/rfc5389#section-15.4
+    pub fn split_at_integrity(
+        &self,
+    ) -> Result<Option<(StunAttributeIterator<'a>, StunAttributeIterator<'a>)>, MessageDecodeError>
+    {
+        let mut attributes = self.attributes();
+        loop {
+            let start = attributes.offset - STUN_HEADER_BYTES;
+            let attribute = match attributes.next() {
+                Some(attribute) => attribute?,
+                None => return Ok(None),
+            };
+            if matches!(
+                attribute.attribute_type(),
+                MESSAGE_INTEGRITY | MESSAGE_INTEGRITY_SHA256
+            ) {
+                let covered = StunAttributeIterator {
+                    data: &self.attribute_buf[..start],
+                    ..self.attributes()
+                };
+                return Ok(Some((covered, attributes)));
+            }
         }
     }
 }
@@ -345,6 +982,8 @@ impl<'a> StunDecoder<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytes::BytesMut;
+    use encodings::{Fingerprint, MessageIntegrity};
 
     #[test]
     fn encode_simple_message() {
@@ -402,6 +1041,564 @@ mod tests {
         assert_eq!(&finished_buf[20..], &expected_bytes);
     }
 
+    #[test]
+    fn encode_message_integrity() {
+        let buf = BytesMut::new();
+        let key = b"secret";
+        let finished_buf = StunEncoder::new(buf)
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .add_message_integrity(key)
+            .finish();
+
+        // Header, 12 bytes for the first attribute, and 24 bytes for MESSAGE-INTEGRITY.
+        assert_eq!(finished_buf.len(), 20 + 12 + 24);
+        assert_eq!(&finished_buf[2..4], &[0, 36]);
+
+        #[rustfmt::skip]
+        let expected_attribute_header = [
+            0x00, 0x08, // MESSAGE-INTEGRITY
+            0, 20,      // Length
+        ];
+        assert_eq!(&finished_buf[32..36], &expected_attribute_header);
+
+        // As MESSAGE-INTEGRITY is the final attribute, the length in the header is already the
+        // one used for the HMAC.
+        let expected = MessageIntegrity::calculate(key, &[&finished_buf[..32]]);
+        assert_eq!(&finished_buf[36..], &expected.hmac);
+    }
+
+    #[test]
+    fn encode_message_integrity_before_other_attributes() {
+        let buf = BytesMut::new();
+        let key = b"secret";
+        let finished_buf = StunEncoder::new(buf)
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_message_integrity(key)
+            .add_attribute(0x8022, &"test1")
+            .finish();
+
+        // The HMAC is calculated with a length that only goes up to the end of the
+        // MESSAGE-INTEGRITY attribute, rather than the full length in the final message.
+        assert_eq!(&finished_buf[2..4], &[0, 36]);
+        let mut header = finished_buf[..20].to_vec();
+        header[2..4].copy_from_slice(&[0, 24]);
+        let expected = MessageIntegrity::calculate(key, &[&header]);
+        assert_eq!(&finished_buf[24..44], &expected.hmac);
+    }
+
+    use test_vectors::sample_ipv4_response::BYTES as RFC5769_SAMPLE_IPV4_RESPONSE;
+    use test_vectors::sample_request::BYTES as RFC5769_SAMPLE_REQUEST;
+    const RFC5769_SHORT_TERM_PASSWORD: &[u8] = test_vectors::SAMPLE_PASSWORD.as_bytes();
+
+    #[test]
+    fn verify_integrity_of_rfc5769_samples() {
+        for sample in [
+            &RFC5769_SAMPLE_REQUEST[..],
+            &RFC5769_SAMPLE_IPV4_RESPONSE[..],
+        ] {
+            let message = StunDecoder::new(sample).unwrap();
+            assert_eq!(
+                message.verify_integrity(RFC5769_SHORT_TERM_PASSWORD),
+                Ok(())
+            );
+            assert_eq!(
+                message.verify_integrity(b"wrong password"),
+                Err(IntegrityError::Mismatch)
+            );
+        }
+    }
+
+    #[test]
+    fn verify_integrity_of_tampered_message() {
+        let mut tampered = RFC5769_SAMPLE_IPV4_RESPONSE;
+        // Change the last character of the SOFTWARE attribute.
+        tampered[31] = 0x21;
+        let message = StunDecoder::new(&tampered).unwrap();
+        assert_eq!(
+            message.verify_integrity(RFC5769_SHORT_TERM_PASSWORD),
+            Err(IntegrityError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn verify_integrity_of_encoded_message() {
+        let key = b"secret";
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .add_message_integrity(key)
+            .add_attribute(0x8028, &"ignored")
+            .finish();
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(message.verify_integrity(key), Ok(()));
+    }
+
+    #[test]
+    fn verify_integrity_without_attribute() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .finish();
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(
+            message.verify_integrity(b"secret"),
+            Err(IntegrityError::MissingAttribute)
+        );
+    }
+
+    #[test]
+    fn fingerprint_of_rfc5769_samples() {
+        for sample in [
+            &RFC5769_SAMPLE_REQUEST[..],
+            &RFC5769_SAMPLE_IPV4_RESPONSE[..],
+        ] {
+            let (message, fingerprint) = sample.split_at(sample.len() - 8);
+            let expected = u32::from_be_bytes(fingerprint[4..].try_into().unwrap());
+            assert_eq!(Fingerprint::calculate(&[message]).value, expected);
+        }
+    }
+
+    #[test]
+    fn encode_fingerprint() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .add_message_integrity(b"secret")
+            .finish_with_fingerprint();
+
+        // Header, 12 bytes for SOFTWARE, 24 for MESSAGE-INTEGRITY, and 8 for FINGERPRINT.
+        assert_eq!(bytes.len(), 20 + 12 + 24 + 8);
+        assert_eq!(&bytes[2..4], &[0, 44]);
+
+        let (message, fingerprint) = bytes.split_at(bytes.len() - 8);
+        assert_eq!(&fingerprint[..4], &[0x80, 0x28, 0, 4]);
+        let expected = Fingerprint::calculate(&[message]);
+        assert_eq!(fingerprint[4..], expected.value.to_be_bytes());
+
+        // Adding the fingerprint must not disturb the integrity of the message.
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(message.verify_integrity(b"secret"), Ok(()));
+    }
+
+    #[test]
+    fn get_attribute_by_type() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"first")
+            .add_attribute(0x0006, &"user")
+            .add_attribute(0x8022, &"second")
+            .finish();
+
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(
+            message.get_attribute(0x0006, &encodings::Utf8Decoder),
+            Some(Ok("user"))
+        );
+        assert_eq!(
+            message.get_attribute(0x8022, &encodings::Utf8Decoder),
+            Some(Ok("first"))
+        );
+        assert_eq!(message.get_attribute(0x0020, &encodings::Utf8Decoder), None);
+
+        let all: Vec<_> = message.get_all(0x8022, &encodings::Utf8Decoder).collect();
+        assert_eq!(all, vec![Ok("first"), Ok("second")]);
+    }
+
+    #[test]
+    fn split_attributes_at_integrity() {
+        use attributes::types::FINGERPRINT;
+
+        let message = StunDecoder::new(&RFC5769_SAMPLE_REQUEST).unwrap();
+        let (covered, after) = message.split_at_integrity().unwrap().unwrap();
+        let covered: Vec<_> = covered
+            .map(|attribute| attribute.unwrap().attribute_type())
+            .collect();
+        assert_eq!(covered, [0x8022, 0x0024, 0x8029, 0x0006]);
+        let after: Vec<_> = after
+            .map(|attribute| attribute.unwrap().attribute_type())
+            .collect();
+        assert_eq!(after, [FINGERPRINT]);
+
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .finish_with_fingerprint();
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert!(message.split_at_integrity().unwrap().is_none());
+    }
+
+    #[test]
+    fn check_duplicate_attributes() {
+        use attributes::types::{XOR_MAPPED_ADDRESS, XOR_PEER_ADDRESS};
+
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .add_attribute(0x8022, &"test1")
+            .add_attribute(XOR_PEER_ADDRESS, &0u64)
+            .add_attribute(XOR_PEER_ADDRESS, &0u64)
+            .add_attribute(0xC001, &0u32)
+            .add_attribute(0xC001, &0u32)
+            .finish();
+        assert_eq!(StunDecoder::new(&bytes).unwrap().check_duplicates(), Ok(()));
+
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(header)
+            .add_attribute(XOR_MAPPED_ADDRESS, &0u64)
+            .add_attribute(0x8022, &"test1")
+            .add_attribute(XOR_MAPPED_ADDRESS, &0u64)
+            .finish();
+        assert_eq!(
+            StunDecoder::new(&bytes).unwrap().check_duplicates(),
+            Err(MessageDecodeError::DuplicateAttribute {
+                offset: 44,
+                attribute_type: XOR_MAPPED_ADDRESS,
+            })
+        );
+    }
+
+    #[test]
+    fn get_attribute_errors() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test")
+            .finish();
+
+        // The value of the attribute is the wrong size to be decoded as MESSAGE-INTEGRITY.
+        let message = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(
+            message.get_attribute(0x8022, &MessageIntegrityDecoder),
+            Some(Err(AttributeError::Decode(
+                encodings::MessageIntegrityDecodeError::InvalidDataSize
+            )))
+        );
+
+        // Giving the attribute a length beyond the end of the message means that the attribute can
+        // no longer be found.
+        let mut corrupted = bytes.to_vec();
+        corrupted[22..24].copy_from_slice(&[0, 8]);
+        let message = StunDecoder::new(&corrupted).unwrap();
+        assert_eq!(
+            message.get_attribute(0x0006, &encodings::Utf8Decoder),
+            Some(Err(AttributeError::Message(
+                MessageDecodeError::UnexpectedEndOfData {
+                    offset: 24,
+                    attribute_type: Some(0x8022),
+                    needed: 8,
+                    available: 4,
+                }
+            )))
+        );
+    }
+
+    #[test]
+    fn resume_encoding() {
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let expected = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .add_attribute(0x8022, &"test1")
+            .add_message_integrity(b"secret")
+            .finish_with_fingerprint();
+
+        let partial = StunEncoder::new(BytesMut::new())
+            .encode_header(header)
+            .add_attribute(0x8022, &"test1")
+            .finish();
+        let resumed = StunEncoder::new(BytesMut::from(&partial[..]))
+            .resume()
+            .unwrap()
+            .add_message_integrity(b"secret")
+            .finish_with_fingerprint();
+        assert_eq!(resumed, expected);
+    }
+
+    #[test]
+    fn enforce_attribute_order() {
+        use attributes::types::{FINGERPRINT, MESSAGE_INTEGRITY_SHA256};
+        use errors::EncodeError;
+
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let encoder = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test1")
+            .unwrap()
+            .add_message_integrity(b"secret");
+        let result = encoder.try_add_attribute(0x8022, &"test2");
+        assert!(matches!(
+            result,
+            Err(EncodeError::InvalidOrder {
+                attribute_type: 0x8022,
+                preceding: MESSAGE_INTEGRITY,
+            })
+        ));
+
+        let partial = StunEncoder::new(BytesMut::new())
+            .encode_header(header)
+            .add_message_integrity(b"secret")
+            .add_attribute(MESSAGE_INTEGRITY_SHA256, &0u64)
+            .finish();
+        let resumed = StunEncoder::new(BytesMut::from(&partial[..]))
+            .resume()
+            .unwrap();
+        let result = resumed.try_add_attribute(MESSAGE_INTEGRITY, &0u64);
+        assert!(matches!(
+            result,
+            Err(EncodeError::InvalidOrder {
+                attribute_type: MESSAGE_INTEGRITY,
+                preceding: MESSAGE_INTEGRITY_SHA256,
+            })
+        ));
+
+        let resumed = StunEncoder::new(BytesMut::from(&partial[..]))
+            .resume()
+            .unwrap();
+        assert!(resumed.try_add_attribute(FINGERPRINT, &0u32).is_ok());
+    }
+
+    #[test]
+    fn enforce_max_size() {
+        use errors::EncodeError;
+
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let encoder = LimitedEncoder::new(BytesMut::new(), 36)
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test")
+            .unwrap();
+        assert_eq!(encoder.size(), 28);
+        let result = encoder.try_add_attribute(0x8022, &"test1");
+        assert!(matches!(
+            result,
+            Err(EncodeError::MessageTooLarge {
+                size: 40,
+                max_size: 36,
+            })
+        ));
+
+        let encoder = LimitedEncoder::new(BytesMut::new(), 36)
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test")
+            .unwrap();
+        assert_eq!(encoder.try_finish_with_fingerprint().unwrap().len(), 36);
+
+        let encoder = LimitedEncoder::new(BytesMut::new(), 32)
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test")
+            .unwrap();
+        assert!(matches!(
+            encoder.try_finish_with_fingerprint(),
+            Err(EncodeError::MessageTooLarge {
+                size: 36,
+                max_size: 32,
+            })
+        ));
+
+        // Every way of adding an attribute checks the size.
+        let encoder = LimitedEncoder::new(BytesMut::new(), 40)
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test")
+            .unwrap();
+        assert!(matches!(
+            encoder.try_add_message_integrity(b"key"),
+            Err(EncodeError::MessageTooLarge {
+                size: 52,
+                max_size: 40,
+            })
+        ));
+        let (message, encoder) = LimitedEncoder::new(BytesMut::new(), 52)
+            .encode_header(header)
+            .try_add_attribute(0x8022, &"test")
+            .unwrap()
+            .try_add_message_integrity(b"key")
+            .unwrap()
+            .finish_and_reset();
+        assert_eq!(message.len(), 52);
+        assert_eq!(encoder.max_size(), 52);
+    }
+
+    #[test]
+    fn enforce_length_limits() {
+        use encodings::Data;
+        use errors::EncodeError;
+
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let payload = vec![0; usize::from(u16::MAX) + 1];
+
+        let result = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &Data { payload: &payload });
+        assert!(matches!(
+            result,
+            Err(EncodeError::AttributeTooLarge {
+                attribute_type: 0x8022,
+                length: 65536,
+            })
+        ));
+
+        // The largest value fits in the attribute, but not in the message.
+        let result = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .try_add_attribute(
+                0x8022,
+                &Data {
+                    payload: &payload[1..],
+                },
+            );
+        assert!(matches!(
+            result,
+            Err(EncodeError::LengthOverflow { length: 65540 })
+        ));
+
+        let encoder = StunEncoder::new(BytesMut::new())
+            .encode_header(header)
+            .try_add_attribute(
+                0x8022,
+                &Data {
+                    payload: &payload[8..],
+                },
+            )
+            .unwrap();
+        assert_eq!(encoder.size(), 20 + 65532);
+        assert!(matches!(
+            encoder.try_finish_with_fingerprint(),
+            Err(EncodeError::LengthOverflow { length: 65540 })
+        ));
+    }
+
+    #[test]
+    fn reuse_encoder_buffer() {
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+        let expected = StunEncoder::new(BytesMut::new())
+            .encode_header(header.clone())
+            .add_attribute(0x8022, &"test")
+            .finish_with_fingerprint();
+
+        let buf = BytesMut::with_capacity(64);
+        let start = buf.as_ptr() as usize;
+        let mut encoder = StunEncoder::new(buf);
+        for _ in 0..10 {
+            let (bytes, next) = encoder
+                .encode_header(header.clone())
+                .add_attribute(0x8022, &"test")
+                .finish_with_fingerprint_and_reset();
+            encoder = next;
+            assert_eq!(bytes, expected);
+            // Every message is written into the original allocation.
+            let offset = bytes.as_ptr() as usize - start;
+            assert!(offset + bytes.len() <= 64);
+        }
+
+        let discarded = encoder
+            .encode_header(header.clone())
+            .add_attribute(0x8022, &"discarded")
+            .reset();
+        let bytes = discarded
+            .encode_header(header)
+            .add_attribute(0x8022, &"test")
+            .finish_with_fingerprint();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn resume_invalid_message() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test1")
+            .finish();
+
+        let result = StunEncoder::new(BytesMut::from(&bytes[..10])).resume();
+        assert!(matches!(
+            result,
+            Err(MessageDecodeError::UnexpectedEndOfData {
+                needed: 20,
+                available: 10,
+                ..
+            })
+        ));
+
+        let result = StunEncoder::new(BytesMut::from(&bytes[..24])).resume();
+        assert!(matches!(
+            result,
+            Err(MessageDecodeError::LengthExceedsSlice {
+                declared: 12,
+                available: 4
+            })
+        ));
+
+        let mut extended = BytesMut::from(&bytes[..]);
+        extended.extend_from_slice(&[0; 4]);
+        let result = StunEncoder::new(extended).resume();
+        assert!(matches!(
+            result,
+            Err(MessageDecodeError::SliceExceedsLength {
+                declared: 12,
+                available: 16
+            })
+        ));
+    }
+
     #[test]
     fn decode_simple_message() {
         #[rustfmt::skip]
@@ -432,14 +1629,320 @@ mod tests {
 
         for x in 0..20 {
             let bytes = &valid_bytes[0..x];
-            let result = StunDecoder::new(&bytes);
-            assert!(matches!(
-                result,
-                Err(MessageDecodeError::UnexpectedEndOfData)
-            ));
+            let result = StunDecoder::new(bytes);
+            assert_eq!(
+                result.err(),
+                Some(MessageDecodeError::UnexpectedEndOfData {
+                    offset: 0,
+                    attribute_type: None,
+                    needed: 20,
+                    available: x,
+                })
+            );
         }
     }
 
+    #[test]
+    fn fail_to_decode_mismatched_length() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test")
+            .finish();
+
+        assert!(matches!(
+            StunDecoder::new(&bytes[..bytes.len() - 4]),
+            Err(MessageDecodeError::LengthExceedsSlice {
+                declared: 8,
+                available: 4
+            })
+        ));
+
+        let mut extended = bytes.to_vec();
+        extended.extend_from_slice(&[0; 4]);
+        assert!(matches!(
+            StunDecoder::new(&extended),
+            Err(MessageDecodeError::SliceExceedsLength {
+                declared: 8,
+                available: 12
+            })
+        ));
+    }
+
+    #[test]
+    fn decode_trailing_bytes_with_options() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &"test")
+            .finish();
+        let mut extended = bytes.to_vec();
+        extended.extend_from_slice(&[0xff; 4]);
+
+        assert!(matches!(
+            StunDecoder::with_options(&extended, DecodeOptions::strict()),
+            Err(MessageDecodeError::SliceExceedsLength { .. })
+        ));
+
+        // The trailing bytes are not treated as part of the attributes.
+        let message = StunDecoder::with_options(&extended, DecodeOptions::lenient()).unwrap();
+        let attributes: Vec<_> = message.attributes().collect();
+        assert_eq!(attributes.len(), 1);
+        assert_eq!(attributes[0].as_ref().unwrap().data(), b"test");
+        assert_eq!(message.trailing_bytes(), &[0xff; 4]);
+
+        let message = StunDecoder::with_options(&bytes, DecodeOptions::lenient()).unwrap();
+        assert!(message.trailing_bytes().is_empty());
+    }
+
+    #[test]
+    fn decode_missing_final_padding_with_options() {
+        #[rustfmt::skip]
+        let bytes = [
+            0, 1, // Binding Request
+            0, 7, // Message Length, which is not a multiple of four
+            0x21, 0x12, 0xA4, 0x42, // Magic Cookie
+            1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, // Transaction ID
+            0x80, 0x22, 0, 3, // SOFTWARE attribute header
+            0x61, 0x62, 0x63, // Value, without padding
+        ];
+
+        assert!(matches!(
+            StunDecoder::new(&bytes),
+            Err(MessageDecodeError::UnalignedLength { declared: 7 })
+        ));
+
+        let message = StunDecoder::with_options(&bytes, DecodeOptions::lenient()).unwrap();
+        let attribute = message.attributes().next().unwrap().unwrap();
+        assert_eq!(attribute.data(), b"abc");
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn trace_decoding() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+
+        /// Records the message of every event.
+        struct Recorder(Arc<Mutex<Vec<String>>>);
+
+        struct MessageVisitor<'a>(&'a mut String);
+
+        impl Visit for MessageVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                if field.name() == "message" {
+                    *self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        impl tracing::Subscriber for Recorder {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &tracing::Event<'_>) {
+                let mut message = String::new();
+                event.record(&mut MessageVisitor(&mut message));
+                self.0.lock().unwrap().push(message);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        let mut bytes = RFC5769_SAMPLE_IPV4_RESPONSE;
+        // Make the FINGERPRINT value overrun the message.
+        bytes[75] = 0x10;
+        let events = Arc::new(Mutex::new(Vec::new()));
+        tracing::subscriber::with_default(Recorder(events.clone()), || {
+            assert!(StunDecoder::new(&bytes[..10]).is_err());
+            let message = StunDecoder::new(&bytes).unwrap();
+            assert_eq!(message.attributes().count(), 4);
+        });
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                "failed to decode STUN header",
+                "decoded STUN header",
+                "decoded STUN attribute",
+                "decoded STUN attribute",
+                "decoded STUN attribute",
+                "failed to decode STUN attribute",
+            ]
+        );
+    }
+
+    #[test]
+    fn decode_classic_message() {
+        #[rustfmt::skip]
+        let bytes = [
+            0x01, 0x01, // Binding Success Response
+            0, 12, // Length
+            0xde, 0xad, 0xbe, 0xef, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, // 128-bit Transaction ID
+            0x00, 0x01, 0, 8, // MAPPED-ADDRESS attribute header
+            0, 1, 0x1f, 0x40, 127, 0, 0, 1,
+        ];
+
+        assert!(matches!(
+            StunDecoder::new(&bytes),
+            Err(MessageDecodeError::InvalidMagicCookie)
+        ));
+
+        let options = DecodeOptions {
+            allow_classic: true,
+            ..DecodeOptions::default()
+        };
+        let message = StunDecoder::with_options(&bytes, options).unwrap();
+        assert!(message.is_classic());
+        assert_eq!(message.class(), MessageClass::SuccessResponse);
+        assert_eq!(message.method(), MessageMethod::BINDING);
+        assert_eq!(message.classic_tx_id().unwrap(), bytes[4..20]);
+        assert_eq!(message.tx_id().as_ref(), &bytes[8..20]);
+        assert_eq!(message.attributes().count(), 1);
+
+        // Modern messages are unaffected by the option.
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .finish();
+        let message = StunDecoder::with_options(&bytes, options).unwrap();
+        assert!(!message.is_classic());
+        assert_eq!(message.classic_tx_id(), None);
+    }
+
+    #[test]
+    fn detect_stun() {
+        assert!(is_stun(&RFC5769_SAMPLE_REQUEST));
+        assert!(is_stun(&RFC5769_SAMPLE_IPV4_RESPONSE));
+
+        // Too short
+        assert!(!is_stun(&RFC5769_SAMPLE_REQUEST[..19]));
+
+        // Non-zero starting bits, as in an RTP packet
+        let mut packet = RFC5769_SAMPLE_REQUEST;
+        packet[0] = 0x80;
+        assert!(!is_stun(&packet));
+
+        // Missing magic cookie
+        let mut packet = RFC5769_SAMPLE_REQUEST;
+        packet[4] = 0;
+        assert!(!is_stun(&packet));
+
+        // Length is not a multiple of four
+        let mut packet = RFC5769_SAMPLE_REQUEST;
+        packet[3] = 0x57;
+        assert!(!is_stun(&packet));
+
+        // Length is larger than the packet
+        assert!(!is_stun(&RFC5769_SAMPLE_REQUEST[..104]));
+    }
+
+    #[test]
+    fn random_tx_id_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let first = TransactionId::random_with(&mut StdRng::seed_from_u64(5389));
+        let second = TransactionId::random_with(&mut StdRng::seed_from_u64(5389));
+        assert_eq!(first, second);
+        assert_ne!(first, TransactionId::random());
+    }
+
+    #[test]
+    fn tx_id_formatting_and_parsing() {
+        let tx_id = TransactionId::from_bytes(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0xab, 0xff]);
+        assert_eq!(tx_id.to_string(), "00010203040506070809abff");
+        assert_eq!("00010203040506070809abff".parse(), Ok(tx_id));
+        assert_eq!("00010203040506070809ABFF".parse(), Ok(tx_id));
+
+        assert_eq!(
+            "00010203040506070809ab".parse::<TransactionId>(),
+            Err(TransactionIdError::InvalidLength)
+        );
+        assert_eq!(
+            "00010203040506070809abfg".parse::<TransactionId>(),
+            Err(TransactionIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            "+0010203040506070809abff".parse::<TransactionId>(),
+            Err(TransactionIdError::InvalidCharacter)
+        );
+        assert_eq!(
+            "0001020304050607080é9ab".parse::<TransactionId>(),
+            Err(TransactionIdError::InvalidCharacter)
+        );
+
+        assert_eq!(TransactionId::try_from(tx_id.as_ref()), Ok(tx_id));
+        assert_eq!(
+            TransactionId::try_from(&[0; 11][..]),
+            Err(TransactionIdError::InvalidLength)
+        );
+    }
+
+    #[test]
+    fn tx_id_as_map_key() {
+        let first = TransactionId::from_bytes(&[1; 12]);
+        let second = TransactionId::from_bytes(&[2; 12]);
+
+        let mut transactions = std::collections::HashMap::new();
+        transactions.insert(first, "first");
+        transactions.insert(second, "second");
+        assert_eq!(transactions[&first], "first");
+
+        let ordered: std::collections::BTreeSet<_> = [second, first].into_iter().collect();
+        assert_eq!(ordered.into_iter().collect::<Vec<_>>(), vec![first, second]);
+    }
+
+    #[test]
+    fn class_helpers() {
+        let classes = [
+            MessageClass::Request,
+            MessageClass::Indication,
+            MessageClass::SuccessResponse,
+            MessageClass::ErrorResponse,
+        ];
+        let responses: Vec<_> = classes.iter().map(|c| c.is_response()).collect();
+        assert_eq!(responses, [false, false, true, true]);
+        let errors: Vec<_> = classes.iter().map(|c| c.is_error()).collect();
+        assert_eq!(errors, [false, false, false, true]);
+        let expects: Vec<_> = classes.iter().map(|c| c.expects_response()).collect();
+        assert_eq!(expects, [true, false, false, false]);
+
+        assert!(MessageClass::Request.is_request());
+        assert!(MessageClass::Indication.is_indication());
+        assert!(MessageClass::SuccessResponse.is_success());
+        assert!(!MessageClass::ErrorResponse.is_success());
+    }
+
+    #[test]
+    fn method_names() {
+        assert_eq!(MessageMethod::BINDING.name(), Some("BINDING"));
+        assert_eq!(MessageMethod::CHANNEL_BIND.name(), Some("CHANNEL_BIND"));
+        assert_eq!(MessageMethod(0x0FFF).name(), None);
+
+        assert_eq!(format!("{}", MessageMethod::ALLOCATE), "ALLOCATE");
+        assert_eq!(format!("{:<8}|", MessageMethod::SEND), "SEND    |");
+        assert_eq!(format!("{}", MessageMethod(0x0FFF)), "0xFFF");
+        assert_eq!(format!("{:?}", MessageMethod::REFRESH), "REFRESH");
+        assert_eq!(
+            format!("{:?}", MessageMethod(0x0002)),
+            "MessageMethod(0x002)"
+        );
+    }
+
     #[test]
     fn fail_to_decode_invalid_header() {
         #[rustfmt::skip]
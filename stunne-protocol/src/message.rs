@@ -0,0 +1,640 @@
+use crate::encodings::{AttributeDecoder, AttributeEncoder, Data};
+use crate::errors::MessageDecodeError;
+#[cfg(feature = "std")]
+use crate::errors::ReadError;
+#[cfg(feature = "arbitrary")]
+use crate::utils::padding_for_attribute_length;
+use crate::STUN_HEADER_BYTES;
+use crate::{DecodeOptions, MessageHeader, StunDecoder, StunEncoder, ATTRIBUTE_HEADER_BYTES};
+use alloc::vec::Vec;
+use bytes::{Bytes, BytesMut};
+use smallvec::SmallVec;
+
+/// The number of bytes of an attribute's value that are stored without allocating. This is enough
+/// for most fixed-size values (e.g., an IPv6 XOR-MAPPED-ADDRESS, or a MESSAGE-INTEGRITY).
+const INLINE_VALUE_BYTES: usize = 20;
+
+/// The number of attributes of a message that are stored without allocating.
+const INLINE_ATTRIBUTES: usize = 8;
+
+/// An attribute whose value is owned, rather than borrowed from a datagram buffer.
+///
+/// The value is stored exactly as it would be encoded, without padding. Values of up to 20 bytes
+/// are stored inline, without allocating.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedAttribute {
+    attribute_type: u16,
+    value: SmallVec<[u8; INLINE_VALUE_BYTES]>,
+}
+
+impl OwnedAttribute {
+    /// Create an attribute from an already-encoded value.
+    pub fn new(attribute_type: u16, value: Vec<u8>) -> Self {
+        Self {
+            attribute_type,
+            value: SmallVec::from_vec(value),
+        }
+    }
+
+    /// Create an attribute by copying an already-encoded value.
+    pub fn from_slice(attribute_type: u16, value: &[u8]) -> Self {
+        Self {
+            attribute_type,
+            value: SmallVec::from_slice(value),
+        }
+    }
+
+    /// Create an attribute by encoding the value using the given encoder.
+    pub fn encode<T: AttributeEncoder>(attribute_type: u16, encoder: &T) -> Self {
+        let mut buf = BytesMut::new();
+        encoder.encode(&mut buf);
+        Self::from_slice(attribute_type, &buf)
+    }
+
+    pub fn attribute_type(&self) -> u16 {
+        self.attribute_type
+    }
+
+    /// Returns the raw bytes of the attribute's value, excluding any padding.
+    pub fn data(&self) -> &[u8] {
+        &self.value
+    }
+
+    pub fn decode<'a, T: AttributeDecoder<'a>>(&'a self, decoder: &T) -> Result<T::Item, T::Error> {
+        decoder.decode(&self.value)
+    }
+}
+
+/// An owned STUN message, consisting of a header and a list of attributes.
+///
+/// Unlike [StunDecoder], which borrows the datagram it decodes, and [StunEncoder], which writes
+/// straight into a buffer, a `StunMessage` can be constructed, stored, queued, and modified
+/// freely before being encoded with [to_bytes](Self::to_bytes).
+///
+/// Up to eight attributes are stored inline, so a typical message can be decoded with
+/// [from_bytes](Self::from_bytes) without allocating.
+///
+/// ```
+/// # use stunne_protocol::{attributes::types::SOFTWARE, encodings::Utf8Decoder, *};
+/// let mut message = StunMessage::new(MessageHeader {
+///     class: MessageClass::Request,
+///     method: MessageMethod::BINDING,
+///     tx_id: TransactionId::random(),
+/// });
+/// message.add_attribute(SOFTWARE, &"Widget, Inc.");
+///
+/// let bytes = message.to_bytes();
+/// let decoded = StunMessage::from_bytes(&bytes).unwrap();
+/// assert_eq!(decoded, message);
+/// let software = decoded.attribute(SOFTWARE).unwrap();
+/// assert_eq!(software.decode(&Utf8Decoder).unwrap(), "Widget, Inc.");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StunMessage {
+    header: MessageHeader,
+    attributes: SmallVec<[OwnedAttribute; INLINE_ATTRIBUTES]>,
+}
+
+impl StunMessage {
+    /// Create a message with the given header and no attributes.
+    pub fn new(header: MessageHeader) -> Self {
+        Self {
+            header,
+            attributes: SmallVec::new(),
+        }
+    }
+
+    /// Decode a message from the given bytes, copying all of its attributes.
+    ///
+    /// Unlike [StunDecoder::new], this will return an error if any of the attributes cannot be
+    /// read.
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, MessageDecodeError> {
+        StunMessage::try_from(&StunDecoder::new(buf)?)
+    }
+
+    /// Encode the message into bytes ready to be sent to a socket.
+    pub fn to_bytes(&self) -> Bytes {
+        let mut encoder = StunEncoder::new(BytesMut::new()).encode_header(self.header.clone());
+        for attribute in &self.attributes {
+            encoder = encoder.add_attribute(
+                attribute.attribute_type,
+                &Data {
+                    payload: &attribute.value,
+                },
+            );
+        }
+        encoder.finish()
+    }
+
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    pub fn header_mut(&mut self) -> &mut MessageHeader {
+        &mut self.header
+    }
+
+    /// Returns all attributes of the message, in the order they will be encoded.
+    pub fn attributes(&self) -> &[OwnedAttribute] {
+        &self.attributes
+    }
+
+    /// Returns the first attribute of the given type, if any.
+    pub fn attribute(&self, attribute_type: u16) -> Option<&OwnedAttribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.attribute_type == attribute_type)
+    }
+
+    /// Encode the value using the given encoder, and append it as an attribute of the message.
+    pub fn add_attribute<T: AttributeEncoder>(&mut self, attribute_type: u16, encoder: &T) {
+        self.push_attribute(OwnedAttribute::encode(attribute_type, encoder));
+    }
+
+    /// Append an already-built attribute to the message.
+    pub fn push_attribute(&mut self, attribute: OwnedAttribute) {
+        self.attributes.push(attribute);
+    }
+
+    /// Removes all attributes of the given type, returning the number removed.
+    pub fn remove_attributes(&mut self, attribute_type: u16) -> usize {
+        let before = self.attributes.len();
+        self.attributes
+            .retain(|attribute| attribute.attribute_type != attribute_type);
+        before - self.attributes.len()
+    }
+
+    /// Decodes a message and encodes it again, returning where the encoded bytes first differ
+    /// from the original, or `None` if they are identical.
+    ///
+    /// This shows whether a message would be changed by passing through this crate (e.g., when
+    /// acting as a proxy), which is useful when testing against other implementations. Bytes can
+    /// differ where the original is not in the form this crate would encode it (e.g., when the
+    /// padding of an attribute is not zero).
+    ///
+    /// ```
+    /// # use stunne_protocol::*;
+    /// let mut bytes = MessageBuilder::binding_request()
+    ///     .software("test")
+    ///     .to_bytes()
+    ///     .unwrap()
+    ///     .to_vec();
+    /// assert_eq!(StunMessage::check_round_trip(&bytes), Ok(None));
+    ///
+    /// // Add another SOFTWARE attribute, whose padding is not zero.
+    /// bytes.extend_from_slice(&[0x80, 0x22, 0, 1, b'x', 0xff, 0xff, 0xff]);
+    /// bytes[3] += 8;
+    /// let divergence = StunMessage::check_round_trip(&bytes).unwrap().unwrap();
+    /// assert_eq!(divergence.offset, 33);
+    /// assert_eq!(divergence.attribute_type, Some(0x8022));
+    /// ```
+    pub fn check_round_trip(buf: &[u8]) -> Result<Option<Divergence>, MessageDecodeError> {
+        let decoder = StunDecoder::new(buf)?;
+        let encoded = StunMessage::try_from(&decoder)?.to_bytes();
+        let offset = match buf.iter().zip(encoded.iter()).position(|(a, b)| a != b) {
+            Some(offset) => offset,
+            None if buf.len() == encoded.len() => return Ok(None),
+            None => buf.len().min(encoded.len()),
+        };
+
+        let mut attribute_type = None;
+        let mut end = STUN_HEADER_BYTES;
+        for attribute in decoder.attributes() {
+            let attribute = attribute?;
+            end += ATTRIBUTE_HEADER_BYTES + attribute.padded_length();
+            if offset < end {
+                attribute_type = Some(attribute.attribute_type());
+                break;
+            }
+        }
+
+        Ok(Some(Divergence {
+            offset,
+            attribute_type,
+            original: buf.get(offset).copied(),
+            encoded: encoded.get(offset).copied(),
+        }))
+    }
+}
+
+/// Generates values that fit in the 16-bit length of an attribute.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for OwnedAttribute {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let attribute_type = u.arbitrary()?;
+        let len = u.arbitrary_len::<u8>()?.min(usize::from(u16::MAX));
+        Ok(Self::from_slice(attribute_type, u.bytes(len)?))
+    }
+}
+
+/// Generates messages whose attributes fit in the 16-bit length of the header, so that every
+/// message can be encoded.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for StunMessage {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut message = StunMessage::new(u.arbitrary()?);
+        let mut length = 0;
+        for attribute in u.arbitrary_iter::<OwnedAttribute>()? {
+            let attribute = attribute?;
+            let value_length = attribute.value.len();
+            length +=
+                ATTRIBUTE_HEADER_BYTES + value_length + padding_for_attribute_length(value_length);
+            if length > usize::from(u16::MAX) {
+                break;
+            }
+            message.push_attribute(attribute);
+        }
+        Ok(message)
+    }
+}
+
+/// Where a message first differs from the original when decoded and encoded again, as returned by
+/// [StunMessage::check_round_trip].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The offset of the first byte that differs, from the start of the message.
+    pub offset: usize,
+
+    /// The type of the attribute containing the differing byte in the original message, or `None`
+    /// if it is in the header.
+    pub attribute_type: Option<u16>,
+
+    /// The byte in the original message, or `None` if the original is shorter.
+    pub original: Option<u8>,
+
+    /// The byte in the encoded message, or `None` if the encoded message is shorter.
+    pub encoded: Option<u8>,
+}
+
+impl TryFrom<&StunDecoder<'_>> for StunMessage {
+    type Error = MessageDecodeError;
+
+    fn try_from(decoder: &StunDecoder<'_>) -> Result<Self, Self::Error> {
+        let attributes = decoder
+            .attributes()
+            .map(|attribute| {
+                attribute.map(|attribute| {
+                    OwnedAttribute::from_slice(attribute.attribute_type(), attribute.data())
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            header: decoder.header().clone(),
+            attributes,
+        })
+    }
+}
+
+/// A decoded message that owns its buffer, rather than borrowing it as [StunDecoder] does.
+///
+/// The bytes are validated once, when the message is created, and a [StunDecoder] can then be
+/// borrowed from it at any time with [decoder](Self::decoder). Since the message has no lifetime,
+/// it can be held across `await` points, sent between threads, or queued, without copying each
+/// attribute as converting to a [StunMessage] would. Unlike a [StunMessage], the original bytes
+/// are kept, so that e.g. MESSAGE-INTEGRITY can still be verified.
+///
+/// ```
+/// # use stunne_protocol::{attributes::types::SOFTWARE, encodings::SoftwareDecoder, *};
+/// let bytes = MessageBuilder::binding_request().software("Widget, Inc.").to_bytes().unwrap();
+/// let message = OwnedDecoder::new(bytes).unwrap();
+///
+/// std::thread::spawn(move || {
+///     assert_eq!(message.header().class, MessageClass::Request);
+///     let software = message.decoder().get_attribute(SOFTWARE, &SoftwareDecoder);
+///     assert_eq!(software.unwrap().unwrap().as_str(), "Widget, Inc.");
+/// })
+/// .join()
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct OwnedDecoder {
+    buf: Bytes,
+    header: MessageHeader,
+    options: DecodeOptions,
+}
+
+impl OwnedDecoder {
+    /// Validate the message as with [StunDecoder::new], taking ownership of the bytes.
+    pub fn new(buf: impl Into<Bytes>) -> Result<Self, MessageDecodeError> {
+        Self::with_options(buf, DecodeOptions::default())
+    }
+
+    /// Validate the message as with [StunDecoder::with_options], taking ownership of the bytes.
+    pub fn with_options(
+        buf: impl Into<Bytes>,
+        options: DecodeOptions,
+    ) -> Result<Self, MessageDecodeError> {
+        let buf = buf.into();
+        let header = StunDecoder::with_options(&buf, options)?.header().clone();
+        Ok(Self {
+            buf,
+            header,
+            options,
+        })
+    }
+
+    /// Reads a single message from a stream transport (e.g., TCP or TLS), blocking until it has
+    /// been received.
+    ///
+    /// Exactly the header, and then the number of bytes given by the length in the header, are
+    /// read, so that the reader is left at the start of the next message. If the stream ends
+    /// before the whole message is read, an [Io](ReadError::Io) error with the kind
+    /// `UnexpectedEof` is returned.
+    ///
+    /// To read from a non-blocking transport, see [StreamDecoder](crate::StreamDecoder).
+    ///
+    /// ```no_run
+    /// # use stunne_protocol::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut stream = std::net::TcpStream::connect("stun.example.org:3478")?;
+    /// let message = OwnedDecoder::read_from(&mut stream)?;
+    /// println!("{:?}", message.header());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "std")]
+    pub fn read_from(mut reader: impl std::io::Read) -> Result<Self, ReadError> {
+        let mut header_buf = [0; STUN_HEADER_BYTES];
+        reader.read_exact(&mut header_buf)?;
+        let (_header, length) = MessageHeader::decode_with_length(&header_buf)?;
+
+        let mut buf = Vec::with_capacity(STUN_HEADER_BYTES + usize::from(length));
+        buf.extend_from_slice(&header_buf);
+        buf.resize(STUN_HEADER_BYTES + usize::from(length), 0);
+        reader.read_exact(&mut buf[STUN_HEADER_BYTES..])?;
+        Ok(Self::new(buf)?)
+    }
+
+    /// Returns a decoder that borrows the message's bytes.
+    pub fn decoder(&self) -> StunDecoder<'_> {
+        StunDecoder::with_options(&self.buf, self.options)
+            .expect("the message was validated when it was created")
+    }
+
+    /// Returns the decoded message header.
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    /// Returns the bytes of the message.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the bytes of the message, giving up ownership of them.
+    pub fn into_bytes(self) -> Bytes {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::types::{SOFTWARE, USERNAME};
+    use crate::encodings::Utf8Decoder;
+    use crate::{MessageClass, MessageMethod, TransactionId};
+
+    fn header() -> MessageHeader {
+        MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+        }
+    }
+
+    #[test]
+    fn test_matches_encoder() {
+        let mut message = StunMessage::new(header());
+        message.add_attribute(SOFTWARE, &"test1");
+        message.add_attribute(USERNAME, &"user");
+
+        let expected = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(SOFTWARE, &"test1")
+            .add_attribute(USERNAME, &"user")
+            .finish();
+        assert_eq!(message.to_bytes(), expected);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut message = StunMessage::new(header());
+        message.add_attribute(SOFTWARE, &"test1");
+        message.push_attribute(OwnedAttribute::new(0x8050, vec![1, 2, 3]));
+
+        let decoded = StunMessage::from_bytes(&message.to_bytes()).unwrap();
+        assert_eq!(decoded, message);
+        assert_eq!(decoded.attributes()[1].data(), &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_round_trip_check() {
+        let mut message = StunMessage::new(header());
+        message.add_attribute(SOFTWARE, &"test1");
+        message.add_attribute(USERNAME, &"user");
+        let mut bytes = message.to_bytes().to_vec();
+        assert_eq!(StunMessage::check_round_trip(&bytes), Ok(None));
+
+        // The padding of the SOFTWARE attribute.
+        bytes[31] = 1;
+        assert_eq!(
+            StunMessage::check_round_trip(&bytes),
+            Ok(Some(Divergence {
+                offset: 31,
+                attribute_type: Some(SOFTWARE),
+                original: Some(1),
+                encoded: Some(0),
+            }))
+        );
+
+        assert_eq!(
+            StunMessage::check_round_trip(&bytes[..19]),
+            Err(MessageDecodeError::header_too_short(19))
+        );
+    }
+
+    #[test]
+    fn test_inline_storage() {
+        let mut message = StunMessage::new(header());
+        for _ in 0..INLINE_ATTRIBUTES {
+            message.add_attribute(SOFTWARE, &"test");
+        }
+
+        let decoded = StunMessage::from_bytes(&message.to_bytes()).unwrap();
+        assert!(!decoded.attributes.spilled());
+        assert!(decoded.attributes.iter().all(|a| !a.value.spilled()));
+
+        message.push_attribute(OwnedAttribute::from_slice(USERNAME, &[b'a'; 21]));
+        let decoded = StunMessage::from_bytes(&message.to_bytes()).unwrap();
+        assert!(decoded.attributes.spilled());
+        assert!(decoded.attributes[INLINE_ATTRIBUTES].value.spilled());
+        assert_eq!(decoded, message);
+    }
+
+    #[test]
+    fn test_modify() {
+        let mut message = StunMessage::new(header());
+        message.add_attribute(SOFTWARE, &"first");
+        message.add_attribute(USERNAME, &"user");
+        message.add_attribute(SOFTWARE, &"second");
+
+        assert_eq!(
+            message.attribute(SOFTWARE).unwrap().decode(&Utf8Decoder),
+            Ok("first")
+        );
+        assert_eq!(message.remove_attributes(SOFTWARE), 2);
+        assert_eq!(message.attributes().len(), 1);
+        assert!(message.attribute(SOFTWARE).is_none());
+
+        message.header_mut().class = MessageClass::Indication;
+        let bytes = message.to_bytes();
+        let decoded = StunDecoder::new(&bytes).unwrap();
+        assert_eq!(decoded.class(), MessageClass::Indication);
+    }
+
+    #[test]
+    fn test_invalid_attributes() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(SOFTWARE, &"test1")
+            .finish();
+        let mut truncated = bytes[..28].to_vec();
+        truncated[2..4].copy_from_slice(&[0, 8]);
+        assert_eq!(
+            StunMessage::from_bytes(&truncated),
+            Err(MessageDecodeError::UnexpectedEndOfData {
+                offset: 24,
+                attribute_type: Some(SOFTWARE),
+                needed: 8,
+                available: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_owned_decoder() {
+        fn assert_send_static<T: Send + Sync + 'static>(_: &T) {}
+
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(SOFTWARE, &"test1")
+            .finish();
+        let message = OwnedDecoder::new(bytes.to_vec()).unwrap();
+        assert_send_static(&message);
+
+        assert_eq!(message.header(), &header());
+        assert_eq!(message.as_bytes(), &bytes[..]);
+        let attribute = message.decoder().attributes().next().unwrap().unwrap();
+        assert_eq!(attribute.data(), b"test1");
+        assert_eq!(message.into_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_owned_decoder_errors() {
+        let bytes = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .finish();
+        let mut padded = bytes.to_vec();
+        padded.extend_from_slice(&[0; 4]);
+        assert_eq!(
+            OwnedDecoder::new(padded.clone()).unwrap_err(),
+            MessageDecodeError::SliceExceedsLength {
+                declared: 0,
+                available: 4,
+            }
+        );
+
+        let options = DecodeOptions {
+            allow_trailing_bytes: true,
+            ..DecodeOptions::default()
+        };
+        let message = OwnedDecoder::with_options(padded, options).unwrap();
+        assert_eq!(message.decoder().trailing_bytes(), &[0; 4]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from() {
+        let first = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(SOFTWARE, &"test1")
+            .finish();
+        let second = StunEncoder::new(BytesMut::new())
+            .encode_header(header().success_response())
+            .finish();
+        let mut stream = [&first[..], &second[..], &first[..10]].concat();
+        let mut reader = &stream[..];
+
+        let message = OwnedDecoder::read_from(&mut reader).unwrap();
+        assert_eq!(message.as_bytes(), &first[..]);
+        let message = OwnedDecoder::read_from(&mut reader).unwrap();
+        assert_eq!(message.header(), &header().success_response());
+
+        // The stream ends part way through the next message.
+        let error = OwnedDecoder::read_from(&mut reader).unwrap_err();
+        assert!(matches!(error, ReadError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+
+        // Corrupt the magic cookie of the first message.
+        stream[4] = 0;
+        let error = OwnedDecoder::read_from(&stream[..]).unwrap_err();
+        assert!(matches!(
+            error,
+            ReadError::Decode(MessageDecodeError::InvalidMagicCookie)
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_read_from_cursor() {
+        let first = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(SOFTWARE, &"test1")
+            .finish();
+        let second = StunEncoder::new(BytesMut::new())
+            .encode_header(header().success_response())
+            .finish();
+        let mut cursor = std::io::Cursor::new([&first[..], &second[..]].concat());
+
+        let message = OwnedDecoder::read_from(&mut cursor).unwrap();
+        assert_eq!(message.as_bytes(), &first[..]);
+        assert_eq!(cursor.position(), first.len() as u64);
+        let message = OwnedDecoder::read_from(&mut cursor).unwrap();
+        assert_eq!(message.as_bytes(), &second[..]);
+        assert_eq!(cursor.position(), (first.len() + second.len()) as u64);
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_round_trip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        // Deterministic, but varied, input data.
+        let data: Vec<u8> = (0u32..4096)
+            .map(|i| (i.wrapping_mul(2654435761) >> 13) as u8)
+            .collect();
+        for start in (0..data.len()).step_by(256) {
+            let mut u = Unstructured::new(&data[start..]);
+            let message = StunMessage::arbitrary(&mut u).unwrap();
+            assert!(u16::from(message.header().method) <= 0xfff);
+            let decoded = StunMessage::from_bytes(&message.to_bytes()).unwrap();
+            assert_eq!(decoded, message);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let mut message = StunMessage::new(header());
+        message.add_attribute(SOFTWARE, &"test");
+
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains(r#""class":"Request""#));
+        assert!(json.contains(r#""method":1"#));
+        assert_eq!(serde_json::from_str::<StunMessage>(&json).unwrap(), message);
+
+        let invalid = json.replace(r#""method":1"#, r#""method":4096"#);
+        assert!(serde_json::from_str::<StunMessage>(&invalid).is_err());
+    }
+}
@@ -0,0 +1,71 @@
+/// Selects how strictly [StunDecoder](crate::StunDecoder) enforces the rules of the message
+/// format.
+///
+/// Public servers in the wild frequently violate minor rules of the protocol (e.g., by not padding
+/// the final attribute). A [strict](Self::strict) decoder rejects such messages, while a
+/// [lenient](Self::lenient) decoder tolerates them where the intent of the message is still clear.
+///
+/// The [default](Self::default) options match the behavior of [StunDecoder::new](crate::StunDecoder::new).
+///
+/// ```
+/// # use stunne_protocol::{DecodeOptions, StunDecoder};
+/// # let bytes = [0, 1, 0, 0, 0x21, 0x12, 0xA4, 0x42, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0xff];
+/// // The trailing byte would normally cause an error.
+/// assert!(StunDecoder::new(&bytes).is_err());
+/// assert!(StunDecoder::with_options(&bytes, DecodeOptions::lenient()).is_ok());
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Allow bytes after the end of the message (as given by the length in the header). These
+    /// bytes are ignored. When disallowed, a
+    /// [SliceExceedsLength](crate::errors::MessageDecodeError::SliceExceedsLength) error is
+    /// returned.
+    pub allow_trailing_bytes: bool,
+
+    /// Allow the padding after an attribute's value to contain non-zero bytes. RFC 5389 requires
+    /// these bytes be ignored, but also requires senders to zero them. When disallowed, a
+    /// [NonZeroPadding](crate::errors::MessageDecodeError::NonZeroPadding) error is returned.
+    pub allow_nonzero_padding: bool,
+
+    /// Allow the final attribute of the message to be missing its padding.
+    pub allow_missing_final_padding: bool,
+
+    /// Accept messages without the magic cookie, as sent by servers that only implement
+    /// [RFC 3489][]. This is not enabled by either [strict](Self::strict) or
+    /// [lenient](Self::lenient), since such messages cannot be reliably distinguished from
+    /// non-STUN traffic. See [StunDecoder::is_classic](crate::StunDecoder::is_classic).
+    ///
+    /// [RFC 3489]: https://datatracker.ietf.org/doc/html/rfc3489
+    pub allow_classic: bool,
+}
+
+impl DecodeOptions {
+    /// Enforce every rule of the message format.
+    pub const fn strict() -> Self {
+        Self {
+            allow_trailing_bytes: false,
+            allow_nonzero_padding: false,
+            allow_missing_final_padding: false,
+            allow_classic: false,
+        }
+    }
+
+    /// Tolerate every quirk that can be tolerated without misinterpreting the message.
+    pub const fn lenient() -> Self {
+        Self {
+            allow_trailing_bytes: true,
+            allow_nonzero_padding: true,
+            allow_missing_final_padding: true,
+            allow_classic: false,
+        }
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            allow_nonzero_padding: true,
+            ..Self::strict()
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::LimitedEncoder;
+use alloc::vec::Vec;
+use bytes::BytesMut;
+use std::sync::Mutex;
+
+/// A pool of fixed-size buffers, so that a server can receive and encode messages without
+/// allocating a buffer for every packet.
+///
+/// Buffers are taken from the pool with [checkout](Self::checkout) (or as an encoder with
+/// [encoder](Self::encoder)), and given back with [checkin](Self::checkin) once they are no longer
+/// needed. If the pool is empty, a new buffer is allocated. The pool can be shared between
+/// threads (e.g., in an `Arc`).
+///
+/// ```
+/// # use std::net::UdpSocket;
+/// # use stunne_protocol::{attributes::types::XOR_MAPPED_ADDRESS, encodings::*, *};
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let socket = UdpSocket::bind("127.0.0.1:0")?;
+/// # let request = MessageBuilder::binding_request().to_bytes()?;
+/// # socket.send_to(&request, socket.local_addr()?)?;
+/// let pool = BufferPool::new(1280, 64);
+///
+/// let mut buf = pool.checkout();
+/// buf.resize(pool.buffer_size(), 0);
+/// let (len, peer) = socket.recv_from(&mut buf)?;
+/// let request = StunDecoder::new(&buf[..len])?;
+///
+/// let response = pool
+///     .encoder()
+///     .encode_header(request.header().success_response())
+///     .try_add_attribute(
+///         XOR_MAPPED_ADDRESS,
+///         &XorMappedAddressEncoder::new(peer, request.tx_id()),
+///     )?
+///     .finish_mut();
+/// socket.send_to(&response, peer)?;
+///
+/// pool.checkin(buf);
+/// pool.checkin(response);
+/// assert_eq!(pool.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    buffer_size: usize,
+    max_buffers: usize,
+}
+
+impl BufferPool {
+    /// Create an empty pool of buffers with a capacity of `buffer_size` bytes, keeping at most
+    /// `max_buffers` buffers that have been given back.
+    pub fn new(buffer_size: usize, max_buffers: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+            buffer_size,
+            max_buffers,
+        }
+    }
+
+    /// Returns the capacity of each buffer in the pool.
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    /// Returns the number of buffers in the pool, waiting to be checked out.
+    pub fn len(&self) -> usize {
+        self.buffers().len()
+    }
+
+    /// Returns true if there are no buffers in the pool, in which case the next checkout will
+    /// allocate.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Takes an empty buffer, with a capacity of at least [buffer_size](Self::buffer_size) bytes,
+    /// from the pool, or allocates one if the pool is empty.
+    pub fn checkout(&self) -> BytesMut {
+        match self.buffers().pop() {
+            Some(buf) => buf,
+            None => BytesMut::with_capacity(self.buffer_size),
+        }
+    }
+
+    /// Gives a buffer back to the pool, so that it can be checked out again.
+    ///
+    /// The buffer is dropped instead if the pool is full, or if the buffer is smaller than
+    /// [buffer_size](Self::buffer_size) (e.g., because it was split).
+    pub fn checkin(&self, mut buf: BytesMut) {
+        if buf.capacity() < self.buffer_size {
+            return;
+        }
+        let mut buffers = self.buffers();
+        if buffers.len() < self.max_buffers {
+            buf.clear();
+            buffers.push(buf);
+        }
+    }
+
+    /// Returns an encoder that writes into a buffer checked out from the pool, limiting the
+    /// message to [buffer_size](Self::buffer_size) bytes.
+    ///
+    /// Finish the message with [finish_mut](crate::LimitedAttributeEncoder::finish_mut) to be able
+    /// to give the buffer back to the pool.
+    pub fn encoder(&self) -> LimitedEncoder {
+        LimitedEncoder::new(self.checkout(), self.buffer_size)
+    }
+
+    fn buffers(&self) -> std::sync::MutexGuard<'_, Vec<BytesMut>> {
+        // The buffers are always left in a valid state, so a panic while the lock was held does
+        // not matter.
+        self.buffers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageClass, MessageHeader, MessageMethod, StunDecoder, TransactionId};
+
+    #[test]
+    fn test_checkout() {
+        let pool = BufferPool::new(128, 2);
+        assert!(pool.is_empty());
+
+        let mut first = pool.checkout();
+        assert!(first.capacity() >= 128);
+        first.extend_from_slice(b"data");
+        let ptr = first.as_ptr();
+        pool.checkin(first);
+        assert_eq!(pool.len(), 1);
+
+        let second = pool.checkout();
+        assert!(second.is_empty());
+        assert_eq!(second.as_ptr(), ptr);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_checkin_limits() {
+        let pool = BufferPool::new(128, 2);
+        for _ in 0..3 {
+            pool.checkin(BytesMut::with_capacity(128));
+        }
+        assert_eq!(pool.len(), 2);
+
+        let pool = BufferPool::new(128, 2);
+        pool.checkin(BytesMut::with_capacity(64));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_encoder() {
+        let pool = BufferPool::new(64, 1);
+        let header = MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::random(),
+        };
+
+        let buf = pool
+            .encoder()
+            .encode_header(header.clone())
+            .try_add_attribute(0x8022, &"test")
+            .unwrap()
+            .finish_mut();
+        let ptr = buf.as_ptr();
+        assert_eq!(StunDecoder::new(&buf).unwrap().header(), &header);
+        pool.checkin(buf);
+
+        let buf = pool.encoder().encode_header(header).finish_mut();
+        assert_eq!(buf.as_ptr(), ptr);
+        assert_eq!(buf.len(), 20);
+
+        let result = pool
+            .encoder()
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .try_add_attribute(0x8022, &"x".repeat(41));
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,187 @@
+use crate::attributes::types::DisplayAttributeType;
+use crate::attributes::KnownAttribute;
+use crate::StunDecoder;
+use core::fmt;
+
+/// Displays a decoded message as a multi-line report, for logging and debugging, as returned by
+/// [StunDecoder::report].
+///
+/// The report lists the class, method and transaction ID of the message, followed by each
+/// attribute by name, with its value decoded if the attribute type is known.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// let tx_id: TransactionId = "b7e7a701bc34d686fa87dfae".parse().unwrap();
+/// let bytes = MessageBuilder::binding_success_response(tx_id)
+///     .xor_mapped_address("192.0.2.1:32853".parse().unwrap())
+///     .software("Widget, Inc.")
+///     .to_bytes()
+///     .unwrap();
+/// let message = StunDecoder::new(&bytes).unwrap();
+/// assert_eq!(
+///     message.report().to_string(),
+///     "\
+/// Class:  SuccessResponse
+/// Method: BINDING
+/// Tx ID:  b7e7a701bc34d686fa87dfae
+/// Attributes:
+///   XOR-MAPPED-ADDRESS  192.0.2.1:32853
+///   SOFTWARE            \"Widget, Inc.\"
+/// "
+/// );
+/// ```
+#[derive(Copy, Clone)]
+pub struct MessageReport<'m, 'a> {
+    message: &'m StunDecoder<'a>,
+}
+
+impl<'m, 'a> MessageReport<'m, 'a> {
+    pub(crate) fn new(message: &'m StunDecoder<'a>) -> Self {
+        Self { message }
+    }
+}
+
+impl fmt::Display for MessageReport<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = self.message;
+        writeln!(f, "Class:  {:?}", message.class())?;
+        writeln!(f, "Method: {}", message.method())?;
+        match message.classic_tx_id() {
+            Some(tx_id) => writeln!(f, "Tx ID:  {} (RFC 3489)", Hex(&tx_id))?,
+            None => writeln!(f, "Tx ID:  {}", message.tx_id())?,
+        }
+        if !message.trailing_bytes().is_empty() {
+            writeln!(f, "Trailing bytes: {}", message.trailing_bytes().len())?;
+        }
+
+        let mut attributes = message.attributes().peekable();
+        if attributes.peek().is_none() {
+            return writeln!(f, "Attributes: none");
+        }
+        writeln!(f, "Attributes:")?;
+        let tx_id = message.tx_id();
+        for attribute in attributes {
+            let attribute = match attribute {
+                Ok(attribute) => attribute,
+                Err(e) => return writeln!(f, "  Error: {}", e),
+            };
+            write!(
+                f,
+                "  {: <20}",
+                DisplayAttributeType(attribute.attribute_type())
+            )?;
+            match KnownAttribute::decode(&attribute, tx_id) {
+                Ok(known) => writeln!(f, "{}", AttributeValue(&known))?,
+                Err(_) => writeln!(f, "invalid value: {}", Hex(attribute.data()))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays the value of a decoded attribute, without its type.
+pub(crate) struct AttributeValue<'b, 'a>(pub &'b KnownAttribute<'a>);
+
+impl fmt::Display for AttributeValue<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            KnownAttribute::MappedAddress(address)
+            | KnownAttribute::XorMappedAddress(address)
+            | KnownAttribute::XorPeerAddress(address)
+            | KnownAttribute::XorRelayedAddress(address)
+            | KnownAttribute::AlternateServer(address)
+            | KnownAttribute::ResponseOrigin(address)
+            | KnownAttribute::OtherAddress(address) => write!(f, "{}", address),
+            KnownAttribute::ChangeRequest(request) => write!(
+                f,
+                "change IP: {}, change port: {}",
+                request.change_ip, request.change_port
+            ),
+            KnownAttribute::MessageIntegrity(integrity) => write!(f, "{}", Hex(&integrity.hmac)),
+            KnownAttribute::ErrorCode(error_code) => write!(f, "{}", error_code),
+            KnownAttribute::Lifetime(seconds) => write!(f, "{} seconds", seconds),
+            KnownAttribute::Priority(priority) => write!(f, "{}", priority),
+            KnownAttribute::UseCandidate => Ok(()),
+            KnownAttribute::Software(software) => write!(f, "{:?}", software),
+            KnownAttribute::Fingerprint(fingerprint) => write!(f, "0x{:08x}", fingerprint.value),
+            KnownAttribute::IceControlled(tie_breaker)
+            | KnownAttribute::IceControlling(tie_breaker) => {
+                write!(f, "0x{:016x}", tie_breaker)
+            }
+            KnownAttribute::Unknown { data, .. } => write!(f, "{}", Hex(data)),
+        }
+    }
+}
+
+/// Displays bytes as lowercase hexadecimal, without separators.
+pub(crate) struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_vectors::{sample_ipv4_response, sample_request};
+    use crate::{DecodeOptions, StunDecoder};
+
+    #[test]
+    fn test_sample_request() {
+        let message = StunDecoder::new(&sample_request::BYTES).unwrap();
+        assert_eq!(
+            message.report().to_string(),
+            "\
+Class:  Request
+Method: BINDING
+Tx ID:  b7e7a701bc34d686fa87dfae
+Attributes:
+  SOFTWARE            \"STUN test client\"
+  PRIORITY            1845494271
+  ICE-CONTROLLED      0x932ff9b151263b36
+  USERNAME            6576746a3a68367659
+  MESSAGE-INTEGRITY   9aeaa70cbfd8cb56781ef2b5b2d3f249c1b571a2
+  FINGERPRINT         0xe57a3bcf
+"
+        );
+    }
+
+    #[test]
+    fn test_sample_ipv4_response() {
+        let message = StunDecoder::new(&sample_ipv4_response::BYTES).unwrap();
+        let report = message.report().to_string();
+        assert!(report.starts_with("Class:  SuccessResponse\n"));
+        assert!(report.contains("\n  XOR-MAPPED-ADDRESS  192.0.2.1:32853\n"));
+        assert!(report.contains("\n  SOFTWARE            \"test vector\"\n"));
+    }
+
+    #[test]
+    fn test_invalid_message() {
+        let mut bytes = sample_ipv4_response::BYTES.to_vec();
+        // Make the XOR-MAPPED-ADDRESS family invalid, and the FINGERPRINT length overrun the message.
+        bytes[41] = 0x03;
+        bytes[75] = 0x10;
+        bytes.extend_from_slice(&[0; 3]);
+        let options = DecodeOptions::lenient();
+        let message = StunDecoder::with_options(&bytes, options).unwrap();
+        let report = message.report().to_string();
+        assert_eq!(
+            report,
+            "\
+Class:  SuccessResponse
+Method: BINDING
+Tx ID:  b7e7a701bc34d686fa87dfae
+Trailing bytes: 3
+Attributes:
+  SOFTWARE            \"test vector\"
+  XOR-MAPPED-ADDRESS  invalid value: 0003a147e112a643
+  MESSAGE-INTEGRITY   2b91f599fd9e90c38c7489f92af9ba53f06be7d7
+  Error: unexpected end of data at offset 76 in FINGERPRINT: needed 16 bytes, but 4 available
+"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::attributes::types::{FINGERPRINT, MESSAGE_INTEGRITY};
+use crate::encodings::fingerprint::FINGERPRINT_BYTES;
+use crate::encodings::message_integrity::MESSAGE_INTEGRITY_BYTES;
+use crate::encodings::{AttributeSliceEncoder, Fingerprint, MessageIntegrity};
+use crate::errors::SliceEncodeError;
+use crate::utils::{check_attribute_order, is_trailing_attribute, padding_for_attribute_length};
+use crate::{MessageHeader, ATTRIBUTE_HEADER_BYTES, PADDING_VALUE, STUN_HEADER_BYTES};
+
+/// Encodes a STUN message into a fixed-size buffer supplied by the caller, without allocating.
+///
+/// This is the counterpart to [StunEncoder](crate::StunEncoder) for targets without an allocator.
+/// If the message does not fit within the buffer, a [SliceEncodeError::BufferTooSmall] error is
+/// returned.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// # use stunne_protocol::attributes::types::SOFTWARE;
+/// let mut buf = [0; 128];
+/// let bytes = SliceEncoder::new(&mut buf)
+///     .encode_header(MessageHeader {
+///         class: MessageClass::Request,
+///         method: MessageMethod::BINDING,
+///         tx_id: TransactionId::from_bytes(&[1; 12]),
+///     })
+///     .unwrap()
+///     .add_attribute(SOFTWARE, &"Widget, Inc.")
+///     .unwrap()
+///     .finish_with_fingerprint()
+///     .unwrap();
+/// assert_eq!(StunDecoder::new(bytes).unwrap().attributes().count(), 2);
+/// ```
+pub struct SliceEncoder<'a> {
+    buf: &'a mut [u8],
+}
+
+impl<'a> SliceEncoder<'a> {
+    /// Create the encoder with the given buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Associates the given header information to be written to the buffer.
+    ///
+    /// As with [StunEncoder::encode_header](crate::StunEncoder::encode_header), the header is
+    /// only written once the message is finished, when the length of the attributes is known.
+    pub fn encode_header(
+        self,
+        header: MessageHeader,
+    ) -> Result<SliceAttributeEncoder<'a>, SliceEncodeError> {
+        if self.buf.len() < STUN_HEADER_BYTES {
+            return Err(SliceEncodeError::BufferTooSmall);
+        }
+        Ok(SliceAttributeEncoder {
+            buf: self.buf,
+            attribute_length: 0,
+            header,
+            trailer: None,
+        })
+    }
+}
+
+pub struct SliceAttributeEncoder<'a> {
+    buf: &'a mut [u8],
+    attribute_length: usize,
+    header: MessageHeader,
+    /// The last MESSAGE-INTEGRITY, MESSAGE-INTEGRITY-SHA256 or FINGERPRINT attribute added.
+    trailer: Option<u16>,
+}
+
+impl<'a> SliceAttributeEncoder<'a> {
+    /// Adds an attribute, encoded with the given encoder.
+    ///
+    /// Unlike [StunAttributeEncoder::add_attribute](crate::StunAttributeEncoder::add_attribute),
+    /// the order of attributes is always checked: a [SliceEncodeError::InvalidOrder] error is
+    /// returned if the attribute may not follow a MESSAGE-INTEGRITY, MESSAGE-INTEGRITY-SHA256 or
+    /// FINGERPRINT attribute already in the message. Likewise, an error is returned if the value
+    /// or the message would be too long for its 16-bit length field.
+    pub fn add_attribute<T: AttributeSliceEncoder>(
+        mut self,
+        attribute_type: u16,
+        encoder: &T,
+    ) -> Result<Self, SliceEncodeError> {
+        check_attribute_order(self.trailer, attribute_type).map_err(|preceding| {
+            SliceEncodeError::InvalidOrder {
+                attribute_type,
+                preceding,
+            }
+        })?;
+
+        let value_length = encoder.encoded_len();
+        if value_length > usize::from(u16::MAX) {
+            return Err(SliceEncodeError::AttributeTooLarge {
+                attribute_type,
+                length: value_length,
+            });
+        }
+        let padding_length = padding_for_attribute_length(value_length);
+        let start = STUN_HEADER_BYTES + self.attribute_length;
+        let end = start + ATTRIBUTE_HEADER_BYTES + value_length + padding_length;
+        if end - STUN_HEADER_BYTES > usize::from(u16::MAX) {
+            return Err(SliceEncodeError::LengthOverflow {
+                length: end - STUN_HEADER_BYTES,
+            });
+        }
+        if end > self.buf.len() {
+            return Err(SliceEncodeError::BufferTooSmall);
+        }
+
+        let attribute = &mut self.buf[start..end];
+        let (attribute_header, remaining) = attribute.split_at_mut(ATTRIBUTE_HEADER_BYTES);
+        let (value, padding) = remaining.split_at_mut(value_length);
+        attribute_header[0..2].copy_from_slice(&attribute_type.to_be_bytes());
+        attribute_header[2..4].copy_from_slice(&(value_length as u16).to_be_bytes());
+        encoder.encode_to_slice(value);
+        padding.fill(PADDING_VALUE);
+
+        self.attribute_length = end - STUN_HEADER_BYTES;
+        if is_trailing_attribute(attribute_type) {
+            self.trailer = Some(attribute_type);
+        }
+        Ok(self)
+    }
+
+    /// Adds a MESSAGE-INTEGRITY attribute, as with
+    /// [StunAttributeEncoder::add_message_integrity](crate::StunAttributeEncoder::add_message_integrity).
+    pub fn add_message_integrity(self, key: &[u8]) -> Result<Self, SliceEncodeError> {
+        let length = self.attribute_length + ATTRIBUTE_HEADER_BYTES + MESSAGE_INTEGRITY_BYTES;
+        let header = self.header.to_bytes_with_length(length as u16);
+
+        let integrity = MessageIntegrity::calculate(key, &[&header, self.attributes()]);
+        self.add_attribute(MESSAGE_INTEGRITY, &integrity)
+    }
+
+    /// Adds a FINGERPRINT attribute as the final attribute of the message, and then finishes the
+    /// message as with [finish](Self::finish).
+    pub fn finish_with_fingerprint(self) -> Result<&'a [u8], SliceEncodeError> {
+        let length = self.attribute_length + ATTRIBUTE_HEADER_BYTES + FINGERPRINT_BYTES;
+        let header = self.header.to_bytes_with_length(length as u16);
+
+        let fingerprint = Fingerprint::calculate(&[&header, self.attributes()]);
+        Ok(self.add_attribute(FINGERPRINT, &fingerprint)?.finish())
+    }
+
+    /// Writes the header, returning the part of the buffer containing the encoded message.
+    pub fn finish(self) -> &'a [u8] {
+        let header = self
+            .header
+            .to_bytes_with_length(self.attribute_length as u16);
+        self.buf[..STUN_HEADER_BYTES].copy_from_slice(&header);
+        &self.buf[..STUN_HEADER_BYTES + self.attribute_length]
+    }
+
+    /// The attributes encoded so far.
+    fn attributes(&self) -> &[u8] {
+        &self.buf[STUN_HEADER_BYTES..STUN_HEADER_BYTES + self.attribute_length]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encodings::{Data, XorMappedAddressEncoder};
+    use crate::{MessageClass, MessageMethod, StunDecoder, StunEncoder, TransactionId};
+    use bytes::BytesMut;
+
+    fn header() -> MessageHeader {
+        MessageHeader {
+            class: MessageClass::SuccessResponse,
+            method: MessageMethod::BINDING,
+            tx_id: TransactionId::from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+        }
+    }
+
+    #[test]
+    fn test_matches_stun_encoder() {
+        let address = "192.0.2.1:32853".parse().unwrap();
+        let tx_id = header().tx_id;
+
+        let expected = StunEncoder::new(BytesMut::new())
+            .encode_header(header())
+            .add_attribute(0x8022, &"test1")
+            .add_attribute(0x0020, &XorMappedAddressEncoder::new(address, tx_id))
+            .add_message_integrity(b"secret")
+            .finish_with_fingerprint();
+
+        let mut buf = [0xff; 128];
+        let bytes = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_attribute(0x8022, &"test1")
+            .unwrap()
+            .add_attribute(0x0020, &XorMappedAddressEncoder::new(address, tx_id))
+            .unwrap()
+            .add_message_integrity(b"secret")
+            .unwrap()
+            .finish_with_fingerprint()
+            .unwrap();
+
+        assert_eq!(bytes, &expected[..]);
+        assert_eq!(
+            StunDecoder::new(bytes).unwrap().verify_integrity(b"secret"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_buffer_too_small() {
+        let mut buf = [0; 19];
+        assert!(matches!(
+            SliceEncoder::new(&mut buf).encode_header(header()),
+            Err(SliceEncodeError::BufferTooSmall)
+        ));
+
+        // The attribute needs 12 bytes, including its header and padding.
+        let mut buf = [0; 31];
+        assert!(matches!(
+            SliceEncoder::new(&mut buf)
+                .encode_header(header())
+                .unwrap()
+                .add_attribute(0x8022, &"test1"),
+            Err(SliceEncodeError::BufferTooSmall)
+        ));
+
+        let mut buf = [0; 32];
+        let encoder = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_attribute(0x8022, &"test1")
+            .unwrap();
+        assert_eq!(encoder.finish().len(), 32);
+    }
+
+    #[test]
+    fn test_invalid_order() {
+        let mut buf = [0; 128];
+        let result = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_message_integrity(b"secret")
+            .unwrap()
+            .add_attribute(0x8022, &"test1");
+        assert!(matches!(
+            result,
+            Err(SliceEncodeError::InvalidOrder {
+                attribute_type: 0x8022,
+                preceding: MESSAGE_INTEGRITY,
+            })
+        ));
+
+        let result = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_attribute(FINGERPRINT, &Fingerprint { value: 0 })
+            .unwrap()
+            .add_message_integrity(b"secret");
+        assert!(matches!(
+            result,
+            Err(SliceEncodeError::InvalidOrder {
+                attribute_type: MESSAGE_INTEGRITY,
+                preceding: FINGERPRINT,
+            })
+        ));
+    }
+
+    #[test]
+    fn test_length_limits() {
+        let payload = vec![0; usize::from(u16::MAX) + 1];
+        let mut buf = vec![0; 2 * payload.len()];
+        let result = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_attribute(0x8022, &Data { payload: &payload });
+        assert!(matches!(
+            result,
+            Err(SliceEncodeError::AttributeTooLarge {
+                attribute_type: 0x8022,
+                length: 65536,
+            })
+        ));
+
+        let result = SliceEncoder::new(&mut buf)
+            .encode_header(header())
+            .unwrap()
+            .add_attribute(
+                0x8022,
+                &Data {
+                    payload: &payload[8..],
+                },
+            )
+            .unwrap()
+            .add_attribute(0x8022, &"test1");
+        assert!(matches!(
+            result,
+            Err(SliceEncodeError::LengthOverflow { length: 65544 })
+        ));
+    }
+}
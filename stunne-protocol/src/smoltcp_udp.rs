@@ -0,0 +1,242 @@
+use crate::attributes::types::{MAPPED_ADDRESS, XOR_MAPPED_ADDRESS};
+use crate::encodings::{MappedAddress, XorMappedAddress};
+use crate::errors::MessageDecodeError;
+use crate::{
+    MessageBuilder, MessageClass, MessageHeader, MessageMethod, StunDecoder, StunTransaction,
+    Timestamp, TransactionConfig, TransactionId, TransactionOutcome,
+};
+use bytes::Bytes;
+use core::net::SocketAddr;
+use core::time::Duration;
+use smoltcp::socket::udp;
+use smoltcp::time::Instant;
+use smoltcp::wire::IpEndpoint;
+
+impl Timestamp for Instant {
+    fn after(self, duration: Duration) -> Self {
+        let micros = i64::try_from(duration.as_micros()).unwrap_or(i64::MAX);
+        Instant::from_micros(self.total_micros().saturating_add(micros))
+    }
+}
+
+/// A [StunTransaction] run over a smoltcp UDP socket, e.g. to discover the public address of an
+/// embedded device behind a NAT.
+///
+/// Like smoltcp itself, the transaction never blocks and never reads the clock. The application
+/// calls [poll](Self::poll) with the socket after each call to smoltcp's `Interface::poll`, and
+/// wakes up no later than [poll_at](Self::poll_at) to let the request be retransmitted.
+///
+/// The socket must be bound, and should be dedicated to the transaction while it runs: every
+/// datagram received on it is consumed, and those that do not come from the server are
+/// discarded. A request that cannot be queued (e.g., because the transmit buffer of the socket is
+/// full) is treated as lost, and sent again at the next retransmission.
+///
+/// ```
+/// # use smoltcp::socket::udp;
+/// # use smoltcp::time::Instant;
+/// # use smoltcp::wire::{IpAddress, IpEndpoint};
+/// # use stunne_protocol::*;
+/// # fn run(socket: &mut udp::Socket, now: impl Fn() -> Instant) {
+/// // On a target without std, the transaction ID is generated with the device's RNG.
+/// # let tx_id = TransactionId::from_bytes(&[1; 12]);
+/// let server = IpEndpoint::new(IpAddress::v4(192, 0, 2, 1), 3478);
+/// let mut binding =
+///     SmoltcpTransaction::binding(server, tx_id, TransactionConfig::default(), now());
+/// loop {
+///     // iface.poll(now(), &mut device, &mut sockets);
+///     if binding.poll(socket, now()).is_some() {
+///         break;
+///     }
+///     // Sleep until binding.poll_at(), or until a packet arrives.
+/// }
+/// match binding.mapped_address() {
+///     Some(address) => println!("Public address: {}", address),
+///     None => println!("No public address: {:?}", binding.transaction().outcome()),
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SmoltcpTransaction {
+    server: IpEndpoint,
+    transaction: StunTransaction<Instant>,
+}
+
+impl SmoltcpTransaction {
+    /// Start a transaction for the given encoded request, to be sent to `server`, at the time
+    /// `now`.
+    ///
+    /// An error is returned if the header of the request cannot be decoded.
+    pub fn new(
+        server: IpEndpoint,
+        request: impl Into<Bytes>,
+        config: TransactionConfig,
+        now: Instant,
+    ) -> Result<Self, MessageDecodeError> {
+        Ok(Self {
+            server,
+            transaction: StunTransaction::new(request, config, now)?,
+        })
+    }
+
+    /// Start a transaction for a Binding request with the given transaction ID, which should be
+    /// random (see [TransactionId::random_with]).
+    pub fn binding(
+        server: IpEndpoint,
+        tx_id: TransactionId,
+        config: TransactionConfig,
+        now: Instant,
+    ) -> Self {
+        let request = MessageBuilder::new(MessageHeader {
+            class: MessageClass::Request,
+            method: MessageMethod::BINDING,
+            tx_id,
+        })
+        .to_bytes()
+        .expect("a request without attributes can always be encoded");
+        Self::new(server, request, config, now).expect("the request has a valid header")
+    }
+
+    /// Returns the server that the request is sent to.
+    pub fn server(&self) -> IpEndpoint {
+        self.server
+    }
+
+    pub fn transaction(&self) -> &StunTransaction<Instant> {
+        &self.transaction
+    }
+
+    /// Advances the transaction to the time `now`: accepts the response if it has been received
+    /// on `socket`, then sends the request if it is due. Returns the outcome once the transaction
+    /// has finished.
+    pub fn poll(&mut self, socket: &mut udp::Socket, now: Instant) -> Option<&TransactionOutcome> {
+        while let Ok((packet, metadata)) = socket.recv() {
+            if metadata.endpoint == self.server {
+                self.transaction.handle_packet(packet);
+            }
+        }
+        self.transaction.handle_timeout(now);
+        while let Some(request) = self.transaction.poll_transmit() {
+            // A request that is not sent is retransmitted like a lost one.
+            let _ = socket.send_slice(&request, self.server);
+        }
+        self.transaction.outcome()
+    }
+
+    /// Returns the time at which [poll](Self::poll) must next be called if no packet is received
+    /// before, or `None` if the transaction has finished.
+    pub fn poll_at(&self) -> Option<Instant> {
+        self.transaction.poll_timeout()
+    }
+
+    /// Returns the mapped address of the success response to a Binding request, from its
+    /// XOR-MAPPED-ADDRESS attribute, or from its MAPPED-ADDRESS attribute for older servers.
+    ///
+    /// Returns `None` if the transaction has not finished, or finished without a success response
+    /// containing either attribute.
+    pub fn mapped_address(&self) -> Option<SocketAddr> {
+        let Some(TransactionOutcome::Response(response)) = self.transaction.outcome() else {
+            return None;
+        };
+        let message = StunDecoder::new(response).ok()?;
+        if message.class() != MessageClass::SuccessResponse {
+            return None;
+        }
+        let decoder = XorMappedAddress::decoder(message.tx_id());
+        match message.get_attribute(XOR_MAPPED_ADDRESS, &decoder) {
+            Some(Ok(address)) => Some(address),
+            _ => message
+                .get_attribute(MAPPED_ADDRESS, &MappedAddress::decoder())?
+                .ok(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use smoltcp::iface::{Config, Interface, SocketHandle, SocketSet};
+    use smoltcp::phy::{Loopback, Medium};
+    use smoltcp::wire::{HardwareAddress, IpAddress, IpCidr};
+
+    const CLIENT: IpEndpoint = IpEndpoint::new(IpAddress::v4(192, 0, 2, 10), 5000);
+    const SERVER: IpEndpoint = IpEndpoint::new(IpAddress::v4(192, 0, 2, 10), 3478);
+
+    fn socket(sockets: &mut SocketSet<'static>, port: u16) -> SocketHandle {
+        let buffer = || udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 2048]);
+        let mut socket = udp::Socket::new(buffer(), buffer());
+        socket.bind(port).unwrap();
+        sockets.add(socket)
+    }
+
+    fn network() -> (Loopback, Interface, SocketSet<'static>) {
+        let mut device = Loopback::new(Medium::Ip);
+        let config = Config::new(HardwareAddress::Ip);
+        let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+        iface.update_ip_addrs(|addresses| {
+            addresses.push(IpCidr::new(CLIENT.addr, 24)).unwrap();
+        });
+        (device, iface, SocketSet::new(Vec::new()))
+    }
+
+    #[test]
+    fn test_binding() {
+        let (mut device, mut iface, mut sockets) = network();
+        let client = socket(&mut sockets, CLIENT.port);
+        let server = socket(&mut sockets, SERVER.port);
+        let mapped: SocketAddr = "203.0.113.1:6000".parse().unwrap();
+        let tx_id = TransactionId::random();
+        let now = Instant::from_millis(1000);
+        let mut binding = SmoltcpTransaction::binding(SERVER, tx_id, Default::default(), now);
+
+        for _ in 0..4 {
+            iface.poll(now, &mut device, &mut sockets);
+            let server = sockets.get_mut::<udp::Socket>(server);
+            while let Ok((request, metadata)) = server.recv() {
+                let request = MessageHeader::peek(request).unwrap();
+                let response = MessageBuilder::new(request.success_response())
+                    .xor_mapped_address(mapped)
+                    .to_bytes()
+                    .unwrap();
+                server.send_slice(&response, metadata.endpoint).unwrap();
+            }
+            binding.poll(sockets.get_mut(client), now);
+        }
+        assert!(matches!(
+            binding.transaction().outcome(),
+            Some(TransactionOutcome::Response(_))
+        ));
+        assert_eq!(binding.transaction().transmissions(), 1);
+        assert_eq!(binding.mapped_address(), Some(mapped));
+        assert_eq!(binding.poll_at(), None);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let (mut device, mut iface, mut sockets) = network();
+        let client = socket(&mut sockets, CLIENT.port);
+        let config = TransactionConfig {
+            rto: Duration::from_millis(100),
+            rc: 3,
+            rm: 4,
+        };
+        let mut now = Instant::ZERO;
+        let tx_id = TransactionId::random();
+        let mut binding = SmoltcpTransaction::binding(SERVER, tx_id, config, now);
+        let mut schedule = Vec::new();
+        loop {
+            iface.poll(now, &mut device, &mut sockets);
+            if binding.poll(sockets.get_mut(client), now).is_some() {
+                break;
+            }
+            now = binding.poll_at().unwrap();
+            schedule.push(now.total_millis());
+        }
+        assert_eq!(schedule, [100, 300, 700]);
+        assert_eq!(binding.transaction().transmissions(), 3);
+        assert_eq!(
+            binding.transaction().outcome(),
+            Some(&TransactionOutcome::TimedOut)
+        );
+        assert_eq!(binding.mapped_address(), None);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::errors::MessageDecodeError;
+use crate::{MessageClass, MessageMethod, StunDecoder};
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// The number of methods counted individually, from 0x000 upwards. This covers the methods of
+/// STUN, TURN and TURN-TCP; all others are counted together.
+const COUNTED_METHODS: usize = 16;
+
+/// The number of attribute types counted individually in each of the comprehension-required and
+/// comprehension-optional ranges, from 0x0000 and 0x8000 upwards. This covers the attribute types
+/// of STUN, TURN and ICE; all others are counted together.
+const COUNTED_ATTRIBUTES: usize = 64;
+
+/// The kind of error counted by [DecodeStats], one for each variant of [MessageDecodeError].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DecodeErrorKind {
+    NonZeroStartingBits,
+    InvalidMagicCookie,
+    InvalidMessageClass,
+    InvalidMessageMethod,
+    UnexpectedEndOfData,
+    UnalignedLength,
+    LengthExceedsSlice,
+    SliceExceedsLength,
+    NonZeroPadding,
+    DuplicateAttribute,
+}
+
+impl DecodeErrorKind {
+    /// Every kind of error, in the order of the variants of [MessageDecodeError].
+    pub const ALL: [Self; 10] = [
+        Self::NonZeroStartingBits,
+        Self::InvalidMagicCookie,
+        Self::InvalidMessageClass,
+        Self::InvalidMessageMethod,
+        Self::UnexpectedEndOfData,
+        Self::UnalignedLength,
+        Self::LengthExceedsSlice,
+        Self::SliceExceedsLength,
+        Self::NonZeroPadding,
+        Self::DuplicateAttribute,
+    ];
+}
+
+impl From<&MessageDecodeError> for DecodeErrorKind {
+    fn from(error: &MessageDecodeError) -> Self {
+        match error {
+            MessageDecodeError::NonZeroStartingBits => Self::NonZeroStartingBits,
+            MessageDecodeError::InvalidMagicCookie => Self::InvalidMagicCookie,
+            MessageDecodeError::InvalidMessageClass => Self::InvalidMessageClass,
+            MessageDecodeError::InvalidMessageMethod => Self::InvalidMessageMethod,
+            MessageDecodeError::UnexpectedEndOfData { .. } => Self::UnexpectedEndOfData,
+            MessageDecodeError::UnalignedLength { .. } => Self::UnalignedLength,
+            MessageDecodeError::LengthExceedsSlice { .. } => Self::LengthExceedsSlice,
+            MessageDecodeError::SliceExceedsLength { .. } => Self::SliceExceedsLength,
+            MessageDecodeError::NonZeroPadding { .. } => Self::NonZeroPadding,
+            MessageDecodeError::DuplicateAttribute { .. } => Self::DuplicateAttribute,
+        }
+    }
+}
+
+/// Counts the messages decoded by [StunDecoder::with_stats], for cheap observability of the
+/// traffic a server receives, and of malformed traffic in particular.
+///
+/// Decoded messages are counted by class and method, errors by kind, and attributes by type. All
+/// counters are atomic, so a single `DecodeStats` can be shared between threads (e.g., in an
+/// `Arc` or a `static`), and counting never allocates or blocks.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// static STATS: DecodeStats = DecodeStats::new();
+///
+/// let bytes = MessageBuilder::binding_request()
+///     .software("Widget, Inc.")
+///     .to_bytes()
+///     .unwrap();
+/// let options = DecodeOptions::default();
+/// StunDecoder::with_stats(&bytes, options, &STATS).unwrap();
+/// assert!(StunDecoder::with_stats(&bytes[..8], options, &STATS).is_err());
+///
+/// assert_eq!(STATS.messages(MessageClass::Request, MessageMethod::BINDING), 1);
+/// assert_eq!(STATS.errors(DecodeErrorKind::UnexpectedEndOfData), 1);
+/// assert_eq!(STATS.attributes(attributes::types::SOFTWARE), 1);
+/// ```
+#[derive(Debug)]
+pub struct DecodeStats {
+    /// Indexed by class, then by method, with the last method counting all others.
+    messages: [[AtomicU64; COUNTED_METHODS + 1]; 4],
+    errors: [AtomicU64; DecodeErrorKind::ALL.len()],
+    /// The comprehension-required types, followed by the comprehension-optional types, with the
+    /// last of each counting all others in its range.
+    attributes: [AtomicU64; 2 * (COUNTED_ATTRIBUTES + 1)],
+}
+
+impl Default for DecodeStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecodeStats {
+    /// Create a collector with all counts at zero.
+    pub const fn new() -> Self {
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZERO: AtomicU64 = AtomicU64::new(0);
+        #[allow(clippy::declare_interior_mutable_const)]
+        const ZEROS: [AtomicU64; COUNTED_METHODS + 1] = [ZERO; COUNTED_METHODS + 1];
+        Self {
+            messages: [ZEROS; 4],
+            errors: [ZERO; DecodeErrorKind::ALL.len()],
+            attributes: [ZERO; 2 * (COUNTED_ATTRIBUTES + 1)],
+        }
+    }
+
+    /// Returns the number of messages of the given class and method that were decoded.
+    ///
+    /// Methods from 0x010 upwards are not counted individually: for any of them, this returns the
+    /// number of messages of the class with any such method.
+    pub fn messages(&self, class: MessageClass, method: MessageMethod) -> u64 {
+        self.messages[class_index(class)][method_index(method)].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of messages that were decoded, of any class or method.
+    pub fn total_messages(&self) -> u64 {
+        self.messages.iter().flatten().map(load).sum()
+    }
+
+    /// Returns the number of errors of the given kind, whether found in the header or in the
+    /// attributes of a message.
+    pub fn errors(&self, kind: DecodeErrorKind) -> u64 {
+        self.errors[kind as usize].load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of errors of any kind.
+    pub fn total_errors(&self) -> u64 {
+        self.errors.iter().map(load).sum()
+    }
+
+    /// Returns the number of attributes of the given type found in decoded messages.
+    ///
+    /// Attribute types from 0x0040 to 0x7FFF, and from 0x8040 upwards, are not counted
+    /// individually: for any of them, this returns the number of attributes with any such type
+    /// in the same comprehension range.
+    pub fn attributes(&self, attribute_type: u16) -> u64 {
+        self.attributes[attribute_index(attribute_type)].load(Ordering::Relaxed)
+    }
+
+    /// Sets every count back to zero, e.g. after the counts have been exported.
+    pub fn reset(&self) {
+        let counters = self.messages.iter().flatten();
+        for counter in counters.chain(&self.errors).chain(&self.attributes) {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Counts a message whose header was decoded, along with its attributes. If the attributes
+    /// cannot be decoded, the error is counted, but the message is still counted as decoded.
+    pub(crate) fn record_message(&self, message: &StunDecoder<'_>) {
+        increment(&self.messages[class_index(message.class())][method_index(message.method())]);
+        for attribute in message.attributes() {
+            match attribute {
+                Ok(attribute) => {
+                    increment(&self.attributes[attribute_index(attribute.attribute_type())])
+                }
+                Err(error) => self.record_error(&error),
+            }
+        }
+    }
+
+    pub(crate) fn record_error(&self, error: &MessageDecodeError) {
+        increment(&self.errors[DecodeErrorKind::from(error) as usize]);
+    }
+}
+
+fn class_index(class: MessageClass) -> usize {
+    match class {
+        MessageClass::Request => 0,
+        MessageClass::Indication => 1,
+        MessageClass::SuccessResponse => 2,
+        MessageClass::ErrorResponse => 3,
+    }
+}
+
+fn method_index(method: MessageMethod) -> usize {
+    usize::from(method.0).min(COUNTED_METHODS)
+}
+
+fn attribute_index(attribute_type: u16) -> usize {
+    let range = usize::from(attribute_type >> 15);
+    let index = usize::from(attribute_type & 0x7FFF).min(COUNTED_ATTRIBUTES);
+    range * (COUNTED_ATTRIBUTES + 1) + index
+}
+
+fn increment(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+fn load(counter: &AtomicU64) -> u64 {
+    counter.load(Ordering::Relaxed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::types::*;
+    use crate::test_vectors::sample_request;
+    use crate::DecodeOptions;
+
+    #[test]
+    fn test_record() {
+        let stats = DecodeStats::new();
+        let options = DecodeOptions::default();
+        StunDecoder::with_stats(&sample_request::BYTES, options, &stats).unwrap();
+
+        // Make the FINGERPRINT value overrun the message.
+        let mut bytes = sample_request::BYTES;
+        bytes[103] = 0x10;
+        StunDecoder::with_stats(&bytes, options, &stats).unwrap();
+
+        assert_eq!(stats.total_messages(), 2);
+        assert_eq!(
+            stats.messages(MessageClass::Request, MessageMethod::BINDING),
+            2
+        );
+        assert_eq!(
+            stats.messages(MessageClass::SuccessResponse, MessageMethod::BINDING),
+            0
+        );
+        assert_eq!(stats.total_errors(), 1);
+        assert_eq!(stats.errors(DecodeErrorKind::UnexpectedEndOfData), 1);
+        assert_eq!(stats.attributes(SOFTWARE), 2);
+        assert_eq!(stats.attributes(USERNAME), 2);
+        assert_eq!(stats.attributes(ICE_CONTROLLED), 2);
+        assert_eq!(stats.attributes(FINGERPRINT), 1);
+        assert_eq!(stats.attributes(MAPPED_ADDRESS), 0);
+    }
+
+    #[test]
+    fn test_uncounted_types() {
+        let stats = DecodeStats::new();
+        stats.record_error(&MessageDecodeError::InvalidMagicCookie);
+        assert_eq!(stats.errors(DecodeErrorKind::InvalidMagicCookie), 1);
+
+        for (index, attribute_type) in [0x0040, 0x7FFF, 0x8040, 0xFFFF].into_iter().enumerate() {
+            increment(&stats.attributes[attribute_index(attribute_type)]);
+            assert_eq!(stats.attributes(attribute_type), 1 + index as u64 % 2);
+        }
+        assert_eq!(stats.attributes(0x003F), 0);
+        assert_eq!(stats.attributes(0x803F), 0);
+        assert_eq!(
+            stats.messages(MessageClass::Request, MessageMethod(0x123)),
+            stats.messages(MessageClass::Request, MessageMethod(0x010)),
+        );
+
+        stats.reset();
+        assert_eq!(stats.total_errors(), 0);
+        assert_eq!(stats.attributes(0x0040), 0);
+    }
+}
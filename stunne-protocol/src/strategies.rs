@@ -0,0 +1,193 @@
+//! [proptest](https://docs.rs/proptest) strategies for generating messages, for property-based
+//! testing of code that handles STUN.
+//!
+//! Valid messages are generated as [StunMessage] values with [stun_message], or already encoded
+//! with [encoded_message]. To test how invalid input is handled, [corrupted_message] generates
+//! encoded messages that have been damaged in some way, and may or may not still decode.
+//!
+//! ```
+//! # use proptest::prelude::*;
+//! # use stunne_protocol::{strategies, StunMessage};
+//! proptest!(|(message in strategies::stun_message())| {
+//!     let decoded = StunMessage::from_bytes(&message.to_bytes()).unwrap();
+//!     prop_assert_eq!(decoded, message);
+//! });
+//! ```
+
+use crate::utils::padding_for_attribute_length;
+use crate::{
+    MessageClass, MessageHeader, MessageMethod, OwnedAttribute, StunMessage, TransactionId,
+    ATTRIBUTE_HEADER_BYTES, STUN_HEADER_BYTES,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// The largest value generated for an attribute. Larger values are valid, but make messages slow
+/// to generate and shrink.
+const MAX_VALUE_BYTES: usize = 128;
+
+/// The largest number of attributes generated for a message.
+const MAX_ATTRIBUTES: usize = 16;
+
+/// Generates any message class.
+pub fn message_class() -> impl Strategy<Value = MessageClass> {
+    prop_oneof![
+        Just(MessageClass::Request),
+        Just(MessageClass::Indication),
+        Just(MessageClass::SuccessResponse),
+        Just(MessageClass::ErrorResponse),
+    ]
+}
+
+/// Generates any method that fits in 12 bits, favoring the methods defined by the RFCs.
+pub fn message_method() -> impl Strategy<Value = MessageMethod> {
+    prop_oneof![
+        Just(MessageMethod::BINDING),
+        Just(MessageMethod::ALLOCATE),
+        Just(MessageMethod::REFRESH),
+        Just(MessageMethod::SEND),
+        Just(MessageMethod::DATA),
+        Just(MessageMethod::CREATE_PERMISSION),
+        Just(MessageMethod::CHANNEL_BIND),
+        (0u16..=0xfff).prop_map(MessageMethod),
+    ]
+}
+
+/// Generates any transaction ID.
+pub fn transaction_id() -> impl Strategy<Value = TransactionId> {
+    any::<[u8; 12]>().prop_map(|bytes| TransactionId::from_bytes(&bytes))
+}
+
+/// Generates any message header.
+pub fn message_header() -> impl Strategy<Value = MessageHeader> {
+    (message_class(), message_method(), transaction_id()).prop_map(|(class, method, tx_id)| {
+        MessageHeader {
+            class,
+            method,
+            tx_id,
+        }
+    })
+}
+
+/// Generates an attribute of any type, with a value of up to 128 bytes.
+pub fn owned_attribute() -> impl Strategy<Value = OwnedAttribute> {
+    (any::<u16>(), vec(any::<u8>(), 0..=MAX_VALUE_BYTES))
+        .prop_map(|(attribute_type, value)| OwnedAttribute::new(attribute_type, value))
+}
+
+/// Generates a list of up to 16 attributes, as generated by [owned_attribute].
+pub fn attribute_list() -> impl Strategy<Value = Vec<OwnedAttribute>> {
+    vec(owned_attribute(), 0..=MAX_ATTRIBUTES)
+}
+
+/// Generates a message with a header from [message_header] and attributes from
+/// [attribute_list].
+pub fn stun_message() -> impl Strategy<Value = StunMessage> {
+    (message_header(), attribute_list()).prop_map(|(header, attributes)| {
+        let mut message = StunMessage::new(header);
+        for attribute in attributes {
+            message.push_attribute(attribute);
+        }
+        message
+    })
+}
+
+/// Generates the bytes of a valid message, as generated by [stun_message].
+pub fn encoded_message() -> impl Strategy<Value = Vec<u8>> {
+    stun_message().prop_map(|message| message.to_bytes().to_vec())
+}
+
+/// A way in which a message can be damaged, as applied by [corrupted_message].
+#[derive(Debug, Clone)]
+enum Corruption {
+    /// Flip a bit anywhere in the message.
+    FlipBit(prop::sample::Index, u8),
+    /// Cut the message short.
+    Truncate(prop::sample::Index),
+    /// Append bytes after the end of the message.
+    Append(Vec<u8>),
+    /// Change the length in the header.
+    SetLength(u16),
+    /// Change the length of one of the attributes.
+    SetAttributeLength(prop::sample::Index, u16),
+}
+
+/// Generates the bytes of a message, as generated by [encoded_message], after damaging it in one
+/// of several ways: flipping a bit, truncating it, appending bytes, or changing the length in the
+/// header or in one of the attributes.
+///
+/// The damaged message may still decode (e.g., if a bit of an attribute's value was flipped), so
+/// this is best used to check that handling such messages does not panic, rather than that they
+/// are rejected.
+pub fn corrupted_message() -> impl Strategy<Value = Vec<u8>> {
+    let corruption = prop_oneof![
+        (any::<prop::sample::Index>(), 0u8..8).prop_map(|(i, bit)| Corruption::FlipBit(i, bit)),
+        any::<prop::sample::Index>().prop_map(Corruption::Truncate),
+        vec(any::<u8>(), 1..=8).prop_map(Corruption::Append),
+        any::<u16>().prop_map(Corruption::SetLength),
+        (any::<prop::sample::Index>(), any::<u16>())
+            .prop_map(|(i, length)| Corruption::SetAttributeLength(i, length)),
+    ];
+    (encoded_message(), corruption).prop_map(|(mut bytes, corruption)| {
+        corrupt(&mut bytes, corruption);
+        bytes
+    })
+}
+
+fn corrupt(bytes: &mut Vec<u8>, corruption: Corruption) {
+    match corruption {
+        Corruption::FlipBit(i, bit) => {
+            let i = i.index(bytes.len());
+            bytes[i] ^= 1 << bit;
+        }
+        Corruption::Truncate(i) => bytes.truncate(i.index(bytes.len())),
+        Corruption::Append(extra) => bytes.extend_from_slice(&extra),
+        Corruption::SetLength(length) => bytes[2..4].copy_from_slice(&length.to_be_bytes()),
+        Corruption::SetAttributeLength(i, length) => {
+            // Find the offsets of the attribute headers.
+            let mut offsets = Vec::new();
+            let mut offset = STUN_HEADER_BYTES;
+            while offset + ATTRIBUTE_HEADER_BYTES <= bytes.len() {
+                offsets.push(offset);
+                let value_length =
+                    usize::from(u16::from_be_bytes([bytes[offset + 2], bytes[offset + 3]]));
+                offset += ATTRIBUTE_HEADER_BYTES
+                    + value_length
+                    + padding_for_attribute_length(value_length);
+            }
+            match offsets.is_empty() {
+                // Without attributes, change the length in the header instead.
+                true => bytes[2..4].copy_from_slice(&length.to_be_bytes()),
+                false => {
+                    let offset = offsets[i.index(offsets.len())];
+                    bytes[offset + 2..offset + 4].copy_from_slice(&length.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StunDecoder;
+
+    proptest! {
+        #[test]
+        fn test_round_trip(message in stun_message()) {
+            let bytes = message.to_bytes();
+            prop_assert!(bytes.len() <= STUN_HEADER_BYTES + usize::from(u16::MAX));
+            let decoded = StunMessage::from_bytes(&bytes).unwrap();
+            prop_assert_eq!(decoded, message);
+        }
+
+        #[test]
+        fn test_corrupted_message(bytes in corrupted_message()) {
+            // Decoding may fail, but must not panic.
+            if let Ok(message) = StunDecoder::new(&bytes) {
+                let _ = message.attributes().count();
+                let _ = message.verify_integrity(b"secret");
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::errors::MessageDecodeError;
+use crate::{MessageHeader, STUN_HEADER_BYTES};
+use bytes::{Bytes, BytesMut};
+
+/// The outcome of trying to take a message from the bytes received on a stream.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StreamItem {
+    /// A complete message, ready to be given to [StunDecoder](crate::StunDecoder).
+    Message(Bytes),
+
+    /// The bytes received so far do not contain a complete message. At least this many more
+    /// bytes must be received before another attempt could succeed.
+    NeedMoreBytes(usize),
+}
+
+/// Reassembles STUN messages from a stream transport (e.g., TCP or TLS), where a single read may
+/// contain part of a message, or several messages.
+///
+/// Messages sent over a stream are not framed; instead, the length in each message's header is
+/// used to find where the message ends, as [described in RFC 5389][].
+///
+/// ```
+/// # use bytes::BytesMut;
+/// # use stunne_protocol::*;
+/// # let message = StunEncoder::new(BytesMut::new())
+/// #     .encode_header(MessageHeader {
+/// #         class: MessageClass::Request,
+/// #         method: MessageMethod::BINDING,
+/// #         tx_id: TransactionId::random(),
+/// #     })
+/// #     .add_attribute(attributes::types::SOFTWARE, &"Widget, Inc.")
+/// #     .finish();
+/// let mut stream = StreamDecoder::new();
+///
+/// // Only part of the message has arrived.
+/// stream.extend_from_slice(&message[..10]);
+/// assert_eq!(stream.next_message(), Ok(StreamItem::NeedMoreBytes(10)));
+///
+/// // The rest of the message arrives.
+/// stream.extend_from_slice(&message[10..]);
+/// assert_eq!(stream.next_message(), Ok(StreamItem::Message(message)));
+/// ```
+///
+/// [described in RFC 5389]: https://datatracker.ietf.org/doc/html/rfc5389#section-7.2.2
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    buf: BytesMut,
+}
+
+impl StreamDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a decoder whose buffer can hold the given number of bytes before needing to
+    /// allocate.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+        }
+    }
+
+    /// Adds bytes received from the stream.
+    pub fn extend_from_slice(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns the number of bytes received that have not yet been returned as part of a message.
+    pub fn buffered_len(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Takes the next complete message from the bytes received so far.
+    ///
+    /// If the header of the next message is invalid, an error is returned. As the boundaries of
+    /// messages can no longer be found, the stream should be closed in this case.
+    pub fn next_message(&mut self) -> Result<StreamItem, MessageDecodeError> {
+        split_message(&mut self.buf)
+    }
+}
+
+/// Splits the first complete message off of the given buffer, if there is one.
+pub(crate) fn split_message(buf: &mut BytesMut) -> Result<StreamItem, MessageDecodeError> {
+    if buf.len() < STUN_HEADER_BYTES {
+        return Ok(StreamItem::NeedMoreBytes(STUN_HEADER_BYTES - buf.len()));
+    }
+
+    let header_buf = buf[..STUN_HEADER_BYTES].try_into().unwrap();
+    let (_header, length) = MessageHeader::decode_with_length(header_buf)?;
+    let message_length = STUN_HEADER_BYTES + usize::from(length);
+    if buf.len() < message_length {
+        return Ok(StreamItem::NeedMoreBytes(message_length - buf.len()));
+    }
+
+    Ok(StreamItem::Message(buf.split_to(message_length).freeze()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageClass, MessageMethod, StunDecoder, StunEncoder, TransactionId};
+
+    fn message(software: &str) -> Bytes {
+        StunEncoder::new(BytesMut::new())
+            .encode_header(MessageHeader {
+                class: MessageClass::Request,
+                method: MessageMethod::BINDING,
+                tx_id: TransactionId::random(),
+            })
+            .add_attribute(0x8022, &software)
+            .finish()
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut stream = StreamDecoder::new();
+        assert_eq!(stream.next_message(), Ok(StreamItem::NeedMoreBytes(20)));
+    }
+
+    #[test]
+    fn test_byte_at_a_time() {
+        let message = message("test");
+        let mut stream = StreamDecoder::new();
+        for (i, byte) in message.iter().enumerate() {
+            assert_eq!(
+                stream.next_message(),
+                Ok(StreamItem::NeedMoreBytes(match i {
+                    0..=19 => 20 - i,
+                    _ => message.len() - i,
+                }))
+            );
+            stream.extend_from_slice(&[*byte]);
+        }
+
+        assert_eq!(stream.next_message(), Ok(StreamItem::Message(message)));
+        assert_eq!(stream.buffered_len(), 0);
+    }
+
+    #[test]
+    fn test_multiple_messages_in_one_read() {
+        let first = message("first");
+        let second = message("second");
+        let mut stream = StreamDecoder::new();
+        stream.extend_from_slice(&first);
+        stream.extend_from_slice(&second);
+        stream.extend_from_slice(&second[..4]);
+
+        assert_eq!(stream.next_message(), Ok(StreamItem::Message(first)));
+        let item = stream.next_message().unwrap();
+        assert_eq!(item, StreamItem::Message(second.clone()));
+        assert_eq!(stream.next_message(), Ok(StreamItem::NeedMoreBytes(16)));
+        assert_eq!(stream.buffered_len(), 4);
+
+        if let StreamItem::Message(bytes) = item {
+            let decoded = StunDecoder::new(&bytes).unwrap();
+            assert_eq!(decoded.attributes().count(), 1);
+        }
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let mut stream = StreamDecoder::new();
+        stream.extend_from_slice(&[0xff; 20]);
+        assert_eq!(
+            stream.next_message(),
+            Err(MessageDecodeError::NonZeroStartingBits)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use crate::errors::SliceEncodeError;
+use crate::utils::encode_message_type;
+use crate::{MessageClass, MessageMethod, TransactionId, MAGIC_COOKIE, STUN_HEADER_BYTES};
+
+/// A message without attributes, encoded at compile time, from which messages are stamped out by
+/// copying the 20 bytes of the header and writing a transaction ID.
+///
+/// This is intended for latency-critical senders of small, fixed messages, such as the Binding
+/// requests or indications used as keepalives, where even the [SliceEncoder](crate::SliceEncoder)
+/// does more work than needed.
+///
+/// ```
+/// # use stunne_protocol::*;
+/// const KEEPALIVE: MessageTemplate =
+///     MessageTemplate::new(MessageClass::Indication, MessageMethod::BINDING);
+///
+/// let tx_id = TransactionId::random();
+/// let bytes = KEEPALIVE.stamp(tx_id);
+/// let message = StunDecoder::new(&bytes).unwrap();
+/// assert_eq!(message.class(), MessageClass::Indication);
+/// assert_eq!(message.tx_id(), tx_id);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    bytes: [u8; STUN_HEADER_BYTES],
+}
+
+impl MessageTemplate {
+    /// A Binding request, as sent to discover a server-reflexive address.
+    pub const BINDING_REQUEST: Self = Self::new(MessageClass::Request, MessageMethod::BINDING);
+
+    /// A Binding indication, as sent to keep NAT bindings alive.
+    pub const BINDING_INDICATION: Self =
+        Self::new(MessageClass::Indication, MessageMethod::BINDING);
+
+    /// Create the template of a message of the given class and method, without attributes.
+    pub const fn new(class: MessageClass, method: MessageMethod) -> Self {
+        let [type_msb, type_lsb] = encode_message_type(class, method);
+        let [c0, c1, c2, c3] = MAGIC_COOKIE;
+        #[rustfmt::skip]
+        let bytes = [
+            type_msb, type_lsb,
+            // Length
+            0, 0,
+            c0, c1, c2, c3,
+            // Placeholder for the transaction ID
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        ];
+        Self { bytes }
+    }
+
+    /// Returns the encoded message, with a transaction ID of all zeros.
+    pub const fn as_bytes(&self) -> &[u8; STUN_HEADER_BYTES] {
+        &self.bytes
+    }
+
+    /// Returns the encoded message with the given transaction ID.
+    pub fn stamp(&self, tx_id: TransactionId) -> [u8; STUN_HEADER_BYTES] {
+        let mut bytes = self.bytes;
+        bytes[8..].copy_from_slice(tx_id.as_ref());
+        bytes
+    }
+
+    /// Writes the message with the given transaction ID to the start of the buffer, returning the
+    /// part of the buffer containing the message.
+    pub fn stamp_into<'a>(
+        &self,
+        buf: &'a mut [u8],
+        tx_id: TransactionId,
+    ) -> Result<&'a [u8], SliceEncodeError> {
+        let buf = buf
+            .get_mut(..STUN_HEADER_BYTES)
+            .ok_or(SliceEncodeError::BufferTooSmall)?;
+        buf.copy_from_slice(&self.stamp(tx_id));
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageHeader, SliceEncoder};
+
+    #[test]
+    fn test_matches_encoder() {
+        let tx_id = TransactionId::from_bytes(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        for (template, class) in [
+            (MessageTemplate::BINDING_REQUEST, MessageClass::Request),
+            (
+                MessageTemplate::BINDING_INDICATION,
+                MessageClass::Indication,
+            ),
+        ] {
+            let mut buf = [0; STUN_HEADER_BYTES];
+            let expected = SliceEncoder::new(&mut buf)
+                .encode_header(MessageHeader {
+                    class,
+                    method: MessageMethod::BINDING,
+                    tx_id,
+                })
+                .unwrap()
+                .finish();
+            assert_eq!(&template.stamp(tx_id), expected);
+        }
+    }
+
+    #[test]
+    fn test_stamp_into() {
+        const TEMPLATE: MessageTemplate =
+            MessageTemplate::new(MessageClass::ErrorResponse, MessageMethod::ALLOCATE);
+        assert_eq!(
+            &TEMPLATE.as_bytes()[..8],
+            &[0x01, 0x13, 0, 0, 0x21, 0x12, 0xA4, 0x42]
+        );
+
+        let tx_id = TransactionId::from_bytes(&[1; 12]);
+        let mut buf = [0xff; 32];
+        let message = TEMPLATE.stamp_into(&mut buf, tx_id).unwrap();
+        assert_eq!(message, &TEMPLATE.stamp(tx_id));
+        assert_eq!(&buf[STUN_HEADER_BYTES..], &[0xff; 12]);
+
+        let mut buf = [0; 19];
+        assert_eq!(
+            TEMPLATE.stamp_into(&mut buf, tx_id),
+            Err(SliceEncodeError::BufferTooSmall)
+        );
+    }
+}
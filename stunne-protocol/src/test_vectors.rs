@@ -0,0 +1,281 @@
+//! The test vectors of [RFC 5769][], along with the values they are expected to decode to.
+//!
+//! These allow other implementations of STUN (e.g., of MESSAGE-INTEGRITY or FINGERPRINT) to check
+//! themselves against the same vectors used to test this crate. Each vector is in its own module,
+//! containing the encoded message as `BYTES`, and the values of its attributes.
+//!
+//! ```
+//! # use stunne_protocol::{test_vectors::sample_ipv4_response, *};
+//! let message = StunDecoder::new(&sample_ipv4_response::BYTES).unwrap();
+//! let key = sample_ipv4_response::PASSWORD.as_bytes();
+//! assert_eq!(message.verify_integrity(key), Ok(()));
+//! ```
+//!
+//! [RFC 5769]: https://datatracker.ietf.org/doc/html/rfc5769
+
+/// The transaction ID shared by the sample request and responses.
+pub const SAMPLE_TRANSACTION_ID: [u8; 12] = [
+    0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae,
+];
+
+/// The short-term password used for the MESSAGE-INTEGRITY of the sample request and responses.
+pub const SAMPLE_PASSWORD: &str = "VOkJxbRl1RmTxUk/WvJxBt";
+
+/// Sample request, from [section 2.1](https://datatracker.ietf.org/doc/html/rfc5769#section-2.1).
+pub mod sample_request {
+    /// The encoded message.
+    #[rustfmt::skip]
+    pub const BYTES: [u8; 108] = [
+        0x00, 0x01, 0x00, 0x58, // Request type and message length
+        0x21, 0x12, 0xa4, 0x42, // Magic cookie
+        0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae, // Transaction ID
+        0x80, 0x22, 0x00, 0x10, // SOFTWARE attribute header
+        0x53, 0x54, 0x55, 0x4e, 0x20, 0x74, 0x65, 0x73, 0x74, 0x20, 0x63, 0x6c, 0x69, 0x65, 0x6e, 0x74,
+        0x00, 0x24, 0x00, 0x04, // PRIORITY attribute header
+        0x6e, 0x00, 0x01, 0xff,
+        0x80, 0x29, 0x00, 0x08, // ICE-CONTROLLED attribute header
+        0x93, 0x2f, 0xf9, 0xb1, 0x51, 0x26, 0x3b, 0x36,
+        0x00, 0x06, 0x00, 0x09, // USERNAME attribute header
+        0x65, 0x76, 0x74, 0x6a, 0x3a, 0x68, 0x36, 0x76, 0x59, 0x20, 0x20, 0x20,
+        0x00, 0x08, 0x00, 0x14, // MESSAGE-INTEGRITY attribute header
+        0x9a, 0xea, 0xa7, 0x0c, 0xbf, 0xd8, 0xcb, 0x56, 0x78, 0x1e, 0xf2, 0xb5, 0xb2, 0xd3, 0xf2, 0x49,
+        0xc1, 0xb5, 0x71, 0xa2,
+        0x80, 0x28, 0x00, 0x04, // FINGERPRINT attribute header
+        0xe5, 0x7a, 0x3b, 0xcf,
+    ];
+
+    /// The transaction ID.
+    pub const TRANSACTION_ID: [u8; 12] = super::SAMPLE_TRANSACTION_ID;
+
+    /// The password used for the MESSAGE-INTEGRITY attribute.
+    pub const PASSWORD: &str = super::SAMPLE_PASSWORD;
+
+    /// The value of the SOFTWARE attribute.
+    pub const SOFTWARE: &str = "STUN test client";
+
+    /// The value of the PRIORITY attribute.
+    pub const PRIORITY: u32 = 0x6e0001ff;
+
+    /// The value of the ICE-CONTROLLED attribute.
+    pub const ICE_CONTROLLED: u64 = 0x932ff9b151263b36;
+
+    /// The value of the USERNAME attribute.
+    pub const USERNAME: &str = "evtj:h6vY";
+
+    /// The value of the FINGERPRINT attribute.
+    pub const FINGERPRINT: u32 = 0xe57a3bcf;
+}
+
+/// Sample IPv4 response, from
+/// [section 2.2](https://datatracker.ietf.org/doc/html/rfc5769#section-2.2).
+pub mod sample_ipv4_response {
+    use core::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+
+    /// The encoded message.
+    #[rustfmt::skip]
+    pub const BYTES: [u8; 80] = [
+        0x01, 0x01, 0x00, 0x3c, // Response type and message length
+        0x21, 0x12, 0xa4, 0x42, // Magic cookie
+        0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae, // Transaction ID
+        0x80, 0x22, 0x00, 0x0b, // SOFTWARE attribute header
+        0x74, 0x65, 0x73, 0x74, 0x20, 0x76, 0x65, 0x63, 0x74, 0x6f, 0x72, 0x20,
+        0x00, 0x20, 0x00, 0x08, // XOR-MAPPED-ADDRESS attribute header
+        0x00, 0x01, 0xa1, 0x47, 0xe1, 0x12, 0xa6, 0x43,
+        0x00, 0x08, 0x00, 0x14, // MESSAGE-INTEGRITY attribute header
+        0x2b, 0x91, 0xf5, 0x99, 0xfd, 0x9e, 0x90, 0xc3, 0x8c, 0x74, 0x89, 0xf9, 0x2a, 0xf9, 0xba, 0x53,
+        0xf0, 0x6b, 0xe7, 0xd7,
+        0x80, 0x28, 0x00, 0x04, // FINGERPRINT attribute header
+        0xc0, 0x7d, 0x4c, 0x96,
+    ];
+
+    /// The transaction ID.
+    pub const TRANSACTION_ID: [u8; 12] = super::SAMPLE_TRANSACTION_ID;
+
+    /// The password used for the MESSAGE-INTEGRITY attribute.
+    pub const PASSWORD: &str = super::SAMPLE_PASSWORD;
+
+    /// The value of the SOFTWARE attribute.
+    pub const SOFTWARE: &str = "test vector";
+
+    /// The value of the XOR-MAPPED-ADDRESS attribute (i.e., after XORing).
+    pub const MAPPED_ADDRESS: SocketAddr =
+        SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(192, 0, 2, 1), 32853));
+
+    /// The value of the FINGERPRINT attribute.
+    pub const FINGERPRINT: u32 = 0xc07d4c96;
+}
+
+/// Sample IPv6 response, from
+/// [section 2.3](https://datatracker.ietf.org/doc/html/rfc5769#section-2.3).
+pub mod sample_ipv6_response {
+    use core::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
+
+    /// The encoded message.
+    #[rustfmt::skip]
+    pub const BYTES: [u8; 92] = [
+        0x01, 0x01, 0x00, 0x48, // Response type and message length
+        0x21, 0x12, 0xa4, 0x42, // Magic cookie
+        0xb7, 0xe7, 0xa7, 0x01, 0xbc, 0x34, 0xd6, 0x86, 0xfa, 0x87, 0xdf, 0xae, // Transaction ID
+        0x80, 0x22, 0x00, 0x0b, // SOFTWARE attribute header
+        0x74, 0x65, 0x73, 0x74, 0x20, 0x76, 0x65, 0x63, 0x74, 0x6f, 0x72, 0x20,
+        0x00, 0x20, 0x00, 0x14, // XOR-MAPPED-ADDRESS attribute header
+        0x00, 0x02, 0xa1, 0x47, 0x01, 0x13, 0xa9, 0xfa, 0xa5, 0xd3, 0xf1, 0x79, 0xbc, 0x25, 0xf4, 0xb5,
+        0xbe, 0xd2, 0xb9, 0xd9,
+        0x00, 0x08, 0x00, 0x14, // MESSAGE-INTEGRITY attribute header
+        0xa3, 0x82, 0x95, 0x4e, 0x4b, 0xe6, 0x7b, 0xf1, 0x17, 0x84, 0xc9, 0x7c, 0x82, 0x92, 0xc2, 0x75,
+        0xbf, 0xe3, 0xed, 0x41,
+        0x80, 0x28, 0x00, 0x04, // FINGERPRINT attribute header
+        0xc8, 0xfb, 0x0b, 0x4c,
+    ];
+
+    /// The transaction ID.
+    pub const TRANSACTION_ID: [u8; 12] = super::SAMPLE_TRANSACTION_ID;
+
+    /// The password used for the MESSAGE-INTEGRITY attribute.
+    pub const PASSWORD: &str = super::SAMPLE_PASSWORD;
+
+    /// The value of the SOFTWARE attribute.
+    pub const SOFTWARE: &str = "test vector";
+
+    /// The value of the XOR-MAPPED-ADDRESS attribute (i.e., after XORing).
+    pub const MAPPED_ADDRESS: SocketAddr = SocketAddr::V6(SocketAddrV6::new(
+        Ipv6Addr::new(0x2001, 0xdb8, 0x1234, 0x5678, 0x11, 0x2233, 0x4455, 0x6677),
+        32853,
+        0,
+        0,
+    ));
+
+    /// The value of the FINGERPRINT attribute.
+    pub const FINGERPRINT: u32 = 0xc8fb0b4c;
+}
+
+/// Sample request with long-term authentication, from
+/// [section 2.4](https://datatracker.ietf.org/doc/html/rfc5769#section-2.4).
+///
+/// The key for the MESSAGE-INTEGRITY attribute is derived from [USERNAME], [REALM] and
+/// [PASSWORD] (see [LongTermKey](crate::LongTermKey)).
+pub mod long_term_request {
+    /// The encoded message.
+    #[rustfmt::skip]
+    pub const BYTES: [u8; 116] = [
+        0x00, 0x01, 0x00, 0x60, // Request type and message length
+        0x21, 0x12, 0xa4, 0x42, // Magic cookie
+        0x78, 0xad, 0x34, 0x33, 0xc6, 0xad, 0x72, 0xc0, 0x29, 0xda, 0x41, 0x2e, // Transaction ID
+        0x00, 0x06, 0x00, 0x12, // USERNAME attribute header
+        0xe3, 0x83, 0x9e, 0xe3, 0x83, 0x88, 0xe3, 0x83, 0xaa, 0xe3, 0x83, 0x83, 0xe3, 0x82, 0xaf, 0xe3,
+        0x82, 0xb9, 0x00, 0x00,
+        0x00, 0x15, 0x00, 0x1c, // NONCE attribute header
+        0x66, 0x2f, 0x2f, 0x34, 0x39, 0x39, 0x6b, 0x39, 0x35, 0x34, 0x64, 0x36, 0x4f, 0x4c, 0x33, 0x34,
+        0x6f, 0x4c, 0x39, 0x46, 0x53, 0x54, 0x76, 0x79, 0x36, 0x34, 0x73, 0x41,
+        0x00, 0x14, 0x00, 0x0b, // REALM attribute header
+        0x65, 0x78, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x2e, 0x6f, 0x72, 0x67, 0x00,
+        0x00, 0x08, 0x00, 0x14, // MESSAGE-INTEGRITY attribute header
+        0xf6, 0x70, 0x24, 0x65, 0x6d, 0xd6, 0x4a, 0x3e, 0x02, 0xb8, 0xe0, 0x71, 0x2e, 0x85, 0xc9, 0xa2,
+        0x8c, 0xa8, 0x96, 0x66,
+    ];
+
+    /// The transaction ID.
+    pub const TRANSACTION_ID: [u8; 12] = [
+        0x78, 0xad, 0x34, 0x33, 0xc6, 0xad, 0x72, 0xc0, 0x29, 0xda, 0x41, 0x2e,
+    ];
+
+    /// The value of the USERNAME attribute ("マトリックス", i.e. "matrix" in katakana).
+    pub const USERNAME: &str = "\u{30de}\u{30c8}\u{30ea}\u{30c3}\u{30af}\u{30b9}";
+
+    /// The password, after SASLprep processing. The password as given in the RFC is
+    /// "The\u{00AD}M\u{00AA}tr\u{2168}".
+    pub const PASSWORD: &str = "TheMatrIX";
+
+    /// The value of the NONCE attribute.
+    pub const NONCE: &str = "f//499k954d6OL34oL9FSTvy64sA";
+
+    /// The value of the REALM attribute.
+    pub const REALM: &str = "example.org";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::attributes::types::*;
+    use crate::encodings::*;
+    use crate::{LongTermKey, StunDecoder, TransactionId};
+
+    #[test]
+    fn test_sample_request() {
+        let message = StunDecoder::new(&sample_request::BYTES).unwrap();
+        let tx_id = TransactionId::from_bytes(&sample_request::TRANSACTION_ID);
+        assert_eq!(message.tx_id(), tx_id);
+        assert_eq!(
+            message.verify_integrity(sample_request::PASSWORD.as_bytes()),
+            Ok(())
+        );
+
+        let software = message.get_attribute(SOFTWARE, &SoftwareDecoder).unwrap();
+        assert_eq!(software.unwrap().as_str(), sample_request::SOFTWARE);
+        let priority = message.get_attribute(PRIORITY, &U32Decoder).unwrap();
+        assert_eq!(priority, Ok(sample_request::PRIORITY));
+        let controlled = message.get_attribute(ICE_CONTROLLED, &U64Decoder).unwrap();
+        assert_eq!(controlled, Ok(sample_request::ICE_CONTROLLED));
+        let username = message.get_attribute(USERNAME, &Utf8Decoder).unwrap();
+        assert_eq!(username, Ok(sample_request::USERNAME));
+        let fingerprint = message.get_attribute(FINGERPRINT, &FingerprintDecoder);
+        assert_eq!(
+            fingerprint.unwrap().unwrap().value,
+            sample_request::FINGERPRINT
+        );
+    }
+
+    #[test]
+    fn test_sample_responses() {
+        for (bytes, address, software, fingerprint) in [
+            (
+                &sample_ipv4_response::BYTES[..],
+                sample_ipv4_response::MAPPED_ADDRESS,
+                sample_ipv4_response::SOFTWARE,
+                sample_ipv4_response::FINGERPRINT,
+            ),
+            (
+                &sample_ipv6_response::BYTES[..],
+                sample_ipv6_response::MAPPED_ADDRESS,
+                sample_ipv6_response::SOFTWARE,
+                sample_ipv6_response::FINGERPRINT,
+            ),
+        ] {
+            let message = StunDecoder::new(bytes).unwrap();
+            let tx_id = TransactionId::from_bytes(&SAMPLE_TRANSACTION_ID);
+            assert_eq!(message.tx_id(), tx_id);
+            assert_eq!(message.verify_integrity(SAMPLE_PASSWORD.as_bytes()), Ok(()));
+
+            let decoder = XorMappedAddressDecoder::new(tx_id);
+            let decoded = message.get_attribute(XOR_MAPPED_ADDRESS, &decoder);
+            assert_eq!(decoded.unwrap().unwrap(), address);
+            let decoded = message.get_attribute(SOFTWARE, &SoftwareDecoder).unwrap();
+            assert_eq!(decoded.unwrap().as_str(), software);
+            let decoded = message.get_attribute(FINGERPRINT, &FingerprintDecoder);
+            assert_eq!(decoded.unwrap().unwrap().value, fingerprint);
+            let (message, _) = bytes.split_at(bytes.len() - 8);
+            assert_eq!(Fingerprint::calculate(&[message]).value, fingerprint);
+        }
+    }
+
+    #[test]
+    fn test_long_term_request() {
+        let message = StunDecoder::new(&long_term_request::BYTES).unwrap();
+        let tx_id = TransactionId::from_bytes(&long_term_request::TRANSACTION_ID);
+        assert_eq!(message.tx_id(), tx_id);
+
+        let key = LongTermKey::new(
+            long_term_request::USERNAME,
+            long_term_request::REALM,
+            long_term_request::PASSWORD,
+        );
+        assert_eq!(message.verify_integrity(key.as_bytes()), Ok(()));
+
+        let username = message.get_attribute(USERNAME, &Utf8Decoder).unwrap();
+        assert_eq!(username, Ok(long_term_request::USERNAME));
+        let nonce = message.get_attribute(NONCE, &Utf8Decoder).unwrap();
+        assert_eq!(nonce, Ok(long_term_request::NONCE));
+        let realm = message.get_attribute(REALM, &Utf8Decoder).unwrap();
+        assert_eq!(realm, Ok(long_term_request::REALM));
+    }
+}
@@ -0,0 +1,135 @@
+//! Preparation of the strings used as credentials, so that strings which look the same to a user
+//! produce the same bytes (and thus the same HMAC keys) on both ends of a connection.
+//!
+//! Two profiles are provided:
+//!
+//! * [saslprep], the [SASLprep][] profile of stringprep used by RFC 5389 for USERNAME, REALM and
+//!   passwords.
+//! * [opaque_string], the [OpaqueString][] profile of PRECIS used by RFC 8489 for REALM and
+//!   passwords.
+//!
+//! Both profiles leave ASCII strings without control characters unchanged.
+//!
+//! ```
+//! # use stunne_protocol::{text, LongTermKey};
+//! // U+00AD SOFT HYPHEN is removed, and U+2168 ROMAN NUMERAL NINE is mapped to "IX".
+//! let password = text::saslprep("The\u{00AD}M\u{00AA}tr\u{2168}").unwrap();
+//! assert_eq!(password, "TheMatrIX");
+//! let key = LongTermKey::new("user", "example.org", &password);
+//! ```
+//!
+//! [SASLprep]: https://datatracker.ietf.org/doc/html/rfc4013
+//! [OpaqueString]: https://datatracker.ietf.org/doc/html/rfc8265#section-4.2
+
+use alloc::borrow::Cow;
+use core::fmt;
+use unicode_normalization::{is_nfc, UnicodeNormalization};
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+
+/// Gives the reason that a string could not be prepared.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TextError {
+    /// The string was empty, which the OpaqueString profile does not allow.
+    Empty,
+
+    /// The string contained characters that the profile does not allow (e.g., control
+    /// characters, or unassigned code points).
+    InvalidCharacters,
+}
+
+impl fmt::Display for TextError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextError::Empty => f.write_str("string is empty"),
+            TextError::InvalidCharacters => f.write_str("string contains invalid characters"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TextError {}
+
+/// Prepares a string using the [SASLprep][] profile of stringprep.
+///
+/// [SASLprep]: https://datatracker.ietf.org/doc/html/rfc4013
+pub fn saslprep(value: &str) -> Result<Cow<'_, str>, TextError> {
+    stringprep::saslprep(value).map_err(|_| TextError::InvalidCharacters)
+}
+
+/// Prepares a string using the [OpaqueString][] profile of PRECIS.
+///
+/// Non-ASCII spaces are mapped to U+0020 SPACE, and the string is normalized to NFC. Control
+/// characters, private use and unassigned code points are rejected, as is an empty string.
+///
+/// [OpaqueString]: https://datatracker.ietf.org/doc/html/rfc8265#section-4.2
+pub fn opaque_string(value: &str) -> Result<Cow<'_, str>, TextError> {
+    if value.is_empty() {
+        return Err(TextError::Empty);
+    }
+
+    let has_wide_space = value
+        .chars()
+        .any(|c| c != ' ' && c.general_category() == GeneralCategory::SpaceSeparator);
+    let value = match has_wide_space || !is_nfc(value) {
+        true => Cow::Owned(map_spaces(value).nfc().collect()),
+        false => Cow::Borrowed(value),
+    };
+
+    if !value.chars().all(is_freeform_char) {
+        return Err(TextError::InvalidCharacters);
+    }
+    Ok(value)
+}
+
+/// Maps every space character to U+0020 SPACE.
+fn map_spaces(value: &str) -> impl Iterator<Item = char> + '_ {
+    value.chars().map(|c| match c.general_category() {
+        GeneralCategory::SpaceSeparator => ' ',
+        _ => c,
+    })
+}
+
+/// Returns true if the character may appear in a string of the PRECIS FreeformClass.
+fn is_freeform_char(c: char) -> bool {
+    !matches!(
+        c.general_category(),
+        GeneralCategory::Control
+            | GeneralCategory::Surrogate
+            | GeneralCategory::PrivateUse
+            | GeneralCategory::Unassigned
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saslprep() {
+        assert!(matches!(saslprep("user"), Ok(Cow::Borrowed("user"))));
+        assert_eq!(saslprep("I\u{00AD}X").unwrap(), "IX");
+        assert_eq!(saslprep("\u{2168}").unwrap(), "IX");
+        assert_eq!(saslprep("a\u{0007}"), Err(TextError::InvalidCharacters));
+    }
+
+    #[test]
+    fn test_opaque_string() {
+        assert!(matches!(opaque_string("user"), Ok(Cow::Borrowed("user"))));
+        assert_eq!(opaque_string("a\u{3000}b").unwrap(), "a b");
+        // A decomposed "é" is composed.
+        assert_eq!(opaque_string("e\u{0301}").unwrap(), "\u{00E9}");
+        // Unlike SASLprep, compatibility characters are left as they are.
+        assert_eq!(opaque_string("\u{2168}").unwrap(), "\u{2168}");
+    }
+
+    #[test]
+    fn test_opaque_string_errors() {
+        assert_eq!(opaque_string(""), Err(TextError::Empty));
+        assert_eq!(
+            opaque_string("a\u{0007}"),
+            Err(TextError::InvalidCharacters)
+        );
+        assert_eq!(opaque_string("\u{E000}"), Err(TextError::InvalidCharacters));
+        assert_eq!(opaque_string("\u{0378}"), Err(TextError::InvalidCharacters));
+    }
+}
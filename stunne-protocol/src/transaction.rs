@@ -0,0 +1,433 @@
+use crate::errors::MessageDecodeError;
+use crate::MessageHeader;
+use bytes::Bytes;
+use core::fmt::Debug;
+use core::time::Duration;
+
+/// A point in time, as given to a [StunTransaction] by the application, which reads the clock.
+///
+/// This is implemented for std's `Instant` (with the `std` feature), for smoltcp's `Instant`
+/// (with the `smoltcp` feature), and for [Duration], as the time elapsed since any fixed epoch
+/// (e.g., since boot), for `no_std` targets with another clock.
+pub trait Timestamp: Copy + Ord + Debug {
+    /// Returns the point in time `duration` after this one, or a point far enough in the future
+    /// that it is never reached if that cannot be represented.
+    fn after(self, duration: Duration) -> Self;
+}
+
+#[cfg(feature = "std")]
+impl Timestamp for std::time::Instant {
+    fn after(self, duration: Duration) -> Self {
+        // An Instant has no maximum, but any platform can represent a century from now.
+        const CENTURY: Duration = Duration::from_secs(100 * 365 * 24 * 60 * 60);
+        self.checked_add(duration).unwrap_or_else(|| self + CENTURY)
+    }
+}
+
+impl Timestamp for Duration {
+    fn after(self, duration: Duration) -> Self {
+        self.saturating_add(duration)
+    }
+}
+
+/// The timestamps of a [StunTransaction] whose type is not given.
+#[cfg(feature = "std")]
+type DefaultTimestamp = std::time::Instant;
+#[cfg(not(feature = "std"))]
+type DefaultTimestamp = Duration;
+
+/// The retransmission parameters of a [StunTransaction], as named in [RFC 5389 section 7.2.1][].
+///
+/// The defaults are those recommended by the RFC for unreliable transports such as UDP.
+///
+/// [RFC 5389 section 7.2.1]: https://datatracker.ietf.org/doc/html/rfc5389#section-7.2.1
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TransactionConfig {
+    /// The initial retransmission timeout, doubled after each retransmission. The RFC recommends
+    /// 500 ms, or an estimate based on the round-trip times of earlier transactions with the
+    /// same server.
+    pub rto: Duration,
+
+    /// The total number of times the request is sent, including the first (Rc).
+    pub rc: u32,
+
+    /// The multiple of the initial RTO to wait for a response after the last request was sent,
+    /// before the transaction times out (Rm).
+    pub rm: u32,
+}
+
+impl Default for TransactionConfig {
+    fn default() -> Self {
+        Self {
+            rto: Duration::from_millis(500),
+            rc: 7,
+            rm: 16,
+        }
+    }
+}
+
+impl TransactionConfig {
+    /// The parameters for reliable transports such as TCP: the request is sent once, and the
+    /// transaction times out after 39.5 seconds (Ti) without a response.
+    pub fn reliable() -> Self {
+        Self {
+            rto: Duration::from_millis(39_500),
+            rc: 1,
+            rm: 1,
+        }
+    }
+}
+
+/// How a [StunTransaction] finished.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionOutcome {
+    /// A response (either success or error) to the request was received.
+    Response(Bytes),
+
+    /// No response was received before the last timeout.
+    TimedOut,
+
+    /// The transaction was cancelled with [cancel](StunTransaction::cancel).
+    Cancelled,
+}
+
+/// The client side of a single STUN transaction: sending a request, retransmitting it until a
+/// response is received, and eventually timing out.
+///
+/// The transaction does no I/O of its own, and never reads the clock. It is driven by the
+/// application, which can use any sockets and any runtime:
+///
+/// * [poll_transmit](Self::poll_transmit) returns the request when it is due to be sent.
+/// * [poll_timeout](Self::poll_timeout) returns when [handle_timeout](Self::handle_timeout) must
+///   next be called.
+/// * [handle_packet](Self::handle_packet) is given every packet received, and accepts the
+///   response to the request.
+///
+/// Once the transaction has finished, [outcome](Self::outcome) gives the response, or the reason
+/// that there is none.
+///
+/// Times are given as a [Timestamp] of type `I`, which is std's `Instant` by default.
+///
+/// ```
+/// # use std::time::{Duration, Instant};
+/// # use stunne_protocol::*;
+/// let request = MessageBuilder::binding_request().to_bytes().unwrap();
+/// let start = Instant::now();
+/// let mut transaction = StunTransaction::new(request, TransactionConfig::default(), start).unwrap();
+///
+/// // The request is sent straight away, and again if there is no response within the RTO.
+/// assert!(transaction.poll_transmit().is_some());
+/// assert_eq!(transaction.poll_timeout(), Some(start + Duration::from_millis(500)));
+/// transaction.handle_timeout(start + Duration::from_millis(500));
+/// assert!(transaction.poll_transmit().is_some());
+///
+/// let header = MessageHeader::peek(transaction.request()).unwrap();
+/// let response = MessageBuilder::binding_success_response(header.tx_id)
+///     .to_bytes()
+///     .unwrap();
+/// assert!(transaction.handle_packet(&response));
+/// assert_eq!(
+///     transaction.outcome(),
+///     Some(&TransactionOutcome::Response(response))
+/// );
+/// assert_eq!(transaction.poll_timeout(), None);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StunTransaction<I = DefaultTimestamp> {
+    request: Bytes,
+    header: MessageHeader,
+    config: TransactionConfig,
+    /// The number of times the request has been sent, or is due to be sent.
+    transmissions: u32,
+    transmit_due: bool,
+    /// The current RTO, doubled after each retransmission.
+    rto: Duration,
+    next_timeout: I,
+    outcome: Option<TransactionOutcome>,
+}
+
+impl<I: Timestamp> StunTransaction<I> {
+    /// Start a transaction for the given encoded request, at the time `now`. The request is due
+    /// to be sent immediately.
+    ///
+    /// An error is returned if the header of the request cannot be decoded. The message should be
+    /// a request: no message can be a response to any other class of message, so the transaction
+    /// would time out.
+    pub fn new(
+        request: impl Into<Bytes>,
+        config: TransactionConfig,
+        now: I,
+    ) -> Result<Self, MessageDecodeError> {
+        let request = request.into();
+        let header = MessageHeader::peek(&request)?;
+        let mut transaction = Self {
+            request,
+            header,
+            config,
+            transmissions: 1,
+            transmit_due: true,
+            rto: config.rto,
+            next_timeout: now,
+            outcome: None,
+        };
+        transaction.next_timeout = now.after(transaction.wait_after_transmission());
+        Ok(transaction)
+    }
+
+    /// Returns the header of the request.
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    /// Returns the encoded request.
+    pub fn request(&self) -> &Bytes {
+        &self.request
+    }
+
+    /// Returns the number of times the request has been sent so far, or is due to be sent.
+    pub fn transmissions(&self) -> u32 {
+        self.transmissions
+    }
+
+    /// Returns the request if it is due to be sent (or re-sent), after which it is no longer due
+    /// until the next retransmission.
+    pub fn poll_transmit(&mut self) -> Option<Bytes> {
+        match core::mem::take(&mut self.transmit_due) {
+            true => Some(self.request.clone()),
+            false => None,
+        }
+    }
+
+    /// Returns the time at which [handle_timeout](Self::handle_timeout) must next be called, or
+    /// `None` if the transaction has finished.
+    pub fn poll_timeout(&self) -> Option<I> {
+        match self.outcome {
+            Some(_) => None,
+            None => Some(self.next_timeout),
+        }
+    }
+
+    /// Advances the transaction to the time `now`, scheduling a retransmission or timing out if
+    /// the time given by [poll_timeout](Self::poll_timeout) has passed.
+    pub fn handle_timeout(&mut self, now: I) {
+        if self.outcome.is_some() || now < self.next_timeout {
+            return;
+        }
+        if self.transmissions >= self.config.rc {
+            self.finish(TransactionOutcome::TimedOut);
+            return;
+        }
+        self.transmissions += 1;
+        self.transmit_due = true;
+        self.rto = self.rto.saturating_mul(2);
+        self.next_timeout = now.after(self.wait_after_transmission());
+    }
+
+    /// Handles a packet received from the server, returning true if it is a response (either
+    /// success or error) to the request, which finishes the transaction.
+    ///
+    /// Any other packet, including further copies of the response to a retransmitted request, is
+    /// ignored and false is returned.
+    pub fn handle_packet(&mut self, packet: &[u8]) -> bool {
+        if self.outcome.is_some() {
+            return false;
+        }
+        match MessageHeader::peek(packet) {
+            Ok(header) if header.is_response_to(&self.header) => {
+                self.finish(TransactionOutcome::Response(Bytes::copy_from_slice(packet)));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Cancels the transaction, unless it has already finished. The request is no longer sent.
+    pub fn cancel(&mut self) {
+        if self.outcome.is_none() {
+            self.finish(TransactionOutcome::Cancelled);
+        }
+    }
+
+    /// Returns how the transaction finished, or `None` if it is still waiting for a response.
+    pub fn outcome(&self) -> Option<&TransactionOutcome> {
+        self.outcome.as_ref()
+    }
+
+    /// Returns true if the transaction has finished.
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// Returns how long to wait for a response after the latest transmission: the current RTO,
+    /// or Rm times the initial RTO after the last transmission.
+    fn wait_after_transmission(&self) -> Duration {
+        match self.transmissions >= self.config.rc {
+            true => self.config.rto.saturating_mul(self.config.rm),
+            false => self.rto,
+        }
+    }
+
+    fn finish(&mut self, outcome: TransactionOutcome) {
+        self.transmit_due = false;
+        self.outcome = Some(outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MessageBuilder, MessageClass, MessageMethod, TransactionId};
+    use std::time::Instant;
+
+    fn request() -> Bytes {
+        MessageBuilder::binding_request().to_bytes().unwrap()
+    }
+
+    /// Drives the transaction without a response, returning the times (in ms from the start) at
+    /// which the request was sent, and the time at which it finished.
+    fn run_to_timeout(config: TransactionConfig) -> (Vec<u128>, u128) {
+        let start = Instant::now();
+        let mut transaction = StunTransaction::new(request(), config, start).unwrap();
+        let mut transmissions = Vec::new();
+        let mut now = start;
+        loop {
+            if transaction.poll_transmit().is_some() {
+                transmissions.push((now - start).as_millis());
+            }
+            match transaction.poll_timeout() {
+                Some(timeout) => now = timeout,
+                None => break,
+            }
+            transaction.handle_timeout(now);
+        }
+        assert_eq!(transaction.outcome(), Some(&TransactionOutcome::TimedOut));
+        (transmissions, (now - start).as_millis())
+    }
+
+    #[test]
+    fn test_retransmission_schedule() {
+        // The schedule given as an example in RFC 5389.
+        assert_eq!(
+            run_to_timeout(TransactionConfig::default()),
+            (vec![0, 500, 1500, 3500, 7500, 15500, 31500], 39500)
+        );
+        assert_eq!(
+            run_to_timeout(TransactionConfig::reliable()),
+            (vec![0], 39500)
+        );
+        let config = TransactionConfig {
+            rto: Duration::from_millis(100),
+            rc: 3,
+            rm: 4,
+        };
+        assert_eq!(run_to_timeout(config), (vec![0, 100, 300], 700));
+    }
+
+    #[test]
+    fn test_huge_config() {
+        let config = TransactionConfig {
+            rto: Duration::MAX / 2,
+            rc: 40,
+            rm: u32::MAX,
+        };
+        let mut now = Duration::ZERO;
+        let mut transaction = StunTransaction::new(request(), config, now).unwrap();
+        while let Some(timeout) = transaction.poll_timeout() {
+            transaction.poll_transmit();
+            now = timeout;
+            transaction.handle_timeout(now);
+        }
+        assert_eq!(now, Duration::MAX);
+        assert_eq!(transaction.outcome(), Some(&TransactionOutcome::TimedOut));
+
+        let start = Instant::now();
+        let mut transaction = StunTransaction::new(request(), config, start).unwrap();
+        transaction.poll_transmit();
+        let timeout = transaction.poll_timeout().unwrap();
+        assert!(timeout > start + Duration::from_secs(1_000_000));
+        transaction.handle_timeout(timeout);
+        assert!(transaction.poll_transmit().is_some());
+    }
+
+    #[test]
+    fn test_early_timeout_is_ignored() {
+        let start = Instant::now();
+        let config = TransactionConfig::default();
+        let mut transaction = StunTransaction::new(request(), config, start).unwrap();
+        assert!(transaction.poll_transmit().is_some());
+        assert!(transaction.poll_transmit().is_none());
+
+        transaction.handle_timeout(start + Duration::from_millis(499));
+        assert!(transaction.poll_transmit().is_none());
+        assert_eq!(transaction.transmissions(), 1);
+
+        // A late call schedules the next retransmission from the time it was made.
+        let late = start + Duration::from_millis(600);
+        transaction.handle_timeout(late);
+        assert!(transaction.poll_transmit().is_some());
+        assert_eq!(transaction.transmissions(), 2);
+        assert_eq!(
+            transaction.poll_timeout(),
+            Some(late + Duration::from_millis(1000))
+        );
+    }
+
+    #[test]
+    fn test_response() {
+        let start = Instant::now();
+        let config = TransactionConfig::default();
+        let mut transaction = StunTransaction::new(request(), config, start).unwrap();
+        let header = transaction.header().clone();
+
+        let other = MessageBuilder::binding_success_response(TransactionId::random())
+            .to_bytes()
+            .unwrap();
+        let wrong_method = MessageBuilder::new(MessageHeader {
+            class: MessageClass::SuccessResponse,
+            method: MessageMethod::ALLOCATE,
+            tx_id: header.tx_id,
+        })
+        .to_bytes()
+        .unwrap();
+        assert!(!transaction.handle_packet(&other));
+        assert!(!transaction.handle_packet(&wrong_method));
+        assert!(!transaction.handle_packet(transaction.request().clone().as_ref()));
+        assert!(!transaction.handle_packet(b"not STUN"));
+        assert!(!transaction.is_finished());
+
+        let response = MessageBuilder::new(header.error_response())
+            .to_bytes()
+            .unwrap();
+        assert!(transaction.handle_packet(&response));
+        assert!(!transaction.handle_packet(&response));
+        assert_eq!(
+            transaction.outcome(),
+            Some(&TransactionOutcome::Response(response))
+        );
+        assert!(transaction.poll_transmit().is_none());
+        assert_eq!(transaction.poll_timeout(), None);
+    }
+
+    #[test]
+    fn test_cancel() {
+        let start = Instant::now();
+        let config = TransactionConfig::default();
+        let mut transaction = StunTransaction::new(request(), config, start).unwrap();
+        transaction.cancel();
+        assert_eq!(transaction.outcome(), Some(&TransactionOutcome::Cancelled));
+        assert!(transaction.poll_transmit().is_none());
+        assert_eq!(transaction.poll_timeout(), None);
+
+        transaction.handle_timeout(start + Duration::from_secs(60));
+        assert_eq!(transaction.outcome(), Some(&TransactionOutcome::Cancelled));
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let config = TransactionConfig::default();
+        assert_eq!(
+            StunTransaction::new(&b"short"[..], config, Instant::now()).unwrap_err(),
+            MessageDecodeError::header_too_short(5)
+        );
+    }
+}
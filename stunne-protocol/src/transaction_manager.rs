@@ -0,0 +1,385 @@
+use crate::errors::TransactionError;
+use crate::{MessageHeader, StunTransaction, TransactionConfig, TransactionId, TransactionOutcome};
+use bytes::Bytes;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+/// The number of finished transactions whose IDs are remembered, so that late responses to them
+/// are reported as [Stale](ResponseMatch::Stale) rather than [Unknown](ResponseMatch::Unknown).
+const FINISHED_HISTORY: usize = 64;
+
+/// What a [TransactionManager] made of a packet given to
+/// [handle_packet](TransactionManager::handle_packet).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResponseMatch {
+    /// The packet is the response to the transaction with this ID, which has now finished.
+    Matched(TransactionId),
+
+    /// The packet is a response to a transaction that has already finished, e.g. a second
+    /// response to a retransmitted request, or a response that arrived after the transaction
+    /// timed out.
+    Stale(TransactionId),
+
+    /// The packet is a response, but not to any transaction started by the manager (or it was
+    /// received from an address other than the one the request was sent to).
+    Unknown,
+
+    /// The packet is not a STUN response.
+    NotResponse,
+}
+
+#[derive(Debug)]
+struct Entry<A> {
+    destination: A,
+    transaction: StunTransaction,
+}
+
+/// Tracks many client transactions in flight at once, e.g. requests to several servers over one
+/// socket, matching each response received to its transaction by transaction ID.
+///
+/// Like [StunTransaction], the manager does no I/O and never reads the clock. The application
+/// sends the packets returned by [poll_transmit](Self::poll_transmit) to their destinations,
+/// calls [handle_timeout](Self::handle_timeout) at the time returned by
+/// [poll_timeout](Self::poll_timeout), gives every packet received to
+/// [handle_packet](Self::handle_packet), and collects finished transactions with
+/// [poll_outcome](Self::poll_outcome).
+///
+/// The destination of each request is an address of type `A`, which is usually a
+/// [SocketAddr], but can be any identifier of a peer.
+///
+/// ```
+/// # use std::net::SocketAddr;
+/// # use std::time::Instant;
+/// # use stunne_protocol::*;
+/// let mut manager = TransactionManager::new(TransactionConfig::default());
+/// let now = Instant::now();
+/// let server_a: SocketAddr = "192.0.2.1:3478".parse().unwrap();
+/// let server_b: SocketAddr = "192.0.2.2:3478".parse().unwrap();
+/// let request = MessageBuilder::binding_request().to_bytes().unwrap();
+/// let tx_a = manager.start(server_a, request, now).unwrap();
+/// let request = MessageBuilder::binding_request().to_bytes().unwrap();
+/// let tx_b = manager.start(server_b, request, now).unwrap();
+///
+/// let mut destinations = vec![];
+/// while let Some((destination, _request)) = manager.poll_transmit() {
+///     destinations.push(destination);
+/// }
+/// destinations.sort();
+/// assert_eq!(destinations, [server_a, server_b]);
+///
+/// let response = MessageBuilder::binding_success_response(tx_b).to_bytes().unwrap();
+/// assert_eq!(manager.handle_packet(server_b, &response), ResponseMatch::Matched(tx_b));
+/// assert_eq!(manager.handle_packet(server_b, &response), ResponseMatch::Stale(tx_b));
+///
+/// let (tx_id, outcome) = manager.poll_outcome().unwrap();
+/// assert_eq!(tx_id, tx_b);
+/// assert_eq!(outcome, TransactionOutcome::Response(response));
+/// assert_eq!(manager.len(), 1);
+/// # let _ = tx_a;
+/// ```
+#[derive(Debug)]
+pub struct TransactionManager<A = SocketAddr> {
+    config: TransactionConfig,
+    transactions: HashMap<TransactionId, Entry<A>>,
+    /// The IDs of transactions that have finished, but whose outcome has not been polled.
+    finished: VecDeque<TransactionId>,
+    /// The IDs of the most recently polled transactions, oldest first.
+    history: VecDeque<TransactionId>,
+}
+
+impl<A: Clone + PartialEq> TransactionManager<A> {
+    /// Create a manager without any transactions, which starts transactions with the given
+    /// retransmission parameters.
+    pub fn new(config: TransactionConfig) -> Self {
+        Self {
+            config,
+            transactions: HashMap::new(),
+            finished: VecDeque::new(),
+            history: VecDeque::with_capacity(FINISHED_HISTORY),
+        }
+    }
+
+    /// Returns the retransmission parameters used for new transactions.
+    pub fn config(&self) -> &TransactionConfig {
+        &self.config
+    }
+
+    /// Starts a transaction for the given encoded request, to be sent to `destination`,
+    /// returning its transaction ID. The request is due to be sent immediately.
+    pub fn start(
+        &mut self,
+        destination: A,
+        request: impl Into<Bytes>,
+        now: Instant,
+    ) -> Result<TransactionId, TransactionError> {
+        self.start_with_config(destination, request, self.config, now)
+    }
+
+    /// Starts a transaction as with [start](Self::start), using the given retransmission
+    /// parameters instead of those of the manager (e.g., an RTO estimated for the destination).
+    pub fn start_with_config(
+        &mut self,
+        destination: A,
+        request: impl Into<Bytes>,
+        config: TransactionConfig,
+        now: Instant,
+    ) -> Result<TransactionId, TransactionError> {
+        let transaction = StunTransaction::new(request, config, now)?;
+        let tx_id = transaction.header().tx_id;
+        if self.transactions.contains_key(&tx_id) {
+            return Err(TransactionError::DuplicateTransactionId(tx_id));
+        }
+        self.transactions.insert(
+            tx_id,
+            Entry {
+                destination,
+                transaction,
+            },
+        );
+        Ok(tx_id)
+    }
+
+    /// Returns the number of transactions, including those that have finished but whose outcome
+    /// has not been polled.
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Returns true if there are no transactions.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Returns the transaction with the given ID, e.g. to check its
+    /// [timeout](StunTransaction::poll_timeout) or number of transmissions.
+    pub fn get(&self, tx_id: &TransactionId) -> Option<&StunTransaction> {
+        self.transactions.get(tx_id).map(|entry| &entry.transaction)
+    }
+
+    /// Returns the destination of the transaction with the given ID.
+    pub fn destination(&self, tx_id: &TransactionId) -> Option<&A> {
+        self.transactions.get(tx_id).map(|entry| &entry.destination)
+    }
+
+    /// Returns a request that is due to be sent (or re-sent), along with its destination.
+    /// This should be called until it returns `None` after each call to
+    /// [start](Self::start) or [handle_timeout](Self::handle_timeout).
+    pub fn poll_transmit(&mut self) -> Option<(A, Bytes)> {
+        self.transactions.values_mut().find_map(|entry| {
+            let request = entry.transaction.poll_transmit()?;
+            Some((entry.destination.clone(), request))
+        })
+    }
+
+    /// Returns the earliest time at which [handle_timeout](Self::handle_timeout) must be called,
+    /// or `None` if no transaction is waiting for a response.
+    pub fn poll_timeout(&self) -> Option<Instant> {
+        self.transactions
+            .values()
+            .filter_map(|entry| entry.transaction.poll_timeout())
+            .min()
+    }
+
+    /// Advances every transaction to the time `now`, scheduling retransmissions and timing out
+    /// transactions as needed.
+    pub fn handle_timeout(&mut self, now: Instant) {
+        for (tx_id, entry) in &mut self.transactions {
+            if entry.transaction.is_finished() {
+                continue;
+            }
+            entry.transaction.handle_timeout(now);
+            if entry.transaction.is_finished() {
+                self.finished.push_back(*tx_id);
+            }
+        }
+    }
+
+    /// Handles a packet received from `source`, finishing the transaction it is a response to.
+    pub fn handle_packet(&mut self, source: A, packet: &[u8]) -> ResponseMatch {
+        let header = match MessageHeader::peek(packet) {
+            Ok(header) if header.class.is_response() => header,
+            _ => return ResponseMatch::NotResponse,
+        };
+        let tx_id = header.tx_id;
+        match self.transactions.get_mut(&tx_id) {
+            Some(entry) if entry.destination == source => {
+                if entry.transaction.handle_packet(packet) {
+                    self.finished.push_back(tx_id);
+                    return ResponseMatch::Matched(tx_id);
+                }
+                match entry.transaction.is_finished() {
+                    true => ResponseMatch::Stale(tx_id),
+                    false => ResponseMatch::Unknown,
+                }
+            }
+            Some(_) => ResponseMatch::Unknown,
+            None if self.history.contains(&tx_id) => ResponseMatch::Stale(tx_id),
+            None => ResponseMatch::Unknown,
+        }
+    }
+
+    /// Cancels the transaction with the given ID, returning false if there is no such
+    /// transaction or it has already finished. Its outcome is still returned by
+    /// [poll_outcome](Self::poll_outcome).
+    pub fn cancel(&mut self, tx_id: &TransactionId) -> bool {
+        match self.transactions.get_mut(tx_id) {
+            Some(entry) if !entry.transaction.is_finished() => {
+                entry.transaction.cancel();
+                self.finished.push_back(*tx_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Removes a finished transaction, returning its ID and outcome. Transactions are returned in
+    /// the order in which they finished.
+    pub fn poll_outcome(&mut self) -> Option<(TransactionId, TransactionOutcome)> {
+        let tx_id = self.finished.pop_front()?;
+        let entry = self.transactions.remove(&tx_id)?;
+        if self.history.len() == FINISHED_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(tx_id);
+        let outcome = entry.transaction.outcome()?.clone();
+        Some((tx_id, outcome))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MessageBuilder;
+    use std::time::Duration;
+
+    fn request() -> Bytes {
+        MessageBuilder::binding_request().to_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_timers() {
+        let start = Instant::now();
+        let mut manager = TransactionManager::new(TransactionConfig::default());
+        let fast_config = TransactionConfig {
+            rto: Duration::from_millis(100),
+            rc: 2,
+            rm: 1,
+        };
+        let slow = manager.start(1, request(), start).unwrap();
+        let fast = manager
+            .start_with_config(2, request(), fast_config, start)
+            .unwrap();
+        while manager.poll_transmit().is_some() {}
+        assert_eq!(
+            manager.poll_timeout(),
+            Some(start + Duration::from_millis(100))
+        );
+        assert_eq!(
+            manager.get(&slow).unwrap().poll_timeout(),
+            Some(start + Duration::from_millis(500))
+        );
+
+        let now = start + Duration::from_millis(100);
+        manager.handle_timeout(now);
+        assert_eq!(
+            manager.poll_transmit(),
+            Some((2, manager.get(&fast).unwrap().request().clone()))
+        );
+        assert_eq!(manager.poll_transmit(), None);
+        assert_eq!(manager.poll_outcome(), None);
+
+        manager.handle_timeout(start + Duration::from_millis(200));
+        assert_eq!(
+            manager.poll_outcome(),
+            Some((fast, TransactionOutcome::TimedOut))
+        );
+        assert_eq!(manager.len(), 1);
+        assert_eq!(
+            manager.poll_timeout(),
+            Some(start + Duration::from_millis(500))
+        );
+    }
+
+    #[test]
+    fn test_matching() {
+        let start = Instant::now();
+        let mut manager = TransactionManager::new(TransactionConfig::default());
+        let tx_id = manager.start(1, request(), start).unwrap();
+        let response = MessageBuilder::binding_success_response(tx_id)
+            .to_bytes()
+            .unwrap();
+        let unknown = MessageBuilder::binding_success_response(TransactionId::random())
+            .to_bytes()
+            .unwrap();
+
+        assert_eq!(manager.handle_packet(1, &unknown), ResponseMatch::Unknown);
+        assert_eq!(manager.handle_packet(2, &response), ResponseMatch::Unknown);
+        assert_eq!(
+            manager.handle_packet(1, manager.get(&tx_id).unwrap().request().clone().as_ref()),
+            ResponseMatch::NotResponse
+        );
+        assert_eq!(
+            manager.handle_packet(1, b"not STUN"),
+            ResponseMatch::NotResponse
+        );
+        assert_eq!(
+            manager.handle_packet(1, &response),
+            ResponseMatch::Matched(tx_id)
+        );
+        assert_eq!(
+            manager.handle_packet(1, &response),
+            ResponseMatch::Stale(tx_id)
+        );
+
+        assert_eq!(
+            manager.poll_outcome(),
+            Some((tx_id, TransactionOutcome::Response(response.clone())))
+        );
+        assert!(manager.is_empty());
+        assert_eq!(
+            manager.handle_packet(1, &response),
+            ResponseMatch::Stale(tx_id)
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_duplicates() {
+        let start = Instant::now();
+        let mut manager = TransactionManager::new(TransactionConfig::default());
+        let request = request();
+        let tx_id = manager.start(1, request.clone(), start).unwrap();
+        assert_eq!(
+            manager.start(2, request, start),
+            Err(TransactionError::DuplicateTransactionId(tx_id))
+        );
+        assert_eq!(manager.destination(&tx_id), Some(&1));
+
+        assert!(manager.cancel(&tx_id));
+        assert!(!manager.cancel(&tx_id));
+        assert_eq!(manager.poll_timeout(), None);
+        assert_eq!(
+            manager.poll_outcome(),
+            Some((tx_id, TransactionOutcome::Cancelled))
+        );
+        assert!(!manager.cancel(&tx_id));
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let start = Instant::now();
+        let mut manager = TransactionManager::new(TransactionConfig::default());
+        let first = manager.start(1, request(), start).unwrap();
+        manager.cancel(&first);
+        manager.poll_outcome();
+        for _ in 0..FINISHED_HISTORY {
+            let tx_id = manager.start(1, request(), start).unwrap();
+            manager.cancel(&tx_id);
+            manager.poll_outcome();
+        }
+        let response = MessageBuilder::binding_success_response(first)
+            .to_bytes()
+            .unwrap();
+        assert_eq!(manager.handle_packet(1, &response), ResponseMatch::Unknown);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::attributes::types::{FINGERPRINT, MESSAGE_INTEGRITY, MESSAGE_INTEGRITY_SHA256};
 use crate::{errors::MessageDecodeError, MessageClass, MessageMethod};
 
 /// Execute an in place XOR operation on `bytes` using bytes from `mask` as the mask.
@@ -13,14 +14,21 @@ pub(crate) fn xor<const N: usize>(bytes: &mut [u8; N], mask: &[u8; N]) {
 //   * The first two bits are zero.
 //   * The next 14 bits are shared between the class and method, with the class using bits 7 and 11,
 //     and the method using the rest.
-pub(crate) fn encode_message_type(class: MessageClass, method: MessageMethod) -> [u8; 2] {
+//
+// This is a `const fn` so that message templates can be encoded at compile time.
+pub(crate) const fn encode_message_type(class: MessageClass, method: MessageMethod) -> [u8; 2] {
     let mut final_value = 0;
 
-    let class_value = u16::from(class);
+    let class_value: u16 = match class {
+        MessageClass::Request => 0b00,
+        MessageClass::Indication => 0b01,
+        MessageClass::SuccessResponse => 0b10,
+        MessageClass::ErrorResponse => 0b11,
+    };
     final_value += (class_value & 0b10) << 7;
     final_value += (class_value & 0b01) << 4;
 
-    let method_value = u16::from(method);
+    let method_value = method.0;
     final_value += (method_value & 0b0000_1111_1000_0000) << 2;
     final_value += (method_value & 0b0000_0000_0111_0000) << 1;
     final_value += method_value & 0b0000_0000_0000_1111;
@@ -68,6 +76,35 @@ pub(crate) fn padding_for_attribute_length(length: usize) -> usize {
     }
 }
 
+/// Checks that an attribute may follow the last MESSAGE-INTEGRITY, MESSAGE-INTEGRITY-SHA256 or
+/// FINGERPRINT attribute already in the message (if any), returning that attribute's type if not.
+///
+/// Only MESSAGE-INTEGRITY-SHA256 and FINGERPRINT may follow MESSAGE-INTEGRITY, only FINGERPRINT
+/// may follow MESSAGE-INTEGRITY-SHA256, and FINGERPRINT must be the last attribute.
+pub(crate) fn check_attribute_order(trailer: Option<u16>, attribute_type: u16) -> Result<(), u16> {
+    let allowed = match trailer {
+        None => true,
+        Some(MESSAGE_INTEGRITY) => {
+            attribute_type == MESSAGE_INTEGRITY_SHA256 || attribute_type == FINGERPRINT
+        }
+        Some(MESSAGE_INTEGRITY_SHA256) => attribute_type == FINGERPRINT,
+        Some(_) => false,
+    };
+    match (allowed, trailer) {
+        (false, Some(trailer)) => Err(trailer),
+        _ => Ok(()),
+    }
+}
+
+/// Returns true if the attribute type restricts the attributes that may follow it (see
+/// [check_attribute_order]).
+pub(crate) fn is_trailing_attribute(attribute_type: u16) -> bool {
+    matches!(
+        attribute_type,
+        MESSAGE_INTEGRITY | MESSAGE_INTEGRITY_SHA256 | FINGERPRINT
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,6 +155,16 @@ mod tests {
             encode_message_type(MessageClass::ErrorResponse, 0b1010_0101.try_into().unwrap()),
             [0b0000_0011, 0b0101_0101]
         );
+
+        assert_eq!(
+            encode_message_type(MessageClass::Request, MessageMethod::CONNECT),
+            [0x00, 0x0A]
+        );
+
+        assert_eq!(
+            encode_message_type(MessageClass::Indication, MessageMethod::CONNECTION_ATTEMPT),
+            [0x00, 0x1C]
+        );
     }
 
     #[test]
@@ -159,6 +206,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_attribute_order() {
+        assert_eq!(check_attribute_order(None, FINGERPRINT), Ok(()));
+        assert_eq!(check_attribute_order(None, 0x8022), Ok(()));
+        assert_eq!(
+            check_attribute_order(Some(MESSAGE_INTEGRITY), MESSAGE_INTEGRITY_SHA256),
+            Ok(())
+        );
+        assert_eq!(
+            check_attribute_order(Some(MESSAGE_INTEGRITY), FINGERPRINT),
+            Ok(())
+        );
+        assert_eq!(
+            check_attribute_order(Some(MESSAGE_INTEGRITY), 0x8022),
+            Err(MESSAGE_INTEGRITY)
+        );
+        assert_eq!(
+            check_attribute_order(Some(MESSAGE_INTEGRITY_SHA256), MESSAGE_INTEGRITY),
+            Err(MESSAGE_INTEGRITY_SHA256)
+        );
+        assert_eq!(
+            check_attribute_order(Some(FINGERPRINT), FINGERPRINT),
+            Err(FINGERPRINT)
+        );
+    }
+
     #[test]
     fn test_padding_for_attribute_length() {
         assert_eq!(0, padding_for_attribute_length(0));
@@ -1,12 +1,10 @@
 use bytes::BytesMut;
 use std::net::SocketAddr;
 use stunne_protocol::{
-    encodings::MappedAddress, ext::SocketAddrExt, MessageClass, MessageHeader, MessageMethod,
-    StunDecoder, StunEncoder, TransactionId,
+    attributes::types::MAPPED_ADDRESS, encodings::MappedAddress, ext::SocketAddrExt, MessageClass,
+    MessageHeader, MessageMethod, StunDecoder, StunEncoder, TransactionId,
 };
 
-const MAPPED_ADDRESS: u16 = 0x01;
-
 #[test]
 pub fn simple_test() {
     let buf = BytesMut::with_capacity(1024);